@@ -0,0 +1,41 @@
+use nanocl_error::io::IoResult;
+use nanocld_client::stubs::audit::Audit;
+
+use crate::{
+  config::CliConfig,
+  models::{AuditArg, AuditCommand, AuditRow},
+};
+
+use super::{GenericCommand, GenericCommandInspect, GenericCommandLs};
+
+impl GenericCommand for AuditArg {
+  fn object_name() -> &'static str {
+    "audit"
+  }
+}
+
+impl GenericCommandLs for AuditArg {
+  type Item = AuditRow;
+  type Args = AuditArg;
+  type ApiItem = Audit;
+
+  fn get_key(item: &Self::Item) -> String {
+    item.key.clone()
+  }
+}
+
+impl GenericCommandInspect for AuditArg {
+  type ApiItem = Audit;
+}
+
+/// Function that execute when running `nanocl audit`
+pub async fn exec_audit(cli_conf: &CliConfig, args: &AuditArg) -> IoResult<()> {
+  match &args.command {
+    AuditCommand::List(opts) => {
+      AuditArg::exec_ls(&cli_conf.client, args, opts).await
+    }
+    AuditCommand::Inspect(opts) => {
+      AuditArg::exec_inspect(cli_conf, opts, None).await
+    }
+  }
+}
@@ -1,7 +1,11 @@
 use nanocl_error::io::{IoError, IoResult};
 use nanocld_client::stubs::{
-  cargo_spec::CargoSpecPartial, generic::GenericFilterNsp, job::JobPartial,
-  resource::ResourcePartial, secret::SecretPartial, statefile::Statefile,
+  cargo_spec::CargoSpecPartial,
+  generic::GenericFilterNsp,
+  job::JobPartial,
+  resource::ResourcePartial,
+  secret::{SecretInspectQuery, SecretPartial},
+  statefile::Statefile,
   vm_spec::VmSpecPartial,
 };
 
@@ -102,13 +106,15 @@ pub async fn exec_backup(
   let file_path = format!("{}/secrets.yml", dir_path);
   let pg_style = utils::progress::create_spinner_style("secrets", "green");
   let pg = utils::progress::create_progress("(processing)", &pg_style);
-  let secrets = cli_conf
-    .client
-    .list_secret(None)
-    .await?
-    .into_iter()
-    .map(|secret| secret.into())
-    .collect::<Vec<SecretPartial>>();
+  let query = SecretInspectQuery { reveal: Some(true) };
+  let mut secrets = Vec::new();
+  for secret in cli_conf.client.list_secret(None).await? {
+    let secret = cli_conf
+      .client
+      .inspect_secret(&secret.name, Some(&query))
+      .await?;
+    secrets.push(SecretPartial::from(secret));
+  }
   if std::path::Path::new(&file_path).exists() && !opts.skip_confirm {
     utils::dialog::confirm("File already exist override ?")?;
   }
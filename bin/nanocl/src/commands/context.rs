@@ -41,6 +41,13 @@ fn exec_context_from(path: &str) -> IoResult<()> {
   Ok(())
 }
 
+/// Function that execute when running `nanocl context token`
+/// Will set or unset the api token of a context
+fn exec_context_token(name: &str, token: &Option<String>) -> IoResult<()> {
+  Context::set_token(name, token.clone())?;
+  Ok(())
+}
+
 /// Function that execute when running `nanocl context`
 pub async fn exec_context(
   cli_conf: &CliConfig,
@@ -51,6 +58,7 @@ pub async fn exec_context(
     ContextCommand::List => exec_context_list(context)?,
     ContextCommand::Use { name } => exec_context_use(name)?,
     ContextCommand::From { path } => exec_context_from(path)?,
+    ContextCommand::Token { name, token } => exec_context_token(name, token)?,
   }
   Ok(())
 }
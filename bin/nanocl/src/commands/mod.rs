@@ -12,6 +12,10 @@ mod uninstall;
 mod node;
 mod context;
 mod secret;
+mod token;
+mod role;
+mod network_policy;
+mod audit;
 mod job;
 mod generic;
 mod metric;
@@ -35,5 +39,9 @@ pub use process::exec_process;
 pub use install::exec_install;
 pub use uninstall::exec_uninstall;
 pub use secret::exec_secret;
+pub use token::exec_token;
+pub use role::exec_role;
+pub use network_policy::exec_network_policy;
+pub use audit::exec_audit;
 pub use metric::exec_metric;
 pub use backup::exec_backup;
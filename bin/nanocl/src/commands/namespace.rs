@@ -6,8 +6,9 @@ use crate::{
   config::CliConfig,
   models::{
     GenericDefaultOpts, NamespaceArg, NamespaceCommand, NamespaceCreateOpts,
-    NamespaceRow,
+    NamespaceQuotaArg, NamespaceQuotaCommand, NamespaceRow,
   },
+  utils,
 };
 
 use super::{
@@ -46,6 +47,32 @@ async fn exec_namespace_create(
   Ok(())
 }
 
+/// Function that execute when running `nanocl namespace quota`
+async fn exec_namespace_quota(
+  cli_conf: &CliConfig,
+  args: &NamespaceQuotaArg,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  match &args.command {
+    NamespaceQuotaCommand::Set(opts) => {
+      let item = client.put_namespace_quota(&opts.name, &opts.into()).await?;
+      println!("{}", item.namespace_name);
+    }
+    NamespaceQuotaCommand::Inspect(opts) => {
+      let item = client.inspect_namespace_quota(&opts.key).await?;
+      let display = opts
+        .display
+        .clone()
+        .unwrap_or(cli_conf.user_config.display_format.clone());
+      utils::print::display_format(&display, item)?;
+    }
+    NamespaceQuotaCommand::Remove(opts) => {
+      client.delete_namespace_quota(&opts.name).await?;
+    }
+  }
+  Ok(())
+}
+
 /// Function that execute when running `nanocl namespace`
 pub async fn exec_namespace(
   cli_conf: &CliConfig,
@@ -63,5 +90,6 @@ pub async fn exec_namespace(
     NamespaceCommand::Remove(opts) => {
       NamespaceArg::exec_rm(client, opts, None).await
     }
+    NamespaceCommand::Quota(args) => exec_namespace_quota(cli_conf, args).await,
   }
 }
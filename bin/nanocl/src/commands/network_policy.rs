@@ -0,0 +1,107 @@
+use nanocl_error::io::IoResult;
+use nanocld_client::stubs::{
+  generic::{GenericFilter, GenericListQueryNsp, GenericNspQuery},
+  network_policy::{NetworkPolicy, NetworkPolicyPartial},
+};
+
+use crate::{
+  utils,
+  config::CliConfig,
+  models::{
+    GenericDefaultOpts, GenericRemoveOpts, NetworkPolicyArg,
+    NetworkPolicyCommand, NetworkPolicyCreateOpts, NetworkPolicyRow,
+  },
+};
+
+use super::{
+  GenericCommand, GenericCommandInspect, GenericCommandLs, GenericCommandRm,
+};
+
+impl GenericCommand for NetworkPolicyArg {
+  fn object_name() -> &'static str {
+    "network-policies"
+  }
+}
+
+impl GenericCommandLs for NetworkPolicyArg {
+  type Item = NetworkPolicyRow;
+  type Args = NetworkPolicyArg;
+  type ApiItem = NetworkPolicy;
+
+  fn get_key(item: &Self::Item) -> String {
+    item.name.clone()
+  }
+
+  fn transform_filter(
+    args: &Self::Args,
+    filter: &GenericFilter,
+  ) -> impl serde::Serialize {
+    GenericListQueryNsp::try_from(filter.clone())
+      .unwrap()
+      .with_namespace(args.namespace.as_deref())
+  }
+}
+
+impl GenericCommandRm<GenericDefaultOpts, GenericNspQuery>
+  for NetworkPolicyArg
+{
+  fn get_query(
+    _opts: &GenericRemoveOpts,
+    namespace: Option<String>,
+  ) -> Option<GenericNspQuery>
+  where
+    GenericNspQuery: serde::Serialize,
+  {
+    Some(GenericNspQuery::new(namespace.as_deref()))
+  }
+}
+
+impl GenericCommandInspect for NetworkPolicyArg {
+  type ApiItem = NetworkPolicy;
+}
+
+/// Function that execute when running `nanocl network-policy create`
+async fn exec_network_policy_create(
+  cli_conf: &CliConfig,
+  args: &NetworkPolicyArg,
+  opts: &NetworkPolicyCreateOpts,
+) -> IoResult<()> {
+  let policy = NetworkPolicyPartial {
+    name: opts.name.clone(),
+    target: opts.target(),
+    ingress: opts.ingress.clone(),
+    metadata: None,
+  };
+  let policy = cli_conf
+    .client
+    .create_network_policy(&policy, args.namespace.as_deref())
+    .await?;
+  match &opts.display {
+    Some(display) => utils::print::display_format(display, policy)?,
+    None => println!("{}", policy.name),
+  }
+  Ok(())
+}
+
+/// Function that execute when running `nanocl network-policy`
+pub async fn exec_network_policy(
+  cli_conf: &CliConfig,
+  args: &NetworkPolicyArg,
+) -> IoResult<()> {
+  match &args.command {
+    NetworkPolicyCommand::List(opts) => {
+      NetworkPolicyArg::exec_ls(&cli_conf.client, args, opts).await
+    }
+    NetworkPolicyCommand::Remove(opts) => {
+      NetworkPolicyArg::exec_rm(&cli_conf.client, opts, args.namespace.clone())
+        .await
+    }
+    NetworkPolicyCommand::Inspect(opts) => {
+      NetworkPolicyArg::exec_inspect(cli_conf, opts, args.namespace.clone())
+        .await
+    }
+    NetworkPolicyCommand::Create(opts) => {
+      exec_network_policy_create(cli_conf, args, opts).await
+    }
+  }
+}
@@ -0,0 +1,69 @@
+use nanocl_error::io::IoResult;
+use nanocld_client::stubs::role::RolePartial;
+
+use crate::{
+  utils,
+  config::CliConfig,
+  models::{GenericDefaultOpts, RoleArg, RoleCommand, RoleCreateOpts, RoleRow},
+};
+
+use super::{
+  GenericCommand, GenericCommandInspect, GenericCommandLs, GenericCommandRm,
+};
+
+impl GenericCommand for RoleArg {
+  fn object_name() -> &'static str {
+    "roles"
+  }
+}
+
+impl GenericCommandLs for RoleArg {
+  type Item = RoleRow;
+  type Args = RoleArg;
+  type ApiItem = nanocld_client::stubs::role::Role;
+
+  fn get_key(item: &Self::Item) -> String {
+    item.name.clone()
+  }
+}
+
+impl GenericCommandRm<GenericDefaultOpts, String> for RoleArg {}
+
+impl GenericCommandInspect for RoleArg {
+  type ApiItem = nanocld_client::stubs::role::Role;
+}
+
+/// Function that execute when running `nanocl role create`
+async fn exec_role_create(
+  cli_conf: &CliConfig,
+  opts: &RoleCreateOpts,
+) -> IoResult<()> {
+  let role = RolePartial {
+    name: opts.name.clone(),
+    rules: opts.rules.clone(),
+    subjects: opts.subjects.clone(),
+    metadata: None,
+  };
+  let role = cli_conf.client.create_role(&role).await?;
+  match &opts.display {
+    Some(display) => utils::print::display_format(display, role)?,
+    None => println!("{}", role.name),
+  }
+  Ok(())
+}
+
+/// Function that execute when running `nanocl role`
+pub async fn exec_role(cli_conf: &CliConfig, args: &RoleArg) -> IoResult<()> {
+  match &args.command {
+    RoleCommand::List(opts) => {
+      RoleArg::exec_ls(&cli_conf.client, args, opts).await
+    }
+    RoleCommand::Remove(opts) => {
+      RoleArg::exec_rm(&cli_conf.client, opts, None).await
+    }
+    RoleCommand::Inspect(opts) => {
+      RoleArg::exec_inspect(cli_conf, opts, None).await
+    }
+    RoleCommand::Create(opts) => exec_role_create(cli_conf, opts).await,
+  }
+}
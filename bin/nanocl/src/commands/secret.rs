@@ -1,16 +1,16 @@
 use nanocl_error::io::IoResult;
-use nanocld_client::stubs::secret::Secret;
+use nanocld_client::stubs::secret::SecretInspectQuery;
 
 use crate::{
+  utils,
   config::CliConfig,
   models::{
-    GenericDefaultOpts, SecretArg, SecretCommand, SecretCreateOpts, SecretRow,
+    GenericDefaultOpts, SecretArg, SecretCommand, SecretCreateOpts,
+    SecretInspectOpts, SecretRow,
   },
 };
 
-use super::{
-  GenericCommand, GenericCommandInspect, GenericCommandLs, GenericCommandRm,
-};
+use super::{GenericCommand, GenericCommandLs, GenericCommandRm};
 
 impl GenericCommand for SecretArg {
   fn object_name() -> &'static str {
@@ -21,7 +21,7 @@ impl GenericCommand for SecretArg {
 impl GenericCommandLs for SecretArg {
   type Item = SecretRow;
   type Args = SecretArg;
-  type ApiItem = nanocld_client::stubs::secret::Secret;
+  type ApiItem = nanocld_client::stubs::secret::SecretSummary;
 
   fn get_key(item: &Self::Item) -> String {
     item.name.clone()
@@ -30,10 +30,6 @@ impl GenericCommandLs for SecretArg {
 
 impl GenericCommandRm<GenericDefaultOpts, String> for SecretArg {}
 
-impl GenericCommandInspect for SecretArg {
-  type ApiItem = Secret;
-}
-
 async fn exec_secret_create(
   cli_conf: &CliConfig,
   opts: &SecretCreateOpts,
@@ -43,6 +39,37 @@ async fn exec_secret_create(
   Ok(())
 }
 
+/// Function that execute when running `nanocl secret inspect`
+/// The values are masked by the daemon unless `--reveal` is set
+async fn exec_secret_inspect(
+  cli_conf: &CliConfig,
+  opts: &SecretInspectOpts,
+) -> IoResult<()> {
+  let query = SecretInspectQuery {
+    reveal: Some(opts.reveal),
+  };
+  let secret = cli_conf
+    .client
+    .inspect_secret(&opts.key, Some(&query))
+    .await?;
+  let display = opts
+    .display
+    .clone()
+    .unwrap_or(cli_conf.user_config.display_format.clone());
+  utils::print::display_format(&display, secret)?;
+  Ok(())
+}
+
+/// Function that execute when running `nanocl secret rotate-key`
+async fn exec_secret_rotate_key(cli_conf: &CliConfig) -> IoResult<()> {
+  let rotation = cli_conf.client.rotate_secret_key().await?;
+  println!(
+    "{} secrets encrypted with key {}",
+    rotation.secrets, rotation.key_id
+  );
+  Ok(())
+}
+
 /// Function that execute when running `nanocl secret`
 pub async fn exec_secret(
   cli_conf: &CliConfig,
@@ -55,9 +82,8 @@ pub async fn exec_secret(
     SecretCommand::Remove(opts) => {
       SecretArg::exec_rm(&cli_conf.client, opts, None).await
     }
-    SecretCommand::Inspect(opts) => {
-      SecretArg::exec_inspect(cli_conf, opts, None).await
-    }
+    SecretCommand::Inspect(opts) => exec_secret_inspect(cli_conf, opts).await,
     SecretCommand::Create(opts) => exec_secret_create(cli_conf, opts).await,
+    SecretCommand::RotateKey => exec_secret_rotate_key(cli_conf).await,
   }
 }
@@ -36,7 +36,7 @@ use nanocld_client::{
     cargo_spec::CargoSpecPartial,
     vm_spec::{VmSpecPartial, VmSpecUpdate},
     resource::{ResourcePartial, ResourceUpdate},
-    secret::{SecretUpdate, SecretPartial},
+    secret::{SecretInspectQuery, SecretUpdate, SecretPartial},
     system::NativeEventAction,
   },
 };
@@ -258,6 +258,7 @@ fn gen_client(
           .ssl
           .clone(),
         version: Some(api_version.clone()),
+        token: cli_conf.client.token.clone(),
       })?
     }
     _ => {
@@ -613,7 +614,8 @@ async fn state_apply(
       let pg = utils::progress::create_progress("(submitting)", &pg_style);
       let metadata = insert_nanocl_group(&secret.metadata, &nanocl_group);
       secret.metadata = Some(metadata);
-      match client.inspect_secret(&secret.name).await {
+      let query = SecretInspectQuery { reveal: Some(true) };
+      match client.inspect_secret(&secret.name, Some(&query)).await {
         Err(_) => {
           client.create_secret(&secret).await?;
           pg.set_message("(created)");
@@ -798,12 +800,19 @@ async fn remove_orphans(
       "io.nanocl.group": get_nanocl_group(state),
     })),
   );
+  // Only the name of the secrets is needed to remove them
   let old_secrets: Vec<SecretPartial> = cli_conf
     .client
     .list_secret(Some(&filter))
     .await?
-    .iter()
-    .map(|secret| secret.clone().into())
+    .into_iter()
+    .map(|secret| SecretPartial {
+      name: secret.name,
+      kind: secret.kind,
+      immutable: secret.immutable,
+      metadata: secret.metadata,
+      data: serde_json::Value::Null,
+    })
     .collect();
   let old_cargoes: Vec<CargoSpecPartial> = cli_conf
     .client
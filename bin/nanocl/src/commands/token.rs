@@ -0,0 +1,87 @@
+use nanocl_error::io::{IoError, IoResult};
+use nanocld_client::stubs::token::TokenPartial;
+
+use crate::{
+  utils,
+  config::CliConfig,
+  models::{
+    GenericDefaultOpts, TokenArg, TokenCommand, TokenCreateOpts, TokenRow,
+  },
+};
+
+use super::{
+  GenericCommand, GenericCommandInspect, GenericCommandLs, GenericCommandRm,
+};
+
+impl GenericCommand for TokenArg {
+  fn object_name() -> &'static str {
+    "tokens"
+  }
+}
+
+impl GenericCommandLs for TokenArg {
+  type Item = TokenRow;
+  type Args = TokenArg;
+  type ApiItem = nanocld_client::stubs::token::Token;
+
+  fn get_key(item: &Self::Item) -> String {
+    item.name.clone()
+  }
+}
+
+impl GenericCommandRm<GenericDefaultOpts, String> for TokenArg {}
+
+impl GenericCommandInspect for TokenArg {
+  type ApiItem = nanocld_client::stubs::token::Token;
+}
+
+/// Function that execute when running `nanocl token create`
+/// The secret is only displayed once
+async fn exec_token_create(
+  cli_conf: &CliConfig,
+  opts: &TokenCreateOpts,
+) -> IoResult<()> {
+  let expires_at = match opts.expires_in {
+    None => None,
+    Some(days) => {
+      let duration =
+        chrono::Duration::try_days(days.into()).ok_or_else(|| {
+          IoError::invalid_input("Expires in", "number of days is too large")
+        })?;
+      Some(chrono::Utc::now().naive_utc() + duration)
+    }
+  };
+  let token = TokenPartial {
+    name: opts.name.clone(),
+    expires_at,
+    metadata: None,
+  };
+  let created = cli_conf.client.create_token(&token).await?;
+  match &opts.display {
+    Some(display) => utils::print::display_format(display, created)?,
+    None => {
+      println!("{}", created.secret);
+      eprintln!(
+        "Save this token now it cannot be displayed again, \
+        use `nanocl context token <context> <token>` to store it"
+      );
+    }
+  }
+  Ok(())
+}
+
+/// Function that execute when running `nanocl token`
+pub async fn exec_token(cli_conf: &CliConfig, args: &TokenArg) -> IoResult<()> {
+  match &args.command {
+    TokenCommand::List(opts) => {
+      TokenArg::exec_ls(&cli_conf.client, args, opts).await
+    }
+    TokenCommand::Remove(opts) => {
+      TokenArg::exec_rm(&cli_conf.client, opts, None).await
+    }
+    TokenCommand::Inspect(opts) => {
+      TokenArg::exec_inspect(cli_conf, opts, None).await
+    }
+    TokenCommand::Create(opts) => exec_token_create(cli_conf, opts).await,
+  }
+}
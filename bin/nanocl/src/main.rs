@@ -63,9 +63,13 @@ fn create_cli_config(cli_args: &Cli) -> IoResult<CliConfig> {
   if let Ok(h) = std::env::var("HOST") {
     host = h;
   }
+  let token = std::env::var("NANOCL_TOKEN")
+    .ok()
+    .or_else(|| endpoint.token.clone());
   let client = NanocldClient::connect_to(&ConnectOpts {
     url: host.clone(),
     ssl,
+    token,
     ..Default::default()
   })?;
   Ok(CliConfig {
@@ -85,6 +89,12 @@ async fn execute_arg(cli_args: &Cli) -> IoResult<()> {
     Command::Resource(args) => commands::exec_resource(&cli_conf, args).await,
     Command::Cargo(args) => commands::exec_cargo(&cli_conf, args).await,
     Command::Secret(args) => commands::exec_secret(&cli_conf, args).await,
+    Command::Token(args) => commands::exec_token(&cli_conf, args).await,
+    Command::Role(args) => commands::exec_role(&cli_conf, args).await,
+    Command::NetworkPolicy(args) => {
+      commands::exec_network_policy(&cli_conf, args).await
+    }
+    Command::Audit(args) => commands::exec_audit(&cli_conf, args).await,
     Command::Event(args) => commands::exec_event(&cli_conf, args).await,
     Command::State(args) => commands::exec_state(&cli_conf, args).await,
     Command::Version => commands::exec_version(&cli_conf).await,
@@ -146,6 +156,17 @@ mod tests {
     assert_cli_ok!("namespace", "ls");
     // Try to inspect namespace
     assert_cli_ok!("namespace", "inspect", NAMESPACE_NAME);
+    // Try to manage the quota of the namespace
+    assert_cli_ok!(
+      "namespace",
+      "quota",
+      "set",
+      "--max-cargoes",
+      "4",
+      NAMESPACE_NAME
+    );
+    assert_cli_ok!("namespace", "quota", "inspect", NAMESPACE_NAME);
+    assert_cli_ok!("namespace", "quota", "rm", NAMESPACE_NAME);
     // Try to remove namespace
     assert_cli_ok!("namespace", "rm", "-y", NAMESPACE_NAME);
   }
@@ -717,4 +738,54 @@ mod tests {
     assert_cli_ok!("event", "ls", "--limit", "2", "--offset", "1");
     assert_cli_ok!("event", "ls", "-q", "--limit", "2", "--offset", "1");
   }
+
+  #[ntex::test]
+  async fn token() {
+    assert_cli_ok!("token", "create", "cli-token", "--expires-in", "1");
+    assert_cli_ok!("token", "ls");
+    assert_cli_ok!("token", "ls", "-q");
+    assert_cli_ok!("token", "inspect", "cli-token");
+    assert_cli_ok!("token", "rm", "-y", "cli-token");
+  }
+
+  #[ntex::test]
+  async fn role() {
+    assert_cli_ok!(
+      "role",
+      "create",
+      "cli-role",
+      "--rule",
+      "kinds=cargo,vm;verbs=list,inspect;namespaces=global",
+      "--subject",
+      "token:cli-role"
+    );
+    assert_cli_ok!("role", "ls");
+    assert_cli_ok!("role", "ls", "-q");
+    assert_cli_ok!("role", "inspect", "cli-role");
+    assert_cli_ok!("role", "rm", "-y", "cli-role");
+  }
+
+  #[ntex::test]
+  async fn network_policy() {
+    assert_cli_ok!(
+      "network-policy",
+      "create",
+      "cli-netpol",
+      "--target-cargo",
+      "db",
+      "--ingress",
+      "cargoes=api;labels=role=backend;ports=5432,53/udp"
+    );
+    assert_cli_ok!("network-policy", "ls");
+    assert_cli_ok!("netpol", "ls", "-q");
+    assert_cli_ok!("network-policy", "inspect", "cli-netpol");
+    assert_cli_ok!("network-policy", "rm", "-y", "cli-netpol");
+  }
+
+  #[ntex::test]
+  async fn audit() {
+    assert_cli_ok!("audit", "ls");
+    assert_cli_ok!("audit", "ls", "-q");
+    assert_cli_ok!("audit", "ls", "--limit", "10");
+  }
 }
@@ -0,0 +1,68 @@
+use chrono::TimeZone;
+use tabled::Tabled;
+use clap::{Parser, Subcommand};
+
+use nanocld_client::stubs::audit::Audit;
+
+use super::{GenericInspectOpts, GenericListOpts};
+
+/// `nanocl audit` available arguments
+#[derive(Clone, Parser)]
+pub struct AuditArg {
+  /// Audit command
+  #[clap(subcommand)]
+  pub command: AuditCommand,
+}
+
+/// `nanocl audit` available commands
+#[derive(Clone, Subcommand)]
+pub enum AuditCommand {
+  /// List the audit entries of the mutating api calls
+  #[clap(alias("ls"))]
+  List(GenericListOpts),
+  /// Inspect an audit entry
+  Inspect(GenericInspectOpts),
+}
+
+/// A row of the audit table
+#[derive(Clone, Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct AuditRow {
+  /// Key of the entry
+  pub key: String,
+  /// When the request have been received
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+  /// Identity of the client
+  pub identity: String,
+  /// Http method of the request
+  pub method: String,
+  /// Path of the request
+  pub path: String,
+  /// Http status code of the response
+  pub status: u16,
+  /// Milliseconds spent to answer
+  #[tabled(rename = "LATENCY (MS)")]
+  pub latency: u64,
+}
+
+impl From<Audit> for AuditRow {
+  fn from(audit: Audit) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    // Convert the created_at to the current timezone
+    let created_at = tz
+      .timestamp_opt(audit.created_at.and_utc().timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    Self {
+      key: audit.key.to_string(),
+      created_at: created_at.to_string(),
+      identity: audit.identity.unwrap_or("<none>".to_owned()),
+      method: audit.method,
+      path: audit.path,
+      status: audit.status_code,
+      latency: audit.latency_ms,
+    }
+  }
+}
@@ -29,6 +29,13 @@ pub enum ContextCommand {
     /// Path to context file
     path: String,
   },
+  /// Set the api token of a context, unset it when no token is given
+  Token {
+    /// Context name
+    name: String,
+    /// Api token
+    token: Option<String>,
+  },
 }
 
 /// A context endpoint definition
@@ -38,6 +45,9 @@ pub struct ContextEndpoint {
   pub host: String,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub ssl: Option<SslConfig>,
+  /// Bearer token used to authenticate to the api
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub token: Option<String>,
 }
 
 /// A context metadata definition
@@ -72,6 +82,7 @@ impl std::default::Default for Context {
             host: std::env::var("NANOCL_HOST")
               .unwrap_or("unix:///run/nanocl/nanocl.sock".into()),
             ssl: None,
+            token: None,
           },
         );
         map
@@ -14,6 +14,10 @@ mod uninstall;
 mod node;
 mod context;
 mod secret;
+mod token;
+mod role;
+mod network_policy;
+mod audit;
 mod job;
 mod generic;
 mod metric;
@@ -25,6 +29,10 @@ pub use generic::*;
 pub use process::*;
 pub use metric::*;
 pub use secret::*;
+pub use token::*;
+pub use role::*;
+pub use network_policy::*;
+pub use audit::*;
 pub use context::*;
 pub use vm::*;
 pub use vm_image::*;
@@ -57,6 +65,15 @@ pub enum Command {
   Namespace(NamespaceArg),
   /// Manage secrets
   Secret(SecretArg),
+  /// Manage api tokens
+  Token(TokenArg),
+  /// Manage roles of the api tokens
+  Role(RoleArg),
+  /// Manage network policies filtering the traffic between cargoes
+  #[clap(alias("netpol"))]
+  NetworkPolicy(NetworkPolicyArg),
+  /// Show the audit of the mutating api calls
+  Audit(AuditArg),
   /// Manage jobs
   Job(JobArg),
   /// Manage cargoes
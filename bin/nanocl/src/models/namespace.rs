@@ -2,7 +2,7 @@ use chrono::TimeZone;
 use tabled::Tabled;
 use clap::{Parser, Subcommand};
 
-use nanocld_client::stubs::namespace::NamespaceSummary;
+use nanocld_client::stubs::namespace::{NamespaceQuota, NamespaceSummary};
 
 use super::{GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
 
@@ -19,6 +19,73 @@ pub enum NamespaceCommand {
   /// List existing namespaces
   #[clap(alias("ls"))]
   List(GenericListOpts),
+  /// Manage the quota of a namespace
+  Quota(NamespaceQuotaArg),
+}
+
+/// `nanocl namespace quota` available commands
+#[derive(Clone, Subcommand)]
+pub enum NamespaceQuotaCommand {
+  /// Create or replace the quota of a namespace
+  Set(NamespaceQuotaSetOpts),
+  /// Inspect the quota of a namespace with his usage
+  Inspect(GenericInspectOpts),
+  /// Remove the quota of a namespace
+  #[clap(alias("rm"))]
+  Remove(NamespaceQuotaRemoveOpts),
+}
+
+/// `nanocl namespace quota` available arguments
+#[derive(Clone, Parser)]
+pub struct NamespaceQuotaArg {
+  #[clap(subcommand)]
+  pub command: NamespaceQuotaCommand,
+}
+
+/// `nanocl namespace quota set` available options
+#[derive(Clone, Parser)]
+pub struct NamespaceQuotaSetOpts {
+  /// Maximum number of cargoes
+  #[clap(long)]
+  pub max_cargoes: Option<usize>,
+  /// Maximum number of replicas of the cargoes
+  #[clap(long)]
+  pub max_replicas: Option<usize>,
+  /// Maximum memory in bytes reserved by the containers
+  #[clap(long)]
+  pub max_memory: Option<i64>,
+  /// Maximum cpu in units of 10^-9 CPUs reserved by the containers
+  #[clap(long)]
+  pub max_nano_cpus: Option<i64>,
+  /// Maximum number of virtual machines
+  #[clap(long)]
+  pub max_vms: Option<usize>,
+  /// Maximum disk size in GB of the virtual machines
+  #[clap(long)]
+  pub max_vm_disk: Option<u64>,
+  /// Name of the namespace
+  pub name: String,
+}
+
+/// Convert the options to a quota
+impl From<&NamespaceQuotaSetOpts> for NamespaceQuota {
+  fn from(opts: &NamespaceQuotaSetOpts) -> Self {
+    Self {
+      max_cargoes: opts.max_cargoes,
+      max_replicas: opts.max_replicas,
+      max_memory: opts.max_memory,
+      max_nano_cpus: opts.max_nano_cpus,
+      max_vms: opts.max_vms,
+      max_vm_disk: opts.max_vm_disk,
+    }
+  }
+}
+
+/// `nanocl namespace quota rm` available options
+#[derive(Clone, Parser)]
+pub struct NamespaceQuotaRemoveOpts {
+  /// Name of the namespace
+  pub name: String,
 }
 
 /// `nanocl namespace delete` available options
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use tabled::Tabled;
+use chrono::TimeZone;
+use clap::{Parser, Subcommand};
+
+use nanocld_client::stubs::network_policy::{
+  NetworkPolicy, NetworkPolicyIngress, NetworkPolicyPort,
+  NetworkPolicyProtocol, NetworkPolicySelector,
+};
+
+use super::{DisplayFormat, GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
+
+/// `nanocl network-policy` available commands
+#[derive(Clone, Subcommand)]
+pub enum NetworkPolicyCommand {
+  /// Remove existing network policies
+  #[clap(alias("rm"))]
+  Remove(GenericRemoveOpts),
+  /// List existing network policies
+  #[clap(alias("ls"))]
+  List(GenericListOpts),
+  /// Inspect a network policy
+  Inspect(GenericInspectOpts),
+  /// Create a new network policy
+  Create(NetworkPolicyCreateOpts),
+}
+
+/// `nanocl network-policy` available arguments
+#[derive(Clone, Parser)]
+pub struct NetworkPolicyArg {
+  /// namespace to target by default global is used
+  #[clap(long, short)]
+  pub namespace: Option<String>,
+  /// Network policy command
+  #[clap(subcommand)]
+  pub command: NetworkPolicyCommand,
+}
+
+/// Parse a label in the format `key=value`
+fn parse_label(value: &str) -> Result<(String, String), String> {
+  let (key, value) = value
+    .split_once('=')
+    .ok_or_else(|| format!("invalid label {value}, expected key=value"))?;
+  Ok((key.trim().to_owned(), value.trim().to_owned()))
+}
+
+/// Parse a port in the format `5432` or `53/udp`
+fn parse_port(value: &str) -> Result<NetworkPolicyPort, String> {
+  let (port, protocol) = match value.split_once('/') {
+    None => (value, NetworkPolicyProtocol::Tcp),
+    Some((port, "tcp")) => (port, NetworkPolicyProtocol::Tcp),
+    Some((port, "udp")) => (port, NetworkPolicyProtocol::Udp),
+    Some((_, protocol)) => return Err(format!("unknown protocol {protocol}")),
+  };
+  let port = port
+    .trim()
+    .parse()
+    .map_err(|err| format!("invalid port {port}: {err}"))?;
+  Ok(NetworkPolicyPort { port, protocol })
+}
+
+/// Parse an ingress in the format `cargoes=api,web;labels=role=backend;ports=5432,53/udp`
+/// every part is optional, without cargoes and labels every cargo is allowed
+/// and without ports every port is allowed.
+fn parse_ingress(value: &str) -> Result<NetworkPolicyIngress, String> {
+  let mut from = NetworkPolicySelector::default();
+  let mut ingress = NetworkPolicyIngress::default();
+  for part in value.split(';').filter(|part| !part.is_empty()) {
+    let (key, values) = part.split_once('=').ok_or_else(|| {
+      format!("invalid ingress part {part}, expected key=values")
+    })?;
+    let values = values
+      .split(',')
+      .map(|value| value.trim())
+      .filter(|value| !value.is_empty());
+    match key.trim() {
+      "cargoes" => from.cargoes = Some(values.map(ToOwned::to_owned).collect()),
+      "labels" => {
+        from.labels = Some(
+          values
+            .map(parse_label)
+            .collect::<Result<HashMap<_, _>, _>>()?,
+        )
+      }
+      "ports" => {
+        ingress.ports =
+          Some(values.map(parse_port).collect::<Result<Vec<_>, _>>()?)
+      }
+      key => return Err(format!("unknown ingress key {key}")),
+    }
+  }
+  if from != NetworkPolicySelector::default() {
+    ingress.from = vec![from];
+  }
+  Ok(ingress)
+}
+
+/// `nanocl network-policy create` available options
+#[derive(Clone, Parser)]
+pub struct NetworkPolicyCreateOpts {
+  /// Display format
+  #[clap(long)]
+  pub display: Option<DisplayFormat>,
+  /// Name of a cargo isolated by the policy
+  #[clap(long = "target-cargo")]
+  pub target_cargoes: Vec<String>,
+  /// Label of the cargoes isolated by the policy, ex: `tier=db`
+  #[clap(long = "target-label", value_parser = parse_label)]
+  pub target_labels: Vec<(String, String)>,
+  /// Ingress allowed to the target cargoes, ex: `cargoes=api;ports=5432`
+  #[clap(long = "ingress", value_parser = parse_ingress)]
+  pub ingress: Vec<NetworkPolicyIngress>,
+  /// Name of the network policy
+  pub name: String,
+}
+
+impl NetworkPolicyCreateOpts {
+  /// Selector of the cargoes isolated by the policy
+  pub fn target(&self) -> NetworkPolicySelector {
+    NetworkPolicySelector {
+      cargoes: (!self.target_cargoes.is_empty())
+        .then(|| self.target_cargoes.clone()),
+      labels: (!self.target_labels.is_empty())
+        .then(|| self.target_labels.iter().cloned().collect()),
+    }
+  }
+}
+
+/// A row of the network policy table
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct NetworkPolicyRow {
+  /// The name of the policy
+  pub name: String,
+  /// The namespace of the policy
+  pub namespace: String,
+  /// Number of ingress rules of the policy
+  pub ingress: usize,
+  /// When the policy have been created
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+}
+
+impl From<NetworkPolicy> for NetworkPolicyRow {
+  fn from(policy: NetworkPolicy) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    // Convert the created_at to the current timezone
+    let created_at = tz
+      .timestamp_opt(policy.created_at.and_utc().timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    Self {
+      name: policy.name,
+      namespace: policy.namespace_name,
+      ingress: policy.ingress.len(),
+      created_at: created_at.to_string(),
+    }
+  }
+}
@@ -25,6 +25,8 @@ pub enum NodeCommand {
 pub struct NodeRow {
   /// Name of the node
   pub name: String,
+  /// State of the node (Ready, NotReady)
+  pub state: String,
   /// IP address of the node
   pub ip_address: String,
   /// Endpoint of the node
@@ -41,6 +43,7 @@ impl From<Node> for NodeRow {
     let created_at = node.created_at.format("%Y-%m-%d %H:%M:%S").to_string();
     Self {
       name: node.name,
+      state: node.state.to_string(),
       ip_address: node.ip_address.to_string(),
       endpoint: node.endpoint,
       version: node.version,
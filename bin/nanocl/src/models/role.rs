@@ -0,0 +1,110 @@
+use tabled::Tabled;
+use chrono::TimeZone;
+use clap::{Parser, Subcommand};
+
+use nanocld_client::stubs::role::{Role, RoleRule};
+
+use super::{DisplayFormat, GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
+
+/// `nanocl role` available commands
+#[derive(Clone, Subcommand)]
+pub enum RoleCommand {
+  /// Remove existing roles
+  #[clap(alias("rm"))]
+  Remove(GenericRemoveOpts),
+  /// List existing roles
+  #[clap(alias("ls"))]
+  List(GenericListOpts),
+  /// Inspect a role
+  Inspect(GenericInspectOpts),
+  /// Create a new role
+  Create(RoleCreateOpts),
+}
+
+/// `nanocl role` available arguments
+#[derive(Clone, Parser)]
+pub struct RoleArg {
+  /// Role command
+  #[clap(subcommand)]
+  pub command: RoleCommand,
+}
+
+/// Parse a rule in the format `kinds=cargo,vm;verbs=list,logs;namespaces=dev`
+/// the namespaces are optional and default to all namespaces
+fn parse_rule(value: &str) -> Result<RoleRule, String> {
+  let mut rule = RoleRule::default();
+  for part in value.split(';').filter(|part| !part.is_empty()) {
+    let (key, values) = part.split_once('=').ok_or_else(|| {
+      format!("invalid rule part {part}, expected key=values")
+    })?;
+    let values = values
+      .split(',')
+      .map(|value| value.trim().to_owned())
+      .filter(|value| !value.is_empty())
+      .collect::<Vec<_>>();
+    match key.trim() {
+      "kinds" => rule.kinds = values,
+      "verbs" => {
+        rule.verbs = values
+          .iter()
+          .map(|verb| verb.parse().map_err(|err| format!("{err}")))
+          .collect::<Result<_, _>>()?
+      }
+      "namespaces" => rule.namespaces = Some(values),
+      key => return Err(format!("unknown rule key {key}")),
+    }
+  }
+  if rule.kinds.is_empty() || rule.verbs.is_empty() {
+    return Err("a rule require at least one kind and one verb".to_owned());
+  }
+  Ok(rule)
+}
+
+/// `nanocl role create` available options
+#[derive(Clone, Parser)]
+pub struct RoleCreateOpts {
+  /// Display format
+  #[clap(long)]
+  pub display: Option<DisplayFormat>,
+  /// Rule granted by the role, ex: `kinds=cargo,vm;verbs=list,logs;namespaces=dev`
+  #[clap(long = "rule", required = true, value_parser = parse_rule)]
+  pub rules: Vec<RoleRule>,
+  /// Identity bound to the role, ex: `token:ci`
+  #[clap(long = "subject")]
+  pub subjects: Vec<String>,
+  /// Name of the role
+  pub name: String,
+}
+
+/// A row of the role table
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct RoleRow {
+  /// The name of the role
+  pub name: String,
+  /// Number of rules of the role
+  pub rules: usize,
+  /// Identities bound to the role
+  pub subjects: String,
+  /// When the role have been created
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+}
+
+impl From<Role> for RoleRow {
+  fn from(role: Role) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    // Convert the created_at to the current timezone
+    let created_at = tz
+      .timestamp_opt(role.created_at.and_utc().timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    Self {
+      name: role.name,
+      rules: role.rules.len(),
+      subjects: role.subjects.join(", "),
+      created_at: created_at.to_string(),
+    }
+  }
+}
@@ -4,9 +4,9 @@ use chrono::TimeZone;
 use clap::{Parser, Subcommand};
 
 use nanocl_error::io::IoError;
-use nanocld_client::stubs::secret::{Secret, SecretPartial};
+use nanocld_client::stubs::secret::{SecretPartial, SecretSummary};
 
-use super::{GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
+use super::{DisplayFormat, GenericListOpts, GenericRemoveOpts};
 
 /// `nanocl resource` available commands
 #[derive(Clone, Subcommand)]
@@ -17,10 +17,25 @@ pub enum SecretCommand {
   /// List existing secret
   #[clap(alias("ls"))]
   List(GenericListOpts),
-  /// Inspect a secret
-  Inspect(GenericInspectOpts),
+  /// Inspect a secret, his values are masked unless `--reveal` is set
+  Inspect(SecretInspectOpts),
   /// Create a new secret
   Create(SecretCreateOpts),
+  /// Generate a new master key and encrypt every secret with it
+  RotateKey,
+}
+
+/// `nanocl secret inspect` available options
+#[derive(Clone, Parser)]
+pub struct SecretInspectOpts {
+  /// Display format
+  #[clap(long)]
+  pub display: Option<DisplayFormat>,
+  /// Show the values of the secret
+  #[clap(long)]
+  pub reveal: bool,
+  /// Name of the secret to inspect
+  pub key: String,
 }
 
 /// `nanocl secret` available arguments
@@ -131,8 +146,8 @@ pub struct SecretRow {
   pub updated_at: String,
 }
 
-impl From<Secret> for SecretRow {
-  fn from(secret: Secret) -> Self {
+impl From<SecretSummary> for SecretRow {
+  fn from(secret: SecretSummary) -> Self {
     // Get the current timezone
     let binding = chrono::Local::now();
     let tz = binding.offset();
@@ -0,0 +1,84 @@
+use tabled::Tabled;
+use chrono::TimeZone;
+use clap::{Parser, Subcommand};
+
+use nanocld_client::stubs::token::Token;
+
+use super::{DisplayFormat, GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
+
+/// `nanocl token` available commands
+#[derive(Clone, Subcommand)]
+pub enum TokenCommand {
+  /// Remove existing api tokens
+  #[clap(alias("rm"))]
+  Remove(GenericRemoveOpts),
+  /// List existing api tokens
+  #[clap(alias("ls"))]
+  List(GenericListOpts),
+  /// Inspect an api token
+  Inspect(GenericInspectOpts),
+  /// Create a new api token and print his secret
+  Create(TokenCreateOpts),
+}
+
+/// `nanocl token` available arguments
+#[derive(Clone, Parser)]
+pub struct TokenArg {
+  /// Token command
+  #[clap(subcommand)]
+  pub command: TokenCommand,
+}
+
+/// `nanocl token create` available options
+#[derive(Clone, Parser)]
+pub struct TokenCreateOpts {
+  /// Display format
+  #[clap(long)]
+  pub display: Option<DisplayFormat>,
+  /// Number of days before the token expires, it never expires if not set
+  #[clap(long)]
+  pub expires_in: Option<u32>,
+  /// Name of the token
+  pub name: String,
+}
+
+/// Format an optional date in the current timezone
+fn format_date(date: Option<chrono::NaiveDateTime>) -> String {
+  let Some(date) = date else {
+    return "-".to_owned();
+  };
+  let binding = chrono::Local::now();
+  let tz = binding.offset();
+  tz.timestamp_opt(date.and_utc().timestamp(), 0)
+    .unwrap()
+    .format("%Y-%m-%d %H:%M:%S")
+    .to_string()
+}
+
+/// A row of the token table
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct TokenRow {
+  /// The name of the token
+  pub name: String,
+  /// When the token have been created
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+  /// When the token expires
+  #[tabled(rename = "EXPIRES AT")]
+  pub expires_at: String,
+  /// When the token have been used for the last time
+  #[tabled(rename = "LAST USED AT")]
+  pub last_used_at: String,
+}
+
+impl From<Token> for TokenRow {
+  fn from(token: Token) -> Self {
+    Self {
+      name: token.name,
+      created_at: format_date(Some(token.created_at)),
+      expires_at: format_date(token.expires_at),
+      last_used_at: format_date(token.last_used_at),
+    }
+  }
+}
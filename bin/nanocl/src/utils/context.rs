@@ -1,3 +1,5 @@
+use std::os::unix::fs::PermissionsExt;
+
 use nanocl_error::io::IoResult;
 
 use crate::config::UserConfig;
@@ -54,10 +56,34 @@ impl Context {
         format!("Could not serialize context {}: {err}", context.name),
       )
     })?;
-    std::fs::write(path, s)?;
+    std::fs::write(&path, s)?;
+    // The endpoints can contain an api token
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
     Ok(())
   }
 
+  /// Set or unset the api token of the nanocl endpoint of a context
+  pub fn set_token(name: &str, token: Option<String>) -> IoResult<()> {
+    if name == "default" {
+      return Err(
+        std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          "Cannot update the default context use NANOCL_TOKEN instead",
+        )
+        .into(),
+      );
+    }
+    let mut context = Context::read_by_name(name)?;
+    let endpoint = context.endpoints.get_mut("Nanocl").ok_or_else(|| {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Context {name} has no Nanocl endpoint"),
+      )
+    })?;
+    endpoint.token = token;
+    Context::write(&context)
+  }
+
   /// List all contexts
   pub fn list() -> IoResult<Vec<ContextRow>> {
     let home = std::env::var("HOME").map_err(|_| {
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "jobs_last_run_at_idx";
+DROP INDEX IF EXISTS "jobs_next_run_at_idx";
+ALTER TABLE "jobs" DROP COLUMN IF EXISTS "last_run_at";
+ALTER TABLE "jobs" DROP COLUMN IF EXISTS "next_run_at";
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE "jobs" ADD COLUMN IF NOT EXISTS "last_run_at" TIMESTAMPTZ;
+ALTER TABLE "jobs" ADD COLUMN IF NOT EXISTS "next_run_at" TIMESTAMPTZ;
+
+CREATE INDEX "jobs_last_run_at_idx" ON "jobs" ("last_run_at");
+CREATE INDEX "jobs_next_run_at_idx" ON "jobs" ("next_run_at");
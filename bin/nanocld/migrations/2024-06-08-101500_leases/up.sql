@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "leases" (
+  "key" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "node_name" VARCHAR NOT NULL REFERENCES nodes("name"),
+  "action" VARCHAR NOT NULL,
+  "expires_at" TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX "leases_key_idx" ON "leases" ("key");
+CREATE INDEX "leases_node_name_idx" ON "leases" ("node_name");
+CREATE INDEX "leases_expires_at_idx" ON "leases" ("expires_at");
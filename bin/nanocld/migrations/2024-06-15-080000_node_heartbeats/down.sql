@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS "nodes_heartbeat_at_idx";
+DROP INDEX IF EXISTS "nodes_state_idx";
+ALTER TABLE "nodes" DROP COLUMN IF EXISTS "capacity";
+ALTER TABLE "nodes" DROP COLUMN IF EXISTS "heartbeat_at";
+ALTER TABLE "nodes" DROP COLUMN IF EXISTS "state";
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE "nodes" ADD COLUMN IF NOT EXISTS "state" VARCHAR NOT NULL DEFAULT 'Ready';
+ALTER TABLE "nodes" ADD COLUMN IF NOT EXISTS "heartbeat_at" TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE "nodes" ADD COLUMN IF NOT EXISTS "capacity" JSONB;
+
+CREATE INDEX "nodes_state_idx" ON "nodes" ("state");
+CREATE INDEX "nodes_heartbeat_at_idx" ON "nodes" ("heartbeat_at");
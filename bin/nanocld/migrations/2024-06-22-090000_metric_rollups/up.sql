@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "metric_rollups" (
+  "key" UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "expires_at" TIMESTAMPTZ NOT NULL,
+  "bucket_at" TIMESTAMPTZ NOT NULL,
+  "node_name" VARCHAR NOT NULL,
+  "kind" VARCHAR NOT NULL,
+  "field" VARCHAR NOT NULL,
+  "count" BIGINT NOT NULL,
+  "sum" DOUBLE PRECISION NOT NULL,
+  "min" DOUBLE PRECISION NOT NULL,
+  "max" DOUBLE PRECISION NOT NULL,
+  "histogram" JSONB NOT NULL
+) WITH (ttl_expiration_expression = 'expires_at');
+
+CREATE UNIQUE INDEX "metric_rollups_bucket_idx" ON "metric_rollups" ("node_name", "kind", "field", "bucket_at");
+CREATE INDEX "metric_rollups_kind_field_idx" ON "metric_rollups" ("kind", "field", "bucket_at");
+CREATE INDEX "metric_rollups_expires_at_idx" ON "metric_rollups" ("expires_at");
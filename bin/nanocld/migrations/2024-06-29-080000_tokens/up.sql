@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "tokens" (
+  "key" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "expires_at" TIMESTAMPTZ,
+  "last_used_at" TIMESTAMPTZ,
+  "hash" VARCHAR NOT NULL UNIQUE,
+  "metadata" JSONB
+);
+
+CREATE INDEX "tokens_hash_idx" ON "tokens" ("hash");
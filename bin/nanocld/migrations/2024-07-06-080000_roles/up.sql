@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "roles" (
+  "key" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "rules" JSONB NOT NULL,
+  "subjects" JSONB NOT NULL,
+  "metadata" JSONB
+);
+
+CREATE INDEX "roles_subjects_idx" ON "roles" USING GIN ("subjects");
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "audits" (
+  "key" UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "expires_at" TIMESTAMPTZ NOT NULL DEFAULT NOW() + INTERVAL '3 month',
+  "node" VARCHAR NOT NULL,
+  "method" VARCHAR NOT NULL,
+  "path" VARCHAR NOT NULL,
+  "identity" VARCHAR,
+  "namespace" VARCHAR,
+  "body_hash" VARCHAR,
+  "status_code" INTEGER NOT NULL,
+  "latency_ms" BIGINT NOT NULL
+) WITH (ttl_expiration_expression = 'expires_at');
+
+CREATE INDEX "audits_created_at_idx" ON "audits" ("created_at");
+CREATE INDEX "audits_expires_at_idx" ON "audits" ("expires_at");
+CREATE INDEX "audits_node_idx" ON "audits" ("node");
+CREATE INDEX "audits_method_idx" ON "audits" ("method");
+CREATE INDEX "audits_identity_idx" ON "audits" ("identity");
+CREATE INDEX "audits_namespace_idx" ON "audits" ("namespace");
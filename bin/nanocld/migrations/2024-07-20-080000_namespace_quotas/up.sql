@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "namespace_quotas" (
+  "namespace_name" VARCHAR NOT NULL PRIMARY KEY REFERENCES namespaces("name"),
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "data" JSONB NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "network_policies" (
+  "key" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "name" VARCHAR NOT NULL,
+  "namespace_name" VARCHAR NOT NULL REFERENCES namespaces("name"),
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "data" JSONB NOT NULL,
+  "metadata" JSONB
+);
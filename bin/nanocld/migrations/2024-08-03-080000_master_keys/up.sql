@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "master_keys" (
+  "key" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "wrap_key_id" VARCHAR NOT NULL,
+  "data" VARCHAR NOT NULL
+);
+
+CREATE INDEX "master_keys_key_idx" ON "master_keys" ("key");
+CREATE INDEX "master_keys_created_at_idx" ON "master_keys" ("created_at");
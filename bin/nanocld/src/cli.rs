@@ -42,6 +42,14 @@ pub struct Cli {
   /// Optional ssl options
   #[clap(flatten)]
   pub ssl: Option<SslConfig>,
+  /// Path to the key wrapping the master keys of the secrets
+  /// It must be the same on every node of the cluster
+  /// [default: {state_dir}/master.key]
+  #[clap(long)]
+  pub master_key_path: Option<String>,
+  /// Require a bearer token for the requests received over tcp
+  #[clap(long)]
+  pub auth: bool,
 }
 
 impl Default for Cli {
@@ -58,6 +66,8 @@ impl Default for Cli {
       advertise_addr: None,
       gid: 0,
       ssl: None,
+      master_key_path: None,
+      auth: false,
     }
   }
 }
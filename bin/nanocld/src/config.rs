@@ -58,6 +58,11 @@ fn gen_daemon_conf(
   } else {
     config.store_addr.clone()
   };
+  let master_key_path = if let Some(ref path) = args.master_key_path {
+    Some(path.to_owned())
+  } else {
+    config.master_key_path.clone()
+  };
   Ok(DaemonConfig {
     hosts,
     gateway,
@@ -70,6 +75,9 @@ fn gen_daemon_conf(
     nodes: args.nodes.clone(),
     conf_dir: args.conf_dir.clone(),
     ssl: args.ssl.clone(),
+    master_key_path,
+    retention: config.retention.clone().unwrap_or_default(),
+    auth: args.auth || config.auth.unwrap_or_default(),
   })
 }
 
@@ -124,6 +132,9 @@ mod tests {
       store_addr: None,
       gateway: None,
       hostname: None,
+      master_key_path: None,
+      retention: None,
+      auth: None,
     };
     let merged = gen_daemon_conf(&args, &config).unwrap();
     assert_eq!(merged.hosts, args.hosts.unwrap());
@@ -0,0 +1,59 @@
+use diesel::prelude::*;
+
+use nanocl_error::io::IoError;
+use nanocl_stubs::audit::Audit;
+
+use crate::schema::audits;
+
+/// This structure represent an audit entry in the database
+#[derive(Debug, Clone, Queryable, Identifiable, Insertable)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = audits)]
+pub struct AuditDb {
+  /// Unique identifier of the entry
+  pub key: uuid::Uuid,
+  /// When the request have been received
+  pub created_at: chrono::NaiveDateTime,
+  /// When the entry expires
+  pub expires_at: chrono::NaiveDateTime,
+  /// Name of the node that served the request
+  pub node: String,
+  /// Http method of the request
+  pub method: String,
+  /// Path of the request with his query string
+  pub path: String,
+  /// Identity of the client if authenticated
+  pub identity: Option<String>,
+  /// Namespace targeted by the request if any
+  pub namespace: Option<String>,
+  /// Sha256 of the body of the request
+  pub body_hash: Option<String>,
+  /// Http status code of the response
+  pub status_code: i32,
+  /// Milliseconds spent to answer the request
+  pub latency_ms: i64,
+}
+
+impl TryFrom<AuditDb> for Audit {
+  type Error = IoError;
+
+  fn try_from(value: AuditDb) -> Result<Self, Self::Error> {
+    Ok(Audit {
+      key: value.key,
+      created_at: value.created_at,
+      expires_at: value.expires_at,
+      node: value.node,
+      method: value.method,
+      path: value.path,
+      identity: value.identity,
+      namespace: value.namespace,
+      body_hash: value.body_hash,
+      status_code: u16::try_from(value.status_code).map_err(|err| {
+        IoError::invalid_data("Audit status code", err.to_string().as_str())
+      })?,
+      latency_ms: u64::try_from(value.latency_ms).map_err(|err| {
+        IoError::invalid_data("Audit latency", err.to_string().as_str())
+      })?,
+    })
+  }
+}
@@ -0,0 +1,39 @@
+use std::{sync::Arc, collections::HashMap};
+
+use futures_util::lock::Mutex;
+
+/// A parsed cron expression
+/// Every field is stored as a bit mask of the allowed values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+  /// Allowed minutes (0-59)
+  pub minutes: u64,
+  /// Allowed hours (0-23)
+  pub hours: u64,
+  /// Allowed days of the month (1-31)
+  pub days: u64,
+  /// Allowed months (1-12)
+  pub months: u64,
+  /// Allowed days of the week (0-6 starting on sunday)
+  pub weekdays: u64,
+  /// Whether the day of the month field was restricted
+  pub days_restricted: bool,
+  /// Whether the day of the week field was restricted
+  pub weekdays_restricted: bool,
+}
+
+/// A job waiting for its next scheduled run
+#[derive(Clone)]
+pub struct CronTask {
+  /// The parsed schedule of the job
+  pub schedule: CronSchedule,
+  /// Next time the job have to be started
+  pub next_run: chrono::DateTime<chrono::Utc>,
+}
+
+/// In memory scheduler of the jobs with a `schedule`
+/// It's rebuilt from the `jobs` table when the daemon boot
+#[derive(Clone, Default)]
+pub struct CronManager {
+  pub tasks: Arc<Mutex<HashMap<String, CronTask>>>,
+}
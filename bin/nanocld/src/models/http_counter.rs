@@ -0,0 +1,51 @@
+use std::{
+  sync::{Arc, Mutex},
+  collections::BTreeMap,
+};
+
+/// Totals of the requests proxied for a host with a status
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpCounter {
+  /// Number of requests
+  pub requests: u64,
+  /// Sum of the request times in seconds
+  pub request_time: f64,
+  /// Sum of the bytes sent to the clients
+  pub bytes_sent: u64,
+}
+
+/// Counters of the `ncproxy.io/http` metrics received by the node
+/// since it started indexed by host and status
+#[derive(Clone, Default)]
+pub struct HttpCounters {
+  pub counters: Arc<Mutex<BTreeMap<(String, i64), HttpCounter>>>,
+}
+
+impl HttpCounters {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Count the request described by the data of a `ncproxy.io/http` metric
+  pub fn record(&self, data: &serde_json::Value) {
+    let host = data["Host"].as_str().unwrap_or_default().to_owned();
+    let status = data["Status"].as_i64().unwrap_or_default();
+    let mut counters = self
+      .counters
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let counter = counters.entry((host, status)).or_default();
+    counter.requests += 1;
+    counter.request_time += data["RequestTime"].as_f64().unwrap_or_default();
+    counter.bytes_sent += data["BytesSent"].as_u64().unwrap_or_default();
+  }
+
+  /// Copy of the current counters
+  pub fn snapshot(&self) -> BTreeMap<(String, i64), HttpCounter> {
+    self
+      .counters
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .clone()
+  }
+}
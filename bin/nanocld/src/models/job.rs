@@ -20,12 +20,18 @@ pub struct JobDb {
   pub data: serde_json::Value,
   /// The metadata
   pub metadata: Option<serde_json::Value>,
+  /// Last time the job was started by the scheduler
+  pub last_run_at: Option<chrono::NaiveDateTime>,
+  /// Next time the job will be started by the scheduler
+  pub next_run_at: Option<chrono::NaiveDateTime>,
 }
 
 /// This structure represent the update of a job.
 /// It will update the job with the new data.
-#[derive(Clone, AsChangeset)]
+#[derive(Clone, Default, AsChangeset)]
 #[diesel(table_name = jobs)]
 pub struct JobUpdateDb {
   pub updated_at: Option<chrono::NaiveDateTime>,
+  pub last_run_at: Option<chrono::NaiveDateTime>,
+  pub next_run_at: Option<chrono::NaiveDateTime>,
 }
@@ -0,0 +1,29 @@
+use diesel::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use crate::schema::leases;
+
+/// This structure represent a lease in the database.
+/// A lease give to a node the ownership of a key until it expires,
+/// the node must renew it to keep the ownership.
+/// It's used to elect a leader and to ensure a single node run the task of an object.
+#[derive(
+  Debug, Clone, Queryable, Identifiable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = leases)]
+#[serde(rename_all = "PascalCase")]
+pub struct LeaseDb {
+  /// The key of the lease (eg: `nanocl.io/leader` or `Cargo@my-cargo.global`)
+  pub key: String,
+  /// The created at date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last time the lease was acquired or renewed
+  pub updated_at: chrono::NaiveDateTime,
+  /// The node holding the lease
+  pub node_name: String,
+  /// The action the node is doing while holding the lease
+  pub action: String,
+  /// When the lease expires if not renewed
+  pub expires_at: chrono::NaiveDateTime,
+}
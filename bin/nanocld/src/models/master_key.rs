@@ -0,0 +1,40 @@
+use std::sync::{RwLock, atomic::AtomicBool};
+
+use diesel::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+  schema::master_keys,
+  utils::crypto::{KeyRing, MasterKey},
+};
+
+/// This structure represent a master key in the database.
+/// The master keys encrypt the data keys of the secrets,
+/// they are stored wrapped with the wrap key shared by every node.
+#[derive(
+  Debug, Clone, Queryable, Identifiable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = master_keys)]
+#[serde(rename_all = "PascalCase")]
+pub struct MasterKeyDb {
+  /// The id of the master key
+  pub key: String,
+  /// The created at date, the newest key is used to encrypt
+  pub created_at: chrono::NaiveDateTime,
+  /// The id of the wrap key used to encrypt the master key
+  pub wrap_key_id: String,
+  /// The master key encrypted with the wrap key
+  pub data: String,
+}
+
+/// Keys used by the node to encrypt and decrypt the secrets
+#[derive(Default)]
+pub struct SecretKeys {
+  /// Key shared by every node wrapping the master keys
+  pub wrap_key: Option<MasterKey>,
+  /// Master keys unwrapped from the store
+  pub key_ring: RwLock<Option<KeyRing>>,
+  /// Whether a rotation of the master key is in progress on this node
+  pub rotating: AtomicBool,
+}
@@ -6,7 +6,10 @@ use nanocl_error::io::IoResult;
 
 use nanocl_stubs::metric::MetricPartial;
 
-use crate::{utils, schema::metrics};
+use crate::{
+  utils,
+  schema::{metric_rollups, metrics},
+};
 
 /// This structure represent a metric in the database.
 /// A metric is a data point that can be used to monitor the system.
@@ -35,6 +38,42 @@ pub struct MetricDb {
   pub note: Option<String>,
 }
 
+/// This structure represent the values of a field of the metrics of a kind
+/// saved by a node during a bucket of time.
+/// They are merged to aggregate the metrics over larger time ranges.
+#[derive(
+  Clone, Debug, Insertable, Identifiable, Queryable, Serialize, Deserialize,
+)]
+#[serde(rename_all = "PascalCase")]
+#[diesel(primary_key(key))]
+#[diesel(table_name = metric_rollups)]
+pub struct MetricRollupDb {
+  /// The key of the rollup in the database `UUID`
+  pub key: Uuid,
+  /// When the rollup was created
+  pub created_at: chrono::NaiveDateTime,
+  /// When the rollup will expire
+  pub expires_at: chrono::NaiveDateTime,
+  /// Start of the bucket
+  pub bucket_at: chrono::NaiveDateTime,
+  /// The node who saved the metrics
+  pub node_name: String,
+  /// The kind of the metrics
+  pub kind: String,
+  /// The field of the data
+  pub field: String,
+  /// Number of values
+  pub count: i64,
+  /// Sum of the values
+  pub sum: f64,
+  /// Minimum value
+  pub min: f64,
+  /// Maximum value
+  pub max: f64,
+  /// Number of values by bin of a log scale used to compute the percentiles
+  pub histogram: serde_json::Value,
+}
+
 /// This structure is used to insert a metric in the database.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MetricNodePartial {
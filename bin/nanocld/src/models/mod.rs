@@ -17,6 +17,12 @@ pub use metric::*;
 mod namespace;
 pub use namespace::*;
 
+mod namespace_quota;
+pub use namespace_quota::*;
+
+mod network_policy;
+pub use network_policy::*;
+
 mod cargo;
 pub use cargo::*;
 
@@ -35,6 +41,15 @@ pub use resource_kind::*;
 mod secret;
 pub use secret::*;
 
+mod master_key;
+pub use master_key::*;
+
+mod token;
+pub use token::*;
+
+mod role;
+pub use role::*;
+
 mod job;
 pub use job::*;
 
@@ -47,15 +62,27 @@ pub use process::*;
 mod event;
 pub use event::*;
 
+mod audit;
+pub use audit::*;
+
 mod raw_emitter;
 pub use raw_emitter::*;
 
 mod task_manager;
 pub use task_manager::*;
 
+mod cron;
+pub use cron::*;
+
 mod object_process_status;
 pub use object_process_status::*;
 
+mod lease;
+pub use lease::*;
+
+mod http_counter;
+pub use http_counter::*;
+
 pub type Pool = R2D2Pool<ConnectionManager<PgConnection>>;
 pub type DBConn = PooledConnection<ConnectionManager<PgConnection>>;
 
@@ -0,0 +1,62 @@
+use diesel::prelude::*;
+
+use nanocl_error::io::IoError;
+use nanocl_stubs::namespace::NamespaceQuota;
+
+use crate::schema::namespace_quotas;
+
+/// This structure represent the quota of a namespace in the database.
+/// The limits are stored as json so new ones can be added without migration.
+#[derive(Debug, Clone, Queryable, Identifiable, Insertable)]
+#[diesel(primary_key(namespace_name))]
+#[diesel(table_name = namespace_quotas)]
+pub struct NamespaceQuotaDb {
+  /// The name of the namespace
+  pub namespace_name: String,
+  /// When the quota was created
+  pub created_at: chrono::NaiveDateTime,
+  /// When the quota was updated
+  pub updated_at: chrono::NaiveDateTime,
+  /// The limits of the namespace
+  pub data: serde_json::Value,
+}
+
+impl NamespaceQuotaDb {
+  /// Create a new quota for the given namespace
+  pub fn try_new(
+    namespace: &str,
+    quota: &NamespaceQuota,
+  ) -> Result<Self, IoError> {
+    let now = chrono::Utc::now().naive_utc();
+    Ok(Self {
+      namespace_name: namespace.to_owned(),
+      created_at: now,
+      updated_at: now,
+      data: serde_json::to_value(quota)?,
+    })
+  }
+
+  /// Convert the stored limits of the quota
+  pub fn try_to_quota(&self) -> Result<NamespaceQuota, IoError> {
+    Ok(serde_json::from_value(self.data.clone())?)
+  }
+}
+
+/// This structure is used to replace the limits of a quota
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = namespace_quotas)]
+pub struct NamespaceQuotaUpdateDb {
+  /// When the quota was updated
+  pub updated_at: chrono::NaiveDateTime,
+  /// The limits of the namespace
+  pub data: serde_json::Value,
+}
+
+impl From<NamespaceQuotaDb> for NamespaceQuotaUpdateDb {
+  fn from(db: NamespaceQuotaDb) -> Self {
+    Self {
+      updated_at: db.updated_at,
+      data: db.data,
+    }
+  }
+}
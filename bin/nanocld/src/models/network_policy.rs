@@ -0,0 +1,93 @@
+use diesel::prelude::*;
+
+use nanocl_error::io::IoError;
+use nanocl_stubs::network_policy::{
+  NetworkPolicy, NetworkPolicyPartial, NetworkPolicySpec,
+};
+
+use crate::{utils, schema::network_policies};
+
+/// This structure represent a network policy in the database.
+/// The target and the ingress rules are stored as json in `data`.
+#[derive(Debug, Clone, Queryable, Identifiable, Insertable)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = network_policies)]
+pub struct NetworkPolicyDb {
+  /// The key of the policy generated with `name` and `namespace_name`
+  pub key: String,
+  /// The name of the policy
+  pub name: String,
+  /// The namespace of the policy
+  pub namespace_name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The target and the ingress rules
+  pub data: serde_json::Value,
+  /// The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl NetworkPolicyDb {
+  /// Create a new policy in the given namespace
+  pub fn try_new(
+    namespace: &str,
+    policy: &NetworkPolicyPartial,
+  ) -> Result<Self, IoError> {
+    let now = chrono::Utc::now().naive_utc();
+    let spec = NetworkPolicySpec {
+      target: policy.target.clone(),
+      ingress: policy.ingress.clone(),
+    };
+    Ok(Self {
+      key: utils::key::gen_key(namespace, &policy.name),
+      name: policy.name.clone(),
+      namespace_name: namespace.to_owned(),
+      created_at: now,
+      updated_at: now,
+      data: serde_json::to_value(spec)?,
+      metadata: policy.metadata.clone(),
+    })
+  }
+}
+
+impl TryFrom<NetworkPolicyDb> for NetworkPolicy {
+  type Error = IoError;
+
+  fn try_from(db: NetworkPolicyDb) -> Result<Self, Self::Error> {
+    let spec = serde_json::from_value::<NetworkPolicySpec>(db.data)?;
+    Ok(NetworkPolicy {
+      key: db.key,
+      name: db.name,
+      namespace_name: db.namespace_name,
+      created_at: db.created_at,
+      updated_at: db.updated_at,
+      target: spec.target,
+      ingress: spec.ingress,
+      metadata: db.metadata,
+    })
+  }
+}
+
+/// This structure is used to replace the rules of a network policy
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = network_policies)]
+pub struct NetworkPolicyUpdateDb {
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The target and the ingress rules
+  pub data: serde_json::Value,
+  /// The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl From<NetworkPolicyDb> for NetworkPolicyUpdateDb {
+  fn from(db: NetworkPolicyDb) -> Self {
+    Self {
+      updated_at: db.updated_at,
+      data: db.data,
+      metadata: db.metadata,
+    }
+  }
+}
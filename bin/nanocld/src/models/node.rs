@@ -1,6 +1,8 @@
 use diesel::prelude::*;
 use serde::{Serialize, Deserialize};
 
+use nanocl_stubs::node::NodeState;
+
 use crate::schema::nodes;
 
 /// This structure represent a node in the database.
@@ -25,4 +27,12 @@ pub struct NodeDb {
   /// User defined metadata
   #[serde(skip_serializing_if = "Option::is_none")]
   pub metadata: Option<serde_json::Value>,
+  /// Liveness state of the node
+  #[diesel(deserialize_as = String, serialize_as = String)]
+  pub state: NodeState,
+  /// Last time the node sent a heartbeat
+  pub heartbeat_at: chrono::NaiveDateTime,
+  /// Resources of the node sent with his last heartbeat
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub capacity: Option<serde_json::Value>,
 }
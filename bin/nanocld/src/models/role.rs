@@ -0,0 +1,87 @@
+use diesel::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use nanocl_error::io::IoError;
+use nanocl_stubs::role::{Role, RolePartial};
+
+use crate::schema::roles;
+
+/// This structure represent a role in the database.
+/// A role grant verbs on kinds of objects to the identities of his subjects,
+/// the rules and the subjects are stored as json arrays.
+#[derive(
+  Debug, Clone, Queryable, Identifiable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = roles)]
+#[serde(rename_all = "PascalCase")]
+pub struct RoleDb {
+  /// The name of the role
+  pub key: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The rules of the role
+  pub rules: serde_json::Value,
+  /// Identities bound to the role
+  pub subjects: serde_json::Value,
+  /// The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl TryFrom<&RolePartial> for RoleDb {
+  type Error = IoError;
+
+  fn try_from(role: &RolePartial) -> Result<Self, Self::Error> {
+    let now = chrono::Utc::now().naive_utc();
+    Ok(Self {
+      key: role.name.clone(),
+      created_at: now,
+      updated_at: now,
+      rules: serde_json::to_value(&role.rules)?,
+      subjects: serde_json::to_value(&role.subjects)?,
+      metadata: role.metadata.clone(),
+    })
+  }
+}
+
+impl TryFrom<RoleDb> for Role {
+  type Error = IoError;
+
+  fn try_from(db: RoleDb) -> Result<Self, Self::Error> {
+    Ok(Role {
+      name: db.key,
+      created_at: db.created_at,
+      updated_at: db.updated_at,
+      rules: serde_json::from_value(db.rules)?,
+      subjects: serde_json::from_value(db.subjects)?,
+      metadata: db.metadata,
+    })
+  }
+}
+
+/// This structure is used to replace the rules and subjects of a role
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = roles)]
+pub struct RoleUpdateDb {
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The rules of the role
+  pub rules: serde_json::Value,
+  /// Identities bound to the role
+  pub subjects: serde_json::Value,
+  /// The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl From<RoleDb> for RoleUpdateDb {
+  fn from(db: RoleDb) -> Self {
+    Self {
+      updated_at: db.updated_at,
+      rules: db.rules,
+      subjects: db.subjects,
+      metadata: db.metadata,
+    }
+  }
+}
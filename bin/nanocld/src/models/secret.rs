@@ -1,8 +1,6 @@
 use diesel::prelude::*;
 use serde::{Serialize, Deserialize};
 
-use nanocl_error::io::IoError;
-
 use nanocl_stubs::secret::{Secret, SecretPartial, SecretUpdate};
 
 use crate::schema::secrets;
@@ -48,11 +46,11 @@ impl From<&SecretPartial> for SecretDb {
   }
 }
 
-impl TryFrom<SecretDb> for Secret {
-  type Error = IoError;
-
-  fn try_from(db: SecretDb) -> Result<Self, Self::Error> {
-    Ok(Secret {
+/// The data is kept encrypted,
+/// use [decrypt_secret](crate::utils::crypto::decrypt_secret) to read it
+impl From<SecretDb> for Secret {
+  fn from(db: SecretDb) -> Self {
+    Secret {
       name: db.key,
       created_at: db.created_at,
       updated_at: db.updated_at,
@@ -60,7 +58,7 @@ impl TryFrom<SecretDb> for Secret {
       immutable: db.immutable,
       data: db.data,
       metadata: db.metadata,
-    })
+    }
   }
 }
 
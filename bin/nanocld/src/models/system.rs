@@ -1,11 +1,13 @@
-use std::sync::Arc;
+use std::sync::{Arc, atomic::AtomicBool};
 
 use ntex::rt;
-use futures::channel::mpsc;
+use futures::{channel::mpsc, lock::Mutex};
 
 use nanocl_stubs::{config::DaemonConfig, system::Event};
 
-use super::{Pool, RawEventEmitter, TaskManager};
+use super::{
+  CronManager, HttpCounters, Pool, RawEventEmitter, SecretKeys, TaskManager,
+};
 
 /// This structure represent the state of the system.
 /// Used to share the state between the different handlers.
@@ -19,6 +21,16 @@ pub struct SystemStateInner {
   pub config: DaemonConfig,
   /// Manager of the tasks
   pub task_manager: TaskManager,
+  /// Scheduler of the jobs with a cron schedule
+  pub cron_manager: CronManager,
+  /// Counters of the http requests proxied by the node
+  pub http_counters: HttpCounters,
+  /// Keys encrypting the secrets
+  pub secret_keys: SecretKeys,
+  /// Whether this node hold the leader lease
+  pub is_leader: AtomicBool,
+  /// Last firewall rules applied for the network policies
+  pub network_rules: Mutex<Option<String>>,
   /// Event emitter
   pub(crate) event_emitter: mpsc::UnboundedSender<Event>,
   /// Http event client
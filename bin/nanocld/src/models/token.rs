@@ -0,0 +1,49 @@
+use diesel::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use nanocl_stubs::token::Token;
+
+use crate::schema::tokens;
+
+/// This structure represent a token in the database.
+/// A token authenticate the clients of the http api,
+/// only the sha256 hash of his secret is stored.
+#[derive(
+  Debug, Clone, Queryable, Identifiable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(primary_key(key))]
+#[diesel(table_name = tokens)]
+#[serde(rename_all = "PascalCase")]
+pub struct TokenDb {
+  /// The name of the token
+  pub key: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// When the token expires, it never expires if none
+  pub expires_at: Option<chrono::NaiveDateTime>,
+  /// The last time the token was used
+  pub last_used_at: Option<chrono::NaiveDateTime>,
+  /// The sha256 hash of the secret in hexadecimal
+  pub hash: String,
+  /// The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl TokenDb {
+  /// Whether the token is expired at the given date
+  pub fn is_expired(&self, now: chrono::NaiveDateTime) -> bool {
+    self.expires_at.map(|date| date <= now).unwrap_or(false)
+  }
+}
+
+impl From<TokenDb> for Token {
+  fn from(db: TokenDb) -> Self {
+    Token {
+      name: db.key,
+      created_at: db.created_at,
+      expires_at: db.expires_at,
+      last_used_at: db.last_used_at,
+      metadata: db.metadata,
+    }
+  }
+}
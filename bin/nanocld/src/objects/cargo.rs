@@ -8,7 +8,7 @@ use nanocl_error::http::{HttpResult, HttpError};
 use nanocl_stubs::{
   system::{ObjPsStatusPartial, ObjPsStatusKind, NativeEventAction},
   cargo::{Cargo, CargoDeleteQuery, CargoInspect},
-  cargo_spec::CargoSpecPartial,
+  cargo_spec::{CargoSpecPartial, ReplicationMode},
 };
 
 use crate::{
@@ -41,7 +41,14 @@ impl ObjCreate for CargoDb {
         "Cargo name can only contain a-z, A-Z, 0-9, and -_",
       ));
     }
+    if let Some(strategy) = &obj.spec.update_strategy {
+      utils::rollout::validate_strategy(strategy)?;
+    }
+    if let Some(ReplicationMode::Autoscale(autoscale)) = &obj.spec.replication {
+      utils::autoscale::validate(autoscale)?;
+    }
     let key = utils::key::gen_key(&obj.namespace, &obj.spec.name);
+    utils::quota::check_cargo(&obj.namespace, &key, &obj.spec, state).await?;
     let new_spec =
       SpecDb::try_from_cargo_partial(&key, &obj.version, &obj.spec)?;
     let spec = SpecDb::create_from(new_spec, &state.inner.pool)
@@ -119,6 +126,15 @@ impl ObjPutByPk for CargoDb {
     obj: &Self::ObjPutIn,
     state: &SystemState,
   ) -> HttpResult<Self::ObjPutOut> {
+    if let Some(strategy) = &obj.spec.update_strategy {
+      utils::rollout::validate_strategy(strategy)?;
+    }
+    if let Some(ReplicationMode::Autoscale(autoscale)) = &obj.spec.replication {
+      utils::autoscale::validate(autoscale)?;
+    }
+    let (cargo, _, _) = CargoDb::read_by_pk(pk, &state.inner.pool).await?;
+    utils::quota::check_cargo(&cargo.namespace_name, pk, &obj.spec, state)
+      .await?;
     let status = ObjPsStatusDb::read_by_pk(pk, &state.inner.pool).await?;
     let new_status = ObjPsStatusUpdate {
       wanted: Some(ObjPsStatusKind::Start.to_string()),
@@ -248,6 +264,11 @@ impl ObjPatchByPk for CargoDb {
       } else {
         cargo.spec.image_pull_policy
       },
+      update_strategy: if obj.spec.update_strategy.is_some() {
+        obj.spec.update_strategy.clone()
+      } else {
+        cargo.spec.update_strategy
+      },
     };
     let obj = &CargoObjPutIn {
       spec,
@@ -1,4 +1,4 @@
-use nanocl_error::http::HttpResult;
+use nanocl_error::http::{HttpError, HttpResult};
 use nanocl_stubs::{
   job::{Job, JobPartial, JobInspect},
   system::{ObjPsStatusPartial, ObjPsStatusKind, NativeEventAction},
@@ -20,6 +20,16 @@ impl ObjCreate for JobDb {
     obj: &Self::ObjCreateIn,
     state: &crate::models::SystemState,
   ) -> HttpResult<Self::ObjCreateOut> {
+    if let Some(schedule) = &obj.schedule {
+      utils::cron::parse_schedule(schedule)?;
+    }
+    utils::job::validate(obj.containers.len(), &obj.steps)?;
+    if obj.active_deadline_seconds == Some(0) {
+      return Err(HttpError::bad_request(
+        "ActiveDeadlineSeconds must be greater than 0",
+      ));
+    }
+    utils::quota::check_job(obj, state).await?;
     let db_model = JobDb::try_from_partial(obj)?;
     let status = ObjPsStatusPartial {
       key: obj.name.clone(),
@@ -29,12 +39,14 @@ impl ObjCreate for JobDb {
       prev_actual: ObjPsStatusKind::Create,
     };
     let status = ObjPsStatusDb::create_from(status, &state.inner.pool).await?;
-    let job = JobDb::create_from(db_model, &state.inner.pool)
+    let mut job = JobDb::create_from(db_model, &state.inner.pool)
       .await?
       .try_to_spec(&status)?;
-    if let Some(schedule) = &job.schedule {
-      utils::cron::add_cron_rule(&job, schedule, state).await?;
-    }
+    job.next_run_at = state
+      .inner
+      .cron_manager
+      .add_job(&job, &state.inner.pool)
+      .await?;
     Ok(job)
   }
 }
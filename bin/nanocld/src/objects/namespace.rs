@@ -6,7 +6,9 @@ use nanocl_stubs::namespace::{NamespacePartial, Namespace, NamespaceInspect};
 use crate::{
   utils,
   repositories::generic::*,
-  models::{CargoDb, NamespaceDb, SystemState},
+  models::{
+    CargoDb, NamespaceDb, NamespaceQuotaDb, NetworkPolicyDb, SystemState,
+  },
 };
 
 use super::generic::*;
@@ -88,6 +90,8 @@ impl ObjDelByPk for NamespaceDb {
   ) -> HttpResult<Self::ObjDelOut> {
     let item = NamespaceDb::read_by_pk(pk, &state.inner.pool).await?;
     CargoDb::delete_by_namespace(pk, state).await?;
+    NamespaceQuotaDb::del_by_pk(pk, &state.inner.pool).await?;
+    NetworkPolicyDb::del_by_namespace(pk, &state.inner.pool).await?;
     NamespaceDb::del_by_pk(pk, &state.inner.pool).await?;
     if let Err(err) = state.inner.docker_api.remove_network(pk).await {
       log::error!("Unable to remove network {} got error: {}", pk, err);
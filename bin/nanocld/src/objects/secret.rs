@@ -5,8 +5,9 @@ use nanocl_stubs::{
 };
 
 use crate::{
+  utils,
   repositories::generic::*,
-  models::{SecretDb, SystemState},
+  models::{SecretDb, SecretUpdateDb, SystemState},
 };
 
 use super::generic::*;
@@ -19,8 +20,25 @@ impl ObjCreate for SecretDb {
     obj: &Self::ObjCreateIn,
     state: &SystemState,
   ) -> HttpResult<Self::ObjCreateOut> {
-    let secret = SecretDb::create_from(obj, &state.inner.pool).await?;
-    let secret: Secret = secret.try_into()?;
+    let mut secret: SecretDb = obj.into();
+    secret.data =
+      utils::crypto::encrypt(&secret.key, &secret.data, state).await?;
+    let secret = SecretDb::create_from(secret, &state.inner.pool).await?;
+    let mut secret: Secret = secret.into();
+    secret.data = obj.data.clone();
+    Ok(secret)
+  }
+}
+
+impl ObjInspectByPk for SecretDb {
+  type ObjInspectOut = Secret;
+
+  async fn inspect_obj_by_pk(
+    pk: &str,
+    state: &SystemState,
+  ) -> HttpResult<Self::ObjInspectOut> {
+    let secret = SecretDb::read_by_pk(pk, &state.inner.pool).await?;
+    let secret = utils::crypto::decrypt_secret(secret, state).await?;
     Ok(secret)
   }
 }
@@ -53,9 +71,12 @@ impl ObjPatchByPk for SecretDb {
     obj: &Self::ObjPatchIn,
     state: &SystemState,
   ) -> HttpResult<Self::ObjPatchOut> {
-    let secret = SecretDb::update_pk(pk, obj, &state.inner.pool)
-      .await?
-      .try_into()?;
+    let mut update: SecretUpdateDb = obj.into();
+    if let Some(data) = &update.data {
+      update.data = Some(utils::crypto::encrypt(pk, data, state).await?);
+    }
+    let secret = SecretDb::update_pk(pk, update, &state.inner.pool).await?;
+    let secret = utils::crypto::decrypt_secret(secret, state).await?;
     Ok(secret)
   }
 }
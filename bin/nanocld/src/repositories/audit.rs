@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::audit::Audit;
+
+use crate::{
+  gen_sql_order_by, gen_sql_multiple, gen_sql_query, utils,
+  schema::audits,
+  models::{AuditDb, ColumnType, Pool},
+};
+
+use super::generic::*;
+
+impl RepositoryBase for AuditDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Uuid, "audits.key")),
+      ("node", (ColumnType::Text, "audits.node")),
+      ("method", (ColumnType::Text, "audits.method")),
+      ("path", (ColumnType::Text, "audits.path")),
+      ("identity", (ColumnType::Text, "audits.identity")),
+      ("namespace", (ColumnType::Text, "audits.namespace")),
+      ("body_hash", (ColumnType::Text, "audits.body_hash")),
+      ("created_at", (ColumnType::Timestamptz, "audits.created_at")),
+    ])
+  }
+}
+
+impl RepositoryCreate for AuditDb {}
+
+impl RepositoryReadBy for AuditDb {
+  type Output = AuditDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &nanocl_stubs::generic::GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::PgConnection,
+    Self::Output,
+  >
+  where
+    Self::Output: Sized,
+  {
+    let mut query = audits::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(audits::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryCountBy for AuditDb {
+  fn gen_count_query(
+    filter: &nanocl_stubs::generic::GenericFilter,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<'static, diesel::PgConnection, i64>
+  {
+    let mut query = audits::table.into_boxed();
+    let columns = Self::get_columns();
+    gen_sql_query!(query, filter, columns).count()
+  }
+}
+
+impl RepositoryReadByTransform for AuditDb {
+  type NewOutput = Audit;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    Self::NewOutput::try_from(input)
+  }
+}
+
+impl AuditDb {
+  /// Delete at most `limit` rows whose `expires_at` is passed
+  /// and return the number of deleted rows
+  pub async fn del_expired(limit: i64, pool: &Pool) -> IoResult<usize> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let keys = audits::table
+        .select(audits::key)
+        .filter(audits::expires_at.lt(diesel::dsl::now))
+        .limit(limit)
+        .load::<uuid::Uuid>(&mut conn)
+        .map_err(Self::map_err)?;
+      if keys.is_empty() {
+        return Ok(0);
+      }
+      let count =
+        diesel::delete(audits::table.filter(audits::key.eq_any(keys)))
+          .execute(&mut conn)
+          .map_err(Self::map_err)?;
+      Ok(count)
+    })
+    .await?
+  }
+}
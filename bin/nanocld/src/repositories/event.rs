@@ -6,9 +6,9 @@ use nanocl_error::io::IoResult;
 use nanocl_stubs::system::Event;
 
 use crate::{
-  gen_sql_order_by, gen_sql_multiple, gen_sql_query,
+  gen_sql_order_by, gen_sql_multiple, gen_sql_query, utils,
   schema::events,
-  models::{ColumnType, EventDb},
+  models::{ColumnType, EventDb, Pool},
 };
 
 use super::generic::*;
@@ -90,3 +90,29 @@ impl RepositoryReadByTransform for EventDb {
     Self::NewOutput::try_from(input)
   }
 }
+
+impl EventDb {
+  /// Delete at most `limit` rows whose `expires_at` is passed
+  /// and return the number of deleted rows
+  pub async fn del_expired(limit: i64, pool: &Pool) -> IoResult<usize> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let keys = events::table
+        .select(events::key)
+        .filter(events::expires_at.lt(diesel::dsl::now))
+        .limit(limit)
+        .load::<uuid::Uuid>(&mut conn)
+        .map_err(Self::map_err)?;
+      if keys.is_empty() {
+        return Ok(0);
+      }
+      let count =
+        diesel::delete(events::table.filter(events::key.eq_any(keys)))
+          .execute(&mut conn)
+          .map_err(Self::map_err)?;
+      Ok(count)
+    })
+    .await?
+  }
+}
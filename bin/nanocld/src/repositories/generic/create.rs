@@ -1,6 +1,6 @@
 use diesel::{prelude::*, associations::HasTable};
 
-use nanocl_error::io::{IoError, IoResult};
+use nanocl_error::io::IoResult;
 
 use crate::{utils, models::Pool};
 
@@ -31,22 +31,4 @@ pub trait RepositoryCreate: super::RepositoryBase {
     })
     .await?
   }
-
-  async fn create_try_from<I>(item: I, pool: &Pool) -> IoResult<Self>
-  where
-    Self: Sized
-      + Send
-      + TryFrom<I, Error = IoError>
-      + HasTable
-      + diesel::Insertable<Self::Table>
-      + 'static,
-    Self::Table: HasTable<Table = Self::Table> + diesel::Table,
-    diesel::query_builder::InsertStatement<
-      Self::Table,
-      <Self as diesel::Insertable<Self::Table>>::Values,
-    >: diesel::query_dsl::LoadQuery<'static, diesel::pg::PgConnection, Self>,
-  {
-    let item = Self::try_from(item)?;
-    Self::create_from(item, pool).await
-  }
 }
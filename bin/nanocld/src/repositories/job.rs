@@ -33,6 +33,8 @@ impl RepositoryBase for JobDb {
       ("metadata", (ColumnType::Json, "jobs.metadata")),
       ("created_at", (ColumnType::Timestamptz, "jobs.created_at")),
       ("updated_at", (ColumnType::Timestamptz, "jobs.updated_at")),
+      ("last_run_at", (ColumnType::Timestamptz, "jobs.last_run_at")),
+      ("next_run_at", (ColumnType::Timestamptz, "jobs.next_run_at")),
       (
         "status.wanted",
         (ColumnType::Text, "object_process_statuses.wanted"),
@@ -123,6 +125,8 @@ impl JobDb {
       created_at: chrono::Utc::now().naive_utc(),
       updated_at: chrono::Utc::now().naive_utc(),
       metadata: Default::default(),
+      last_run_at: None,
+      next_run_at: None,
       data,
     })
   }
@@ -139,8 +143,14 @@ impl JobDb {
       ttl: p.ttl,
       status: status.clone().try_into()?,
       containers: p.containers.clone(),
+      steps: p.steps.clone(),
+      backoff_limit: p.backoff_limit,
+      backoff_delay: p.backoff_delay,
+      active_deadline_seconds: p.active_deadline_seconds,
       image_pull_secret: p.image_pull_secret.clone(),
       image_pull_policy: p.image_pull_policy.clone(),
+      last_run_at: self.last_run_at,
+      next_run_at: self.next_run_at,
     })
   }
 
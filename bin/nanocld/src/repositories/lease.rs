@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use diesel::{prelude::*, sql_types::Text};
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{generic::GenericFilter, node::CLIENT_LEASE_PREFIX};
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query, utils,
+  models::{ColumnType, LeaseDb, Pool},
+  schema::leases,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for LeaseDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Text, "leases.key")),
+      ("node_name", (ColumnType::Text, "leases.node_name")),
+      ("action", (ColumnType::Text, "leases.action")),
+      ("created_at", (ColumnType::Timestamptz, "leases.created_at")),
+      ("updated_at", (ColumnType::Timestamptz, "leases.updated_at")),
+      ("expires_at", (ColumnType::Timestamptz, "leases.expires_at")),
+    ])
+  }
+}
+
+impl RepositoryDelByPk for LeaseDb {}
+
+impl RepositoryReadBy for LeaseDb {
+  type Output = LeaseDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = leases::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(leases::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl LeaseDb {
+  /// Acquire the lease `key` for `node` during `ttl` seconds.
+  /// It succeed when the lease doesn't exist, is already owned by the node
+  /// or is expired, the expiration is computed with the clock of the store
+  /// so the nodes doesn't need to have their clock synchronized.
+  pub async fn acquire(
+    key: &str,
+    node: &str,
+    action: &str,
+    ttl: u64,
+    pool: &Pool,
+  ) -> IoResult<bool> {
+    let pool = pool.clone();
+    let key = key.to_owned();
+    let node = node.to_owned();
+    let action = action.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let res = diesel::sql_query(
+        "INSERT INTO leases (key, node_name, action, expires_at) \
+        VALUES ($1, $2, $3, NOW() + CAST($4 AS INTERVAL)) \
+        ON CONFLICT (key) DO UPDATE SET \
+        node_name = excluded.node_name, action = excluded.action, \
+        expires_at = excluded.expires_at, updated_at = NOW() \
+        WHERE leases.node_name = excluded.node_name \
+        OR leases.expires_at < NOW()",
+      )
+      .bind::<Text, _>(key)
+      .bind::<Text, _>(node)
+      .bind::<Text, _>(action)
+      .bind::<Text, _>(format!("{ttl} seconds"))
+      .execute(&mut conn)
+      .map_err(Self::map_err)?;
+      Ok(res == 1)
+    })
+    .await?
+  }
+
+  /// Extend by `ttl` seconds every lease owned by `node`
+  /// except the client leases renewed by their client
+  pub async fn renew_by_node(
+    node: &str,
+    ttl: u64,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    let pool = pool.clone();
+    let node = node.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let res = diesel::sql_query(
+        "UPDATE leases SET expires_at = NOW() + CAST($2 AS INTERVAL), \
+        updated_at = NOW() WHERE node_name = $1 AND key NOT LIKE $3",
+      )
+      .bind::<Text, _>(node)
+      .bind::<Text, _>(format!("{ttl} seconds"))
+      .bind::<Text, _>(format!("{CLIENT_LEASE_PREFIX}%"))
+      .execute(&mut conn)
+      .map_err(Self::map_err)?;
+      Ok(res)
+    })
+    .await?
+  }
+
+  /// Release the lease `key` if it's owned by `node`
+  pub async fn release(key: &str, node: &str, pool: &Pool) -> IoResult<()> {
+    let pool = pool.clone();
+    let key = key.to_owned();
+    let node = node.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      diesel::delete(
+        leases::table
+          .filter(leases::key.eq(key))
+          .filter(leases::node_name.eq(node)),
+      )
+      .execute(&mut conn)
+      .map_err(Self::map_err)?;
+      Ok(())
+    })
+    .await?
+  }
+
+  /// List the leases that haven't been renewed in time
+  pub async fn read_expired(pool: &Pool) -> IoResult<Vec<LeaseDb>> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let items = leases::table
+        .filter(leases::expires_at.lt(diesel::dsl::now))
+        .load::<LeaseDb>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(items)
+    })
+    .await?
+  }
+}
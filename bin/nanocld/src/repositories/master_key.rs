@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_stubs::generic::GenericFilter;
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{ColumnType, MasterKeyDb},
+  schema::master_keys,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for MasterKeyDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Text, "master_keys.key")),
+      ("wrap_key_id", (ColumnType::Text, "master_keys.wrap_key_id")),
+      (
+        "created_at",
+        (ColumnType::Timestamptz, "master_keys.created_at"),
+      ),
+    ])
+  }
+}
+
+impl RepositoryCreate for MasterKeyDb {}
+
+impl RepositoryDelByPk for MasterKeyDb {}
+
+impl RepositoryReadBy for MasterKeyDb {
+  type Output = MasterKeyDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = master_keys::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(master_keys::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
@@ -1,10 +1,12 @@
 use diesel::prelude::*;
+
+use nanocl_error::io::IoResult;
 use nanocl_stubs::generic::GenericFilter;
 
 use crate::{
-  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{ColumnType, MetricDb},
-  schema::metrics,
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query, utils,
+  models::{ColumnType, MetricDb, MetricRollupDb, Pool},
+  schema::{metric_rollups, metrics},
 };
 
 use super::generic::*;
@@ -66,3 +68,200 @@ impl RepositoryCountBy for MetricDb {
     gen_sql_query!(query, filter, columns).count()
   }
 }
+
+impl MetricDb {
+  /// Delete at most `limit` rows whose `expires_at` is passed
+  /// and return the number of deleted rows
+  pub async fn del_expired(limit: i64, pool: &Pool) -> IoResult<usize> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let keys = metrics::table
+        .select(metrics::key)
+        .filter(metrics::expires_at.lt(diesel::dsl::now))
+        .limit(limit)
+        .load::<uuid::Uuid>(&mut conn)
+        .map_err(Self::map_err)?;
+      if keys.is_empty() {
+        return Ok(0);
+      }
+      let count =
+        diesel::delete(metrics::table.filter(metrics::key.eq_any(keys)))
+          .execute(&mut conn)
+          .map_err(Self::map_err)?;
+      Ok(count)
+    })
+    .await?
+  }
+  /// Save the metrics at once and return the number of created rows
+  pub async fn create_many(
+    items: Vec<MetricDb>,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    if items.is_empty() {
+      return Ok(0);
+    }
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let count = diesel::insert_into(metrics::table)
+        .values(&items)
+        .execute(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(count)
+    })
+    .await?
+  }
+
+  /// Read the metrics saved by a node between `since` included
+  /// and `until` excluded
+  pub async fn read_range(
+    node_name: &str,
+    since: chrono::NaiveDateTime,
+    until: chrono::NaiveDateTime,
+    pool: &Pool,
+  ) -> IoResult<Vec<MetricDb>> {
+    let pool = pool.clone();
+    let node_name = node_name.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let items = metrics::table
+        .filter(metrics::node_name.eq(node_name))
+        .filter(metrics::created_at.ge(since))
+        .filter(metrics::created_at.lt(until))
+        .load::<MetricDb>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(items)
+    })
+    .await?
+  }
+
+  /// Date of the first metric saved by a node from `since`
+  pub async fn first_after(
+    node_name: &str,
+    since: Option<chrono::NaiveDateTime>,
+    pool: &Pool,
+  ) -> IoResult<Option<chrono::NaiveDateTime>> {
+    let pool = pool.clone();
+    let node_name = node_name.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let mut query = metrics::table
+        .select(diesel::dsl::min(metrics::created_at))
+        .filter(metrics::node_name.eq(node_name))
+        .into_boxed();
+      if let Some(since) = since {
+        query = query.filter(metrics::created_at.ge(since));
+      }
+      let date = query
+        .get_result::<Option<chrono::NaiveDateTime>>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(date)
+    })
+    .await?
+  }
+}
+
+impl RepositoryBase for MetricRollupDb {}
+
+impl MetricRollupDb {
+  /// Save the rollups ignoring the buckets already saved
+  /// and return the number of created rows
+  pub async fn create_many(
+    items: Vec<MetricRollupDb>,
+    pool: &Pool,
+  ) -> IoResult<usize> {
+    if items.is_empty() {
+      return Ok(0);
+    }
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let count = diesel::insert_into(metric_rollups::table)
+        .values(&items)
+        .on_conflict_do_nothing()
+        .execute(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(count)
+    })
+    .await?
+  }
+
+  /// Start of the last bucket saved by a node
+  pub async fn last_bucket(
+    node_name: &str,
+    pool: &Pool,
+  ) -> IoResult<Option<chrono::NaiveDateTime>> {
+    let pool = pool.clone();
+    let node_name = node_name.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let date = metric_rollups::table
+        .select(diesel::dsl::max(metric_rollups::bucket_at))
+        .filter(metric_rollups::node_name.eq(node_name))
+        .get_result::<Option<chrono::NaiveDateTime>>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(date)
+    })
+    .await?
+  }
+
+  /// Read the rollups of a field of a kind whose bucket start
+  /// between `since` included and `until` excluded
+  pub async fn read_range(
+    kind: &str,
+    field: &str,
+    node_name: Option<&str>,
+    since: chrono::NaiveDateTime,
+    until: chrono::NaiveDateTime,
+    pool: &Pool,
+  ) -> IoResult<Vec<MetricRollupDb>> {
+    let pool = pool.clone();
+    let kind = kind.to_owned();
+    let field = field.to_owned();
+    let node_name = node_name.map(|name| name.to_owned());
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let mut query = metric_rollups::table
+        .filter(metric_rollups::kind.eq(kind))
+        .filter(metric_rollups::field.eq(field))
+        .filter(metric_rollups::bucket_at.ge(since))
+        .filter(metric_rollups::bucket_at.lt(until))
+        .into_boxed();
+      if let Some(node_name) = node_name {
+        query = query.filter(metric_rollups::node_name.eq(node_name));
+      }
+      let items = query
+        .order(metric_rollups::bucket_at.asc())
+        .load::<MetricRollupDb>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(items)
+    })
+    .await?
+  }
+
+  /// Delete at most `limit` rows whose `expires_at` is passed
+  /// and return the number of deleted rows
+  pub async fn del_expired(limit: i64, pool: &Pool) -> IoResult<usize> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let keys = metric_rollups::table
+        .select(metric_rollups::key)
+        .filter(metric_rollups::expires_at.lt(diesel::dsl::now))
+        .limit(limit)
+        .load::<uuid::Uuid>(&mut conn)
+        .map_err(Self::map_err)?;
+      if keys.is_empty() {
+        return Ok(0);
+      }
+      let count = diesel::delete(
+        metric_rollups::table.filter(metric_rollups::key.eq_any(keys)),
+      )
+      .execute(&mut conn)
+      .map_err(Self::map_err)?;
+      Ok(count)
+    })
+    .await?
+  }
+}
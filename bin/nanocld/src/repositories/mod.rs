@@ -1,6 +1,11 @@
 mod node;
 mod namespace;
+mod namespace_quota;
+mod network_policy;
 mod secret;
+mod master_key;
+mod token;
+mod role;
 mod process;
 mod spec;
 mod job;
@@ -11,6 +16,8 @@ mod metric;
 mod vm;
 mod vm_image;
 mod event;
+mod audit;
 mod object_process_status;
+mod lease;
 
 pub mod generic;
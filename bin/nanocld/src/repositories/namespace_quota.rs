@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_stubs::generic::GenericFilter;
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  schema::namespace_quotas,
+  models::{ColumnType, NamespaceQuotaDb, NamespaceQuotaUpdateDb},
+};
+
+use super::generic::*;
+
+impl RepositoryBase for NamespaceQuotaDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      (
+        "namespace_name",
+        (ColumnType::Text, "namespace_quotas.namespace_name"),
+      ),
+      (
+        "created_at",
+        (ColumnType::Timestamptz, "namespace_quotas.created_at"),
+      ),
+      (
+        "updated_at",
+        (ColumnType::Timestamptz, "namespace_quotas.updated_at"),
+      ),
+      ("data", (ColumnType::Json, "namespace_quotas.data")),
+    ])
+  }
+}
+
+impl RepositoryCreate for NamespaceQuotaDb {}
+
+impl RepositoryDelByPk for NamespaceQuotaDb {}
+
+impl RepositoryUpdate for NamespaceQuotaDb {
+  type UpdateItem = NamespaceQuotaUpdateDb;
+}
+
+impl RepositoryReadBy for NamespaceQuotaDb {
+  type Output = NamespaceQuotaDb;
+
+  fn get_pk() -> &'static str {
+    "namespace_name"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = namespace_quotas::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(namespace_quotas::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  network_policy::NetworkPolicy,
+};
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{ColumnType, NetworkPolicyDb, NetworkPolicyUpdateDb, Pool},
+  schema::network_policies,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for NetworkPolicyDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Text, "network_policies.key")),
+      ("name", (ColumnType::Text, "network_policies.name")),
+      (
+        "namespace_name",
+        (ColumnType::Text, "network_policies.namespace_name"),
+      ),
+      (
+        "created_at",
+        (ColumnType::Timestamptz, "network_policies.created_at"),
+      ),
+      (
+        "updated_at",
+        (ColumnType::Timestamptz, "network_policies.updated_at"),
+      ),
+      ("data", (ColumnType::Json, "network_policies.data")),
+      ("metadata", (ColumnType::Json, "network_policies.metadata")),
+    ])
+  }
+}
+
+impl RepositoryCreate for NetworkPolicyDb {}
+
+impl RepositoryDelByPk for NetworkPolicyDb {}
+
+impl RepositoryDelBy for NetworkPolicyDb {
+  fn gen_del_query(
+    filter: &GenericFilter,
+  ) -> diesel::query_builder::BoxedDeleteStatement<
+    'static,
+    diesel::pg::Pg,
+    <Self as diesel::associations::HasTable>::Table,
+  >
+  where
+    Self: diesel::associations::HasTable,
+  {
+    let mut query = diesel::delete(network_policies::table).into_boxed();
+    let columns = Self::get_columns();
+    gen_sql_query!(query, filter, columns)
+  }
+}
+
+impl RepositoryUpdate for NetworkPolicyDb {
+  type UpdateItem = NetworkPolicyUpdateDb;
+}
+
+impl RepositoryReadBy for NetworkPolicyDb {
+  type Output = NetworkPolicyDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = network_policies::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(network_policies::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryCountBy for NetworkPolicyDb {
+  fn gen_count_query(
+    filter: &GenericFilter,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<'static, diesel::PgConnection, i64>
+  {
+    let mut query = network_policies::table.into_boxed();
+    let columns = Self::get_columns();
+    gen_sql_query!(query, filter, columns).count()
+  }
+}
+
+impl RepositoryReadByTransform for NetworkPolicyDb {
+  type NewOutput = NetworkPolicy;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    input.try_into()
+  }
+}
+
+impl NetworkPolicyDb {
+  /// Delete the policies of a namespace
+  pub async fn del_by_namespace(namespace: &str, pool: &Pool) -> IoResult<()> {
+    let filter = GenericFilter::new()
+      .r#where("namespace_name", GenericClause::Eq(namespace.to_owned()));
+    NetworkPolicyDb::del_by(&filter, pool).await
+  }
+}
@@ -4,11 +4,14 @@ use diesel::prelude::*;
 
 use nanocl_error::io::{IoError, IoResult};
 
-use nanocl_stubs::generic::GenericFilter;
+use nanocl_stubs::{
+  generic::GenericFilter,
+  node::{NodeCapacity, NodeState},
+};
 
 use crate::{
-  gen_sql_multiple, gen_sql_order_by, gen_sql_query, vars,
-  schema::nodes,
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query, vars, utils,
+  schema::{nodes, node_group_links},
   models::{ColumnType, NodeDb, Pool, SystemState},
 };
 
@@ -20,6 +23,11 @@ impl RepositoryBase for NodeDb {
       ("name", (ColumnType::Text, "nodes.name")),
       ("ip_address", (ColumnType::Text, "nodes.ip_address")),
       ("created_at", (ColumnType::Timestamptz, "nodes.created_at")),
+      ("state", (ColumnType::Text, "nodes.state")),
+      (
+        "heartbeat_at",
+        (ColumnType::Timestamptz, "nodes.heartbeat_at"),
+      ),
     ])
   }
 }
@@ -79,6 +87,85 @@ impl NodeDb {
     }
   }
 
+  /// List the node names of every given node group
+  /// The result is indexed by group name
+  pub async fn read_by_groups(
+    groups: &[String],
+    pool: &Pool,
+  ) -> IoResult<HashMap<String, Vec<String>>> {
+    let pool = pool.clone();
+    let groups = groups.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let links = node_group_links::table
+        .select((
+          node_group_links::node_group_name,
+          node_group_links::node_name,
+        ))
+        .filter(node_group_links::node_group_name.eq_any(groups))
+        .load::<(String, String)>(&mut conn)
+        .map_err(Self::map_err)?;
+      let mut items: HashMap<String, Vec<String>> = HashMap::new();
+      for (group, node) in links {
+        items.entry(group).or_default().push(node);
+      }
+      Ok(items)
+    })
+    .await?
+  }
+
+  /// Save the heartbeat of a node with his state and capacity,
+  /// the date of the heartbeat come from the clock of the store
+  pub async fn heartbeat(
+    name: &str,
+    node_state: &NodeState,
+    capacity: Option<&NodeCapacity>,
+    pool: &Pool,
+  ) -> IoResult<()> {
+    let pool = pool.clone();
+    let name = name.to_owned();
+    let node_state = node_state.to_string();
+    let capacity = capacity.map(serde_json::to_value).transpose()?;
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      diesel::update(nodes::table.filter(nodes::name.eq(name)))
+        .set((
+          nodes::state.eq(node_state),
+          nodes::heartbeat_at.eq(diesel::dsl::now),
+          nodes::capacity.eq(capacity),
+        ))
+        .execute(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(())
+    })
+    .await?
+  }
+
+  /// Mark as `NotReady` the ready nodes without heartbeat since `timeout` seconds
+  /// and return their names
+  pub async fn mark_not_ready(
+    timeout: u64,
+    pool: &Pool,
+  ) -> IoResult<Vec<String>> {
+    let pool = pool.clone();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let names = diesel::update(
+        nodes::table
+          .filter(nodes::state.eq(NodeState::Ready.to_string()))
+          .filter(diesel::dsl::sql::<diesel::sql_types::Bool>(&format!(
+            "nodes.heartbeat_at < NOW() - INTERVAL '{timeout} seconds'"
+          ))),
+      )
+      .set(nodes::state.eq(NodeState::NotReady.to_string()))
+      .returning(nodes::name)
+      .get_results::<String>(&mut conn)
+      .map_err(Self::map_err)?;
+      Ok(names)
+    })
+    .await?
+  }
+
   pub async fn register(state: &SystemState) -> IoResult<()> {
     let ip_address =
       state
@@ -97,6 +184,9 @@ impl NodeDb {
       created_at: chrono::Utc::now().naive_utc(),
       version: vars::VERSION.to_owned(),
       metadata: None,
+      state: NodeState::Ready,
+      heartbeat_at: chrono::Utc::now().naive_utc(),
+      capacity: None,
     };
     NodeDb::create_if_not_exists(&node, &state.inner.pool).await?;
     Ok(())
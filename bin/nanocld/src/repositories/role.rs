@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{generic::GenericFilter, role::Role};
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query, utils,
+  models::{ColumnType, Pool, RoleDb, RoleUpdateDb},
+  schema::roles,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for RoleDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Text, "roles.key")),
+      ("created_at", (ColumnType::Timestamptz, "roles.created_at")),
+      ("updated_at", (ColumnType::Timestamptz, "roles.updated_at")),
+      ("rules", (ColumnType::Json, "roles.rules")),
+      ("subjects", (ColumnType::Json, "roles.subjects")),
+      ("metadata", (ColumnType::Json, "roles.metadata")),
+    ])
+  }
+}
+
+impl RepositoryCreate for RoleDb {}
+
+impl RepositoryDelByPk for RoleDb {}
+
+impl RepositoryUpdate for RoleDb {
+  type UpdateItem = RoleUpdateDb;
+}
+
+impl RepositoryReadBy for RoleDb {
+  type Output = RoleDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = roles::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(roles::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryReadByTransform for RoleDb {
+  type NewOutput = Role;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    input.try_into()
+  }
+}
+
+impl RoleDb {
+  /// Read the roles bound to a subject
+  pub async fn read_by_subject(
+    subject: &str,
+    pool: &Pool,
+  ) -> IoResult<Vec<Role>> {
+    let pool = pool.clone();
+    let subject = serde_json::json!([subject]);
+    let items = ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let items = roles::table
+        .filter(roles::subjects.contains(subject))
+        .load::<RoleDb>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok::<_, IoError>(items)
+    })
+    .await??;
+    items.into_iter().map(Role::try_from).collect()
+  }
+}
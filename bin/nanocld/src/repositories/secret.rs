@@ -88,6 +88,6 @@ impl RepositoryReadByTransform for SecretDb {
   fn transform(
     input: Self::Output,
   ) -> nanocl_error::io::IoResult<Self::NewOutput> {
-    input.try_into()
+    Ok(input.into())
   }
 }
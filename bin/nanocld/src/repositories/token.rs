@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{generic::GenericFilter, token::Token};
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query, utils,
+  models::{ColumnType, Pool, TokenDb},
+  schema::tokens,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for TokenDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("key", (ColumnType::Text, "tokens.key")),
+      ("created_at", (ColumnType::Timestamptz, "tokens.created_at")),
+      ("expires_at", (ColumnType::Timestamptz, "tokens.expires_at")),
+      (
+        "last_used_at",
+        (ColumnType::Timestamptz, "tokens.last_used_at"),
+      ),
+      ("metadata", (ColumnType::Json, "tokens.metadata")),
+    ])
+  }
+}
+
+impl RepositoryCreate for TokenDb {}
+
+impl RepositoryDelByPk for TokenDb {}
+
+impl RepositoryReadBy for TokenDb {
+  type Output = TokenDb;
+
+  fn get_pk() -> &'static str {
+    "key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = tokens::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(tokens::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl RepositoryReadByTransform for TokenDb {
+  type NewOutput = Token;
+
+  fn transform(input: Self::Output) -> IoResult<Self::NewOutput> {
+    Ok(input.into())
+  }
+}
+
+impl TokenDb {
+  /// Find the token matching the hash of a secret
+  pub async fn read_by_hash(hash: &str, pool: &Pool) -> IoResult<TokenDb> {
+    let pool = pool.clone();
+    let hash = hash.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      let item = tokens::table
+        .filter(tokens::hash.eq(hash))
+        .get_result::<TokenDb>(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(item)
+    })
+    .await?
+  }
+
+  /// Update the last time the token was used
+  pub async fn touch(
+    key: &str,
+    date: chrono::NaiveDateTime,
+    pool: &Pool,
+  ) -> IoResult<()> {
+    let pool = pool.clone();
+    let key = key.to_owned();
+    ntex::rt::spawn_blocking(move || {
+      let mut conn = utils::store::get_pool_conn(&pool)?;
+      diesel::update(tokens::table.filter(tokens::key.eq(key)))
+        .set(tokens::last_used_at.eq(date))
+        .execute(&mut conn)
+        .map_err(Self::map_err)?;
+      Ok(())
+    })
+    .await?
+  }
+}
@@ -1,5 +1,21 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audits (key) {
+        key -> Uuid,
+        created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        node -> Varchar,
+        method -> Varchar,
+        path -> Varchar,
+        identity -> Nullable<Varchar>,
+        namespace -> Nullable<Varchar>,
+        body_hash -> Nullable<Varchar>,
+        status_code -> Int4,
+        latency_ms -> Int8,
+    }
+}
+
 diesel::table! {
     cargoes (key) {
         key -> Varchar,
@@ -36,6 +52,45 @@ diesel::table! {
         status_key -> Varchar,
         data -> Jsonb,
         metadata -> Nullable<Jsonb>,
+        last_run_at -> Nullable<Timestamptz>,
+        next_run_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    leases (key) {
+        key -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        node_name -> Varchar,
+        action -> Varchar,
+        expires_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    master_keys (key) {
+        key -> Varchar,
+        created_at -> Timestamptz,
+        wrap_key_id -> Varchar,
+        data -> Varchar,
+    }
+}
+
+diesel::table! {
+    metric_rollups (key) {
+        key -> Uuid,
+        created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        bucket_at -> Timestamptz,
+        node_name -> Varchar,
+        kind -> Varchar,
+        field -> Varchar,
+        count -> Int8,
+        sum -> Float8,
+        min -> Float8,
+        max -> Float8,
+        histogram -> Jsonb,
     }
 }
 
@@ -51,6 +106,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    namespace_quotas (namespace_name) {
+        namespace_name -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        data -> Jsonb,
+    }
+}
+
 diesel::table! {
     namespaces (name) {
         name -> Varchar,
@@ -59,6 +123,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    network_policies (key) {
+        key -> Varchar,
+        name -> Varchar,
+        namespace_name -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        data -> Jsonb,
+        metadata -> Nullable<Jsonb>,
+    }
+}
+
 diesel::table! {
     node_group_links (rowid) {
         node_name -> Varchar,
@@ -81,6 +157,9 @@ diesel::table! {
         endpoint -> Varchar,
         version -> Varchar,
         metadata -> Nullable<Jsonb>,
+        state -> Varchar,
+        heartbeat_at -> Timestamptz,
+        capacity -> Nullable<Jsonb>,
     }
 }
 
@@ -126,6 +205,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    roles (key) {
+        key -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        rules -> Jsonb,
+        subjects -> Jsonb,
+        metadata -> Nullable<Jsonb>,
+    }
+}
+
 diesel::table! {
     secrets (key) {
         key -> Varchar,
@@ -150,6 +240,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tokens (key) {
+        key -> Varchar,
+        created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+        last_used_at -> Nullable<Timestamptz>,
+        hash -> Varchar,
+        metadata -> Nullable<Jsonb>,
+    }
+}
+
 diesel::table! {
     vm_images (name) {
         name -> Varchar,
@@ -179,6 +280,9 @@ diesel::joinable!(cargoes -> namespaces (namespace_name));
 diesel::joinable!(cargoes -> object_process_statuses (status_key));
 diesel::joinable!(cargoes -> specs (spec_key));
 diesel::joinable!(jobs -> object_process_statuses (status_key));
+diesel::joinable!(leases -> nodes (node_name));
+diesel::joinable!(namespace_quotas -> namespaces (namespace_name));
+diesel::joinable!(network_policies -> namespaces (namespace_name));
 diesel::joinable!(node_group_links -> node_groups (node_group_name));
 diesel::joinable!(node_group_links -> nodes (node_name));
 diesel::joinable!(processes -> nodes (node_name));
@@ -190,11 +294,17 @@ diesel::joinable!(vms -> object_process_statuses (status_key));
 diesel::joinable!(vms -> specs (spec_key));
 
 diesel::allow_tables_to_appear_in_same_query!(
+  audits,
   cargoes,
   events,
   jobs,
+  leases,
+  master_keys,
+  metric_rollups,
   metrics,
+  namespace_quotas,
   namespaces,
+  network_policies,
   node_group_links,
   node_groups,
   nodes,
@@ -202,8 +312,10 @@ diesel::allow_tables_to_appear_in_same_query!(
   processes,
   resource_kinds,
   resources,
+  roles,
   secrets,
   specs,
+  tokens,
   vm_images,
   vms,
 );
@@ -0,0 +1,139 @@
+/*
+* Endpoints to read the audit entries of the mutating api calls
+*/
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::{
+  generic::{GenericCount, GenericListQuery},
+  role::RoleVerb,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{AuditDb, SystemState},
+};
+
+/// List audit entries
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Audit",
+  path = "/audit",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"identity\": { \"eq\": \"token:ci\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "List of audit entries", body = [Audit]),
+  ),
+))]
+#[web::get("/audit")]
+pub async fn list_audit(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "audit", RoleVerb::List, None).await?;
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let items = AuditDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&items))
+}
+
+/// Get detailed information about an audit entry
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Audit",
+  path = "/audit/{key}/inspect",
+  params(
+    ("key" = String, Path, description = "Key of the audit entry"),
+  ),
+  responses(
+    (status = 200, description = "Detailed information about the audit entry", body = Audit),
+    (status = 404, description = "Audit entry is not existing", body = ApiError),
+  ),
+))]
+#[web::get("/audit/{key}/inspect")]
+pub async fn inspect_audit(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "audit", RoleVerb::Inspect, None)
+    .await?;
+  let item = AuditDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&item))
+}
+
+/// Count audit entries
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Audit",
+  path = "/audit/count",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"method\": { \"eq\": \"DELETE\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "Count result", body = GenericCount),
+  ),
+))]
+#[web::get("/audit/count")]
+pub async fn count_audit(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "audit", RoleVerb::List, None).await?;
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let count = AuditDb::count_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_audit);
+  config.service(count_audit);
+  config.service(inspect_audit);
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::{http, time};
+
+  use nanocl_stubs::{
+    audit::Audit,
+    generic::{GenericClause, GenericFilter, GenericListQuery},
+  };
+
+  use crate::utils::tests::*;
+
+  const ENDPOINT: &str = "/audit";
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post("/events/prune", None::<String>, None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "prune events");
+    // The entries are saved in background
+    time::sleep(std::time::Duration::from_secs(1)).await;
+    let filter = GenericFilter::new()
+      .r#where("method", GenericClause::Eq("POST".to_owned()))
+      .r#where("path", GenericClause::Like("%/events/prune".to_owned()));
+    let qs = GenericListQuery::try_from(filter).unwrap();
+    let mut res = client.send_get(ENDPOINT, Some(&qs)).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list audit");
+    let items = res.json::<Vec<Audit>>().await.unwrap();
+    let item = items.first().expect("Expect the prune to be audited");
+    assert_eq!(item.status_code, 200);
+    assert!(item.body_hash.is_none());
+    let res = client
+      .send_get(&format!("{ENDPOINT}/{}/inspect", item.key), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect audit");
+    let res = client
+      .send_get(&format!("{ENDPOINT}/count"), Some(&qs))
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "count audit");
+  }
+}
@@ -5,6 +5,7 @@ use nanocl_error::{http::HttpResult, io::IoResult};
 use nanocl_stubs::{
   cargo::CargoDeleteQuery,
   cargo_spec::{CargoSpecPartial, CargoSpecUpdate},
+  role::RoleVerb,
   generic::{
     GenericClause, GenericCount, GenericListQueryNsp, GenericNspQuery,
   },
@@ -35,9 +36,19 @@ use crate::{
 ))]
 #[web::get("/cargoes")]
 pub async fn list_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQueryNsp>,
 ) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::List,
+    Some(&namespace),
+  )
+  .await?;
   let query = utils::query_string::parse_qs_nsp_filter(&qs)?;
   log::debug!("got query {query:#?}");
   let cargoes = CargoDb::list(&query, &state).await?;
@@ -59,11 +70,20 @@ pub async fn list_cargo(
 ))]
 #[web::get("/cargoes/{name}/inspect")]
 pub async fn inspect_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Inspect,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let cargo = CargoDb::inspect_obj_by_pk(&key, &state).await?;
   Ok(web::HttpResponse::Ok().json(&cargo))
@@ -84,12 +104,21 @@ pub async fn inspect_cargo(
 ))]
 #[web::post("/cargoes")]
 pub async fn create_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<String>,
   payload: web::types::Json<CargoSpecPartial>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Create,
+    Some(&namespace),
+  )
+  .await?;
   let obj = CargoObjCreateIn {
     namespace: namespace.clone(),
     spec: payload.into_inner(),
@@ -116,11 +145,20 @@ pub async fn create_cargo(
 ))]
 #[web::delete("/cargoes/{name}")]
 pub async fn delete_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<CargoDeleteQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Delete,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   log::debug!("service::delete_cargo: {key}");
   CargoDb::del_obj_by_pk(&key, &qs, &state).await?;
@@ -144,12 +182,21 @@ pub async fn delete_cargo(
 ))]
 #[web::put("/cargoes/{name}")]
 pub async fn put_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<CargoSpecPartial>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Patch,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let obj = &CargoObjPutIn {
     spec: payload.into_inner(),
@@ -176,12 +223,21 @@ pub async fn put_cargo(
 ))]
 #[web::patch("/cargoes/{name}")]
 pub async fn patch_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<CargoSpecUpdate>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Patch,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let obj = &CargoObjPatchIn {
     spec: payload.into_inner(),
@@ -207,11 +263,20 @@ pub async fn patch_cargo(
 ))]
 #[web::get("/cargoes/{name}/histories")]
 pub async fn list_cargo_history(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Inspect,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let histories = SpecDb::read_by_kind_key(&key, &state.inner.pool)
     .await?
@@ -238,11 +303,20 @@ pub async fn list_cargo_history(
 ))]
 #[web::patch("/cargoes/{name}/histories/{id}/revert")]
 pub async fn revert_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, uuid::Uuid)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Patch,
+    Some(&namespace),
+  )
+  .await?;
   let cargo_key = utils::key::gen_key(&namespace, &path.1);
   let spec = SpecDb::read_by_pk(&path.2, &state.inner.pool)
     .await?
@@ -270,11 +344,20 @@ pub async fn revert_cargo(
 ))]
 #[web::get("/cargoes/count")]
 pub async fn count_cargo(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQueryNsp>,
 ) -> HttpResult<web::HttpResponse> {
   let filter = utils::query_string::parse_qs_nsp_filter(&qs)?;
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::List,
+    Some(&namespace),
+  )
+  .await?;
   let filter = filter
     .filter
     .clone()
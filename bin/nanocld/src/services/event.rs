@@ -3,6 +3,7 @@ use ntex::web;
 use nanocl_error::http::HttpResult;
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
+  role::RoleVerb,
   system::EventCondition,
 };
 
@@ -26,9 +27,11 @@ use crate::{
 ))]
 #[web::get("/events")]
 pub async fn list_event(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "event", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let events = EventDb::transform_read_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&events))
@@ -48,9 +51,12 @@ pub async fn list_event(
 ))]
 #[web::get("/events/{key}/inspect")]
 pub async fn inspect_event(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "event", RoleVerb::Inspect, None)
+    .await?;
   let event = EventDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&event))
 }
@@ -67,9 +73,11 @@ pub async fn inspect_event(
 ))]
 #[web::post("/events/watch")]
 pub async fn watch_event(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   condition: Option<web::types::Json<Vec<EventCondition>>>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "event", RoleVerb::List, None).await?;
   let stream = state
     .subscribe_raw(condition.map(|c| c.into_inner()))
     .await?;
@@ -94,19 +102,43 @@ pub async fn watch_event(
 ))]
 #[web::get("/events/count")]
 pub async fn count_event(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "event", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = EventDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Delete the expired events
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Events",
+  path = "/events/prune",
+  responses(
+    (status = 200, description = "Number of deleted events", body = GenericCount),
+  ),
+))]
+#[web::post("/events/prune")]
+pub async fn prune_event(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "event", RoleVerb::Delete, None).await?;
+  let count = utils::retention::prune_events(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&GenericCount {
+    count: count as i64,
+  }))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_event);
   config.service(watch_event);
   config.service(inspect_event);
   config.service(count_event);
+  config.service(prune_event);
 }
 
 #[cfg(test)]
@@ -116,6 +148,7 @@ mod tests {
   use bollard_next::container::Config;
   use nanocl_stubs::{
     cargo_spec::CargoSpecPartial,
+    generic::GenericCount,
     system::{
       Event, EventActorKind, EventCondition, EventKind, NativeEventAction,
     },
@@ -150,6 +183,17 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "watch events");
   }
 
+  #[ntex::test]
+  async fn prune_events() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut res = client
+      .send_post("/events/prune", None::<String>, None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "prune events");
+    res.json::<GenericCount>().await.unwrap();
+  }
+
   #[ntex::test]
   async fn watch_events_condition() {
     const CARGO_NAME: &str = "event-condition";
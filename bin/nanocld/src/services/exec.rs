@@ -3,7 +3,7 @@ use ntex::web;
 use nanocl_error::http::HttpResult;
 
 use bollard_next::exec::{CreateExecOptions, StartExecOptions};
-use nanocl_stubs::generic::GenericNspQuery;
+use nanocl_stubs::{generic::GenericNspQuery, role::RoleVerb};
 
 use crate::utils;
 use crate::models::SystemState;
@@ -23,9 +23,11 @@ use crate::models::SystemState;
 ))]
 #[web::get("/exec/{id}/cargo/inspect")]
 pub async fn inspect_exec_command(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "cargo", RoleVerb::Exec, None).await?;
   let infos = utils::exec::inspect_exec_command(&path.1, &state).await?;
   Ok(web::HttpResponse::Ok().json(&infos))
 }
@@ -46,10 +48,12 @@ pub async fn inspect_exec_command(
 ))]
 #[web::post("/exec/{id}/cargo/start")]
 pub async fn start_exec_command(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<StartExecOptions>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "cargo", RoleVerb::Exec, None).await?;
   utils::exec::start_exec_command(&path.1, &payload, &state).await
 }
 
@@ -70,12 +74,21 @@ pub async fn start_exec_command(
 ))]
 #[web::post("/cargoes/{cargo_name}/exec")]
 pub async fn create_exec_command(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<CreateExecOptions>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "cargo",
+    RoleVerb::Exec,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let result = utils::exec::create_exec_command(&key, &payload, &state).await?;
   Ok(web::HttpResponse::Ok().json(&result))
@@ -3,6 +3,7 @@ use ntex::web;
 use nanocl_error::http::HttpResult;
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
+  role::RoleVerb,
   job::JobPartial,
 };
 
@@ -27,10 +28,12 @@ use crate::{
 ))]
 #[web::get("/jobs")]
 pub async fn list_job(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   _version: web::types::Path<String>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "job", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   log::debug!("job filter {filter:#?}");
   let jobs = JobDb::list(&filter, &state).await?;
@@ -49,10 +52,12 @@ pub async fn list_job(
 ))]
 #[web::post("/jobs")]
 pub async fn create_job(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   _version: web::types::Path<String>,
   payload: web::types::Json<JobPartial>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "job", RoleVerb::Create, None).await?;
   let job = JobDb::create_obj(&payload, &state).await?;
   Ok(web::HttpResponse::Created().json(&job))
 }
@@ -72,9 +77,11 @@ pub async fn create_job(
 ))]
 #[web::delete("/jobs/{name}")]
 pub async fn delete_job(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "job", RoleVerb::Delete, None).await?;
   JobDb::del_obj_by_pk(&path.1, &(), &state).await?;
   Ok(web::HttpResponse::Accepted().finish())
 }
@@ -93,9 +100,11 @@ pub async fn delete_job(
 ))]
 #[web::get("/jobs/{name}/inspect")]
 pub async fn inspect_job(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "job", RoleVerb::Inspect, None).await?;
   let job = JobDb::inspect_obj_by_pk(&path.1, &state).await?;
   Ok(web::HttpResponse::Ok().json(&job))
 }
@@ -114,9 +123,11 @@ pub async fn inspect_job(
 ))]
 #[web::get("/jobs/count")]
 pub async fn count_job(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "job", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = JobDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
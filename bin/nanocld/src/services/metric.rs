@@ -4,7 +4,8 @@ use nanocl_error::http::{HttpError, HttpResult};
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
-  metric::MetricPartial,
+  role::RoleVerb,
+  metric::{MetricAggregateQuery, MetricPartial},
 };
 
 use crate::{
@@ -13,6 +14,9 @@ use crate::{
   models::{MetricDb, MetricNodePartial, SystemState},
 };
 
+/// Maximum number of metrics created at once
+const MAX_BULK_METRICS: usize = 1000;
+
 /// Get metrics of all peer nodes
 #[cfg_attr(feature = "dev", utoipa::path(
   get,
@@ -27,9 +31,11 @@ use crate::{
 ))]
 #[web::get("/metrics")]
 pub async fn list_metric(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let metrics = MetricDb::read_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&metrics))
@@ -49,9 +55,12 @@ pub async fn list_metric(
 ))]
 #[web::get("/metrics/{key}/inspect")]
 pub async fn inspect_metric(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::Inspect, None)
+    .await?;
   let metric = MetricDb::read_by_pk(&path.1, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&metric))
 }
@@ -68,19 +77,69 @@ pub async fn inspect_metric(
 ))]
 #[web::post("/metrics")]
 pub async fn create_metric(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   _path: web::types::Path<String>,
   payload: web::types::Json<MetricPartial>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::Create, None)
+    .await?;
   if payload.kind.starts_with("nanocl.io") {
     return Err(HttpError::bad_request("reserved kind nanocl.io"));
   }
   let new_metric =
     MetricNodePartial::try_new_node(&state.inner.config.hostname, &payload)?;
-  let metric = MetricDb::create_from(&new_metric, &state.inner.pool).await?;
+  let metric = utils::retention::create_metric(&new_metric, &state).await?;
+  if metric.kind == "ncproxy.io/http" {
+    state.inner.http_counters.record(&metric.data);
+  }
   Ok(web::HttpResponse::Created().json(&metric))
 }
 
+/// Create metrics at once, used by the agents to submit their metrics in batches
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Metrics",
+  path = "/metrics/bulk",
+  request_body = Vec<MetricPartial>,
+  responses(
+    (status = 201, description = "Number of created metrics", body = GenericCount),
+  ),
+))]
+#[web::post("/metrics/bulk")]
+pub async fn create_bulk_metric(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  _path: web::types::Path<String>,
+  payload: web::types::Json<Vec<MetricPartial>>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::Create, None)
+    .await?;
+  if payload.len() > MAX_BULK_METRICS {
+    return Err(HttpError::bad_request(format!(
+      "too many metrics, the maximum is {MAX_BULK_METRICS}"
+    )));
+  }
+  let metrics = payload
+    .iter()
+    .map(|metric| {
+      if metric.kind.starts_with("nanocl.io") {
+        return Err(HttpError::bad_request("reserved kind nanocl.io"));
+      }
+      let metric =
+        MetricNodePartial::try_new_node(&state.inner.config.hostname, metric)?;
+      Ok(metric)
+    })
+    .collect::<HttpResult<Vec<_>>>()?;
+  let count = utils::retention::create_metrics(&metrics, &state).await?;
+  for metric in metrics.iter().filter(|m| m.kind == "ncproxy.io/http") {
+    state.inner.http_counters.record(&metric.data);
+  }
+  Ok(web::HttpResponse::Created().json(&GenericCount {
+    count: count as i64,
+  }))
+}
+
 /// Count metrics
 #[cfg_attr(feature = "dev", utoipa::path(
   get,
@@ -95,27 +154,106 @@ pub async fn create_metric(
 ))]
 #[web::get("/metrics/count")]
 pub async fn count_metric(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = MetricDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Delete the expired metrics
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Metrics",
+  path = "/metrics/prune",
+  responses(
+    (status = 200, description = "Number of deleted metrics", body = GenericCount),
+  ),
+))]
+#[web::post("/metrics/prune")]
+pub async fn prune_metric(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::Delete, None)
+    .await?;
+  let count = utils::retention::prune_metrics(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&GenericCount {
+    count: count as i64,
+  }))
+}
+
+/// Aggregate a field of the metrics of a kind over a time range
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Metrics",
+  path = "/metrics/aggregate",
+  params(
+    ("kind" = String, Query, description = "Kind of the metrics", example = "ncproxy.io/http"),
+    ("field" = String, Query, description = "Field of the data to aggregate", example = "RequestTime"),
+    ("node" = Option<String>, Query, description = "Only include the metrics of this node"),
+    ("since" = Option<i64>, Query, description = "Start of the range as unix timestamp (default to one hour ago)"),
+    ("until" = Option<i64>, Query, description = "End of the range as unix timestamp (default to now)"),
+    ("interval" = Option<u64>, Query, description = "Size of a bucket in seconds rounded to the minute (default to 60)"),
+  ),
+  responses(
+    (status = 200, description = "Aggregated values by bucket", body = MetricSeries),
+  ),
+))]
+#[web::get("/metrics/aggregate")]
+pub async fn aggregate_metric(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<MetricAggregateQuery>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::List, None).await?;
+  let series = utils::metric::aggregate(&qs, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&series))
+}
+
+/// Export the metrics of the cluster in the OpenMetrics text format
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Metrics",
+  path = "/metrics/prometheus",
+  responses(
+    (status = 200, description = "Metrics in the OpenMetrics text format", content_type = "application/openmetrics-text", body = String),
+  ),
+))]
+#[web::get("/metrics/prometheus")]
+pub async fn prometheus_metric(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "metric", RoleVerb::List, None).await?;
+  let families = utils::prometheus::collect(&state).await?;
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type(utils::prometheus::CONTENT_TYPE)
+      .body(utils::prometheus::render(&families)),
+  )
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_metric);
   config.service(create_metric);
+  config.service(create_bulk_metric);
   config.service(inspect_metric);
   config.service(count_metric);
+  config.service(prune_metric);
+  config.service(aggregate_metric);
+  config.service(prometheus_metric);
 }
 
 #[cfg(test)]
 mod tests {
   use ntex::http;
   use nanocl_stubs::{
-    metric::{Metric, MetricPartial},
-    generic::{GenericClause, GenericFilter, GenericListQuery},
+    metric::{Metric, MetricAggregateQuery, MetricPartial, MetricSeries},
+    generic::{GenericClause, GenericCount, GenericFilter, GenericListQuery},
   };
 
   use crate::utils::tests::*;
@@ -170,5 +308,89 @@ mod tests {
       )
       .await;
     test_status_code!(res.status(), http::StatusCode::OK, "inspect metric");
+    let mut res = client
+      .send_post(
+        &format!("{ENDPOINT}/bulk"),
+        Some(vec![
+          MetricPartial {
+            kind: "test.io/test".to_owned(),
+            data: serde_json::json!({ "Value": 1 }),
+            note: None,
+          },
+          MetricPartial {
+            kind: "test.io/test".to_owned(),
+            data: serde_json::json!({ "Value": 2 }),
+            note: None,
+          },
+        ]),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::CREATED,
+      "create bulk metrics"
+    );
+    assert_eq!(res.json::<GenericCount>().await.unwrap().count, 2);
+    let res = client
+      .send_post(
+        &format!("{ENDPOINT}/bulk"),
+        Some(vec![MetricPartial {
+          kind: "nanocl.io/test".to_owned(),
+          data: serde_json::json!({ "Value": 1 }),
+          note: None,
+        }]),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "create bulk metrics with a reserved kind"
+    );
+    let mut res = client
+      .send_post(&format!("{ENDPOINT}/prune"), None::<String>, None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "prune metrics");
+    res.json::<GenericCount>().await.unwrap();
+    let mut res = client
+      .send_get(
+        &format!("{ENDPOINT}/aggregate"),
+        Some(&MetricAggregateQuery {
+          kind: "test.io/test".to_owned(),
+          field: "Value".to_owned(),
+          interval: Some(90),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "aggregate metrics");
+    let series = res.json::<MetricSeries>().await.unwrap();
+    assert_eq!(series.interval, 120);
+    let res = client
+      .send_get(
+        &format!("{ENDPOINT}/aggregate"),
+        Some(&MetricAggregateQuery {
+          kind: "test.io/test".to_owned(),
+          field: "Value".to_owned(),
+          since: Some(10),
+          until: Some(0),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "aggregate metrics with an invalid range"
+    );
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/prometheus"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "prometheus metrics");
+    let body = res.body().await.unwrap();
+    let body = String::from_utf8_lossy(&body);
+    assert!(body.contains("# TYPE nanocl_node_ready gauge"));
+    assert!(body.ends_with("# EOF\n"));
   }
 }
@@ -15,6 +15,10 @@ mod metric;
 mod vm;
 mod vm_image;
 mod secret;
+mod token;
+mod role;
+mod network_policy;
+mod audit;
 mod job;
 mod process;
 mod resource_kind;
@@ -46,6 +50,10 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   }
   config.service(
     web::scope("/{version}")
+      .wrap(nanocl_utils::ntex::middlewares::BearerAuth::new(
+        crate::utils::token::TokenAuth,
+      ))
+      .wrap(crate::utils::audit::Audit)
       .wrap(
         nanocl_utils::ntex::middlewares::Versioning::new(crate::vars::VERSION)
           .finish(),
@@ -60,6 +68,10 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
       .configure(vm::ntex_config)
       .configure(metric::ntex_config)
       .configure(secret::ntex_config)
+      .configure(token::ntex_config)
+      .configure(role::ntex_config)
+      .configure(network_policy::ntex_config)
+      .configure(audit::ntex_config)
       .configure(process::ntex_config)
       .configure(job::ntex_config)
       .configure(event::ntex_config)
@@ -4,14 +4,15 @@ use nanocl_error::http::HttpResult;
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
-  namespace::NamespacePartial,
+  role::RoleVerb,
+  namespace::{NamespacePartial, NamespaceQuota},
 };
 
 use crate::{
   utils,
   objects::generic::*,
   repositories::generic::*,
-  models::{NamespaceDb, SystemState},
+  models::{NamespaceDb, NamespaceQuotaDb, SystemState},
 };
 
 /// List namespaces
@@ -28,9 +29,12 @@ use crate::{
 ))]
 #[web::get("/namespaces")]
 pub async fn list_namespace(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "namespace", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let items = NamespaceDb::list(&filter, &state).await?;
   Ok(web::HttpResponse::Ok().json(&items))
@@ -51,9 +55,18 @@ pub async fn list_namespace(
 ))]
 #[web::get("/namespaces/{name}/inspect")]
 pub async fn inspect_namespace(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "namespace",
+    RoleVerb::Inspect,
+    Some(&path.1),
+  )
+  .await?;
   let namespace = NamespaceDb::inspect_obj_by_pk(&path.1, &state).await?;
   Ok(web::HttpResponse::Ok().json(&namespace))
 }
@@ -71,9 +84,18 @@ pub async fn inspect_namespace(
 ))]
 #[web::post("/namespaces")]
 pub async fn create_namespace(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   payload: web::types::Json<NamespacePartial>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "namespace",
+    RoleVerb::Create,
+    Some(&payload.name),
+  )
+  .await?;
   let item = NamespaceDb::create_obj(&payload, &state).await?;
   Ok(web::HttpResponse::Created().json(&item))
 }
@@ -93,9 +115,18 @@ pub async fn create_namespace(
 ))]
 #[web::delete("/namespaces/{name}")]
 pub async fn delete_namespace(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "namespace",
+    RoleVerb::Delete,
+    Some(&path.1),
+  )
+  .await?;
   NamespaceDb::del_obj_by_pk(&path.1, &(), &state).await?;
   Ok(web::HttpResponse::Accepted().into())
 }
@@ -114,27 +145,137 @@ pub async fn delete_namespace(
 ))]
 #[web::get("/namespaces/count")]
 pub async fn count_namespace(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "namespace", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = NamespaceDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Get the quota of a namespace with his current usage
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Namespaces",
+  path = "/namespaces/{name}/quota",
+  params(
+    ("name" = String, Path, description = "Name of the namespace")
+  ),
+  responses(
+    (status = 200, description = "Quota of the namespace", body = NamespaceQuotaInspect),
+    (status = 404, description = "Namespace has no quota", body = ApiError),
+  ),
+))]
+#[web::get("/namespaces/{name}/quota")]
+pub async fn inspect_namespace_quota(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "namespace",
+    RoleVerb::Inspect,
+    Some(&path.1),
+  )
+  .await?;
+  let quota = utils::quota::inspect(&path.1, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&quota))
+}
+
+/// Create or replace the quota of a namespace
+#[cfg_attr(feature = "dev", utoipa::path(
+  put,
+  request_body = NamespaceQuota,
+  tag = "Namespaces",
+  path = "/namespaces/{name}/quota",
+  params(
+    ("name" = String, Path, description = "Name of the namespace")
+  ),
+  responses(
+    (status = 200, description = "Quota of the namespace", body = NamespaceQuotaInspect),
+    (status = 404, description = "Namespace is not existing", body = ApiError),
+  ),
+))]
+#[web::put("/namespaces/{name}/quota")]
+pub async fn put_namespace_quota(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<NamespaceQuota>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "namespace",
+    RoleVerb::Patch,
+    Some(&path.1),
+  )
+  .await?;
+  NamespaceDb::read_by_pk(&path.1, &state.inner.pool).await?;
+  let quota = utils::quota::put(&path.1, &payload, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&quota))
+}
+
+/// Delete the quota of a namespace
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Namespaces",
+  path = "/namespaces/{name}/quota",
+  params(
+    ("name" = String, Path, description = "Name of the namespace")
+  ),
+  responses(
+    (status = 202, description = "Quota have been deleted"),
+    (status = 404, description = "Namespace has no quota", body = ApiError),
+  ),
+))]
+#[web::delete("/namespaces/{name}/quota")]
+pub async fn delete_namespace_quota(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "namespace",
+    RoleVerb::Patch,
+    Some(&path.1),
+  )
+  .await?;
+  NamespaceQuotaDb::read_by_pk(&path.1, &state.inner.pool).await?;
+  NamespaceQuotaDb::del_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_namespace);
   config.service(create_namespace);
   config.service(inspect_namespace);
   config.service(delete_namespace);
   config.service(count_namespace);
+  config.service(inspect_namespace_quota);
+  config.service(put_namespace_quota);
+  config.service(delete_namespace_quota);
 }
 
 #[cfg(test)]
 mod test_namespace {
   use serde_json::json;
 
-  use nanocl_stubs::namespace::{Namespace, NamespacePartial};
+  use ntex::http;
+
+  use nanocl_stubs::{
+    generic::GenericNspQuery,
+    namespace::{
+      Namespace, NamespacePartial, NamespaceQuota, NamespaceQuotaInspect,
+    },
+  };
 
   use crate::utils::tests::*;
 
@@ -193,6 +334,39 @@ mod test_namespace {
     assert!(res.status().is_success(), "Expect success on delete");
   }
 
+  async fn quota(client: &TestClient) {
+    const NAME: &str = "controller-default";
+    let url = format!("{ENDPOINT}/{NAME}/quota");
+    let res = client.send_get(&url, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::NOT_FOUND, "no quota");
+    let quota = NamespaceQuota {
+      max_cargoes: Some(0),
+      ..Default::default()
+    };
+    let mut res = client.send_put(&url, Some(&quota), None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put quota");
+    let item = res.json::<NamespaceQuotaInspect>().await.unwrap();
+    assert_eq!(item.quota, quota);
+    assert_eq!(item.usage.cargoes, 0);
+    let res = client
+      .send_post(
+        "/cargoes",
+        Some(&json!({
+          "Name": "quota-exceeded",
+          "Container": { "Image": "alpine:latest" },
+        })),
+        Some(&GenericNspQuery::new(Some(NAME))),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::FORBIDDEN,
+      "cargo over quota"
+    );
+    let res = client.send_delete(&url, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete quota");
+  }
+
   #[ntex::test]
   async fn basic() {
     let system = gen_default_test_system().await;
@@ -200,6 +374,7 @@ mod test_namespace {
     test_fail_create(&client).await;
     create(&client).await;
     inspect_by_id(&client).await;
+    quota(&client).await;
     list(&client).await;
     delete(&client).await;
     system.state.wait_event_loop().await;
@@ -0,0 +1,308 @@
+/*
+* Endpoints to manipulate the network policies filtering the traffic
+* between the cargoes of a namespace
+*/
+use ntex::web;
+
+use nanocl_error::http::{HttpError, HttpResult};
+
+use nanocl_stubs::{
+  generic::{GenericClause, GenericListQueryNsp, GenericNspQuery},
+  network_policy::{NetworkPolicy, NetworkPolicyPartial},
+  role::RoleVerb,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{NamespaceDb, NetworkPolicyDb, SystemState},
+};
+
+/// List network policies
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "NetworkPolicies",
+  path = "/network-policies",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"name\": { \"eq\": \"db\" } } } }"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the policies are"),
+  ),
+  responses(
+    (status = 200, description = "List of network policy", body = [NetworkPolicy]),
+  ),
+))]
+#[web::get("/network-policies")]
+pub async fn list_network_policy(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQueryNsp>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "network_policy",
+    RoleVerb::List,
+    Some(&namespace),
+  )
+  .await?;
+  let query = utils::query_string::parse_qs_nsp_filter(&qs)?;
+  let filter = query
+    .filter
+    .unwrap_or_default()
+    .r#where("namespace_name", GenericClause::Eq(namespace));
+  let items =
+    NetworkPolicyDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&items))
+}
+
+/// Get detailed information about a network policy
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "NetworkPolicies",
+  path = "/network-policies/{name}/inspect",
+  params(
+    ("name" = String, Path, description = "Name of the network policy"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the policy belongs"),
+  ),
+  responses(
+    (status = 200, description = "Detailed information about a network policy", body = NetworkPolicy),
+    (status = 404, description = "Network policy is not existing", body = ApiError),
+  ),
+))]
+#[web::get("/network-policies/{name}/inspect")]
+pub async fn inspect_network_policy(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "network_policy",
+    RoleVerb::Inspect,
+    Some(&namespace),
+  )
+  .await?;
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let item: NetworkPolicy =
+    NetworkPolicyDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&item))
+}
+
+/// Create a network policy
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  request_body = NetworkPolicyPartial,
+  tag = "NetworkPolicies",
+  path = "/network-policies",
+  params(
+    ("namespace" = Option<String>, Query, description = "Namespace where the policy belongs"),
+  ),
+  responses(
+    (status = 201, description = "Network policy created", body = NetworkPolicy),
+    (status = 409, description = "Network policy already exist", body = ApiError),
+  ),
+))]
+#[web::post("/network-policies")]
+pub async fn create_network_policy(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  payload: web::types::Json<NetworkPolicyPartial>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "network_policy",
+    RoleVerb::Create,
+    Some(&namespace),
+  )
+  .await?;
+  utils::key::validate_name(&payload.name)?;
+  NamespaceDb::read_by_pk(&namespace, &state.inner.pool).await?;
+  let item = NetworkPolicyDb::try_new(&namespace, &payload)?;
+  if NetworkPolicyDb::read_by_pk(&item.key, &state.inner.pool)
+    .await
+    .is_ok()
+  {
+    return Err(HttpError::conflict(format!(
+      "Network policy {} already exists in namespace {namespace}",
+      payload.name
+    )));
+  }
+  let item: NetworkPolicy =
+    NetworkPolicyDb::create_from(item, &state.inner.pool)
+      .await?
+      .try_into()?;
+  utils::network_policy::spawn_sync(&state);
+  Ok(web::HttpResponse::Created().json(&item))
+}
+
+/// Replace the target and the ingress of a network policy
+#[cfg_attr(feature = "dev", utoipa::path(
+  put,
+  request_body = NetworkPolicyPartial,
+  tag = "NetworkPolicies",
+  path = "/network-policies/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the network policy"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the policy belongs"),
+  ),
+  responses(
+    (status = 200, description = "Network policy updated", body = NetworkPolicy),
+    (status = 404, description = "Network policy is not existing", body = ApiError),
+  ),
+))]
+#[web::put("/network-policies/{name}")]
+pub async fn put_network_policy(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<NetworkPolicyPartial>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "network_policy",
+    RoleVerb::Patch,
+    Some(&namespace),
+  )
+  .await?;
+  let key = utils::key::gen_key(&namespace, &path.1);
+  NetworkPolicyDb::read_by_pk(&key, &state.inner.pool).await?;
+  let mut payload = payload.into_inner();
+  payload.name.clone_from(&path.1);
+  let item = NetworkPolicyDb::try_new(&namespace, &payload)?;
+  let item: NetworkPolicy =
+    NetworkPolicyDb::update_pk(&key, item, &state.inner.pool)
+      .await?
+      .try_into()?;
+  utils::network_policy::spawn_sync(&state);
+  Ok(web::HttpResponse::Ok().json(&item))
+}
+
+/// Delete a network policy
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "NetworkPolicies",
+  path = "/network-policies/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the network policy"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the policy belongs"),
+  ),
+  responses(
+    (status = 202, description = "Network policy have been deleted"),
+    (status = 404, description = "Network policy is not existing", body = ApiError),
+  ),
+))]
+#[web::delete("/network-policies/{name}")]
+pub async fn delete_network_policy(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "network_policy",
+    RoleVerb::Delete,
+    Some(&namespace),
+  )
+  .await?;
+  let key = utils::key::gen_key(&namespace, &path.1);
+  NetworkPolicyDb::read_by_pk(&key, &state.inner.pool).await?;
+  NetworkPolicyDb::del_by_pk(&key, &state.inner.pool).await?;
+  utils::network_policy::spawn_sync(&state);
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_network_policy);
+  config.service(create_network_policy);
+  config.service(inspect_network_policy);
+  config.service(put_network_policy);
+  config.service(delete_network_policy);
+}
+
+#[cfg(test)]
+mod test_network_policy {
+  use ntex::http;
+
+  use nanocl_stubs::{
+    generic::GenericNspQuery,
+    network_policy::{
+      NetworkPolicy, NetworkPolicyIngress, NetworkPolicyPartial,
+      NetworkPolicyPort, NetworkPolicySelector,
+    },
+  };
+
+  use crate::utils::tests::*;
+
+  const ENDPOINT: &str = "/network-policies";
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let qs = GenericNspQuery::new(Some("global"));
+    let mut policy = NetworkPolicyPartial {
+      name: "test-policy".to_owned(),
+      target: NetworkPolicySelector {
+        cargoes: Some(vec!["db".to_owned()]),
+        labels: None,
+      },
+      ingress: vec![NetworkPolicyIngress {
+        from: vec![NetworkPolicySelector {
+          cargoes: Some(vec!["api".to_owned()]),
+          labels: None,
+        }],
+        ports: None,
+      }],
+      metadata: None,
+    };
+    let mut res = client.send_post(ENDPOINT, Some(&policy), Some(&qs)).await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create policy");
+    let created = res.json::<NetworkPolicy>().await.unwrap();
+    assert_eq!(created.key, "test-policy.global");
+    let res = client.send_post(ENDPOINT, Some(&policy), Some(&qs)).await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::CONFLICT,
+      "create existing policy"
+    );
+    policy.ingress[0].ports = Some(vec![NetworkPolicyPort {
+      port: 5432,
+      ..Default::default()
+    }]);
+    let mut res = client
+      .send_put(&format!("{ENDPOINT}/test-policy"), Some(&policy), Some(&qs))
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put policy");
+    let updated = res.json::<NetworkPolicy>().await.unwrap();
+    assert_eq!(updated.ingress, policy.ingress);
+    let res = client
+      .send_get(&format!("{ENDPOINT}/test-policy/inspect"), Some(&qs))
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect policy");
+    let mut res = client.send_get(ENDPOINT, Some(&qs)).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list policies");
+    let items = res.json::<Vec<NetworkPolicy>>().await.unwrap();
+    assert!(items.iter().any(|item| item.name == "test-policy"));
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-policy"), Some(&qs))
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::ACCEPTED,
+      "delete policy"
+    );
+  }
+}
@@ -8,14 +8,18 @@ use ntex::{
 };
 use futures::future::ready;
 
-use nanocl_error::http::HttpResult;
+use nanocl_error::http::{HttpError, HttpResult};
 
-use nanocl_stubs::generic::{GenericCount, GenericListQuery};
+use nanocl_stubs::{
+  generic::{GenericCount, GenericListQuery},
+  node::{NodeLeasePartial, CLIENT_LEASE_PREFIX},
+  role::RoleVerb,
+};
 
 use crate::{
   utils,
   repositories::generic::*,
-  models::{SystemState, WsConState, NodeDb},
+  models::{SystemState, WsConState, NodeDb, LeaseDb},
 };
 
 /// List nodes
@@ -32,9 +36,11 @@ use crate::{
 ))]
 #[web::get("/nodes")]
 pub async fn list_node(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "node", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let items = NodeDb::read_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&items))
@@ -54,14 +60,97 @@ pub async fn list_node(
 ))]
 #[web::get("/nodes/count")]
 pub async fn count_node(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "node", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = NodeDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Ensure a lease key can be used by a client, the other keys are reserved
+/// to the daemon for the leader and the tasks of the objects
+fn validate_client_lease(key: &str) -> HttpResult<()> {
+  match key.strip_prefix(CLIENT_LEASE_PREFIX) {
+    Some(name) if !name.is_empty() => Ok(()),
+    _ => Err(HttpError::bad_request(format!(
+      "Lease key must start with {CLIENT_LEASE_PREFIX}"
+    ))),
+  }
+}
+
+/// Acquire or extend a lease for the node of the daemon,
+/// it expires unless it's acquired again before its expiry
+#[cfg_attr(feature = "dev", utoipa::path(
+  put,
+  tag = "Nodes",
+  path = "/nodes/leases/{key}",
+  request_body = NodeLeasePartial,
+  params(
+    ("key" = String, Path, description = "Key of the lease starting with `ncproxy.io/acme/`"),
+  ),
+  responses(
+    (status = 200, description = "The acquired lease", body = NodeLease),
+    (status = 400, description = "The key is reserved", body = ApiError),
+    (status = 409, description = "The lease is held by another node", body = ApiError),
+  ),
+))]
+#[web::put("/nodes/leases/{key}*")]
+pub async fn acquire_node_lease(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<NodeLeasePartial>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "node", RoleVerb::Create, None).await?;
+  validate_client_lease(&path.1)?;
+  let node = &state.inner.config.hostname;
+  if !LeaseDb::acquire(
+    &path.1,
+    node,
+    &payload.action,
+    utils::lease::CLIENT_LEASE_TTL,
+    &state.inner.pool,
+  )
+  .await?
+  {
+    return Err(HttpError::conflict(format!(
+      "Lease {} is held by another node",
+      path.1
+    )));
+  }
+  let lease = LeaseDb::read_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&lease))
+}
+
+/// Release a lease held by the node of the daemon
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Nodes",
+  path = "/nodes/leases/{key}",
+  params(
+    ("key" = String, Path, description = "Key of the lease starting with `ncproxy.io/acme/`"),
+  ),
+  responses(
+    (status = 202, description = "The lease isn't held by the node anymore"),
+    (status = 400, description = "The key is reserved", body = ApiError),
+  ),
+))]
+#[web::delete("/nodes/leases/{key}*")]
+pub async fn release_node_lease(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "node", RoleVerb::Delete, None).await?;
+  validate_client_lease(&path.1)?;
+  LeaseDb::release(&path.1, &state.inner.config.hostname, &state.inner.pool)
+    .await?;
+  Ok(web::HttpResponse::Accepted().finish())
+}
+
 async fn node_ws_service(
   (sink, state): (ws::WsSink, web::types::State<SystemState>),
 ) -> Result<
@@ -114,6 +203,7 @@ pub async fn node_ws(
   state: web::types::State<SystemState>,
   req: web::HttpRequest,
 ) -> Result<web::HttpResponse, web::Error> {
+  utils::rbac::authorize(&req, &state, "node", RoleVerb::Exec, None).await?;
   web::ws::start(
     req,
     // inject state to the node_ws_service
@@ -127,6 +217,8 @@ pub async fn node_ws(
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_node);
   config.service(count_node);
+  config.service(acquire_node_lease);
+  config.service(release_node_lease);
   config.service(web::resource("/nodes/ws").route(web::get().to(node_ws)));
 }
 
@@ -135,9 +227,9 @@ mod tests {
 
   use ntex::http;
 
-  use nanocl_stubs::node::Node;
+  use nanocl_stubs::node::{Node, NodeLease, NodeLeasePartial, CLIENT_LEASE_PREFIX};
 
-  use crate::utils::tests::*;
+  use crate::utils::{tests::*, lease::LEADER_KEY};
 
   const ENDPOINT: &str = "/nodes";
 
@@ -149,4 +241,63 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "list nodes");
     let _ = res.json::<Vec<Node>>().await.unwrap();
   }
+
+  #[ntex::test]
+  async fn lease() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let key = format!("{CLIENT_LEASE_PREFIX}test-node-lease");
+    let payload = NodeLeasePartial {
+      action: "test".to_owned(),
+    };
+    for reserved in [LEADER_KEY, "Cargo@nstore.system", "test-node-lease"] {
+      let res = client
+        .send_put(
+          &format!("{ENDPOINT}/leases/{reserved}"),
+          Some(&payload),
+          None::<String>,
+        )
+        .await;
+      test_status_code!(
+        res.status(),
+        http::StatusCode::BAD_REQUEST,
+        "acquire reserved lease"
+      );
+      let res = client
+        .send_delete(&format!("{ENDPOINT}/leases/{reserved}"), None::<String>)
+        .await;
+      test_status_code!(
+        res.status(),
+        http::StatusCode::BAD_REQUEST,
+        "release reserved lease"
+      );
+    }
+    let mut res = client
+      .send_put(
+        &format!("{ENDPOINT}/leases/{key}"),
+        Some(&payload),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "acquire lease");
+    let lease = res.json::<NodeLease>().await.unwrap();
+    assert_eq!(lease.key, key);
+    assert_eq!(lease.action, "test");
+    let res = client
+      .send_put(
+        &format!("{ENDPOINT}/leases/{key}"),
+        Some(&payload),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "acquire held lease");
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/leases/{key}"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::ACCEPTED,
+      "release lease"
+    );
+  }
 }
@@ -34,10 +34,21 @@ use bollard_next::service::{
   HealthcheckResult,
 };
 
-use nanocl_stubs::node::Node;
+use nanocl_stubs::node::{
+  Node, NodeCapacity, NodeLease, NodeLeasePartial, NodeState,
+};
 use nanocl_stubs::process::{Process, ProcessKind, ProcessStats};
-use nanocl_stubs::config::DaemonConfig;
-use nanocl_stubs::secret::{Secret, SecretPartial, SecretUpdate};
+use nanocl_stubs::config::{DaemonConfig, RetentionConfig};
+use nanocl_stubs::secret::{
+  Secret, SecretKeyRotation, SecretPartial, SecretSummary, SecretUpdate,
+};
+use nanocl_stubs::token::{Token, TokenCreated, TokenPartial};
+use nanocl_stubs::role::{Role, RolePartial, RoleRule, RoleVerb};
+use nanocl_stubs::network_policy::{
+  NetworkPolicy, NetworkPolicyPartial, NetworkPolicySelector,
+  NetworkPolicyIngress, NetworkPolicyPort, NetworkPolicyProtocol,
+};
+use nanocl_stubs::audit::Audit;
 use nanocl_stubs::generic::{
   GenericCount, GenericClause, GenericFilter, GenericWhere, ImagePullPolicy,
 };
@@ -45,18 +56,19 @@ use nanocl_stubs::system::{
   BinaryInfo, Event, EventActor, EventActorKind, EventCondition, EventKind,
   HostInfo, NativeEventAction, ObjPsStatus, ObjPsStatusKind, SslConfig,
 };
-use nanocl_stubs::metric::{Metric, MetricPartial};
+use nanocl_stubs::metric::{Metric, MetricPartial, MetricPoint, MetricSeries};
 use nanocl_stubs::vm_image::{VmImage, VmImageResizePayload};
 use nanocl_stubs::namespace::{
   Namespace, NamespaceSummary, NamespacePartial, NamespaceInspect,
+  NamespaceQuota, NamespaceQuotaUsage, NamespaceQuotaInspect,
 };
-use nanocl_stubs::job::{Job, JobPartial, JobInspect, JobSummary};
+use nanocl_stubs::job::{Job, JobPartial, JobInspect, JobStep, JobSummary};
 use nanocl_stubs::cargo::{
   Cargo, CargoInspect, CargoSummary, CargoKillOptions, CreateExecOptions,
 };
 use nanocl_stubs::cargo_spec::{
   CargoSpec, CargoSpecPartial, CargoSpecUpdate, ReplicationMode,
-  ReplicationStatic,
+  ReplicationStatic, ReplicationAutoscale, UpdateStrategy,
 };
 use nanocl_stubs::vm::{Vm, VmInspect, VmSummary};
 use nanocl_stubs::vm_spec::{
@@ -74,7 +86,10 @@ use nanocl_stubs::proxy::{
   ResourceProxyRule, ProxyRuleHttp, ProxyHttpLocation, ProxySsl,
   ProxyRuleStream, StreamTarget, ProxyStreamProtocol, UriTarget,
   LocationTarget, HttpTarget, UrlRedirect, UpstreamTarget, ProxyRule,
-  UnixTarget, ProxySslConfig,
+  UnixTarget, ProxySslConfig, UpstreamBalancing, UpstreamHashKey, SplitTarget,
+  SplitWeight, SplitPin, ProxySslAcme, ProxyBasicAuth, ProxyAuthRequest,
+  ProxyAuthRequestTarget, ProxyCache, ProxyCacheValid, ProxyCacheBypass,
+  ProxyCompression, ProxyCors,
 };
 use nanocl_stubs::statefile::{
   Statefile, StatefileArg, StatefileArgKind, SubState, SubStateDef,
@@ -85,7 +100,7 @@ use crate::vars;
 
 use super::{
   node, system, namespace, exec, cargo, vm, vm_image, resource, metric, secret,
-  job, process, resource_kind, event,
+  job, process, resource_kind, event, token, role, audit, network_policy,
 };
 
 /// When returning a [HttpError](nanocl_error::http::HttpError)
@@ -231,6 +246,8 @@ impl Modify for VersionModifier {
     node::list_node,
     node::count_node,
     node::node_ws,
+    node::acquire_node_lease,
+    node::release_node_lease,
     // System
     system::get_info,
     system::get_version,
@@ -241,6 +258,9 @@ impl Modify for VersionModifier {
     namespace::create_namespace,
     namespace::delete_namespace,
     namespace::count_namespace,
+    namespace::inspect_namespace_quota,
+    namespace::put_namespace_quota,
+    namespace::delete_namespace_quota,
     // Secret
     secret::list_secret,
     secret::inspect_secret,
@@ -248,6 +268,28 @@ impl Modify for VersionModifier {
     secret::delete_secret,
     secret::patch_secret,
     secret::count_secret,
+    secret::rotate_secret_key,
+    // Token
+    token::list_token,
+    token::inspect_token,
+    token::create_token,
+    token::delete_token,
+    // Role
+    role::list_role,
+    role::inspect_role,
+    role::create_role,
+    role::put_role,
+    role::delete_role,
+    // Network Policy
+    network_policy::list_network_policy,
+    network_policy::inspect_network_policy,
+    network_policy::create_network_policy,
+    network_policy::put_network_policy,
+    network_policy::delete_network_policy,
+    // Audit
+    audit::list_audit,
+    audit::inspect_audit,
+    audit::count_audit,
     // Job
     job::list_job,
     job::delete_job,
@@ -302,8 +344,12 @@ impl Modify for VersionModifier {
     // Metric
     metric::list_metric,
     metric::create_metric,
+    metric::create_bulk_metric,
     metric::inspect_metric,
     metric::count_metric,
+    metric::prune_metric,
+    metric::aggregate_metric,
+    metric::prometheus_metric,
     // Process
     process::logs_processes,
     process::logs_process,
@@ -320,14 +366,39 @@ impl Modify for VersionModifier {
     event::watch_event,
     event::inspect_event,
     event::count_event,
+    event::prune_event,
   ),
   components(schemas(
     // Node
     Node,
+    NodeState,
+    NodeLease,
+    NodeLeasePartial,
+    NodeCapacity,
     // Secret
     Secret,
     SecretPartial,
     SecretUpdate,
+    SecretKeyRotation,
+    SecretSummary,
+    // Token
+    Token,
+    TokenPartial,
+    TokenCreated,
+    // Role
+    Role,
+    RolePartial,
+    RoleRule,
+    RoleVerb,
+    // Network Policy
+    NetworkPolicy,
+    NetworkPolicyPartial,
+    NetworkPolicySelector,
+    NetworkPolicyIngress,
+    NetworkPolicyPort,
+    NetworkPolicyProtocol,
+    // Audit
+    Audit,
     // System
     BinaryInfo,
     HostInfo,
@@ -366,6 +437,9 @@ impl Modify for VersionModifier {
     NamespacePartial,
     NamespaceInspect,
     NamespaceSummary,
+    NamespaceQuota,
+    NamespaceQuotaUsage,
+    NamespaceQuotaInspect,
     // Process
     Process,
     ProcessKind,
@@ -377,6 +451,7 @@ impl Modify for VersionModifier {
     Job,
     JobPartial,
     JobInspect,
+    JobStep,
     JobSummary,
     // Cargo
     Cargo,
@@ -389,6 +464,8 @@ impl Modify for VersionModifier {
     CargoSpecPartial,
     CargoSpecUpdate,
     ReplicationStatic,
+    ReplicationAutoscale,
+    UpdateStrategy,
     PidsStats,
     NetworkStats,
     BlkioStats,
@@ -486,6 +563,15 @@ impl Modify for VersionModifier {
     ProxyHttpLocation,
     ProxySsl,
     ProxySslConfig,
+    ProxySslAcme,
+    ProxyBasicAuth,
+    ProxyAuthRequest,
+    ProxyAuthRequestTarget,
+    ProxyCache,
+    ProxyCacheValid,
+    ProxyCacheBypass,
+    ProxyCompression,
+    ProxyCors,
     ProxyRuleStream,
     StreamTarget,
     ProxyStreamProtocol,
@@ -493,6 +579,11 @@ impl Modify for VersionModifier {
     HttpTarget,
     UrlRedirect,
     UpstreamTarget,
+    UpstreamBalancing,
+    UpstreamHashKey,
+    SplitTarget,
+    SplitWeight,
+    SplitPin,
     UnixTarget,
     UriTarget,
     // DnsRules
@@ -507,8 +598,11 @@ impl Modify for VersionModifier {
     // Metric
     Metric,
     MetricPartial,
+    MetricPoint,
+    MetricSeries,
     // Daemon
     DaemonConfig,
+    RetentionConfig,
     // Error
     ApiError,
     // Generic Types
@@ -538,6 +632,10 @@ impl Modify for VersionModifier {
     (name = "Metrics", description = "Metrics management endpoints."),
     (name = "Processes", description = "Processes management endpoints."),
     (name = "Secrets", description = "Secrets management endpoints."),
+    (name = "Tokens", description = "Api tokens management endpoints."),
+    (name = "Roles", description = "Roles management endpoints."),
+    (name = "NetworkPolicies", description = "Network policies management endpoints."),
+    (name = "Audit", description = "Audit of the mutating api calls."),
     (name = "Jobs", description = "Jobs management endpoints."),
     (name = "Events", description = "Events management endpoints."),
   ),
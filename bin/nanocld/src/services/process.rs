@@ -12,6 +12,7 @@ use bollard_next::{
 use nanocl_stubs::{
   cargo::CargoKillOptions,
   generic::{GenericCount, GenericListQuery, GenericNspQuery},
+  role::RoleVerb,
   process::{
     ProcessLogQuery, ProcessOutputLog, ProcessStats, ProcessStatsQuery,
     ProcessWaitQuery, ProcessWaitResponse,
@@ -38,9 +39,11 @@ use crate::{
 ))]
 #[web::get("/processes")]
 pub async fn list_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "process", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let processes =
     ProcessDb::transform_read_by(&filter, &state.inner.pool).await?;
@@ -68,12 +71,21 @@ pub async fn list_processes(
 ))]
 #[web::get("/processes/{kind}/{name}/logs")]
 async fn logs_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<ProcessLogQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Logs,
+    &qs.namespace,
+  )
+  .await?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   let processes =
     ProcessDb::read_by_kind_key(&kind_key, &state.inner.pool).await?;
@@ -136,10 +148,12 @@ async fn logs_processes(
 ))]
 #[web::get("/processes/{name}/logs")]
 async fn logs_process(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<ProcessLogQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "process", RoleVerb::Logs, None).await?;
   let (_, name) = path.into_inner();
   log::debug!("process::logs_process: {name}");
   let options: LogsOptions<String> = qs.into_inner().into();
@@ -187,9 +201,12 @@ async fn logs_process(
 ))]
 #[web::post("/processes/{pk}/start")]
 pub async fn start_process_by_pk(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "process", RoleVerb::Patch, None)
+    .await?;
   let (_, pk) = path.into_inner();
   let process = ProcessDb::read_by_pk(&pk, &state.inner.pool).await?;
   state
@@ -216,12 +233,21 @@ pub async fn start_process_by_pk(
 ))]
 #[web::post("/processes/{kind}/{name}/start")]
 pub async fn start_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Patch,
+    &qs.namespace,
+  )
+  .await?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   utils::container::emit_starting(&kind_key, &kind, &state).await?;
   Ok(web::HttpResponse::Accepted().finish())
@@ -243,12 +269,21 @@ pub async fn start_processes(
 ))]
 #[web::post("/processes/{kind}/{name}/restart")]
 pub async fn restart_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Patch,
+    &qs.namespace,
+  )
+  .await?;
   let kind_pk = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   utils::container::restart_instances(&kind_pk, &kind, &state).await?;
   Ok(web::HttpResponse::Accepted().finish())
@@ -270,12 +305,21 @@ pub async fn restart_processes(
 ))]
 #[web::post("/processes/{kind}/{name}/stop")]
 pub async fn stop_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Patch,
+    &qs.namespace,
+  )
+  .await?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   utils::container::emit_stopping(&kind_key, &kind, &state).await?;
   Ok(web::HttpResponse::Accepted().finish())
@@ -298,6 +342,7 @@ pub async fn stop_processes(
 ))]
 #[web::post("/processes/{kind}/{name}/kill")]
 pub async fn kill_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   payload: web::types::Json<CargoKillOptions>,
@@ -305,6 +350,14 @@ pub async fn kill_processes(
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Patch,
+    &qs.namespace,
+  )
+  .await?;
   let kind_pk = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   utils::container::kill_by_kind_key(&kind_pk, &payload, &state).await?;
   Ok(web::HttpResponse::Ok().into())
@@ -325,12 +378,21 @@ pub async fn kill_processes(
 ))]
 #[web::get("/processes/{kind}/{name}/wait")]
 pub async fn wait_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<ProcessWaitQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Inspect,
+    &qs.namespace,
+  )
+  .await?;
   let kind_pk = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   let opts = WaitContainerOptions {
     condition: qs.condition.clone().unwrap_or_default(),
@@ -400,12 +462,21 @@ pub async fn wait_processes(
 ))]
 #[web::get("/processes/{kind}/{name}/stats")]
 pub async fn stats_processes(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
   qs: web::types::Query<ProcessStatsQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
+  utils::rbac::authorize_process(
+    &req,
+    &state,
+    &kind,
+    RoleVerb::Inspect,
+    &qs.namespace,
+  )
+  .await?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
   let opts: StatsOptions = qs.clone().into();
   let processes =
@@ -449,9 +520,11 @@ pub async fn stats_processes(
 ))]
 #[web::get("/processes/count")]
 pub async fn count_process(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "process", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = ProcessDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
@@ -474,11 +547,21 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
 mod tests {
   use ntex::http;
 
-  use crate::utils::tests::*;
+  use crate::{
+    services,
+    vars::VERSION,
+    utils::{self, tests::*},
+    models::{RoleDb, TokenDb},
+    repositories::generic::*,
+  };
 
   use nanocl_stubs::{
+    config::DaemonConfig,
+    cargo::CargoKillOptions,
     generic::{GenericClause, GenericFilter, GenericListQuery},
     process::{Process, ProcessStatsQuery},
+    role::{RolePartial, RoleRule, RoleVerb},
+    token::TokenPartial,
   };
 
   #[ntex::test]
@@ -544,4 +627,64 @@ mod tests {
     let items: Vec<Process> = res.json::<Vec<Process>>().await.unwrap();
     assert!(items.iter().any(|i| i.name == "nstore.system.c"));
   }
+
+  #[ntex::test]
+  async fn job_outside_of_namespace() {
+    let config = DaemonConfig {
+      auth: true,
+      ..gen_test_config()
+    };
+    let system =
+      gen_test_system_from(config, services::ntex_config, VERSION).await;
+    let client = system.client;
+    let pool = &system.state.inner.pool;
+    let name = "test-process-nsp";
+    let role = RolePartial {
+      name: name.to_owned(),
+      rules: vec![RoleRule {
+        kinds: vec!["process".to_owned()],
+        verbs: vec![RoleVerb::Patch],
+        namespaces: Some(vec!["dev".to_owned()]),
+      }],
+      subjects: vec![format!("token:{name}")],
+      metadata: None,
+    };
+    RoleDb::create_from(RoleDb::try_from(&role).unwrap(), pool)
+      .await
+      .unwrap();
+    let token = utils::token::create(
+      &TokenPartial {
+        name: name.to_owned(),
+        ..Default::default()
+      },
+      &system.state,
+    )
+    .await
+    .unwrap();
+    // The namespace of the query doesn't scope the jobs
+    let res = client
+      .post("/processes/job/test-job/kill?Namespace=dev")
+      .header("Authorization", format!("Bearer {}", token.secret))
+      .send_json(&CargoKillOptions::default())
+      .await
+      .unwrap();
+    test_status_code!(
+      res.status(),
+      http::StatusCode::FORBIDDEN,
+      "kill job with a namespace scoped token"
+    );
+    let res = client
+      .post("/processes/cargo/test-cargo/kill?Namespace=prod")
+      .header("Authorization", format!("Bearer {}", token.secret))
+      .send_json(&CargoKillOptions::default())
+      .await
+      .unwrap();
+    test_status_code!(
+      res.status(),
+      http::StatusCode::FORBIDDEN,
+      "kill cargo outside of the token namespace"
+    );
+    RoleDb::del_by_pk(name, pool).await.unwrap();
+    TokenDb::del_by_pk(name, pool).await.unwrap();
+  }
 }
@@ -7,6 +7,7 @@ use nanocl_error::http::HttpResult;
 
 use nanocl_stubs::{
   generic::{GenericClause, GenericCount, GenericFilter, GenericListQuery},
+  role::RoleVerb,
   resource::{ResourcePartial, ResourceSpec, ResourceUpdate},
 };
 
@@ -31,9 +32,12 @@ use crate::{
 ))]
 #[web::get("/resources")]
 pub async fn list_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let items = ResourceDb::transform_read_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&items))
@@ -54,9 +58,12 @@ pub async fn list_resource(
 ))]
 #[web::get("/resources/{name}/inspect")]
 pub async fn inspect_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::Inspect, None)
+    .await?;
   let resource =
     ResourceDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&resource))
@@ -74,9 +81,12 @@ pub async fn inspect_resource(
 ))]
 #[web::post("/resources")]
 pub async fn create_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   payload: web::types::Json<ResourcePartial>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::Create, None)
+    .await?;
   let resource = ResourceDb::create_obj(&payload, &state).await?;
   Ok(web::HttpResponse::Created().json(&resource))
 }
@@ -96,9 +106,12 @@ pub async fn create_resource(
 ))]
 #[web::delete("/resources/{name}")]
 pub async fn delete_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::Delete, None)
+    .await?;
   ResourceDb::del_obj_by_pk(&path.1, &(), &state).await?;
   Ok(web::HttpResponse::Accepted().finish())
 }
@@ -119,10 +132,13 @@ pub async fn delete_resource(
 ))]
 #[web::put("/resources/{name}")]
 pub async fn put_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<ResourceUpdate>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::Patch, None)
+    .await?;
   let resource =
     ResourceDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
   let new_resource = ResourcePartial {
@@ -151,9 +167,12 @@ pub async fn put_resource(
 ))]
 #[web::get("/resources/{name}/histories")]
 pub async fn list_resource_history(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::Inspect, None)
+    .await?;
   let filter =
     GenericFilter::new().r#where("kind_key", GenericClause::Eq(path.1.clone()));
   let items = SpecDb::read_by(&filter, &state.inner.pool)
@@ -180,9 +199,12 @@ pub async fn list_resource_history(
 ))]
 #[web::patch("/resources/{name}/histories/{id}/revert")]
 pub async fn revert_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, uuid::Uuid)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::Patch, None)
+    .await?;
   let history = SpecDb::read_by_pk(&path.2, &state.inner.pool).await?;
   let resource =
     ResourceDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
@@ -211,9 +233,12 @@ pub async fn revert_resource(
 ))]
 #[web::get("/resources/count")]
 pub async fn count_resource(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = ResourceDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
@@ -4,6 +4,7 @@ use nanocl_error::http::HttpResult;
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
+  role::RoleVerb,
   resource_kind::{ResourceKindPartial, ResourceKindVersion},
 };
 
@@ -27,10 +28,13 @@ use crate::{
 ))]
 #[web::get("/resource/kinds")]
 pub async fn list_resource_kind(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   _version: web::types::Path<String>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource_kind", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let resource_kinds =
     ResourceKindDb::transform_read_by(&filter, &state.inner.pool).await?;
@@ -49,10 +53,13 @@ pub async fn list_resource_kind(
 ))]
 #[web::post("/resource/kinds")]
 pub async fn create_resource_kind(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   _version: web::types::Path<String>,
   payload: web::types::Json<ResourceKindPartial>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource_kind", RoleVerb::Create, None)
+    .await?;
   let item =
     ResourceKindDb::create_from_spec(&payload, &state.inner.pool).await?;
   Ok(web::HttpResponse::Created().json(&item))
@@ -74,9 +81,12 @@ pub async fn create_resource_kind(
 ))]
 #[web::delete("/resource/kinds/{domain}/{name}")]
 pub async fn delete_resource_kind(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource_kind", RoleVerb::Delete, None)
+    .await?;
   let key = format!("{}/{}", path.1, path.2);
   ResourceKindDb::read_by_pk(&key, &state.inner.pool).await?;
   ResourceKindDb::del_by_pk(&key, &state.inner.pool).await?;
@@ -99,9 +109,18 @@ pub async fn delete_resource_kind(
 ))]
 #[web::get("/resource/kinds/{domain}/{name}/inspect")]
 pub async fn inspect_resource_kind(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "resource_kind",
+    RoleVerb::Inspect,
+    None,
+  )
+  .await?;
   let key: String = format!("{}/{}", path.1, path.2);
   let kind = ResourceKindDb::inspect_by_pk(&key, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&kind))
@@ -122,9 +141,18 @@ pub async fn inspect_resource_kind(
 ))]
 #[web::get("/resource/kinds/{domain}/{name}/version/{version}/inspect")]
 pub async fn inspect_resource_kind_version(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "resource_kind",
+    RoleVerb::Inspect,
+    None,
+  )
+  .await?;
   let key = format!("{}/{}", path.1, path.2);
   let kind_version =
     SpecDb::get_version(&key, &path.3, &state.inner.pool).await?;
@@ -146,9 +174,12 @@ pub async fn inspect_resource_kind_version(
 ))]
 #[web::get("/resource/kinds/count")]
 pub async fn count_resource_kind(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "resource_kind", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = ResourceKindDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
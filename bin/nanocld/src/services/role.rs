@@ -0,0 +1,226 @@
+/*
+* Endpoints to manipulate the roles granting access to the http api
+*/
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+
+use nanocl_stubs::{
+  generic::GenericListQuery,
+  role::{Role, RolePartial, RoleVerb},
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{RoleDb, SystemState},
+};
+
+/// List roles
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Roles",
+  path = "/roles",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"key\": { \"eq\": \"developer\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "List of role", body = [Role]),
+  ),
+))]
+#[web::get("/roles")]
+pub async fn list_role(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "role", RoleVerb::List, None).await?;
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let items = RoleDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&items))
+}
+
+/// Get detailed information about a role
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Roles",
+  path = "/roles/{name}/inspect",
+  params(
+    ("name" = String, Path, description = "Name of the role"),
+  ),
+  responses(
+    (status = 200, description = "Detailed information about a role", body = Role),
+    (status = 404, description = "Role is not existing", body = ApiError),
+  ),
+))]
+#[web::get("/roles/{name}/inspect")]
+pub async fn inspect_role(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "role", RoleVerb::Inspect, None).await?;
+  let role: Role =
+    RoleDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&role))
+}
+
+/// Create a role
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  request_body = RolePartial,
+  tag = "Roles",
+  path = "/roles",
+  responses(
+    (status = 201, description = "Role created", body = Role),
+    (status = 409, description = "Role already exist", body = ApiError),
+  ),
+))]
+#[web::post("/roles")]
+pub async fn create_role(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  payload: web::types::Json<RolePartial>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "role", RoleVerb::Create, None).await?;
+  utils::key::validate_name(&payload.name)?;
+  for rule in &payload.rules {
+    utils::rbac::validate_kinds(&rule.kinds)?;
+  }
+  let item = RoleDb::try_from(&*payload)?;
+  let role: Role = RoleDb::create_from(item, &state.inner.pool)
+    .await?
+    .try_into()?;
+  Ok(web::HttpResponse::Created().json(&role))
+}
+
+/// Replace the rules and subjects of a role
+#[cfg_attr(feature = "dev", utoipa::path(
+  put,
+  request_body = RolePartial,
+  tag = "Roles",
+  path = "/roles/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the role"),
+  ),
+  responses(
+    (status = 200, description = "Role updated", body = Role),
+    (status = 404, description = "Role is not existing", body = ApiError),
+  ),
+))]
+#[web::put("/roles/{name}")]
+pub async fn put_role(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<RolePartial>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "role", RoleVerb::Patch, None).await?;
+  for rule in &payload.rules {
+    utils::rbac::validate_kinds(&rule.kinds)?;
+  }
+  RoleDb::read_by_pk(&path.1, &state.inner.pool).await?;
+  let item = RoleDb::try_from(&*payload)?;
+  let role: Role = RoleDb::update_pk(&path.1, item, &state.inner.pool)
+    .await?
+    .try_into()?;
+  Ok(web::HttpResponse::Ok().json(&role))
+}
+
+/// Delete a role
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Roles",
+  path = "/roles/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the role")
+  ),
+  responses(
+    (status = 202, description = "Role have been deleted"),
+    (status = 404, description = "Role don't exists", body = ApiError),
+  ),
+))]
+#[web::delete("/roles/{name}")]
+pub async fn delete_role(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "role", RoleVerb::Delete, None).await?;
+  RoleDb::read_by_pk(&path.1, &state.inner.pool).await?;
+  RoleDb::del_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_role);
+  config.service(create_role);
+  config.service(inspect_role);
+  config.service(put_role);
+  config.service(delete_role);
+}
+
+#[cfg(test)]
+mod test_role {
+  use ntex::http;
+
+  use nanocl_stubs::role::{Role, RolePartial, RoleRule, RoleVerb};
+
+  use crate::utils::tests::*;
+
+  const ENDPOINT: &str = "/roles";
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let mut role = RolePartial {
+      name: "test-role".to_owned(),
+      rules: vec![RoleRule {
+        kinds: vec!["cargoes".to_owned()],
+        verbs: vec![RoleVerb::List],
+        namespaces: Some(vec!["global".to_owned()]),
+      }],
+      subjects: vec!["token:test-role".to_owned()],
+      metadata: None,
+    };
+    let res = client
+      .send_post(ENDPOINT, Some(&role), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "create role with unknown kind"
+    );
+    role.rules[0].kinds = vec!["cargo".to_owned()];
+    let mut res = client
+      .send_post(ENDPOINT, Some(&role), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create role");
+    let created = res.json::<Role>().await.unwrap();
+    assert_eq!(created.rules, role.rules);
+    role.rules[0].verbs.push(RoleVerb::Logs);
+    let mut res = client
+      .send_put(
+        &format!("{ENDPOINT}/test-role"),
+        Some(&role),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put role");
+    let updated = res.json::<Role>().await.unwrap();
+    assert_eq!(updated.rules[0].verbs, vec![RoleVerb::List, RoleVerb::Logs]);
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/test-role/inspect"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect role");
+    let _ = res.json::<Role>().await.unwrap();
+    let mut res = client.send_get(ENDPOINT, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list roles");
+    let _ = res.json::<Vec<Role>>().await.unwrap();
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-role"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete role");
+  }
+}
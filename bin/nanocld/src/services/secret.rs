@@ -8,8 +8,9 @@ use nanocl_error::http::{HttpError, HttpResult};
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
+  role::RoleVerb,
   proxy::ProxySslConfig,
-  secret::{SecretPartial, SecretUpdate},
+  secret::{SecretInspectQuery, SecretPartial, SecretSummary, SecretUpdate},
 };
 
 use crate::{
@@ -28,16 +29,22 @@ use crate::{
     ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"kind\": { \"eq\": \"Env\" } } } }"),
   ),
   responses(
-    (status = 200, description = "List of secret", body = [Secret]),
+    (status = 200, description = "List of secret without their data", body = [SecretSummary]),
   ),
 ))]
 #[web::get("/secrets")]
 pub async fn list_secret(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
-  let items = SecretDb::transform_read_by(&filter, &state.inner.pool).await?;
+  let items = SecretDb::transform_read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .map(SecretSummary::from)
+    .collect::<Vec<_>>();
   Ok(web::HttpResponse::Ok().json(&items))
 }
 
@@ -47,7 +54,8 @@ pub async fn list_secret(
   tag = "Secrets",
   path = "/secrets/{key}/inspect",
   params(
-    ("key" = String, Path, description = "Key of the secret")
+    ("key" = String, Path, description = "Key of the secret"),
+    ("reveal" = Option<bool>, Query, description = "Return the data of the secret instead of a mask"),
   ),
   responses(
     (status = 200, description = "Detailed information about a secret", body = Secret),
@@ -56,11 +64,17 @@ pub async fn list_secret(
 ))]
 #[web::get("/secrets/{key}/inspect")]
 pub async fn inspect_secret(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
+  qs: web::types::Query<SecretInspectQuery>,
 ) -> HttpResult<web::HttpResponse> {
-  let secret =
-    SecretDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::Inspect, None)
+    .await?;
+  let mut secret = SecretDb::inspect_obj_by_pk(&path.1, &state).await?;
+  if !qs.reveal.unwrap_or_default() {
+    secret = secret.redact();
+  }
   Ok(web::HttpResponse::Ok().json(&secret))
 }
 
@@ -71,22 +85,25 @@ pub async fn inspect_secret(
   tag = "Secrets",
   path = "/secrets",
   responses(
-    (status = 200, description = "List of secret", body = Secret),
+    (status = 201, description = "Secret created with its data masked", body = Secret),
     (status = 409, description = "Namespace already exist", body = ApiError),
   ),
 ))]
 #[web::post("/secrets")]
 pub async fn create_secret(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   payload: web::types::Json<SecretPartial>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::Create, None)
+    .await?;
   utils::key::ensure_kind(&payload.kind)?;
   match payload.kind.as_str() {
     "nanocl.io/tls" => {
       serde_json::from_value::<ProxySslConfig>(payload.data.clone())
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
     }
-    "nanocl.io/env" => {
+    "nanocl.io/env" | "nanocl.io/htpasswd" => {
       serde_json::from_value::<Vec<String>>(payload.data.clone())
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
     }
@@ -96,7 +113,7 @@ pub async fn create_secret(
     }
     _ => {}
   }
-  let secret = SecretDb::create_obj(&payload, &state).await?;
+  let secret = SecretDb::create_obj(&payload, &state).await?.redact();
   Ok(web::HttpResponse::Created().json(&secret))
 }
 
@@ -115,9 +132,12 @@ pub async fn create_secret(
 ))]
 #[web::delete("/secrets/{key}")]
 pub async fn delete_secret(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::Delete, None)
+    .await?;
   SecretDb::del_obj_by_pk(&path.1, &(), &state).await?;
   Ok(web::HttpResponse::Accepted().into())
 }
@@ -132,17 +152,21 @@ pub async fn delete_secret(
     ("key" = String, Path, description = "Key of the secret"),
   ),
   responses(
-    (status = 200, description = "Secret scaled", body = Secret),
+    (status = 200, description = "Secret updated with its data masked", body = Secret),
     (status = 404, description = "Secret does not exist", body = ApiError),
   ),
 ))]
 #[web::patch("/secrets/{key}")]
 pub async fn patch_secret(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<SecretUpdate>,
 ) -> HttpResult<web::HttpResponse> {
-  let item = SecretDb::patch_obj_by_pk(&path.1, &payload, &state).await?;
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::Patch, None).await?;
+  let item = SecretDb::patch_obj_by_pk(&path.1, &payload, &state)
+    .await?
+    .redact();
   Ok(web::HttpResponse::Ok().json(&item))
 }
 
@@ -160,15 +184,38 @@ pub async fn patch_secret(
 ))]
 #[web::get("/secrets/count")]
 pub async fn count_secret(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::List, None).await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let count = SecretDb::count_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&GenericCount { count }))
 }
 
+/// Generate a new master key and encrypt every secret with it
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Secrets",
+  path = "/secrets/rotate-key",
+  responses(
+    (status = 200, description = "Secrets encrypted with the new key", body = SecretKeyRotation),
+    (status = 409, description = "A rotation is already in progress", body = ApiError),
+  ),
+))]
+#[web::post("/secrets/rotate-key")]
+pub async fn rotate_secret_key(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "secret", RoleVerb::Patch, None).await?;
+  let rotation = utils::crypto::rotate_master_key(&state).await?;
+  Ok(web::HttpResponse::Ok().json(&rotation))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(rotate_secret_key);
   config.service(list_secret);
   config.service(create_secret);
   config.service(inspect_secret);
@@ -183,15 +230,23 @@ mod test_secret {
 
   use serde_json::json;
 
-  use nanocl_stubs::secret::{Secret, SecretPartial};
+  use nanocl_stubs::secret::{
+    Secret, SecretInspectQuery, SecretKeyRotation, SecretPartial,
+    SecretSummary, SecretUpdate, SECRET_MASK,
+  };
 
   use crate::utils::tests::*;
 
   const ENDPOINT: &str = "/secrets";
 
   async fn test_list(client: &TestClient) {
-    let res = client.send_get(ENDPOINT, None::<String>).await;
+    let mut res = client.send_get(ENDPOINT, None::<String>).await;
     test_status_code!(res.status(), http::StatusCode::OK, "list secrets");
+    let secrets = res.json::<Vec<serde_json::Value>>().await.unwrap();
+    for secret in secrets {
+      assert!(secret.get("Data").is_none(), "list secrets leak data");
+      serde_json::from_value::<SecretSummary>(secret).unwrap();
+    }
   }
 
   async fn test_create(client: &TestClient) {
@@ -208,7 +263,13 @@ mod test_secret {
       .send_post(ENDPOINT, Some(new_secret), None::<String>)
       .await;
     test_status_code!(res.status(), http::StatusCode::CREATED, "create secret");
-    let _ = res.json::<Secret>().await.unwrap();
+    let secret = res.json::<Secret>().await.unwrap();
+    assert_eq!(
+      secret.data,
+      json!({
+        "Tls": { "cert": SECRET_MASK, "key": SECRET_MASK },
+      })
+    );
   }
 
   async fn test_fail_create(client: &TestClient) {
@@ -237,10 +298,97 @@ mod test_secret {
   }
 
   async fn test_inspect_by_id(client: &TestClient) {
-    let res = client
+    let mut res = client
       .send_get(&format!("{ENDPOINT}/test-secret/inspect"), None::<String>)
       .await;
     test_status_code!(res.status(), http::StatusCode::OK, "inspect secret");
+    let secret = res.json::<Secret>().await.unwrap();
+    assert_eq!(
+      secret.data,
+      json!({
+        "Tls": { "cert": SECRET_MASK, "key": SECRET_MASK },
+      })
+    );
+    let mut res = client
+      .send_get(
+        &format!("{ENDPOINT}/test-secret/inspect"),
+        Some(SecretInspectQuery { reveal: Some(true) }),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::OK,
+      "inspect secret revealed"
+    );
+    let secret = res.json::<Secret>().await.unwrap();
+    assert_eq!(
+      secret.data,
+      json!({
+        "Tls": { "cert": "MY CERT", "key": "MY KEY" },
+      })
+    );
+  }
+
+  async fn test_patch(client: &TestClient) {
+    let update = SecretUpdate {
+      metadata: None,
+      data: json!({
+        "Tls": { "cert": "MY NEW CERT", "key": "MY NEW KEY" },
+      }),
+    };
+    let mut res = client
+      .send_patch(
+        &format!("{ENDPOINT}/test-secret"),
+        Some(update),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "patch secret");
+    let secret = res.json::<Secret>().await.unwrap();
+    assert_eq!(
+      secret.data,
+      json!({
+        "Tls": { "cert": SECRET_MASK, "key": SECRET_MASK },
+      })
+    );
+    let mut res = client
+      .send_get(
+        &format!("{ENDPOINT}/test-secret/inspect"),
+        Some(SecretInspectQuery { reveal: Some(true) }),
+      )
+      .await;
+    let secret = res.json::<Secret>().await.unwrap();
+    assert_eq!(
+      secret.data,
+      json!({
+        "Tls": { "cert": "MY NEW CERT", "key": "MY NEW KEY" },
+      })
+    );
+  }
+
+  async fn test_rotate_key(client: &TestClient) {
+    let mut res = client
+      .send_post(
+        &format!("{ENDPOINT}/rotate-key"),
+        None::<String>,
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "rotate secret key");
+    let _ = res.json::<SecretKeyRotation>().await.unwrap();
+    let mut res = client
+      .send_get(
+        &format!("{ENDPOINT}/test-secret/inspect"),
+        Some(SecretInspectQuery { reveal: Some(true) }),
+      )
+      .await;
+    let secret = res.json::<Secret>().await.unwrap();
+    assert_eq!(
+      secret.data,
+      json!({
+        "Tls": { "cert": "MY NEW CERT", "key": "MY NEW KEY" },
+      })
+    );
   }
 
   async fn test_delete(client: &TestClient) {
@@ -262,6 +410,8 @@ mod test_secret {
     test_create(&client).await;
     test_inspect_by_id(&client).await;
     test_list(&client).await;
+    test_patch(&client).await;
+    test_rotate_key(&client).await;
     test_delete(&client).await;
   }
 }
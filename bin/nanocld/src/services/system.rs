@@ -2,9 +2,9 @@ use ntex::web;
 
 use nanocl_error::http::HttpResult;
 
-use nanocl_stubs::system::HostInfo;
+use nanocl_stubs::{role::RoleVerb, system::HostInfo};
 
-use crate::vars;
+use crate::{vars, utils};
 use crate::models::SystemState;
 
 /// Get version information
@@ -51,8 +51,11 @@ pub async fn get_version() -> web::HttpResponse {
 ))]
 #[web::get("/info")]
 pub async fn get_info(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "system", RoleVerb::Inspect, None)
+    .await?;
   let docker = state.inner.docker_api.info().await?;
   let host_gateway = state.inner.config.gateway.clone();
   let info = HostInfo {
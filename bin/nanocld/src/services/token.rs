@@ -0,0 +1,198 @@
+/*
+* Endpoints to manipulate the tokens of the http api
+*/
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+
+use nanocl_stubs::{
+  generic::GenericListQuery,
+  role::RoleVerb,
+  token::{Token, TokenPartial},
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{SystemState, TokenDb},
+};
+
+/// List tokens
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Tokens",
+  path = "/tokens",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"key\": { \"eq\": \"ci\" } } } }"),
+  ),
+  responses(
+    (status = 200, description = "List of token without their secret", body = [Token]),
+  ),
+))]
+#[web::get("/tokens")]
+pub async fn list_token(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQuery>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "token", RoleVerb::List, None).await?;
+  let filter = utils::query_string::parse_qs_filter(&qs)?;
+  let items = TokenDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&items))
+}
+
+/// Get detailed information about a token
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Tokens",
+  path = "/tokens/{name}/inspect",
+  params(
+    ("name" = String, Path, description = "Name of the token"),
+  ),
+  responses(
+    (status = 200, description = "Detailed information about a token", body = Token),
+    (status = 404, description = "Token is not existing", body = ApiError),
+  ),
+))]
+#[web::get("/tokens/{name}/inspect")]
+pub async fn inspect_token(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "token", RoleVerb::Inspect, None)
+    .await?;
+  let token: Token =
+    TokenDb::transform_read_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&token))
+}
+
+/// Create a token, his secret is only returned in this response
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  request_body = TokenPartial,
+  tag = "Tokens",
+  path = "/tokens",
+  responses(
+    (status = 201, description = "Token created with his secret", body = TokenCreated),
+    (status = 409, description = "Token already exist", body = ApiError),
+  ),
+))]
+#[web::post("/tokens")]
+pub async fn create_token(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  payload: web::types::Json<TokenPartial>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "token", RoleVerb::Create, None).await?;
+  let token = utils::token::create(&payload, &state).await?;
+  Ok(web::HttpResponse::Created().json(&token))
+}
+
+/// Delete a token
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Tokens",
+  path = "/tokens/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the token")
+  ),
+  responses(
+    (status = 202, description = "Token have been deleted"),
+    (status = 404, description = "Token don't exists", body = ApiError),
+  ),
+))]
+#[web::delete("/tokens/{name}")]
+pub async fn delete_token(
+  req: web::HttpRequest,
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "token", RoleVerb::Delete, None).await?;
+  TokenDb::read_by_pk(&path.1, &state.inner.pool).await?;
+  TokenDb::del_by_pk(&path.1, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_token);
+  config.service(create_token);
+  config.service(inspect_token);
+  config.service(delete_token);
+}
+
+#[cfg(test)]
+mod test_token {
+  use ntex::{http, web};
+
+  use nanocl_stubs::token::{Token, TokenCreated, TokenPartial};
+  use nanocl_utils::ntex::middlewares::BearerValidator;
+
+  use crate::utils::{tests::*, token::TokenAuth};
+
+  const ENDPOINT: &str = "/tokens";
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(
+        ENDPOINT,
+        Some(&TokenPartial {
+          name: "test-token".to_owned(),
+          expires_at: Some(chrono::DateTime::UNIX_EPOCH.naive_utc()),
+          ..Default::default()
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "create expired token"
+    );
+    let mut res = client
+      .send_post(
+        ENDPOINT,
+        Some(&TokenPartial {
+          name: "test-token".to_owned(),
+          ..Default::default()
+        }),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create token");
+    let created = res.json::<TokenCreated>().await.unwrap();
+    assert_eq!(created.token.name, "test-token");
+    // The token is validated with his secret only
+    let req = web::test::TestRequest::default()
+      .state(system.state.clone())
+      .to_srv_request();
+    let identity = TokenAuth.validate(&req, &created.secret).await.unwrap();
+    assert_eq!(identity.name, "test-token");
+    assert!(TokenAuth.validate(&req, "ncl_invalid").await.is_err());
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/test-token/inspect"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect token");
+    let token = res.json::<Token>().await.unwrap();
+    assert!(token.last_used_at.is_some());
+    let mut res = client.send_get(ENDPOINT, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list tokens");
+    let tokens = res.json::<Vec<serde_json::Value>>().await.unwrap();
+    assert!(tokens.iter().all(|token| token.get("Hash").is_none()));
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-token"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete token");
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-token"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::NOT_FOUND,
+      "delete deleted token"
+    );
+  }
+}
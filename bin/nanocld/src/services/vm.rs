@@ -16,6 +16,7 @@ use nanocl_error::{
 
 use bollard_next::container::AttachContainerOptions;
 use nanocl_stubs::{
+  role::RoleVerb,
   generic::{
     GenericClause, GenericCount, GenericListQueryNsp, GenericNspQuery,
   },
@@ -47,9 +48,13 @@ use crate::{
 ))]
 #[web::get("/vms")]
 pub async fn list_vm(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQueryNsp>,
 ) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(&req, &state, "vm", RoleVerb::List, Some(&namespace))
+    .await?;
   let query = utils::query_string::parse_qs_nsp_filter(&qs)?;
   let vms = VmDb::list(&query, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&vms))
@@ -70,12 +75,21 @@ pub async fn list_vm(
 ))]
 #[web::get("/vms/{name}/inspect")]
 pub async fn inspect_vm(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let name = path.1.to_owned();
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "vm",
+    RoleVerb::Inspect,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &name);
   let vm = VmDb::inspect_obj_by_pk(&key, &state).await?;
   Ok(web::HttpResponse::Ok().json(&vm))
@@ -96,12 +110,21 @@ pub async fn inspect_vm(
 ))]
 #[web::delete("/vms/{name}")]
 pub async fn delete_vm(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let name = path.1.to_owned();
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "vm",
+    RoleVerb::Delete,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &name);
   VmDb::del_obj_by_pk(&key, &(), &state).await?;
   Ok(web::HttpResponse::Ok().finish())
@@ -122,12 +145,21 @@ pub async fn delete_vm(
 ))]
 #[web::post("/vms")]
 pub async fn create_vm(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<String>,
   payload: web::types::Json<VmSpecPartial>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "vm",
+    RoleVerb::Create,
+    Some(&namespace),
+  )
+  .await?;
   let obj = VmObjCreateIn {
     namespace,
     spec: payload.into_inner(),
@@ -152,11 +184,20 @@ pub async fn create_vm(
 ))]
 #[web::get("/vms/{name}/histories")]
 pub async fn list_vm_history(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(
+    &req,
+    &state,
+    "vm",
+    RoleVerb::Inspect,
+    Some(&namespace),
+  )
+  .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let histories = SpecDb::read_by_kind_key(&key, &state.inner.pool)
     .await?
@@ -183,12 +224,15 @@ pub async fn list_vm_history(
 ))]
 #[web::patch("/vms/{name}")]
 pub async fn patch_vm(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<VmSpecUpdate>,
   qs: web::types::Query<GenericNspQuery>,
 ) -> HttpResult<web::HttpResponse> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(&req, &state, "vm", RoleVerb::Patch, Some(&namespace))
+    .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   let version = path.0.clone();
   let obj = &VmObjPatchIn {
@@ -317,6 +361,8 @@ pub async fn vm_attach(
   qs: web::types::Query<GenericNspQuery>,
 ) -> Result<web::HttpResponse, web::Error> {
   let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(&req, &state, "vm", RoleVerb::Exec, Some(&namespace))
+    .await?;
   let key = utils::key::gen_key(&namespace, &path.1);
   web::ws::start(
     req,
@@ -342,11 +388,14 @@ pub async fn vm_attach(
 ))]
 #[web::get("/vms/count")]
 pub async fn count_vm(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQueryNsp>,
 ) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  utils::rbac::authorize(&req, &state, "vm", RoleVerb::List, Some(&namespace))
+    .await?;
   let query = utils::query_string::parse_qs_nsp_filter(&qs)?;
-  let namespace = utils::key::resolve_nsp(&query.namespace);
   let filter = query
     .filter
     .unwrap_or_default()
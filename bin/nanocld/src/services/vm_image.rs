@@ -7,6 +7,7 @@ use nanocl_error::http::{HttpError, HttpResult};
 
 use nanocl_stubs::{
   generic::{GenericCount, GenericListQuery},
+  role::RoleVerb,
   vm_image::VmImageResizePayload,
 };
 
@@ -30,9 +31,12 @@ use crate::{
 ))]
 #[web::get("/vms/images")]
 pub async fn list_vm_images(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::List, None)
+    .await?;
   let filter = utils::query_string::parse_qs_filter(&qs)?;
   let images = VmImageDb::read_by(&filter, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&images))
@@ -53,10 +57,13 @@ pub async fn list_vm_images(
 ))]
 #[web::post("/vms/images/{name}/import")]
 pub async fn import_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   mut payload: web::types::Payload,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::Create, None)
+    .await?;
   let name = path.1.to_owned();
   utils::key::validate_name(&name)?;
   if VmImageDb::read_by_pk(&name, &state.inner.pool)
@@ -110,9 +117,12 @@ pub async fn import_vm_image(
 ))]
 #[web::post("/vms/images/{name}/snapshot/{snapshot_name}")]
 pub async fn snapshot_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::Create, None)
+    .await?;
   let name = path.1.to_owned();
   let snapshot_name = path.2.to_owned();
   utils::key::validate_name(&snapshot_name)?;
@@ -138,9 +148,12 @@ pub async fn snapshot_vm_image(
 ))]
 #[web::post("/vms/images/{name}/clone/{clone_name}")]
 pub async fn clone_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::Create, None)
+    .await?;
   let name = path.1.to_owned();
   let clone_name = path.2.to_owned();
   utils::key::validate_name(&clone_name)?;
@@ -164,10 +177,13 @@ pub async fn clone_vm_image(
 ))]
 #[web::post("/vms/images/{name}/resize")]
 pub async fn resize_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
   web::types::Json(payload): web::types::Json<VmImageResizePayload>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::Patch, None)
+    .await?;
   let name = path.1.to_owned();
   let rx =
     utils::vm_image::resize_by_name(&name, &payload, &state.inner.pool).await?;
@@ -188,9 +204,12 @@ pub async fn resize_vm_image(
 ))]
 #[web::get("/vms/images/{name}/inspect")]
 pub async fn inspect_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::Inspect, None)
+    .await?;
   let name = path.1.to_owned();
   let item = VmImageDb::read_by_pk(&name, &state.inner.pool).await?;
   Ok(web::HttpResponse::Ok().json(&item))
@@ -210,9 +229,12 @@ pub async fn inspect_vm_image(
 ))]
 #[web::delete("/vms/images/{name}")]
 pub async fn delete_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   path: web::types::Path<(String, String)>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::Delete, None)
+    .await?;
   let pk = path.1.to_owned();
   utils::vm_image::delete_by_pk(&pk, &state).await?;
   Ok(web::HttpResponse::Ok().into())
@@ -232,9 +254,12 @@ pub async fn delete_vm_image(
 ))]
 #[web::get("/vms/images/count")]
 pub async fn count_vm_image(
+  req: web::HttpRequest,
   state: web::types::State<SystemState>,
   qs: web::types::Query<GenericListQuery>,
 ) -> HttpResult<web::HttpResponse> {
+  utils::rbac::authorize(&req, &state, "vm_image", RoleVerb::List, None)
+    .await?;
   let filter: nanocl_stubs::generic::GenericFilter =
     utils::query_string::parse_qs_filter(&qs)?;
   let count = VmImageDb::count_by(&filter, &state.inner.pool).await?;
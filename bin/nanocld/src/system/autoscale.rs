@@ -0,0 +1,84 @@
+use std::{
+  collections::HashMap,
+  time::{Duration, Instant},
+};
+
+use ntex::{rt, time::interval};
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  system::ObjPsStatusKind,
+};
+
+use crate::{
+  utils::{self, autoscale::AutoscaleHistory},
+  repositories::generic::*,
+  models::{CargoDb, SystemState},
+};
+
+/// Delay between two samples of the autoscaled cargoes
+const AUTOSCALE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Scale every started cargo with an `Autoscale` replication
+async fn autoscale_cargoes(
+  histories: &mut HashMap<String, AutoscaleHistory>,
+  state: &SystemState,
+) -> IoResult<()> {
+  let mut offset = 0;
+  let mut sampled = Vec::new();
+  loop {
+    let filter = GenericFilter::new()
+      .r#where(
+        "data",
+        GenericClause::Contains(serde_json::json!({
+          "Replication": {
+            "Mode": "Autoscale"
+          }
+        })),
+      )
+      .limit(100)
+      .offset(offset);
+    let cargoes =
+      CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+    for cargo in &cargoes {
+      if cargo.status.actual != ObjPsStatusKind::Start {
+        continue;
+      }
+      let key = &cargo.spec.cargo_key;
+      sampled.push(key.clone());
+      let history = histories
+        .entry(key.clone())
+        .or_insert_with(|| AutoscaleHistory::new(Instant::now()));
+      if let Err(err) =
+        utils::autoscale::autoscale_cargo(cargo, history, state).await
+      {
+        log::warn!("autoscale: {} {err}", cargo.spec.cargo_key);
+      }
+    }
+    if cargoes.len() < 100 {
+      break;
+    }
+    offset += 100;
+  }
+  histories.retain(|key, _| sampled.contains(key));
+  Ok(())
+}
+
+/// Spawn a background thread that sample the cargoes with an `Autoscale` replication
+/// and scale them to reach their CPU and memory targets.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let interval = interval(AUTOSCALE_INTERVAL);
+      let mut histories = HashMap::new();
+      loop {
+        interval.tick().await;
+        if let Err(err) = autoscale_cargoes(&mut histories, &state).await {
+          log::error!("autoscale::spawn: {err}");
+        }
+      }
+    });
+  });
+}
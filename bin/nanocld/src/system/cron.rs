@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Utc};
+use ntex::{rt, time::interval};
+
+use nanocl_error::{http::HttpResult, io::IoResult};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  process::ProcessKind,
+  system::ObjPsStatusKind,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{JobDb, JobUpdateDb, ObjPsStatusDb, SystemState},
+};
+
+/// Seconds between two reload of the scheduled jobs by the leader
+const SYNC_INTERVAL: u64 = 60;
+
+/// Start a scheduled job unless his previous run is still in progress
+async fn run_job(
+  name: &str,
+  next_run: Option<NaiveDateTime>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let status = ObjPsStatusDb::read_by_pk(name, &state.inner.pool).await?;
+  let actual = status.actual.parse().unwrap_or_default();
+  if matches!(actual, ObjPsStatusKind::Starting | ObjPsStatusKind::Start) {
+    log::info!("cron::run_job: {name} is still running skipping this run");
+    JobDb::update_pk(
+      name,
+      JobUpdateDb {
+        next_run_at: next_run,
+        ..Default::default()
+      },
+      &state.inner.pool,
+    )
+    .await?;
+    return Ok(());
+  }
+  log::debug!("cron::run_job: {name}");
+  JobDb::update_pk(
+    name,
+    JobUpdateDb {
+      last_run_at: Some(Utc::now().naive_utc()),
+      next_run_at: next_run,
+      ..Default::default()
+    },
+    &state.inner.pool,
+  )
+  .await?;
+  utils::container::emit_starting(name, &ProcessKind::Job, state).await?;
+  Ok(())
+}
+
+/// Load every job with a schedule from the store into the scheduler
+async fn sync_jobs(state: &SystemState) -> IoResult<()> {
+  state.inner.cron_manager.clear().await;
+  let mut offset = 0;
+  loop {
+    let filter = GenericFilter::new()
+      .r#where("data", GenericClause::HasKey("Schedule".to_owned()))
+      .limit(100)
+      .offset(offset);
+    let jobs = JobDb::transform_read_by(&filter, &state.inner.pool).await?;
+    for job in &jobs {
+      if let Err(err) = state
+        .inner
+        .cron_manager
+        .add_job(job, &state.inner.pool)
+        .await
+      {
+        log::warn!("cron::sync_jobs: {} {err}", job.name);
+      }
+    }
+    if jobs.len() < 100 {
+      break;
+    }
+    offset += 100;
+  }
+  Ok(())
+}
+
+/// Spawn a background thread that start the jobs with a `schedule`
+/// when their next run is due.
+/// Only the leader start the jobs, it reload them from the store every minute
+/// to pick up the schedules created on the other nodes.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let interval = interval(Duration::from_secs(1));
+      let mut ticks_since_sync: Option<u64> = None;
+      loop {
+        interval.tick().await;
+        if !state.is_leader() {
+          ticks_since_sync = None;
+          continue;
+        }
+        if ticks_since_sync.map_or(true, |ticks| ticks >= SYNC_INTERVAL) {
+          ticks_since_sync = Some(0);
+          if let Err(err) = sync_jobs(&state).await {
+            log::error!("cron::spawn: {err}");
+          }
+        }
+        ticks_since_sync = ticks_since_sync.map(|ticks| ticks + 1);
+        let due = state.inner.cron_manager.take_due(&Utc::now()).await;
+        for (name, next_run) in due {
+          let state = state.clone();
+          rt::spawn(async move {
+            if let Err(err) = run_job(&name, next_run, &state).await {
+              log::warn!("cron::run_job: {name} {err}");
+            }
+          });
+        }
+      }
+    });
+  });
+}
@@ -14,7 +14,7 @@ use nanocl_stubs::system::{
 };
 
 use crate::{
-  vars,
+  vars, utils,
   repositories::generic::*,
   models::{ObjPsStatusDb, ProcessDb, ProcessUpdateDb, SystemState},
 };
@@ -151,6 +151,9 @@ async fn exec_docker(
     "destroy" => {
       state.spawn_emit_event(event);
       let _ = ProcessDb::del_by_pk(&id, &state.inner.pool).await;
+      if kind == EventActorKind::Cargo {
+        utils::network_policy::spawn_sync(state);
+      }
       return Ok(());
     }
     "create" => {
@@ -178,6 +181,10 @@ async fn exec_docker(
     ..Default::default()
   };
   ProcessDb::update_pk(&id, new_instance, &state.inner.pool).await?;
+  // The address of the instance may have changed
+  if kind == EventActorKind::Cargo {
+    utils::network_policy::spawn_sync(state);
+  }
   Ok(())
 }
 
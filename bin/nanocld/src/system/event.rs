@@ -14,7 +14,6 @@ use nanocl_stubs::{
 use crate::{
   utils,
   tasks::generic::*,
-  objects::generic::*,
   repositories::generic::*,
   models::{CargoDb, JobDb, ObjPsStatusDb, ProcessDb, SystemState, VmDb},
 };
@@ -27,6 +26,12 @@ async fn job_ttl(actor: &EventActor, state: &SystemState) -> IoResult<()> {
     Some(job_id) => job_id.as_str().unwrap_or_default(),
   };
   log::debug!("event::job_ttl: {job_id}");
+  // The start task of the job set his status once all the steps are done
+  let task_key = format!("{}@{job_id}", EventActorKind::Job);
+  if state.inner.task_manager.get_task(&task_key).await.is_some() {
+    log::debug!("event::job_ttl: {job_id} steps are still running");
+    return Ok(());
+  }
   let job = JobDb::transform_read_by_pk(job_id, &state.inner.pool).await?;
   match job.status.actual {
     ObjPsStatusKind::Finish | ObjPsStatusKind::Fail => {
@@ -46,38 +51,12 @@ async fn job_ttl(actor: &EventActor, state: &SystemState) -> IoResult<()> {
   if running != 0 {
     return Ok(());
   }
-  println!("instance_failed: {instance_failed}");
-  if instance_failed > 0 {
-    ObjPsStatusDb::update_actual_status(
-      &job.name,
-      &ObjPsStatusKind::Fail,
-      &state.inner.pool,
-    )
-    .await?;
-    state
-      .emit_normal_native_action_sync(&job, NativeEventAction::Fail)
-      .await;
+  let status = if instance_failed > 0 {
+    ObjPsStatusKind::Fail
   } else {
-    ObjPsStatusDb::update_actual_status(
-      &job.name,
-      &ObjPsStatusKind::Finish,
-      &state.inner.pool,
-    )
-    .await?;
-    state
-      .emit_normal_native_action_sync(&job, NativeEventAction::Finish)
-      .await;
-  }
-  let ttl = match job.ttl {
-    None => return Ok(()),
-    Some(ttl) => ttl,
+    ObjPsStatusKind::Finish
   };
-  let state = state.clone();
-  rt::spawn(async move {
-    log::debug!("event::job_ttl: {} will be deleted in {ttl}s", job.name);
-    ntex::time::sleep(std::time::Duration::from_secs(ttl as u64)).await;
-    let _ = JobDb::del_obj_by_pk(&job.name, &(), &state).await;
-  });
+  utils::job::finish(&job, &status, None, state).await?;
   Ok(())
 }
 
@@ -254,6 +233,13 @@ async fn _exec_event(e: &Event, state: &SystemState) -> IoResult<()> {
     _ => None,
   };
   let Some(task) = task else { return Ok(()) };
+  // Only the node holding the lease of the object act on it
+  let task = match actor.kind {
+    EventActorKind::Cargo | EventActorKind::Vm | EventActorKind::Job => {
+      utils::lease::own_task(&task_key, &action, task, state)
+    }
+    _ => task,
+  };
   // push the task into the task manager
   let state_ptr = state.clone();
   let actor = actor.clone();
@@ -1,4 +1,4 @@
-use std::{path::Path, process::Command, os::unix::prelude::PermissionsExt};
+use std::{path::Path, os::unix::prelude::PermissionsExt};
 
 use ntex::rt;
 use tokio::fs;
@@ -81,30 +81,6 @@ fn set_uds_perm() {
   });
 }
 
-/// Create a new thread and spawn and manage a crond instance to run cron jobs
-fn spawn_crond() {
-  log::trace!("boot::spawn_crond: start thread");
-  rt::Arbiter::new().exec_fn(|| {
-    rt::spawn(async {
-      let task = ntex::web::block(move || {
-        match Command::new("crond").args(["-f"]).spawn() {
-          Ok(mut child) => {
-            child.wait()?;
-            Ok(())
-          }
-          Err(err) => Err(err),
-        }
-      })
-      .await;
-      if let Err(err) = task {
-        log::error!("boot::spawn_crond: {err}");
-      }
-      log::trace!("boot::spawn_crond: stop thread");
-      rt::Arbiter::current().stop();
-    });
-  });
-}
-
 /// Ensure that the state dir exists and is ready to use
 async fn ensure_state_dir(state_dir: &str) -> IoResult<()> {
   let vm_dir = format!("{state_dir}/vms/images");
@@ -117,7 +93,6 @@ async fn ensure_state_dir(state_dir: &str) -> IoResult<()> {
 /// Init function called before http server start.
 /// To boot and initialize our state and database.
 pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
-  spawn_crond();
   set_uds_perm();
   ensure_state_dir(&conf.state_dir).await?;
   let system_state = SystemState::new(conf).await?;
@@ -125,6 +100,15 @@ pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
   NodeDb::register(&system_ptr).await?;
   utils::system::register_namespace("global", true, &system_ptr).await?;
   utils::system::register_namespace("system", false, &system_ptr).await?;
+  // A node that can't read the master keys still boot without its secrets
+  match utils::crypto::encrypt_secrets(&system_ptr).await {
+    Ok(encrypted) if encrypted > 0 => {
+      log::info!("boot::init: {encrypted} secrets encrypted");
+    }
+    Ok(_) => {}
+    Err(err) => log::error!("boot::init: {err}"),
+  }
+  utils::lease::elect(&system_ptr).await?;
   rt::spawn(async move {
     let fut = async move {
       utils::system::sync_processes(&system_ptr).await?;
@@ -138,6 +122,13 @@ pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
   });
   super::docker_event::analyze(&system_state);
   super::metric::spawn(&system_state);
+  super::lease::spawn(&system_state);
+  super::node::spawn(&system_state);
+  super::reconcile::spawn(&system_state);
+  super::retention::spawn(&system_state);
+  super::network_policy::spawn(&system_state);
+  super::cron::spawn(&system_state);
+  super::autoscale::spawn(&system_state);
   Ok(system_state)
 }
 
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use ntex::{rt, time::interval};
+
+use nanocl_error::io::IoResult;
+
+use crate::{utils, models::SystemState};
+
+/// Renew the leases of the node, run the election
+/// and take over the tasks of the dead nodes when leader
+async fn sync_leases(state: &SystemState) -> IoResult<()> {
+  utils::lease::renew(state).await?;
+  if utils::lease::elect(state).await? {
+    utils::lease::take_over(state).await?;
+  }
+  Ok(())
+}
+
+/// Spawn a background thread that keep the leases of the node alive
+/// so the other nodes know it's still acting on its objects.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let interval =
+        interval(Duration::from_secs(utils::lease::LEASE_RENEW_INTERVAL));
+      loop {
+        interval.tick().await;
+        if let Err(err) = sync_leases(&state).await {
+          log::error!("lease::spawn: {err}");
+        }
+      }
+    });
+  });
+}
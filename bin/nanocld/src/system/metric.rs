@@ -8,20 +8,16 @@ use nanocl_error::io::IoResult;
 use metrsd_client::{MetrsdClient, stubs::MetrsdEvent};
 
 use crate::{
-  repositories::generic::*,
-  models::{Pool, SystemState, MetricDb, MetricNodePartial},
+  utils,
+  models::{SystemState, MetricNodePartial},
 };
 
 /// Save metric event send by [metrsd](http://github.com/next-hat/metrs) to the database
 /// The event can be a `CPU`, `MEMORY`, `DISK` or `NETWORK` event.
 /// The metric is saved for the current node.
 /// This allow us to know what node is the most used.
-async fn save_metric(
-  node: &str,
-  ev: &MetrsdEvent,
-  pool: &Pool,
-) -> IoResult<()> {
-  let node_name = node.to_owned();
+async fn save_metric(ev: &MetrsdEvent, state: &SystemState) -> IoResult<()> {
+  let node_name = state.inner.config.hostname.clone();
   let kind = "nanocl.io/metrs";
   let data = serde_json::to_value(ev)?;
   let mut cpu_percent = ev.cpus.iter().fold(0.0, |acc, cpu| acc + cpu.usage);
@@ -47,16 +43,32 @@ async fn save_metric(
     kind: kind.to_owned(),
     note: Some(display),
   };
-  MetricDb::create_from(&metric, pool).await?;
+  utils::retention::create_metric(&metric, state).await?;
   Ok(())
 }
 
+/// Rollup every minute the metrics saved by the current node
+/// to aggregate them over time ranges
+async fn rollup_metrics(state: SystemState) {
+  let interval =
+    interval(Duration::from_secs(utils::metric::ROLLUP_INTERVAL as u64));
+  let mut since = None;
+  loop {
+    interval.tick().await;
+    match utils::metric::rollup(since, &state).await {
+      Ok(next) => since = next,
+      Err(err) => log::warn!("metrics::rollup: {err}"),
+    }
+  }
+}
+
 /// Spawn a background thread that will listen to the metrics daemon
 /// and save the metrics to the database.
-/// The metrics are saved for the current node.
+/// The metrics are saved for the current node and rolled up every minute.
 pub fn spawn(state: &SystemState) {
   let state = state.clone();
   rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(rollup_metrics(state.clone()));
     let client = MetrsdClient::connect("unix:///run/nanocl/metrics.sock");
     rt::spawn(async move {
       loop {
@@ -67,13 +79,7 @@ pub fn spawn(state: &SystemState) {
             while let Some(res) = stream.next().await {
               match res {
                 Ok(ev) => {
-                  if let Err(err) = save_metric(
-                    &state.inner.config.hostname,
-                    &ev,
-                    &state.inner.pool,
-                  )
-                  .await
-                  {
+                  if let Err(err) = save_metric(&ev, &state).await {
                     log::warn!("metrics::spawn_logger: {err}");
                   }
                 }
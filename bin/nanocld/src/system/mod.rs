@@ -1,4 +1,11 @@
 mod init;
+mod cron;
+mod autoscale;
+mod lease;
+mod node;
+mod reconcile;
+mod retention;
+mod network_policy;
 mod event;
 mod metric;
 mod docker_event;
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use ntex::{rt, time::interval};
+
+use crate::{utils, models::SystemState};
+
+/// Interval in seconds between two updates of the firewall of the node
+const SYNC_INTERVAL: u64 = 30;
+
+/// Spawn a background thread that update the firewall of the node
+/// with the network policies, every node enforce the policies
+/// for the cargo instances it runs.
+/// The docker events trigger the updates when the instances change,
+/// this loop catch up with the policies changed from another node.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let interval = interval(Duration::from_secs(SYNC_INTERVAL));
+      loop {
+        interval.tick().await;
+        if let Err(err) = utils::network_policy::sync(&state).await {
+          log::warn!("network_policy::spawn: {err}");
+        }
+      }
+    });
+  });
+}
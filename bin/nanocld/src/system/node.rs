@@ -0,0 +1,54 @@
+use std::{collections::BTreeSet, time::Duration};
+
+use ntex::{rt, time::interval};
+
+use nanocl_error::io::IoResult;
+
+use crate::{
+  utils,
+  models::{NodeDb, SystemState},
+};
+
+/// Publish the heartbeat of the node, mark the nodes without heartbeat
+/// as `NotReady` when leader and reschedule the replicas of the nodes
+/// that became `NotReady` since the last tick.
+async fn sync_nodes(
+  lost: &mut BTreeSet<String>,
+  state: &SystemState,
+) -> IoResult<()> {
+  utils::node::heartbeat(state).await?;
+  if state.is_leader() {
+    let names =
+      NodeDb::mark_not_ready(utils::node::NODE_TIMEOUT, &state.inner.pool)
+        .await?;
+    for name in names {
+      log::warn!("node::sync_nodes: {name} missed his heartbeats");
+    }
+  }
+  let not_ready = utils::node::list_not_ready(state).await?;
+  let new_lost = not_ready.difference(lost).cloned().collect::<Vec<_>>();
+  *lost = not_ready;
+  if !new_lost.is_empty() {
+    utils::node::reschedule(&new_lost, state).await?;
+  }
+  Ok(())
+}
+
+/// Spawn a background thread that publish the heartbeats of the node
+/// and fail over the replicas of the nodes that stopped sending them.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let interval =
+        interval(Duration::from_secs(utils::node::HEARTBEAT_INTERVAL));
+      let mut lost = BTreeSet::new();
+      loop {
+        interval.tick().await;
+        if let Err(err) = sync_nodes(&mut lost, &state).await {
+          log::error!("node::spawn: {err}");
+        }
+      }
+    });
+  });
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+use ntex::{rt, time::interval};
+
+use crate::{utils, models::SystemState};
+
+/// Spawn a background thread that periodically restore the wanted instances
+/// of the cargoes and vms when they drift from it.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let interval =
+        interval(Duration::from_secs(utils::reconcile::RECONCILE_INTERVAL));
+      loop {
+        interval.tick().await;
+        if let Err(err) = utils::reconcile::reconcile(&state).await {
+          log::error!("reconcile::spawn: {err}");
+        }
+      }
+    });
+  });
+}
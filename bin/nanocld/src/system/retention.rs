@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use ntex::{rt, time::interval};
+
+use nanocl_error::io::IoResult;
+
+use crate::{utils, models::SystemState};
+
+/// Delete the expired events, metrics and audit entries
+async fn prune(state: &SystemState) -> IoResult<()> {
+  let events = utils::retention::prune_events(state).await?;
+  let metrics = utils::retention::prune_metrics(state).await?;
+  let audits = utils::retention::prune_audits(state).await?;
+  if events > 0 || metrics > 0 || audits > 0 {
+    log::info!(
+      "retention::prune: {events} events {metrics} metrics {audits} audits deleted"
+    );
+  }
+  Ok(())
+}
+
+/// Spawn a background thread that delete the expired events, metrics and audits,
+/// only the leader purge them.
+pub fn spawn(state: &SystemState) {
+  let state = state.clone();
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      let secs = state
+        .inner
+        .config
+        .retention
+        .interval
+        .unwrap_or(utils::retention::DEFAULT_PRUNE_INTERVAL)
+        .max(1);
+      let interval = interval(Duration::from_secs(secs));
+      loop {
+        interval.tick().await;
+        if !state.is_leader() {
+          continue;
+        }
+        if let Err(err) = prune(&state).await {
+          log::error!("retention::spawn: {err}");
+        }
+      }
+    });
+  });
+}
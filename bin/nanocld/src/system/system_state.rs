@@ -1,7 +1,10 @@
-use std::sync::Arc;
+use std::sync::{
+  Arc,
+  atomic::{AtomicBool, Ordering},
+};
 
 use ntex::rt;
-use futures::channel::mpsc;
+use futures::{channel::mpsc, lock::Mutex};
 use futures_util::{SinkExt, StreamExt};
 
 use nanocl_error::io::{FromIo, IoError, IoResult};
@@ -18,8 +21,8 @@ use crate::{
   vars, utils,
   repositories::generic::*,
   models::{
-    EventDb, RawEventEmitter, RawEventReceiver, SystemState, SystemStateInner,
-    TaskManager,
+    CronManager, EventDb, HttpCounters, RawEventEmitter, RawEventReceiver,
+    SystemState, SystemStateInner, TaskManager,
   },
 };
 
@@ -35,6 +38,7 @@ impl SystemState {
     )
     .map_err(|err| err.map_err_context(|| "Docker"))?;
     let pool = utils::store::init(conf).await?;
+    let secret_keys = utils::crypto::load_secret_keys(conf, &pool).await;
     let (sx, rx) = mpsc::unbounded();
     let system_state = SystemState {
       inner: Arc::new(SystemStateInner {
@@ -44,6 +48,11 @@ impl SystemState {
         event_emitter: sx,
         event_emitter_raw: RawEventEmitter::new(),
         task_manager: TaskManager::new(),
+        cron_manager: CronManager::new(),
+        http_counters: HttpCounters::new(),
+        secret_keys,
+        is_leader: AtomicBool::new(false),
+        network_rules: Mutex::new(None),
         arbiter: rt::Arbiter::new(),
       }),
     };
@@ -51,6 +60,11 @@ impl SystemState {
     Ok(system_state)
   }
 
+  /// Whether this node is currently the leader of the cluster
+  pub fn is_leader(&self) -> bool {
+    self.inner.is_leader.load(Ordering::SeqCst)
+  }
+
   /// Start the system event loop
   /// It will handle events and execute some actions
   /// It will also emit the event to the raw event emitter for the http clients
@@ -72,7 +86,10 @@ impl SystemState {
 
   /// Emit an event to the system event loop
   pub async fn emit_event(&self, new_ev: EventPartial) -> IoResult<()> {
-    let ev: Event = EventDb::create_try_from(new_ev, &self.inner.pool)
+    let mut ev = EventDb::try_from(new_ev)?;
+    ev.expires_at =
+      utils::retention::event_expires_at(&self.inner.config.retention);
+    let ev: Event = EventDb::create_from(ev, &self.inner.pool)
       .await?
       .try_into()?;
     self
@@ -1,16 +1,7 @@
-use futures_util::StreamExt;
-use futures::stream::FuturesUnordered;
-use ntex::rt;
-use bollard_next::container::{
-  RemoveContainerOptions, RenameContainerOptions, StopContainerOptions,
-};
+use bollard_next::container::{RemoveContainerOptions, StopContainerOptions};
 
-use nanocl_error::{
-  io::IoError,
-  http::{HttpError, HttpResult},
-};
+use nanocl_error::io::IoError;
 use nanocl_stubs::{
-  cargo_spec::ReplicationMode,
   process::ProcessKind,
   system::{NativeEventAction, ObjPsStatusKind},
 };
@@ -33,14 +24,20 @@ impl ObjTaskStart for CargoDb {
       let cargo =
         CargoDb::transform_read_by_pk(&key, &state.inner.pool).await?;
       let processes =
-        ProcessDb::read_by_kind_key(&cargo.spec.cargo_key, &state.inner.pool)
+        utils::container::list_replicas(&cargo.spec.cargo_key, &state).await?;
+      // Only keep the number of replicas assigned to the current node
+      let number = utils::placement::get_local_number(&cargo, &state).await?;
+      let local = processes
+        .into_iter()
+        .filter(|process| process.node_name == state.inner.config.hostname)
+        .collect::<Vec<_>>();
+      if local.len() < number {
+        let indexes =
+          utils::container::get_free_indexes(&local, number - local.len());
+        utils::container::create_cargo_replicas(&cargo, indexes, &state)
           .await?;
-      if processes.is_empty() {
-        let number = match &cargo.spec.replication {
-          Some(ReplicationMode::Static(replication)) => replication.number,
-          _ => 1,
-        };
-        utils::container::create_cargo(&cargo, number, &state).await?;
+      } else {
+        utils::container::delete_extra_replicas(local, number, &state).await?;
       }
       utils::container::start_instances(
         &cargo.spec.cargo_key,
@@ -92,114 +89,28 @@ impl ObjTaskUpdate for CargoDb {
     Box::pin(async move {
       let cargo =
         CargoDb::transform_read_by_pk(&key, &state.inner.pool).await?;
-      let processes =
-        ProcessDb::read_by_kind_key(&key, &state.inner.pool).await?;
-      // rename old instances to flag them for deletion
-      processes
-        .iter()
-        .map(|process| {
-          let docker_api = state.inner.docker_api.clone();
-          async move {
-            if process
-              .data
-              .state
-              .clone()
-              .unwrap_or_default()
-              .restarting
-              .unwrap_or_default()
-            {
-              docker_api
-                .stop_container(&process.name, None::<StopContainerOptions>)
-                .await?;
-            }
-            let new_name = format!("tmp-{}", process.name);
-            docker_api
-              .rename_container(
-                &process.key,
-                RenameContainerOptions { name: &new_name },
-              )
-              .await?;
-            Ok::<_, HttpError>(())
-          }
-        })
-        .collect::<FuturesUnordered<_>>()
-        .collect::<Vec<_>>()
-        .await
+      // We can only replace the instances running on the current node
+      let processes = utils::container::list_replicas(&key, &state)
+        .await?
         .into_iter()
-        .collect::<HttpResult<Vec<_>>>()?;
-      let number = match &cargo.spec.replication {
-        Some(ReplicationMode::Static(replication)) => replication.number,
-        _ => 1,
-      };
-      // Create instance with the new spec
-      let new_instances =
-        match utils::container::create_cargo(&cargo, number, &state).await {
-          Err(err) => {
-            log::warn!(
-              "Unable to create cargo instance {} : {err}",
-              cargo.spec.cargo_key
-            );
-            Vec::default()
-          }
-          Ok(instances) => instances,
-        };
-      // start created containers
-      match utils::container::start_instances(&key, &ProcessKind::Cargo, &state)
-        .await
+        .filter(|process| process.node_name == state.inner.config.hostname)
+        .collect::<Vec<_>>();
+      let number = utils::placement::get_local_number(&cargo, &state).await?;
+      // Replace the replicas batch by batch, it rollback on failure
+      if let Err(err) =
+        utils::rollout::rolling_update(&cargo, &processes, number, &state).await
       {
-        Err(err) => {
-          log::error!(
-            "Unable to start cargo instance {} : {err}",
-            cargo.spec.cargo_key
-          );
-          let state_ptr_ptr = state.clone();
-          rt::spawn(async move {
-            ntex::time::sleep(std::time::Duration::from_secs(2)).await;
-            let _ = utils::container::delete_instances(
-              &new_instances
-                .iter()
-                .map(|p| p.key.clone())
-                .collect::<Vec<_>>(),
-              &state_ptr_ptr,
-            )
-            .await;
-            let res = processes
-              .iter()
-              .map(|process| {
-                let docker_api = state_ptr_ptr.inner.docker_api.clone();
-                async move {
-                  docker_api
-                    .rename_container(
-                      &process.key,
-                      RenameContainerOptions {
-                        name: &process.name,
-                      },
-                    )
-                    .await?;
-                  Ok::<_, HttpError>(())
-                }
-              })
-              .collect::<FuturesUnordered<_>>()
-              .collect::<Vec<_>>()
-              .await
-              .into_iter()
-              .collect::<HttpResult<Vec<_>>>();
-            if let Err(err) = res {
-              log::error!("Unable to rename containers back: {err}");
-            }
-          });
-        }
-        Ok(_) => {
-          log::debug!("cargo instance {} started", cargo.spec.cargo_key);
-          // Delete old containers
-          let state_ptr_ptr = state.clone();
-          let _ = utils::container::delete_instances(
-            &processes.iter().map(|p| p.key.clone()).collect::<Vec<_>>(),
-            &state_ptr_ptr,
-          )
-          .await;
-        }
+        log::error!("Unable to update cargo {} : {err}", cargo.spec.cargo_key);
+        // The rollback warning emitted by the rollout stay the last event
+        ObjPsStatusDb::update_actual_status(
+          &key,
+          &ObjPsStatusKind::Fail,
+          &state.inner.pool,
+        )
+        .await?;
+        return Err(err.into());
       }
+      log::debug!("cargo instance {} started", cargo.spec.cargo_key);
       ObjPsStatusDb::update_actual_status(
         &key,
         &ObjPsStatusKind::Start,
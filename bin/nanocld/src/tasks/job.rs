@@ -1,8 +1,4 @@
-use futures_util::StreamExt;
-
-use bollard_next::container::{StartContainerOptions, WaitContainerOptions};
-
-use nanocl_error::{io::IoError, http::HttpError};
+use nanocl_error::io::IoError;
 
 use nanocl_stubs::{
   process::ProcessKind,
@@ -40,25 +36,12 @@ impl ObjTaskStart for JobDb {
       state
         .emit_normal_native_action_sync(&job, NativeEventAction::Start)
         .await;
-      for process in processes {
-        let _ = state
-          .inner
-          .docker_api
-          .start_container(&process.key, None::<StartContainerOptions<String>>)
-          .await;
-        // We currently run a sequential order so we wait for the container to finish to start the next one.
-        let mut stream = state.inner.docker_api.wait_container(
-          &process.key,
-          Some(WaitContainerOptions {
-            condition: "not-running",
-          }),
-        );
-        while let Some(stream) = stream.next().await {
-          let result = stream.map_err(HttpError::internal_server_error)?;
-          if result.status_code == 0 {
-            break;
-          }
-        }
+      // The steps run according to their dependencies and the job is finished
+      // here, the die events of his containers are ignored while this task run.
+      if let Some((status, note)) =
+        utils::job::run_steps(&job, &processes, &state).await?
+      {
+        utils::job::finish(&job, &status, note, &state).await?;
       }
       Ok::<_, IoError>(())
     })
@@ -84,7 +67,7 @@ impl ObjTaskDelete for JobDb {
       log::debug!("JobDb::delete_by_pk({:?})", &job.name);
       JobDb::clear_by_pk(&job.name, &state.inner.pool).await?;
       if job.schedule.is_some() {
-        utils::cron::remove_cron_rule(&job, &state).await?;
+        state.inner.cron_manager.remove_job(&job.name).await;
       }
       state
         .emit_normal_native_action_sync(&job, NativeEventAction::Destroy)
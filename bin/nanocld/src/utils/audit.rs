@@ -0,0 +1,171 @@
+/// Audit of the mutating calls of the http api
+/// Every request that isn't a `GET` is recorded with the identity
+/// of his client, the hash of his body and the status of the response.
+use std::{cell::RefCell, rc::Rc, time::Instant};
+
+use futures_util::StreamExt;
+use ntex::http::{Method, Payload};
+use ntex::tls::openssl::PeerCert;
+use ntex::web::{self, Error, ErrorRenderer, HttpRequest, WebRequest, WebResponse};
+use ntex::{Middleware, Service, ServiceCtx};
+use openssl::{nid::Nid, sha::Sha256};
+
+use nanocl_stubs::generic::GenericNspQuery;
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{AuditDb, SystemState},
+};
+
+use super::token::TokenIdentity;
+
+/// Sha256 of a request body updated while the handler read it
+#[derive(Default)]
+struct BodyHash {
+  hasher: Sha256,
+  len: usize,
+}
+
+impl BodyHash {
+  fn update(&mut self, bytes: &[u8]) {
+    self.hasher.update(bytes);
+    self.len += bytes.len();
+  }
+
+  /// Hexadecimal hash of the body, none if the body was empty
+  fn finish(self) -> Option<String> {
+    if self.len == 0 {
+      return None;
+    }
+    Some(utils::token::to_hex(&self.hasher.finish()))
+  }
+}
+
+/// Common name of the client certificate of a tls connection
+fn get_cert_cn(req: &HttpRequest) -> Option<String> {
+  let io = req.io()?;
+  let cert = io.query::<PeerCert>();
+  let cert = cert.as_ref()?;
+  let entry = cert
+    .0
+    .subject_name()
+    .entries_by_nid(Nid::COMMONNAME)
+    .next()?;
+  entry.data().as_utf8().ok().map(|cn| cn.to_string())
+}
+
+/// Identity of the client, the token has the priority over the certificate
+fn get_identity(req: &HttpRequest) -> Option<String> {
+  if let Some(identity) = req.extensions().get::<TokenIdentity>() {
+    return Some(identity.subject());
+  }
+  get_cert_cn(req).map(|cn| format!("cert:{cn}"))
+}
+
+/// Namespace targeted by the request,
+/// given in the query string or as name of a namespace object
+fn get_namespace(req: &HttpRequest) -> Option<String> {
+  let namespace =
+    web::types::Query::<GenericNspQuery>::from_query(req.query_string())
+      .ok()
+      .and_then(|qs| qs.into_inner().namespace);
+  if namespace.is_some() {
+    return namespace;
+  }
+  let is_namespace = req.path().split('/').nth(2) == Some("namespaces");
+  if !is_namespace {
+    return None;
+  }
+  req.match_info().get("name").map(|name| name.to_owned())
+}
+
+/// Audit middleware recording the requests that aren't `GET` or `HEAD`
+pub struct Audit;
+
+impl<S> Middleware<S> for Audit {
+  type Service = AuditMiddleware<S>;
+
+  fn create(&self, service: S) -> Self::Service {
+    AuditMiddleware { service }
+  }
+}
+
+pub struct AuditMiddleware<S> {
+  service: S,
+}
+
+impl<S, Err> Service<WebRequest<Err>> for AuditMiddleware<S>
+where
+  S: Service<WebRequest<Err>, Response = WebResponse, Error = Error>,
+  Err: ErrorRenderer,
+{
+  type Response = WebResponse;
+  type Error = Error;
+
+  ntex::forward_ready!(service);
+  ntex::forward_shutdown!(service);
+
+  async fn call(
+    &self,
+    mut req: WebRequest<Err>,
+    ctx: ServiceCtx<'_, Self>,
+  ) -> Result<Self::Response, Self::Error> {
+    if matches!(*req.method(), Method::GET | Method::HEAD) {
+      return ctx.call(&self.service, req).await;
+    }
+    let Some(state) = req.app_state::<SystemState>().cloned() else {
+      return ctx.call(&self.service, req).await;
+    };
+    let started = Instant::now();
+    let created_at = chrono::Utc::now().naive_utc();
+    let method = req.method().to_string();
+    let path = req
+      .uri()
+      .path_and_query()
+      .map(|path| path.to_string())
+      .unwrap_or_else(|| req.path().to_owned());
+    let body_hash = Rc::new(RefCell::new(BodyHash::default()));
+    let body_hash_ptr = body_hash.clone();
+    let payload = req.take_payload().map(move |chunk| {
+      if let Ok(bytes) = &chunk {
+        body_hash_ptr.borrow_mut().update(bytes);
+      }
+      chunk
+    });
+    req.set_payload(Payload::from_stream(payload));
+    let res = ctx.call(&self.service, req).await;
+    let latency_ms =
+      i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX);
+    let (status_code, identity, namespace) = match &res {
+      Ok(res) => (
+        res.status().as_u16(),
+        get_identity(res.request()),
+        get_namespace(res.request()),
+      ),
+      Err(_) => (500, None, None),
+    };
+    let body_hash = body_hash.take().finish();
+    let item = AuditDb {
+      key: uuid::Uuid::new_v4(),
+      created_at,
+      expires_at: utils::retention::audit_expires_at(
+        &state.inner.config.retention,
+      ),
+      node: state.inner.config.hostname.clone(),
+      method,
+      path,
+      identity,
+      namespace,
+      body_hash,
+      status_code: status_code.into(),
+      latency_ms,
+    };
+    ntex::rt::spawn(async move {
+      if let Err(err) = AuditDb::create_from(item, &state.inner.pool).await {
+        log::error!("audit::create: {err}");
+      }
+    });
+    res
+  }
+}
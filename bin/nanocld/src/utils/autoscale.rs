@@ -0,0 +1,341 @@
+use std::{
+  collections::VecDeque,
+  time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use bollard_next::container::{StartContainerOptions, Stats, StatsOptions};
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  cargo::Cargo,
+  cargo_spec::{ReplicationAutoscale, ReplicationMode},
+  process::Process,
+  system::{EventActor, EventKind, NativeEventAction},
+};
+
+use crate::models::SystemState;
+
+/// Usage variation under which we don't scale to avoid flapping
+const TOLERANCE: f64 = 0.1;
+/// Delay after a scale, or the first sample, before a cargo can be scaled
+/// down, it's scaled down to the highest recommendation of this window
+const SCALE_DOWN_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Recommendations of the last samples of an autoscaled cargo,
+/// they keep a short drop of the usage from scaling it down
+#[derive(Debug, Clone)]
+pub struct AutoscaleHistory {
+  /// Number of replicas desired by every sample of the window
+  recommendations: VecDeque<(Instant, usize)>,
+  /// When the cargo was last scaled or first sampled
+  last_scale: Instant,
+}
+
+impl AutoscaleHistory {
+  pub fn new(now: Instant) -> Self {
+    Self {
+      recommendations: VecDeque::new(),
+      last_scale: now,
+    }
+  }
+
+  /// Record the replicas `desired` by a sample and return the number
+  /// of replicas to scale to.
+  /// Scale ups are applied at once, scale downs wait for the window
+  /// since the last scale and don't go under its highest recommendation.
+  pub fn stabilize(
+    &mut self,
+    current: usize,
+    desired: usize,
+    now: Instant,
+  ) -> usize {
+    while self
+      .recommendations
+      .front()
+      .is_some_and(|(at, _)| now.duration_since(*at) > SCALE_DOWN_WINDOW)
+    {
+      self.recommendations.pop_front();
+    }
+    self.recommendations.push_back((now, desired));
+    if desired >= current {
+      return desired;
+    }
+    if now.duration_since(self.last_scale) < SCALE_DOWN_WINDOW {
+      return current;
+    }
+    let highest = self
+      .recommendations
+      .iter()
+      .map(|(_, desired)| *desired)
+      .max()
+      .unwrap_or(desired);
+    highest.min(current)
+  }
+
+  /// Record that the cargo has been scaled
+  pub fn scaled(&mut self, now: Instant) {
+    self.last_scale = now;
+  }
+}
+
+/// Ensure an autoscale configuration is valid
+pub fn validate(autoscale: &ReplicationAutoscale) -> HttpResult<()> {
+  if autoscale.min == 0 {
+    return Err(HttpError::bad_request(
+      "Autoscale Min must be greater than 0",
+    ));
+  }
+  if autoscale.min > autoscale.max {
+    return Err(HttpError::bad_request(
+      "Autoscale Min must be lower or equal to Max",
+    ));
+  }
+  if autoscale.cpu_target == Some(0) || autoscale.memory_target == Some(0) {
+    return Err(HttpError::bad_request(
+      "Autoscale targets must be greater than 0",
+    ));
+  }
+  Ok(())
+}
+
+/// CPU usage in percent of one core like `docker stats` does
+pub fn cpu_percent(stats: &Stats) -> Option<f64> {
+  let cpu_delta = stats
+    .cpu_stats
+    .cpu_usage
+    .total_usage
+    .checked_sub(stats.precpu_stats.cpu_usage.total_usage)?;
+  let system_delta = stats
+    .cpu_stats
+    .system_cpu_usage?
+    .checked_sub(stats.precpu_stats.system_cpu_usage?)?;
+  if system_delta == 0 {
+    return None;
+  }
+  let online_cpus = stats.cpu_stats.online_cpus.unwrap_or(1) as f64;
+  Some(cpu_delta as f64 / system_delta as f64 * online_cpus * 100.0)
+}
+
+/// Memory usage in percent of the memory limit
+pub fn memory_percent(stats: &Stats) -> Option<f64> {
+  let usage = stats.memory_stats.usage?;
+  let limit = stats.memory_stats.limit?;
+  if limit == 0 {
+    return None;
+  }
+  Some(usage as f64 / limit as f64 * 100.0)
+}
+
+/// Compute the number of replicas needed to reach the targets
+/// The ratio between the average usage and the target is applied
+/// to the current number of replicas, the highest ratio win.
+pub fn desired_replicas(
+  current: usize,
+  cpu: Option<f64>,
+  memory: Option<f64>,
+  autoscale: &ReplicationAutoscale,
+) -> usize {
+  let max = autoscale.max.max(autoscale.min);
+  if current == 0 {
+    return autoscale.min;
+  }
+  let ratios = [
+    cpu.zip(autoscale.cpu_target),
+    memory.zip(autoscale.memory_target),
+  ]
+  .into_iter()
+  .flatten()
+  .map(|(usage, target)| usage / target as f64)
+  .collect::<Vec<_>>();
+  let Some(ratio) = ratios.into_iter().reduce(f64::max) else {
+    return current.clamp(autoscale.min, max);
+  };
+  if (ratio - 1.0).abs() <= TOLERANCE {
+    return current.clamp(autoscale.min, max);
+  }
+  let desired = (current as f64 * ratio).ceil() as usize;
+  desired.clamp(autoscale.min, max)
+}
+
+/// Get the CPU and memory usage of a container
+async fn sample(
+  process: &Process,
+  state: &SystemState,
+) -> HttpResult<(Option<f64>, Option<f64>)> {
+  let mut stream = state.inner.docker_api.stats(
+    &process.key,
+    Some(StatsOptions {
+      stream: false,
+      one_shot: false,
+    }),
+  );
+  let Some(stats) = stream.next().await else {
+    return Ok((None, None));
+  };
+  let stats = stats?;
+  Ok((cpu_percent(&stats), memory_percent(&stats)))
+}
+
+/// Average of the given values
+fn average(values: &[f64]) -> Option<f64> {
+  if values.is_empty() {
+    return None;
+  }
+  Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sample the replicas of a cargo running on the current node
+/// and scale it up or down to reach the autoscale targets
+pub async fn autoscale_cargo(
+  cargo: &Cargo,
+  history: &mut AutoscaleHistory,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let Some(ReplicationMode::Autoscale(autoscale)) = &cargo.spec.replication
+  else {
+    return Ok(());
+  };
+  let mut processes =
+    super::container::list_replicas(&cargo.spec.cargo_key, state)
+      .await?
+      .into_iter()
+      .filter(|process| process.node_name == state.inner.config.hostname)
+      .collect::<Vec<_>>();
+  let mut cpus = Vec::new();
+  let mut memories = Vec::new();
+  for process in &processes {
+    let running = process
+      .data
+      .state
+      .clone()
+      .unwrap_or_default()
+      .running
+      .unwrap_or_default();
+    if !running {
+      continue;
+    }
+    let (cpu, memory) = sample(process, state).await?;
+    cpus.extend(cpu);
+    memories.extend(memory);
+  }
+  let cpu = average(&cpus);
+  let memory = average(&memories);
+  let current = processes.len();
+  let now = Instant::now();
+  let desired = history.stabilize(
+    current,
+    desired_replicas(current, cpu, memory, autoscale),
+    now,
+  );
+  if desired == current {
+    return Ok(());
+  }
+  history.scaled(now);
+  log::info!(
+    "autoscale: cargo {} from {current} to {desired} replicas (cpu {cpu:?} memory {memory:?})",
+    cargo.spec.cargo_key
+  );
+  if desired > current {
+    // Removed replicas leave holes in the indexes that are filled first
+    let indexes =
+      super::container::get_free_indexes(&processes, desired - current);
+    let created =
+      super::container::create_cargo_replicas(cargo, indexes, state).await?;
+    for process in created {
+      state
+        .inner
+        .docker_api
+        .start_container(&process.key, None::<StartContainerOptions<String>>)
+        .await?;
+    }
+  } else {
+    // Remove the most recent replicas first
+    processes.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    let keys = processes
+      .iter()
+      .take(current - desired)
+      .map(|process| process.key.clone())
+      .collect::<Vec<_>>();
+    super::container::delete_instances(&keys, state).await?;
+  }
+  let actor: EventActor = cargo.clone().into();
+  state.emit_action(
+    &actor,
+    NativeEventAction::Scale,
+    EventKind::Normal,
+    "autoscale",
+    Some(format!("Scaled from {current} to {desired} replicas")),
+    Some(serde_json::json!({
+      "From": current,
+      "To": desired,
+      "Cpu": cpu,
+      "Memory": memory,
+    })),
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn autoscale(
+    cpu_target: Option<u32>,
+    memory_target: Option<u32>,
+  ) -> ReplicationAutoscale {
+    ReplicationAutoscale {
+      min: 1,
+      max: 10,
+      cpu_target,
+      memory_target,
+    }
+  }
+
+  #[test]
+  fn desired() {
+    let config = autoscale(Some(50), None);
+    assert_eq!(desired_replicas(2, Some(100.0), None, &config), 4);
+    assert_eq!(desired_replicas(4, Some(10.0), None, &config), 1);
+    // Within the tolerance nothing change
+    assert_eq!(desired_replicas(3, Some(53.0), None, &config), 3);
+    // No stats keep the current number
+    assert_eq!(desired_replicas(3, None, None, &config), 3);
+    assert_eq!(desired_replicas(0, None, None, &config), 1);
+    // Bounded by the maximum
+    assert_eq!(desired_replicas(8, Some(200.0), None, &config), 10);
+    // The highest ratio win
+    let config = autoscale(Some(50), Some(50));
+    assert_eq!(desired_replicas(2, Some(25.0), Some(100.0), &config), 4);
+  }
+
+  #[test]
+  fn stabilization() {
+    let start = Instant::now();
+    let mut history = AutoscaleHistory::new(start);
+    // A single low sample doesn't scale down
+    assert_eq!(history.stabilize(4, 1, start + Duration::from_secs(30)), 4);
+    // Scale ups are applied at once
+    assert_eq!(history.stabilize(4, 6, start + Duration::from_secs(60)), 6);
+    history.scaled(start + Duration::from_secs(60));
+    // The window start again after a scale
+    let after = start + Duration::from_secs(60) + SCALE_DOWN_WINDOW;
+    assert_eq!(history.stabilize(6, 2, after - Duration::from_secs(1)), 6);
+    // Then the highest recommendation of the window is used
+    assert_eq!(history.stabilize(6, 2, after), 6);
+    assert_eq!(history.stabilize(6, 3, after + Duration::from_secs(61)), 3);
+  }
+
+  #[test]
+  fn validation() {
+    assert!(validate(&autoscale(Some(80), None)).is_ok());
+    assert!(validate(&autoscale(Some(0), None)).is_err());
+    let mut config = autoscale(None, Some(80));
+    config.min = 11;
+    assert!(validate(&config).is_err());
+    config.min = 0;
+    assert!(validate(&config).is_err());
+    config.max = 0;
+    assert!(validate(&config).is_err());
+  }
+}
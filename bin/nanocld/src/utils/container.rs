@@ -30,7 +30,8 @@ use nanocl_stubs::{
 };
 
 use crate::{
-  vars,
+  vars, utils,
+  objects::generic::*,
   models::{
     CargoDb, JobDb, JobUpdateDb, ObjPsStatusDb, ObjPsStatusUpdate, ProcessDb,
     SecretDb, SystemState, VmDb, VmImageDb,
@@ -73,7 +74,7 @@ where
   }
   let credentials = match secret {
     Some(secret) => {
-      let secret = SecretDb::read_by_pk(&secret, &state.inner.pool).await?;
+      let secret = SecretDb::inspect_obj_by_pk(&secret, state).await?;
       serde_json::from_value::<DockerCredentials>(secret.data)
         .map(Some)
         .map_err(|err| HttpError::bad_request(err.to_string()))?
@@ -151,9 +152,9 @@ where
   Ok(())
 }
 
-/// Internal utils to emit an event when the state of a process kind changes
+/// Emit an event when the state of a process kind changes
 /// Eg: (job, cargo, vm)
-async fn _emit(
+pub async fn emit_action(
   kind_key: &str,
   kind: &ProcessKind,
   action: NativeEventAction,
@@ -174,6 +175,7 @@ async fn _emit(
         kind_key,
         JobUpdateDb {
           updated_at: Some(chrono::Utc::now().naive_utc()),
+          ..Default::default()
         },
         &state.inner.pool,
       )
@@ -322,15 +324,68 @@ async fn execute_cargo_before(
   }
 }
 
-/// Create instances (containers) based on the cargo spec
-/// The number of containers created is based on the number of instances defined in the cargo spec
-/// Example: cargo-key-(random-id), cargo-key-(random-id1), cargo-key-(random-id2)
-pub async fn create_cargo(
+/// Whether the process is a replica of its cargo,
+/// init containers and instances renamed during a rolling update aren't
+pub fn is_replica(process: &Process) -> bool {
+  let is_init = process
+    .data
+    .config
+    .as_ref()
+    .and_then(|config| config.labels.as_ref())
+    .is_some_and(|labels| labels.contains_key("io.nanocl.init-c"));
+  !is_init
+    && !process.name.starts_with("init-")
+    && !process.name.starts_with("tmp-")
+}
+
+/// List the replicas of a cargo on every node
+pub async fn list_replicas(
+  cargo_key: &str,
+  state: &SystemState,
+) -> IoResult<Vec<Process>> {
+  let processes =
+    ProcessDb::read_by_kind_key(cargo_key, &state.inner.pool).await?;
+  Ok(processes.into_iter().filter(is_replica).collect())
+}
+
+/// Index of a cargo replica read from its `NANOCL_CARGO_INSTANCE` env
+pub fn get_replica_index(process: &Process) -> Option<usize> {
+  process
+    .data
+    .config
+    .as_ref()?
+    .env
+    .as_ref()?
+    .iter()
+    .find_map(|env| env.strip_prefix("NANOCL_CARGO_INSTANCE="))?
+    .parse()
+    .ok()
+}
+
+/// The `number` lowest replica indexes not used by the given replicas
+pub fn get_free_indexes(replicas: &[Process], number: usize) -> Vec<usize> {
+  let used = replicas
+    .iter()
+    .filter_map(get_replica_index)
+    .collect::<Vec<_>>();
+  (0..)
+    .filter(|index| !used.contains(index))
+    .take(number)
+    .collect()
+}
+
+/// Create the instances (containers) of a cargo for the given replica indexes
+/// The init container is only executed before the first replica (index 0)
+/// It's used by rolling updates to create the replicas batch by batch
+pub async fn create_cargo_replicas(
   cargo: &Cargo,
-  number: usize,
+  replicas: impl IntoIterator<Item = usize>,
   state: &SystemState,
 ) -> HttpResult<Vec<Process>> {
-  execute_cargo_before(cargo, state).await?;
+  let replicas = replicas.into_iter().collect::<Vec<_>>();
+  if replicas.contains(&0) {
+    execute_cargo_before(cargo, state).await?;
+  }
   download_image(
     &cargo.spec.container.image.clone().unwrap_or_default(),
     cargo.spec.image_pull_secret.clone(),
@@ -344,19 +399,15 @@ pub async fn create_cargo(
     let filter = GenericFilter::new()
       .r#where("key", GenericClause::In(secrets.clone()))
       .r#where("kind", GenericClause::Eq("nanocl.io/env".to_owned()));
-    let secrets = SecretDb::transform_read_by(&filter, &state.inner.pool)
-      .await?
-      .into_iter()
-      .map(|secret| {
-        let envs = serde_json::from_value::<Vec<String>>(secret.data)?;
-        Ok::<_, IoError>(envs)
-      })
-      .collect::<IoResult<Vec<Vec<String>>>>()?;
-    // Flatten the secrets to have envs in a single vector
-    secret_envs = secrets.into_iter().flatten().collect();
+    let secrets = SecretDb::read_by(&filter, &state.inner.pool).await?;
+    for secret in secrets {
+      let secret = utils::crypto::decrypt_secret(secret, state).await?;
+      let envs = serde_json::from_value::<Vec<String>>(secret.data)
+        .map_err(IoError::from)?;
+      secret_envs.extend(envs);
+    }
   }
-  (0..number)
-    .collect::<Vec<usize>>()
+  replicas
     .into_iter()
     .map(move |current| {
       let secret_envs = secret_envs.clone();
@@ -472,6 +523,24 @@ pub async fn delete_instance(
   Ok(())
 }
 
+/// Delete the newest of the given replicas to keep only `number` of them
+pub async fn delete_extra_replicas(
+  mut replicas: Vec<Process>,
+  number: usize,
+  state: &SystemState,
+) -> HttpResult<()> {
+  if replicas.len() <= number {
+    return Ok(());
+  }
+  replicas.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+  let keys = replicas
+    .split_off(number)
+    .into_iter()
+    .map(|process| process.key)
+    .collect::<Vec<_>>();
+  delete_instances(&keys, state).await
+}
+
 /// Delete a group of instances (containers) by their names
 pub async fn delete_instances(
   instances: &[String],
@@ -531,7 +600,7 @@ pub async fn restart_instances(
       .restart_container(&process.key, None)
       .await?;
   }
-  _emit(pk, kind, NativeEventAction::Restart, state).await?;
+  emit_action(pk, kind, NativeEventAction::Restart, state).await?;
   Ok(())
 }
 
@@ -566,7 +635,7 @@ pub async fn stop_instances(
     &state.inner.pool,
   )
   .await?;
-  _emit(kind_pk, kind, NativeEventAction::Stop, state).await?;
+  emit_action(kind_pk, kind, NativeEventAction::Stop, state).await?;
   Ok(())
 }
 
@@ -600,7 +669,7 @@ pub async fn start_instances(
     &state.inner.pool,
   )
   .await?;
-  _emit(kind_key, kind, NativeEventAction::Start, state).await?;
+  emit_action(kind_key, kind, NativeEventAction::Start, state).await?;
   Ok(())
 }
 
@@ -754,9 +823,9 @@ pub async fn create_vm_instance(
 }
 
 /// Create process (container) for a job
-async fn create_job_instance(
+pub async fn create_job_instance(
   name: &str,
-  index: usize,
+  step: &str,
   container: &Config,
   state: &SystemState,
 ) -> HttpResult<Process> {
@@ -765,18 +834,19 @@ async fn create_job_instance(
   labels.insert("io.nanocl.j".to_owned(), name.to_owned());
   container.labels = Some(labels);
   let short_id = super::key::generate_short_id(6);
-  let container_name = format!("{name}-{index}-{short_id}.j");
+  let container_name = format!("{name}-{step}-{short_id}.j");
   create_instance(&ProcessKind::Job, &container_name, name, &container, state)
     .await
 }
 
-/// Create processes (container) for a job
+/// Create processes (container) for every step of a job
 pub async fn create_job_instances(
   job: &Job,
   state: &SystemState,
 ) -> HttpResult<Vec<Process>> {
   let mut processes = Vec::new();
-  for (index, container) in job.containers.iter().enumerate() {
+  for step in super::job::get_steps(job) {
+    let container = &step.container;
     download_image(
       &container.image.clone().unwrap_or_default(),
       job.image_pull_secret.clone(),
@@ -786,7 +856,7 @@ pub async fn create_job_instances(
     )
     .await?;
     let process =
-      create_job_instance(&job.name, index, container, state).await?;
+      create_job_instance(&job.name, &step.name, container, state).await?;
     processes.push(process);
   }
   Ok(processes)
@@ -816,7 +886,7 @@ pub async fn emit_starting(
     prev_actual: Some(current_status.actual),
   };
   ObjPsStatusDb::update_pk(kind_key, status_update, &state.inner.pool).await?;
-  _emit(kind_key, kind, NativeEventAction::Starting, state).await?;
+  emit_action(kind_key, kind, NativeEventAction::Starting, state).await?;
   Ok(())
 }
 
@@ -842,6 +912,40 @@ pub async fn emit_stopping(
     prev_actual: Some(current_status.actual),
   };
   ObjPsStatusDb::update_pk(kind_key, status_update, &state.inner.pool).await?;
-  _emit(kind_key, kind, NativeEventAction::Stopping, state).await?;
+  emit_action(kind_key, kind, NativeEventAction::Stopping, state).await?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use bollard_next::service::{ContainerConfig, ContainerInspectResponse};
+
+  use super::*;
+
+  fn replica(index: usize) -> Process {
+    Process {
+      key: format!("replica-{index}"),
+      created_at: chrono::Utc::now().naive_utc(),
+      updated_at: chrono::Utc::now().naive_utc(),
+      name: format!("test-{index}.global.c"),
+      kind: ProcessKind::Cargo,
+      node_name: "node1".to_owned(),
+      kind_key: "test.global".to_owned(),
+      data: ContainerInspectResponse {
+        config: Some(ContainerConfig {
+          env: Some(vec![format!("NANOCL_CARGO_INSTANCE={index}")]),
+          ..Default::default()
+        }),
+        ..Default::default()
+      },
+    }
+  }
+
+  #[test]
+  fn free_indexes() {
+    assert_eq!(get_free_indexes(&[], 2), vec![0, 1]);
+    let replicas = vec![replica(0), replica(2), replica(3)];
+    assert_eq!(get_replica_index(&replicas[1]), Some(2));
+    assert_eq!(get_free_indexes(&replicas, 3), vec![1, 4, 5]);
+  }
+}
@@ -1,82 +1,333 @@
-use ntex::web;
-use tokio::{fs, io::AsyncWriteExt};
+use chrono::{Datelike, Duration, DateTime, NaiveDateTime, Timelike, Utc};
 
-use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_error::io::{IoError, IoResult};
 use nanocl_stubs::job::Job;
 
-use crate::{vars, models::SystemState};
-
-/// Format the cron job command to start a job at a given time
-fn format_cron_job_command(job: &Job, state: &SystemState) -> String {
-  let host = state
-    .inner
-    .config
-    .hosts
-    .first()
-    .cloned()
-    .unwrap_or("unix:///run/nanocl/nanocl.sock".to_owned())
-    .replace("unix://", "");
-  format!(
-    "curl -X POST --unix {host} http://localhost/v{}/processes/job/{}/start",
-    vars::VERSION,
-    &job.name
-  )
+use crate::{
+  repositories::generic::*,
+  models::{CronManager, CronSchedule, CronTask, JobDb, JobUpdateDb, Pool},
+};
+
+const MONTH_NAMES: [&str; 12] = [
+  "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov",
+  "dec",
+];
+
+const WEEKDAY_NAMES: [&str; 7] =
+  ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Parse a single value of a field that can be a number or a name
+fn parse_value(
+  value: &str,
+  min: u32,
+  max: u32,
+  names: &[&str],
+) -> Result<u32, String> {
+  let lower = value.to_ascii_lowercase();
+  if let Some(pos) = names.iter().position(|name| *name == lower) {
+    // Names start at the minimum value of the field
+    return Ok(pos as u32 + min);
+  }
+  let value = value
+    .parse::<u32>()
+    .map_err(|_| format!("invalid value {value}"))?;
+  if value < min || value > max {
+    return Err(format!("value {value} out of range {min}-{max}"));
+  }
+  Ok(value)
+}
+
+/// Parse a field of a cron expression into a bit mask of the allowed values
+/// Supported syntax: `*`, `a`, `a-b`, `*/n`, `a/n`, `a-b/n` and lists `a,b-c`
+fn parse_field(
+  field: &str,
+  min: u32,
+  max: u32,
+  names: &[&str],
+) -> Result<u64, String> {
+  let mut mask = 0u64;
+  for part in field.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => {
+        let step = step
+          .parse::<u32>()
+          .map_err(|_| format!("invalid step {step}"))?;
+        if step == 0 {
+          return Err("step must be greater than 0".to_owned());
+        }
+        (range, Some(step))
+      }
+      None => (part, None),
+    };
+    let (start, end) = if range == "*" {
+      (min, max)
+    } else if let Some((start, end)) = range.split_once('-') {
+      (
+        parse_value(start, min, max, names)?,
+        parse_value(end, min, max, names)?,
+      )
+    } else {
+      let start = parse_value(range, min, max, names)?;
+      // `a/n` means every n starting at a
+      let end = if step.is_some() { max } else { start };
+      (start, end)
+    };
+    if start > end {
+      return Err(format!("invalid range {range}"));
+    }
+    let mut value = start;
+    while value <= end {
+      mask |= 1 << value;
+      value += step.unwrap_or(1);
+    }
+  }
+  Ok(mask)
 }
 
-/// Execute the crontab command to update the cron jobs
-async fn exec_crontab() -> IoResult<()> {
-  web::block(|| {
-    std::process::Command::new("crontab")
-      .arg("/tmp/crontab")
-      .output()
-      .map_err(|err| err.map_err_context(|| "Cron job"))?;
-    Ok::<_, IoError>(())
+/// Parse a standard cron expression `minute hour day month weekday`
+/// The shortcuts `@yearly`, `@annually`, `@monthly`, `@weekly`, `@daily`, `@midnight`
+/// and `@hourly` are also supported.
+/// Schedules are evaluated in UTC.
+pub fn parse_schedule(schedule: &str) -> IoResult<CronSchedule> {
+  let expr = match schedule.trim() {
+    "@yearly" | "@annually" => "0 0 1 1 *",
+    "@monthly" => "0 0 1 * *",
+    "@weekly" => "0 0 * * 0",
+    "@daily" | "@midnight" => "0 0 * * *",
+    "@hourly" => "0 * * * *",
+    expr => expr,
+  };
+  let fields = expr.split_whitespace().collect::<Vec<_>>();
+  let [minutes, hours, days, months, weekdays] = fields[..] else {
+    return Err(IoError::invalid_input(
+      "Schedule",
+      &format!("{schedule} must have 5 fields"),
+    ));
+  };
+  let map_err = |err: String| {
+    IoError::invalid_input("Schedule", &format!("{schedule} {err}"))
+  };
+  let mut weekdays_mask =
+    parse_field(weekdays, 0, 7, &WEEKDAY_NAMES).map_err(map_err)?;
+  // 7 is an alias of sunday
+  if weekdays_mask & (1 << 7) != 0 {
+    weekdays_mask = (weekdays_mask | 1) & !(1 << 7);
+  }
+  Ok(CronSchedule {
+    minutes: parse_field(minutes, 0, 59, &[]).map_err(map_err)?,
+    hours: parse_field(hours, 0, 23, &[]).map_err(map_err)?,
+    days: parse_field(days, 1, 31, &[]).map_err(map_err)?,
+    months: parse_field(months, 1, 12, &MONTH_NAMES).map_err(map_err)?,
+    weekdays: weekdays_mask,
+    days_restricted: !days.starts_with('*'),
+    weekdays_restricted: !weekdays.starts_with('*'),
   })
-  .await?;
-  Ok(())
 }
 
-/// Add a cron rule to the crontab to start a job at a given time
-pub async fn add_cron_rule(
-  item: &Job,
-  schedule: &str,
-  state: &SystemState,
-) -> IoResult<()> {
-  let cmd = format_cron_job_command(item, state);
-  let cron_rule = format!("{} {cmd}", schedule);
-  log::debug!("Creating cron rule: {cron_rule}");
-  fs::copy("/var/spool/cron/crontabs/root", "/tmp/crontab")
-    .await
-    .map_err(|err| err.map_err_context(|| "Cron job"))?;
-  let mut file = fs::OpenOptions::new()
-    .write(true)
-    .append(true)
-    .open("/tmp/crontab")
-    .await
-    .map_err(|err| err.map_err_context(|| "Cron job"))?;
-  file
-    .write_all(format!("{cron_rule}\n").as_bytes())
-    .await
-    .map_err(|err| err.map_err_context(|| "Cron job"))?;
-  exec_crontab().await?;
-  Ok(())
+impl CronSchedule {
+  /// Check if the given date match the day fields
+  /// When both day of the month and day of the week are restricted
+  /// the date match if any of them match like crontab does.
+  fn match_day(&self, date: &NaiveDateTime) -> bool {
+    let day = self.days & (1 << date.day()) != 0;
+    let weekday =
+      self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+    match (self.days_restricted, self.weekdays_restricted) {
+      (true, true) => day || weekday,
+      (true, false) => day,
+      (false, true) => weekday,
+      (false, false) => true,
+    }
+  }
+
+  /// Compute the next run strictly after the given date
+  /// Return None if the schedule never match in the next 5 years
+  pub fn next_after(&self, after: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let after = after.naive_utc();
+    let mut date = after.date().and_hms_opt(after.hour(), after.minute(), 0)?
+      + Duration::minutes(1);
+    let limit = after + Duration::days(5 * 366);
+    while date <= limit {
+      if self.months & (1 << date.month()) == 0 {
+        // Jump to the first minute of the next month
+        let (year, month) = if date.month() == 12 {
+          (date.year() + 1, 1)
+        } else {
+          (date.year(), date.month() + 1)
+        };
+        date = chrono::NaiveDate::from_ymd_opt(year, month, 1)?
+          .and_hms_opt(0, 0, 0)?;
+        continue;
+      }
+      if !self.match_day(&date) {
+        date = date.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+        continue;
+      }
+      if self.hours & (1 << date.hour()) == 0 {
+        date = date.date().and_hms_opt(date.hour(), 0, 0)? + Duration::hours(1);
+        continue;
+      }
+      if self.minutes & (1 << date.minute()) == 0 {
+        date += Duration::minutes(1);
+        continue;
+      }
+      return Some(date.and_utc());
+    }
+    None
+  }
+}
+
+impl CronManager {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Schedule a job or replace his current schedule
+  /// The next run is saved in the store and returned
+  pub async fn add_job(
+    &self,
+    job: &Job,
+    pool: &Pool,
+  ) -> IoResult<Option<NaiveDateTime>> {
+    let Some(schedule) = &job.schedule else {
+      return Ok(None);
+    };
+    let schedule = parse_schedule(schedule)?;
+    let Some(next_run) = schedule.next_after(&Utc::now()) else {
+      return Err(IoError::invalid_input(
+        "Schedule",
+        &format!("{} will never run", job.name),
+      ));
+    };
+    log::debug!("cron::add_job: {} next run at {next_run}", job.name);
+    JobDb::update_pk(
+      &job.name,
+      JobUpdateDb {
+        next_run_at: Some(next_run.naive_utc()),
+        ..Default::default()
+      },
+      pool,
+    )
+    .await?;
+    self
+      .tasks
+      .lock()
+      .await
+      .insert(job.name.clone(), CronTask { schedule, next_run });
+    Ok(Some(next_run.naive_utc()))
+  }
+
+  /// Remove every job from the scheduler
+  pub async fn clear(&self) {
+    self.tasks.lock().await.clear();
+  }
+
+  /// Remove a job from the scheduler
+  pub async fn remove_job(&self, name: &str) {
+    log::debug!("cron::remove_job: {name}");
+    self.tasks.lock().await.remove(name);
+  }
+
+  /// Return the jobs that have to run at the given date with their next run
+  /// Jobs that will never run again are removed from the scheduler
+  pub async fn take_due(
+    &self,
+    now: &DateTime<Utc>,
+  ) -> Vec<(String, Option<NaiveDateTime>)> {
+    let mut tasks = self.tasks.lock().await;
+    let mut due = Vec::new();
+    tasks.retain(|name, task| {
+      if task.next_run > *now {
+        return true;
+      }
+      match task.schedule.next_after(now) {
+        Some(next_run) => {
+          task.next_run = next_run;
+          due.push((name.clone(), Some(next_run.naive_utc())));
+          true
+        }
+        None => {
+          due.push((name.clone(), None));
+          false
+        }
+      }
+    });
+    due
+  }
 }
 
-/// Remove a cron rule from the crontab for the given job
-pub async fn remove_cron_rule(item: &Job, state: &SystemState) -> IoResult<()> {
-  let mut content = fs::read_to_string("/var/spool/cron/crontabs/root")
-    .await
-    .map_err(|err| err.map_err_context(|| "Cron job"))?;
-  let cmd = format_cron_job_command(item, state);
-  log::debug!("Removing cron rule: {cmd}");
-  content = content
-    .lines()
-    .filter(|line| !line.contains(&cmd))
-    .collect::<Vec<_>>()
-    .join("\n");
-  fs::write("/tmp/crontab", format!("{content}\n"))
-    .await
-    .map_err(|err| err.map_err_context(|| "Cron job"))?;
-  exec_crontab().await?;
-  Ok(())
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(value: &str) -> DateTime<Utc> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M")
+      .unwrap()
+      .and_utc()
+  }
+
+  fn next(schedule: &str, after: &str) -> DateTime<Utc> {
+    parse_schedule(schedule)
+      .unwrap()
+      .next_after(&date(after))
+      .unwrap()
+  }
+
+  #[test]
+  fn parse() {
+    assert!(parse_schedule("* * * * *").is_ok());
+    assert!(parse_schedule("*/15 0-6,22 1 jan-mar mon-fri").is_ok());
+    assert!(parse_schedule("@daily").is_ok());
+    assert!(parse_schedule("* * * *").is_err());
+    assert!(parse_schedule("60 * * * *").is_err());
+    assert!(parse_schedule("*/0 * * * *").is_err());
+    assert!(parse_schedule("5-1 * * * *").is_err());
+    assert!(parse_schedule("* * * * * *").is_err());
+  }
+
+  #[test]
+  fn next_run() {
+    assert_eq!(
+      next("* * * * *", "2024-01-01 10:00"),
+      date("2024-01-01 10:01")
+    );
+    assert_eq!(
+      next("*/15 * * * *", "2024-01-01 10:01"),
+      date("2024-01-01 10:15")
+    );
+    assert_eq!(
+      next("30 2 * * *", "2024-01-01 10:00"),
+      date("2024-01-02 02:30")
+    );
+    assert_eq!(
+      next("@monthly", "2024-01-15 10:00"),
+      date("2024-02-01 00:00")
+    );
+    assert_eq!(
+      next("0 0 29 2 *", "2024-03-01 00:00"),
+      date("2028-02-29 00:00")
+    );
+    // 2024-01-01 is a monday
+    assert_eq!(
+      next("0 9 * * 0", "2024-01-01 10:00"),
+      date("2024-01-07 09:00")
+    );
+    assert_eq!(
+      next("0 9 * * 7", "2024-01-01 10:00"),
+      date("2024-01-07 09:00")
+    );
+    assert_eq!(
+      next("0 9 * * fri", "2024-01-01 10:00"),
+      date("2024-01-05 09:00")
+    );
+    // Day of month or day of week when both are restricted
+    assert_eq!(
+      next("0 0 15 * mon", "2024-01-02 00:00"),
+      date("2024-01-08 00:00")
+    );
+    assert!(parse_schedule("0 0 31 2 *")
+      .unwrap()
+      .next_after(&date("2024-01-01 00:00"))
+      .is_none());
+  }
 }
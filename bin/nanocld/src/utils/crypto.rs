@@ -0,0 +1,595 @@
+/// Encryption at rest of the secrets
+/// Every secret is encrypted with his own data key using AES-256-GCM
+/// and the data key is encrypted with the master key (envelope encryption).
+/// The master keys are stored in the store wrapped with a key read from a file
+/// that must be the same on every node, the newest one being the current key.
+/// Previous keys are kept during a rotation so every row can still be decrypted.
+use std::{
+  os::unix::fs::PermissionsExt,
+  path::Path,
+  sync::{RwLock, atomic::Ordering},
+};
+
+use serde::{Serialize, Deserialize};
+use openssl::{
+  base64,
+  rand::rand_bytes,
+  sha::sha256,
+  symm::{Cipher, decrypt_aead, encrypt_aead},
+};
+
+use nanocl_error::{
+  io::{FromIo, IoError, IoResult},
+  http::{HttpError, HttpResult},
+};
+use nanocl_stubs::{
+  config::DaemonConfig,
+  generic::GenericFilter,
+  secret::{Secret, SecretKeyRotation},
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{
+    LeaseDb, MasterKeyDb, Pool, SecretDb, SecretKeys, SecretUpdateDb,
+    SystemState,
+  },
+};
+
+/// Key of the json object wrapping an encrypted secret data
+const ENVELOPE_KEY: &str = "nanocl.io/encrypted";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Lease held by the node rotating the master key
+const ROTATION_LEASE_KEY: &str = "nanocl.io/secret-key-rotation";
+
+fn map_ssl_err(err: openssl::error::ErrorStack) -> IoError {
+  IoError::other("Secret encryption", &err.to_string())
+}
+
+/// A master key with his identifier
+#[derive(Clone)]
+pub struct MasterKey {
+  /// Identifier of the key, derived from his hash
+  pub id: String,
+  key: Vec<u8>,
+}
+
+impl MasterKey {
+  fn from_bytes(key: Vec<u8>) -> IoResult<Self> {
+    if key.len() != KEY_LEN {
+      return Err(IoError::invalid_data(
+        "Master key",
+        &format!("must be {KEY_LEN} bytes long"),
+      ));
+    }
+    let id = sha256(&key)[..8]
+      .iter()
+      .map(|b| format!("{b:02x}"))
+      .collect::<Vec<_>>()
+      .join("");
+    Ok(Self { id, key })
+  }
+
+  /// Generate a new random key
+  pub fn generate() -> IoResult<Self> {
+    let mut key = vec![0; KEY_LEN];
+    rand_bytes(&mut key).map_err(map_ssl_err)?;
+    Self::from_bytes(key)
+  }
+
+  /// Parse a base64 encoded key
+  pub fn parse(value: &str) -> IoResult<Self> {
+    let key = base64::decode_block(value.trim()).map_err(map_ssl_err)?;
+    Self::from_bytes(key)
+  }
+
+  pub fn to_base64(&self) -> String {
+    base64::encode_block(&self.key)
+  }
+
+  /// Encrypt a master key with this key to store it
+  pub fn wrap(&self, key: &MasterKey) -> IoResult<MasterKeyDb> {
+    Ok(MasterKeyDb {
+      key: key.id.clone(),
+      created_at: chrono::Utc::now().naive_utc(),
+      wrap_key_id: self.id.clone(),
+      data: seal(&self.key, key.id.as_bytes(), &key.key)?,
+    })
+  }
+
+  /// Decrypt a master key wrapped with this key
+  pub fn unwrap(&self, db: &MasterKeyDb) -> IoResult<MasterKey> {
+    if db.wrap_key_id != self.id {
+      return Err(IoError::invalid_data(
+        "Master key",
+        &format!("{} is wrapped with the key {}", db.key, db.wrap_key_id),
+      ));
+    }
+    let key = Self::from_bytes(open(&self.key, db.key.as_bytes(), &db.data)?)?;
+    if key.id != db.key {
+      return Err(IoError::invalid_data(
+        "Master key",
+        &format!("{} doesn't match his id", db.key),
+      ));
+    }
+    Ok(key)
+  }
+}
+
+/// The encrypted form of a secret data
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Envelope {
+  /// Id of the master key used to encrypt the data key
+  key_id: String,
+  /// Data key encrypted with the master key
+  key: String,
+  /// Data encrypted with the data key
+  data: String,
+}
+
+/// Encrypt the given bytes and return `nonce | tag | ciphertext` in base64
+fn seal(key: &[u8], aad: &[u8], plain: &[u8]) -> IoResult<String> {
+  let mut nonce = [0; NONCE_LEN];
+  rand_bytes(&mut nonce).map_err(map_ssl_err)?;
+  let mut tag = [0; TAG_LEN];
+  let cipher = encrypt_aead(
+    Cipher::aes_256_gcm(),
+    key,
+    Some(&nonce),
+    aad,
+    plain,
+    &mut tag,
+  )
+  .map_err(map_ssl_err)?;
+  let mut sealed = Vec::with_capacity(NONCE_LEN + TAG_LEN + cipher.len());
+  sealed.extend_from_slice(&nonce);
+  sealed.extend_from_slice(&tag);
+  sealed.extend_from_slice(&cipher);
+  Ok(base64::encode_block(&sealed))
+}
+
+/// Decrypt a value produced by [seal](seal)
+fn open(key: &[u8], aad: &[u8], sealed: &str) -> IoResult<Vec<u8>> {
+  let sealed = base64::decode_block(sealed).map_err(map_ssl_err)?;
+  if sealed.len() < NONCE_LEN + TAG_LEN {
+    return Err(IoError::invalid_data("Secret encryption", "data too short"));
+  }
+  let (nonce, rest) = sealed.split_at(NONCE_LEN);
+  let (tag, cipher) = rest.split_at(TAG_LEN);
+  decrypt_aead(Cipher::aes_256_gcm(), key, Some(nonce), aad, cipher, tag)
+    .map_err(map_ssl_err)
+}
+
+/// Return the id of the master key used to encrypt the data if it's encrypted
+pub fn get_key_id(data: &serde_json::Value) -> Option<String> {
+  let envelope = data.as_object()?.get(ENVELOPE_KEY)?;
+  let envelope = serde_json::from_value::<Envelope>(envelope.clone()).ok()?;
+  Some(envelope.key_id)
+}
+
+/// The master keys, the first one is used to encrypt
+#[derive(Clone)]
+pub struct KeyRing {
+  keys: Vec<MasterKey>,
+}
+
+impl KeyRing {
+  pub fn new(keys: Vec<MasterKey>) -> IoResult<Self> {
+    if keys.is_empty() {
+      return Err(IoError::invalid_data("Master key", "no key found"));
+    }
+    Ok(Self { keys })
+  }
+
+  /// The key used to encrypt
+  pub fn current(&self) -> &MasterKey {
+    &self.keys[0]
+  }
+
+  /// Whether the key ring contains the key `id`
+  pub fn contains(&self, id: &str) -> bool {
+    self.keys.iter().any(|key| key.id == id)
+  }
+
+  /// Encrypt a secret data, `aad` bind the encrypted data to his secret key
+  pub fn encrypt(
+    &self,
+    aad: &str,
+    data: &serde_json::Value,
+  ) -> IoResult<serde_json::Value> {
+    let master = self.current();
+    let mut data_key = vec![0; KEY_LEN];
+    rand_bytes(&mut data_key).map_err(map_ssl_err)?;
+    let plain = serde_json::to_vec(data)?;
+    let envelope = Envelope {
+      key_id: master.id.clone(),
+      key: seal(&master.key, aad.as_bytes(), &data_key)?,
+      data: seal(&data_key, aad.as_bytes(), &plain)?,
+    };
+    Ok(serde_json::json!({ ENVELOPE_KEY: envelope }))
+  }
+
+  /// Decrypt a secret data, data that isn't encrypted is returned as is
+  pub fn decrypt(
+    &self,
+    aad: &str,
+    data: serde_json::Value,
+  ) -> IoResult<serde_json::Value> {
+    let Some(envelope) = data
+      .as_object()
+      .and_then(|object| object.get(ENVELOPE_KEY))
+      .cloned()
+    else {
+      return Ok(data);
+    };
+    let envelope = serde_json::from_value::<Envelope>(envelope)?;
+    let Some(master) = self.keys.iter().find(|key| key.id == envelope.key_id)
+    else {
+      return Err(IoError::invalid_data(
+        "Secret encryption",
+        &format!("master key {} not found", envelope.key_id),
+      ));
+    };
+    let data_key = open(&master.key, aad.as_bytes(), &envelope.key)?;
+    let plain = open(&data_key, aad.as_bytes(), &envelope.data)?;
+    Ok(serde_json::from_slice(&plain)?)
+  }
+}
+
+/// Path of the file holding the key wrapping the master keys
+pub fn get_master_key_path(conf: &DaemonConfig) -> String {
+  conf
+    .master_key_path
+    .clone()
+    .unwrap_or_else(|| format!("{}/master.key", conf.state_dir))
+}
+
+/// Write the wrap key file only readable by the daemon
+async fn write_wrap_key(path: &str, key: &MasterKey) -> IoResult<()> {
+  if let Some(parent) = Path::new(path).parent() {
+    tokio::fs::create_dir_all(parent)
+      .await
+      .map_err(|err| err.map_err_context(|| format!("Master key {path}")))?;
+  }
+  let tmp_path = format!("{path}.tmp");
+  tokio::fs::write(&tmp_path, format!("{}\n", key.to_base64()))
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Master key {tmp_path}")))?;
+  tokio::fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(0o600))
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Master key {tmp_path}")))?;
+  tokio::fs::rename(&tmp_path, path)
+    .await
+    .map_err(|err| err.map_err_context(|| format!("Master key {path}")))?;
+  Ok(())
+}
+
+/// Read the wrap key from his file.
+/// It's only generated for a new cluster, when the store doesn't have
+/// any master key yet, the other nodes must use a copy of it.
+async fn load_wrap_key(path: &str, pool: &Pool) -> IoResult<MasterKey> {
+  if Path::new(path).exists() {
+    let content = tokio::fs::read_to_string(path)
+      .await
+      .map_err(|err| err.map_err_context(|| format!("Master key {path}")))?;
+    return MasterKey::parse(&content);
+  }
+  let filter = GenericFilter::new().limit(1);
+  if !MasterKeyDb::read_by(&filter, pool).await?.is_empty() {
+    return Err(IoError::not_found(
+      "Master key",
+      &format!("{path} must be a copy of the key of the other nodes"),
+    ));
+  }
+  log::info!("crypto::load_wrap_key: generating a new key at {path}");
+  let key = MasterKey::generate()?;
+  write_wrap_key(path, &key).await?;
+  Ok(key)
+}
+
+/// Unwrap the master keys of the store, the newest one being the current key.
+/// A key that can't be unwrapped is skipped so the others stay usable.
+async fn read_key_ring(wrap_key: &MasterKey, pool: &Pool) -> IoResult<KeyRing> {
+  let keys = MasterKeyDb::read_by(&GenericFilter::new(), pool)
+    .await?
+    .iter()
+    .filter_map(|db| match wrap_key.unwrap(db) {
+      Ok(key) => Some(key),
+      Err(err) => {
+        log::error!("crypto::read_key_ring: {err}");
+        None
+      }
+    })
+    .collect::<Vec<_>>();
+  KeyRing::new(keys)
+}
+
+/// Read the master keys of the store, a first key is created if there is none
+async fn init_key_ring(wrap_key: &MasterKey, pool: &Pool) -> IoResult<KeyRing> {
+  let filter = GenericFilter::new().limit(1);
+  if MasterKeyDb::read_by(&filter, pool).await?.is_empty() {
+    let key = MasterKey::generate()?;
+    MasterKeyDb::create_from(wrap_key.wrap(&key)?, pool).await?;
+  }
+  read_key_ring(wrap_key, pool).await
+}
+
+/// Load the keys encrypting the secrets.
+/// A node without valid keys still start but can't read or write the secrets.
+pub async fn load_secret_keys(conf: &DaemonConfig, pool: &Pool) -> SecretKeys {
+  let path = get_master_key_path(conf);
+  let wrap_key = match load_wrap_key(&path, pool).await {
+    Ok(wrap_key) => wrap_key,
+    Err(err) => {
+      log::error!("crypto::load_secret_keys: {err}");
+      return SecretKeys::default();
+    }
+  };
+  let key_ring = match init_key_ring(&wrap_key, pool).await {
+    Ok(key_ring) => {
+      log::debug!("crypto::load_secret_keys: key {}", key_ring.current().id);
+      Some(key_ring)
+    }
+    Err(err) => {
+      log::error!("crypto::load_secret_keys: {err}");
+      None
+    }
+  };
+  SecretKeys {
+    wrap_key: Some(wrap_key),
+    key_ring: RwLock::new(key_ring),
+    ..Default::default()
+  }
+}
+
+fn get_key_ring(state: &SystemState) -> IoResult<KeyRing> {
+  state
+    .inner
+    .secret_keys
+    .key_ring
+    .read()
+    .map_err(|err| IoError::other("Master key", &err.to_string()))?
+    .clone()
+    .ok_or_else(|| IoError::not_found("Master key", "not loaded"))
+}
+
+/// Read again the master keys of the store to get the ones added by other nodes
+async fn reload_key_ring(state: &SystemState) -> IoResult<KeyRing> {
+  let Some(wrap_key) = &state.inner.secret_keys.wrap_key else {
+    return Err(IoError::not_found("Master key", "wrap key not loaded"));
+  };
+  let key_ring = read_key_ring(wrap_key, &state.inner.pool).await?;
+  *state
+    .inner
+    .secret_keys
+    .key_ring
+    .write()
+    .map_err(|err| IoError::other("Master key", &err.to_string()))? =
+    Some(key_ring.clone());
+  Ok(key_ring)
+}
+
+/// Encrypt a secret data with the newest master key
+pub async fn encrypt(
+  aad: &str,
+  data: &serde_json::Value,
+  state: &SystemState,
+) -> IoResult<serde_json::Value> {
+  reload_key_ring(state).await?.encrypt(aad, data)
+}
+
+/// Decrypt a secret data, the master keys are reloaded
+/// when it's encrypted with a key unknown to the node
+pub async fn decrypt(
+  aad: &str,
+  data: serde_json::Value,
+  state: &SystemState,
+) -> IoResult<serde_json::Value> {
+  let Some(key_id) = get_key_id(&data) else {
+    return Ok(data);
+  };
+  let key_ring = match get_key_ring(state) {
+    Ok(key_ring) if key_ring.contains(&key_id) => key_ring,
+    _ => reload_key_ring(state).await?,
+  };
+  key_ring.decrypt(aad, data)
+}
+
+/// Convert a secret of the store to a secret with his data decrypted
+pub async fn decrypt_secret(
+  secret: SecretDb,
+  state: &SystemState,
+) -> IoResult<Secret> {
+  let data = decrypt(&secret.key, secret.data.clone(), state).await?;
+  let mut secret = Secret::from(secret);
+  secret.data = data;
+  Ok(secret)
+}
+
+/// Encrypt with the current master key every secret that isn't already.
+/// A secret that can't be encrypted is logged and left as is.
+/// Return the number of secrets encrypted and the number of failures.
+async fn reencrypt_secrets(
+  key_ring: &KeyRing,
+  pool: &Pool,
+) -> IoResult<(usize, usize)> {
+  let current_id = key_ring.current().id.clone();
+  let mut count = 0;
+  let mut failed = 0;
+  let mut offset = 0;
+  loop {
+    let filter = GenericFilter::new().limit(100).offset(offset);
+    let secrets = SecretDb::read_by(&filter, pool).await?;
+    for secret in &secrets {
+      if get_key_id(&secret.data).as_deref() == Some(current_id.as_str()) {
+        continue;
+      }
+      let res = async {
+        let data = key_ring.decrypt(&secret.key, secret.data.clone())?;
+        let update = SecretUpdateDb {
+          data: Some(key_ring.encrypt(&secret.key, &data)?),
+          ..Default::default()
+        };
+        SecretDb::update_pk(&secret.key, update, pool).await?;
+        Ok::<_, IoError>(())
+      }
+      .await;
+      match res {
+        Ok(_) => count += 1,
+        Err(err) => {
+          log::warn!("crypto::encrypt_secrets: {} {err}", secret.key);
+          failed += 1;
+        }
+      }
+    }
+    if secrets.len() < 100 {
+      break;
+    }
+    offset += 100;
+  }
+  Ok((count, failed))
+}
+
+/// Encrypt with the current master key every secret that isn't already.
+/// Return the number of secrets encrypted.
+pub async fn encrypt_secrets(state: &SystemState) -> IoResult<usize> {
+  let key_ring = get_key_ring(state)?;
+  let (count, failed) = reencrypt_secrets(&key_ring, &state.inner.pool).await?;
+  if failed > 0 {
+    log::warn!("crypto::encrypt_secrets: {failed} secrets left as is");
+  }
+  Ok(count)
+}
+
+async fn rotate(state: &SystemState) -> IoResult<SecretKeyRotation> {
+  let pool = &state.inner.pool;
+  let Some(wrap_key) = &state.inner.secret_keys.wrap_key else {
+    return Err(IoError::not_found("Master key", "wrap key not loaded"));
+  };
+  let previous = reload_key_ring(state).await?;
+  let new_key = MasterKey::generate()?;
+  MasterKeyDb::create_from(wrap_key.wrap(&new_key)?, pool).await?;
+  let key_ring = reload_key_ring(state).await?;
+  let (mut secrets, _) = reencrypt_secrets(&key_ring, pool).await?;
+  // A node may have encrypted a secret with a previous key
+  // before reading the new one, so they are checked again
+  let (late, failed) = reencrypt_secrets(&key_ring, pool).await?;
+  secrets += late;
+  // Keep the previous keys until every secret is encrypted with the new one
+  if failed > 0 {
+    log::warn!(
+      "crypto::rotate: {failed} secrets still use a previous key, keeping them"
+    );
+  } else {
+    for key in &previous.keys {
+      MasterKeyDb::del_by_pk(&key.id, pool).await?;
+    }
+    reload_key_ring(state).await?;
+  }
+  Ok(SecretKeyRotation {
+    key_id: new_key.id,
+    secrets,
+  })
+}
+
+/// Generate a new master key and encrypt every secret with it.
+/// The rotation is guarded by a lease so a single node run it at a time.
+pub async fn rotate_master_key(
+  state: &SystemState,
+) -> HttpResult<SecretKeyRotation> {
+  let secret_keys = &state.inner.secret_keys;
+  if secret_keys.rotating.swap(true, Ordering::SeqCst) {
+    return Err(HttpError::conflict("A key rotation is already in progress"));
+  }
+  let node = &state.inner.config.hostname;
+  let res = match LeaseDb::acquire(
+    ROTATION_LEASE_KEY,
+    node,
+    "rotate",
+    utils::lease::LEASE_TTL,
+    &state.inner.pool,
+  )
+  .await
+  {
+    Ok(true) => {
+      let res = rotate(state).await;
+      if let Err(err) =
+        LeaseDb::release(ROTATION_LEASE_KEY, node, &state.inner.pool).await
+      {
+        log::warn!("crypto::rotate_master_key: {err}");
+      }
+      res.map_err(HttpError::from)
+    }
+    Ok(false) => {
+      Err(HttpError::conflict("A key rotation is already in progress"))
+    }
+    Err(err) => Err(err.into()),
+  };
+  secret_keys.rotating.store(false, Ordering::SeqCst);
+  let rotation = res?;
+  log::info!(
+    "crypto::rotate_master_key: {} secrets encrypted with key {}",
+    rotation.secrets,
+    rotation.key_id
+  );
+  Ok(rotation)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encrypt_decrypt() {
+    let key_ring = KeyRing::new(vec![MasterKey::generate().unwrap()]).unwrap();
+    let data = serde_json::json!({ "Password": "my-password" });
+    let encrypted = key_ring.encrypt("my-secret", &data).unwrap();
+    assert!(!encrypted.to_string().contains("my-password"));
+    assert_eq!(get_key_id(&encrypted), Some(key_ring.current().id.clone()));
+    let decrypted = key_ring.decrypt("my-secret", encrypted.clone()).unwrap();
+    assert_eq!(decrypted, data);
+    // The data is bound to his secret
+    assert!(key_ring.decrypt("other-secret", encrypted).is_err());
+    // Plain data is returned as is
+    let plain = key_ring.decrypt("my-secret", data.clone()).unwrap();
+    assert_eq!(plain, data);
+  }
+
+  #[test]
+  fn key_ring() {
+    let old = MasterKey::generate().unwrap();
+    let new = MasterKey::generate().unwrap();
+    let old_ring = KeyRing::new(vec![old.clone()]).unwrap();
+    let data = serde_json::json!(["KEY=value"]);
+    let encrypted = old_ring.encrypt("env", &data).unwrap();
+    let rotating = KeyRing::new(vec![new.clone(), old]).unwrap();
+    assert_eq!(rotating.decrypt("env", encrypted.clone()).unwrap(), data);
+    assert!(rotating.contains(&new.id));
+    let new_ring = KeyRing::new(vec![new]).unwrap();
+    assert!(new_ring.decrypt("env", encrypted).is_err());
+    assert!(KeyRing::new(vec![]).is_err());
+    assert!(MasterKey::parse("bm90LWEta2V5").is_err());
+  }
+
+  #[test]
+  fn wrap_key() {
+    let wrap_key = MasterKey::generate().unwrap();
+    let key = MasterKey::generate().unwrap();
+    let wrapped = wrap_key.wrap(&key).unwrap();
+    assert_eq!(wrapped.key, key.id);
+    assert!(!wrapped.data.contains(&key.to_base64()));
+    let unwrapped = wrap_key.unwrap(&wrapped).unwrap();
+    assert_eq!(unwrapped.to_base64(), key.to_base64());
+    // A node with another wrap key can't read the master key
+    let other = MasterKey::generate().unwrap();
+    assert!(other.unwrap(&wrapped).is_err());
+    let mut tampered = wrapped.clone();
+    tampered.key.clone_from(&other.id);
+    assert!(wrap_key.unwrap(&tampered).is_err());
+  }
+}
@@ -0,0 +1,627 @@
+/// Execution of the jobs as a graph of steps
+/// Every step run a container once all the steps it depends on are done,
+/// steps that doesn't depend on each other run in parallel.
+/// The legacy `Containers` of a job are a chain of steps named by their index.
+/// Failed instances are re-created with an exponential backoff.
+use std::{
+  rc::Rc,
+  cell::RefCell,
+  collections::HashMap,
+  time::{Duration, Instant},
+};
+
+use ntex::rt;
+use futures::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use bollard_next::container::{
+  KillContainerOptions, StartContainerOptions, WaitContainerOptions,
+};
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  job::{Job, JobStep},
+  process::Process,
+  system::{EventActor, EventKind, NativeEventAction, ObjPsStatusKind},
+};
+
+use crate::{
+  objects::generic::*,
+  repositories::generic::*,
+  models::{JobDb, ObjPsStatusDb, SystemState},
+};
+
+/// Default number of seconds to wait before re-creating a failed instance
+const DEFAULT_BACKOFF_DELAY: u64 = 10;
+/// Maximum number of seconds to wait before re-creating a failed instance
+const MAX_BACKOFF_DELAY: u64 = 360;
+
+/// State of a step while the job is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepState {
+  Pending,
+  Running,
+  Success,
+  Failed,
+  /// A dependency failed so the step will never run
+  Skipped,
+}
+
+/// Return the steps of a job, `Containers` are converted to sequential steps
+pub fn get_steps(job: &Job) -> Vec<JobStep> {
+  if let Some(steps) = &job.steps {
+    return steps.clone();
+  }
+  job
+    .containers
+    .iter()
+    .enumerate()
+    .map(|(index, container)| JobStep {
+      name: index.to_string(),
+      depends_on: index.checked_sub(1).map(|prev| vec![prev.to_string()]),
+      container: container.clone(),
+      ..Default::default()
+    })
+    .collect()
+}
+
+/// Ensure the steps of a job form a valid graph
+pub fn validate(
+  containers_len: usize,
+  steps: &Option<Vec<JobStep>>,
+) -> HttpResult<()> {
+  let Some(steps) = steps else {
+    return Ok(());
+  };
+  if containers_len > 0 {
+    return Err(HttpError::bad_request(
+      "Containers and Steps cannot be used together",
+    ));
+  }
+  let mut names = HashMap::new();
+  for (index, step) in steps.iter().enumerate() {
+    if step.name.is_empty()
+      || !step
+        .name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+      return Err(HttpError::bad_request(format!(
+        "Step name {} is invalid",
+        step.name
+      )));
+    }
+    if names.insert(step.name.as_str(), index).is_some() {
+      return Err(HttpError::bad_request(format!(
+        "Step {} is duplicated",
+        step.name
+      )));
+    }
+  }
+  for step in steps {
+    for dep in step.depends_on.clone().unwrap_or_default() {
+      if !names.contains_key(dep.as_str()) {
+        return Err(HttpError::bad_request(format!(
+          "Step {} depends on unknown step {dep}",
+          step.name
+        )));
+      }
+    }
+  }
+  // Resolve the graph without running it to detect the cycles
+  let mut graph = JobGraph::new(steps.clone());
+  loop {
+    let ready = graph.ready();
+    if ready.is_empty() {
+      break;
+    }
+    for index in ready {
+      graph.complete(index, true);
+    }
+  }
+  if let Some(index) =
+    graph.states.iter().position(|s| *s != StepState::Success)
+  {
+    return Err(HttpError::bad_request(format!(
+      "Step {} has a circular dependency",
+      graph.steps[index].name
+    )));
+  }
+  Ok(())
+}
+
+/// Track the state of the steps of a running job
+pub struct JobGraph {
+  pub steps: Vec<JobStep>,
+  pub states: Vec<StepState>,
+}
+
+impl JobGraph {
+  pub fn new(steps: Vec<JobStep>) -> Self {
+    let states = vec![StepState::Pending; steps.len()];
+    Self { steps, states }
+  }
+
+  fn state_of(&self, name: &str) -> Option<(StepState, bool)> {
+    let index = self.steps.iter().position(|step| step.name == name)?;
+    let continue_on_failure =
+      self.steps[index].continue_on_failure.unwrap_or_default();
+    Some((self.states[index], continue_on_failure))
+  }
+
+  /// Return the pending steps with all their dependencies done
+  /// and mark them as running.
+  /// Steps with a dependency that failed are skipped.
+  pub fn ready(&mut self) -> Vec<usize> {
+    let mut ready = Vec::new();
+    let mut changed = true;
+    while changed {
+      changed = false;
+      for index in 0..self.steps.len() {
+        if self.states[index] != StepState::Pending {
+          continue;
+        }
+        let deps = self.steps[index].depends_on.clone().unwrap_or_default();
+        let mut done = true;
+        let mut skip = false;
+        for dep in &deps {
+          match self.state_of(dep) {
+            Some((StepState::Success, _)) | Some((StepState::Failed, true)) => {
+            }
+            Some((StepState::Failed, false))
+            | Some((StepState::Skipped, _)) => {
+              skip = true;
+            }
+            _ => done = false,
+          }
+        }
+        if skip {
+          self.states[index] = StepState::Skipped;
+          changed = true;
+        } else if done {
+          self.states[index] = StepState::Running;
+          ready.push(index);
+        }
+      }
+    }
+    ready
+  }
+
+  pub fn complete(&mut self, index: usize, success: bool) {
+    self.states[index] = if success {
+      StepState::Success
+    } else {
+      StepState::Failed
+    };
+  }
+
+  /// Whether a step failed without `ContinueOnFailure`
+  pub fn failed(&self) -> bool {
+    self.steps.iter().zip(&self.states).any(|(step, state)| {
+      *state == StepState::Skipped
+        || (*state == StepState::Failed
+          && !step.continue_on_failure.unwrap_or_default())
+    })
+  }
+
+  /// Final status of the job once every step is done
+  pub fn status(&self) -> ObjPsStatusKind {
+    if self.failed() {
+      ObjPsStatusKind::Fail
+    } else {
+      ObjPsStatusKind::Finish
+    }
+  }
+}
+
+/// Find the process of a step, containers are named `{job}-{step}-{id}.j`
+pub fn find_step_process<'a>(
+  job_name: &str,
+  step: &JobStep,
+  processes: &'a [Process],
+) -> Option<&'a Process> {
+  let prefix = format!("{job_name}-{}", step.name);
+  processes.iter().find(|process| {
+    process
+      .name
+      .trim_start_matches('/')
+      .trim_end_matches(".j")
+      .rsplit_once('-')
+      .map(|(name, _)| name == prefix)
+      .unwrap_or_default()
+  })
+}
+
+/// Whether the job is still wanted to run
+async fn is_wanted(job_name: &str, state: &SystemState) -> HttpResult<bool> {
+  let status = ObjPsStatusDb::read_by_pk(job_name, &state.inner.pool).await?;
+  Ok(status.wanted == ObjPsStatusKind::Finish.to_string())
+}
+
+/// Wait for a container to exit and return his exit code
+async fn wait_exit(key: &str, state: &SystemState) -> HttpResult<i64> {
+  let mut stream = state.inner.docker_api.wait_container(
+    key,
+    Some(WaitContainerOptions {
+      condition: "not-running",
+    }),
+  );
+  let mut code = 0;
+  while let Some(res) = stream.next().await {
+    code = match res {
+      Ok(res) => res.status_code,
+      Err(bollard_next::errors::Error::DockerContainerWaitError {
+        code,
+        ..
+      }) => code,
+      Err(err) => return Err(err.into()),
+    };
+  }
+  Ok(code)
+}
+
+/// Run the container of a step and restart it up to `Retry` times when it fail
+async fn run_step(
+  job_name: &str,
+  step: &JobStep,
+  process: &Process,
+  state: &SystemState,
+) -> HttpResult<bool> {
+  let attempts = step.retry.unwrap_or_default() + 1;
+  for attempt in 0..attempts {
+    if !is_wanted(job_name, state).await? {
+      return Ok(false);
+    }
+    if attempt > 0 {
+      log::info!(
+        "job::run_step: {job_name} retrying step {} ({attempt}/{})",
+        step.name,
+        attempts - 1
+      );
+    }
+    state
+      .inner
+      .docker_api
+      .start_container(&process.key, None::<StartContainerOptions<String>>)
+      .await?;
+    let code = wait_exit(&process.key, state).await?;
+    if code == 0 {
+      return Ok(true);
+    }
+    log::warn!(
+      "job::run_step: {job_name} step {} exited with {code}",
+      step.name
+    );
+  }
+  Ok(false)
+}
+
+/// Delay before re-creating a failed instance for the given retry (starting at 0)
+pub fn backoff_delay(base: u64, retry: usize) -> Duration {
+  let max = MAX_BACKOFF_DELAY.max(base);
+  let factor = 2u64.saturating_pow(retry.min(32) as u32);
+  Duration::from_secs(base.saturating_mul(factor).min(max))
+}
+
+/// Run the step `index`, when `backoff` is greater than 0 the failed instance
+/// is re-created after the backoff delay before running it again.
+/// The new instance is recorded in `processes` as soon as it's created
+/// so it can be killed when the deadline is exceeded.
+async fn run_attempt(
+  job: &Job,
+  step: &JobStep,
+  index: usize,
+  processes: &RefCell<Vec<Process>>,
+  backoff: usize,
+  state: &SystemState,
+) -> HttpResult<bool> {
+  let mut process = processes.borrow()[index].clone();
+  if backoff > 0 {
+    let base = job.backoff_delay.unwrap_or(DEFAULT_BACKOFF_DELAY);
+    ntex::time::sleep(backoff_delay(base, backoff - 1)).await;
+    if !is_wanted(&job.name, state).await? {
+      return Ok(false);
+    }
+    let limit = job.backoff_limit.unwrap_or_default();
+    let actor: EventActor = job.clone().into();
+    state.emit_action(
+      &actor,
+      NativeEventAction::Retry,
+      EventKind::Normal,
+      "backoff",
+      Some(format!(
+        "Re-creating step {} ({backoff}/{limit})",
+        step.name
+      )),
+      Some(serde_json::json!({
+        "Step": step.name,
+        "Retry": backoff,
+        "BackoffLimit": limit,
+      })),
+    );
+    super::container::delete_instances(&[process.key.clone()], state).await?;
+    process = super::container::create_job_instance(
+      &job.name,
+      &step.name,
+      &step.container,
+      state,
+    )
+    .await?;
+    processes.borrow_mut()[index] = process.clone();
+  }
+  run_step(&job.name, step, &process, state).await
+}
+
+/// Kill the instances of the steps still running
+async fn kill_running(
+  graph: &JobGraph,
+  processes: &[Process],
+  state: &SystemState,
+) {
+  for (process, step_state) in processes.iter().zip(&graph.states) {
+    if *step_state != StepState::Running {
+      continue;
+    }
+    if let Err(err) = state
+      .inner
+      .docker_api
+      .kill_container(&process.key, None::<KillContainerOptions<String>>)
+      .await
+    {
+      log::debug!("job::kill_running: {} {err}", process.name);
+    }
+  }
+}
+
+/// Run the steps of a job according to their dependencies
+/// Failed instances are re-created up to the `BackoffLimit` of the job
+/// and the running instances are killed after `ActiveDeadlineSeconds`.
+/// Return the final status of the job with a note explaining a failure,
+/// `None` if the job have been stopped before the end.
+pub async fn run_steps(
+  job: &Job,
+  processes: &[Process],
+  state: &SystemState,
+) -> HttpResult<Option<(ObjPsStatusKind, Option<String>)>> {
+  let mut graph = JobGraph::new(get_steps(job));
+  let step_processes = graph
+    .steps
+    .iter()
+    .map(|step| {
+      find_step_process(&job.name, step, processes)
+        .cloned()
+        .ok_or_else(|| {
+          HttpError::internal_server_error(format!(
+            "Unable to find the process of step {}",
+            step.name
+          ))
+        })
+    })
+    .collect::<HttpResult<Vec<_>>>()?;
+  let step_processes = Rc::new(RefCell::new(step_processes));
+  let deadline = job
+    .active_deadline_seconds
+    .map(|secs| (secs, Instant::now() + Duration::from_secs(secs)));
+  let backoff_limit = job.backoff_limit.unwrap_or_default();
+  let mut backoffs = 0;
+  let mut running = FuturesUnordered::new();
+  let mut retries = Vec::new();
+  loop {
+    if is_wanted(&job.name, state).await? {
+      let next = graph.ready().into_iter().map(|index| (index, 0));
+      for (index, backoff) in next.chain(retries.drain(..)) {
+        let step = graph.steps[index].clone();
+        let processes = step_processes.clone();
+        let job = job.clone();
+        let state = state.clone();
+        running.push(async move {
+          let res =
+            run_attempt(&job, &step, index, &processes, backoff, &state).await;
+          (index, res)
+        });
+      }
+    }
+    let next = match deadline {
+      None => running.next().await,
+      Some((secs, deadline)) => {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match ntex::time::timeout(remaining, running.next()).await {
+          Ok(next) => next,
+          Err(_) => {
+            drop(running);
+            let processes = step_processes.borrow().clone();
+            kill_running(&graph, &processes, state).await;
+            let note = format!("Active deadline of {secs}s exceeded");
+            log::warn!("job::run_steps: {} {note}", job.name);
+            return Ok(Some((ObjPsStatusKind::Fail, Some(note))));
+          }
+        }
+      }
+    };
+    let Some((index, res)) = next else {
+      break;
+    };
+    let success = res.unwrap_or_else(|err| {
+      log::warn!("job::run_steps: {} {err}", job.name);
+      false
+    });
+    if !success
+      && backoffs < backoff_limit
+      && is_wanted(&job.name, state).await?
+    {
+      backoffs += 1;
+      retries.push((index, backoffs));
+      continue;
+    }
+    graph.complete(index, success);
+  }
+  if !is_wanted(&job.name, state).await? {
+    return Ok(None);
+  }
+  Ok(Some((graph.status(), None)))
+}
+
+/// Set the final status of a job and remove it after his `Ttl`
+pub async fn finish(
+  job: &Job,
+  status: &ObjPsStatusKind,
+  note: Option<String>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  ObjPsStatusDb::update_actual_status(&job.name, status, &state.inner.pool)
+    .await?;
+  let action = if *status == ObjPsStatusKind::Fail {
+    NativeEventAction::Fail
+  } else {
+    NativeEventAction::Finish
+  };
+  match note {
+    None => state.emit_normal_native_action_sync(job, action).await,
+    // A note explain why the job failed
+    Some(note) => {
+      let actor: EventActor = job.clone().into();
+      state
+        .emit_action_sync(
+          &actor,
+          action,
+          EventKind::Error,
+          "state_sync",
+          Some(note),
+          None,
+        )
+        .await
+    }
+  }
+  let Some(ttl) = job.ttl else {
+    return Ok(());
+  };
+  let name = job.name.clone();
+  let state = state.clone();
+  rt::spawn(async move {
+    log::debug!("job::finish: {name} will be deleted in {ttl}s");
+    ntex::time::sleep(Duration::from_secs(ttl as u64)).await;
+    let _ = JobDb::del_obj_by_pk(&name, &(), &state).await;
+  });
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use bollard_next::container::Config;
+
+  use nanocl_stubs::process::ProcessKind;
+
+  use super::*;
+
+  fn step(name: &str, depends_on: &[&str]) -> JobStep {
+    JobStep {
+      name: name.to_owned(),
+      depends_on: if depends_on.is_empty() {
+        None
+      } else {
+        Some(depends_on.iter().map(|dep| dep.to_string()).collect())
+      },
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn graph() {
+    let mut build = step("build", &["fetch"]);
+    build.continue_on_failure = Some(true);
+    let steps = vec![
+      step("fetch", &[]),
+      step("lint", &[]),
+      build,
+      step("test", &["build", "lint"]),
+      step("deploy", &["test"]),
+    ];
+    let mut graph = JobGraph::new(steps);
+    // Steps without dependencies run in parallel
+    assert_eq!(graph.ready(), vec![0, 1]);
+    assert!(graph.ready().is_empty());
+    graph.complete(0, true);
+    assert_eq!(graph.ready(), vec![2]);
+    graph.complete(1, true);
+    assert!(graph.ready().is_empty());
+    // A failure with continue on failure doesn't block the next steps
+    graph.complete(2, false);
+    assert_eq!(graph.ready(), vec![3]);
+    // A failure skip the steps depending on it
+    graph.complete(3, false);
+    assert!(graph.ready().is_empty());
+    assert_eq!(graph.states[4], StepState::Skipped);
+    assert_eq!(graph.status(), ObjPsStatusKind::Fail);
+  }
+
+  #[test]
+  fn containers() {
+    let job = Job {
+      name: "my-job".to_owned(),
+      containers: vec![Config::default(), Config::default()],
+      ..Default::default()
+    };
+    let steps = get_steps(&job);
+    assert_eq!(steps[0].depends_on, None);
+    assert_eq!(steps[1].depends_on, Some(vec!["0".to_owned()]));
+    let mut graph = JobGraph::new(steps);
+    assert_eq!(graph.ready(), vec![0]);
+    graph.complete(0, true);
+    assert_eq!(graph.ready(), vec![1]);
+    graph.complete(1, true);
+    assert_eq!(graph.status(), ObjPsStatusKind::Finish);
+  }
+
+  #[test]
+  fn validation() {
+    let steps = Some(vec![step("a", &[]), step("b", &["a"])]);
+    assert!(validate(0, &steps).is_ok());
+    assert!(validate(1, &steps).is_err());
+    assert!(validate(0, &Some(vec![step("a", &["b"])])).is_err());
+    assert!(validate(0, &Some(vec![step("a", &[]), step("a", &[])])).is_err());
+    assert!(validate(0, &Some(vec![step("a b", &[])])).is_err());
+    let cycle = Some(vec![
+      step("a", &["c"]),
+      step("b", &["a"]),
+      step("c", &["b"]),
+    ]);
+    assert!(validate(0, &cycle).is_err());
+  }
+
+  #[test]
+  fn backoff() {
+    assert_eq!(backoff_delay(10, 0), Duration::from_secs(10));
+    assert_eq!(backoff_delay(10, 1), Duration::from_secs(20));
+    assert_eq!(backoff_delay(10, 3), Duration::from_secs(80));
+    assert_eq!(
+      backoff_delay(10, 10),
+      Duration::from_secs(MAX_BACKOFF_DELAY)
+    );
+    assert_eq!(
+      backoff_delay(10, 100),
+      Duration::from_secs(MAX_BACKOFF_DELAY)
+    );
+    assert_eq!(backoff_delay(0, 4), Duration::from_secs(0));
+    assert_eq!(backoff_delay(600, 2), Duration::from_secs(600));
+  }
+
+  #[test]
+  fn step_process() {
+    let process = |name: &str| Process {
+      key: name.to_owned(),
+      created_at: Default::default(),
+      updated_at: Default::default(),
+      name: name.to_owned(),
+      kind: ProcessKind::Job,
+      node_name: "nanocl.internal".to_owned(),
+      kind_key: "my-job".to_owned(),
+      data: Default::default(),
+    };
+    let processes =
+      vec![process("my-job-a-b-x1y2z3.j"), process("my-job-a-4f5g6h.j")];
+    let found = find_step_process("my-job", &step("a", &[]), &processes);
+    assert_eq!(found.unwrap().name, "my-job-a-4f5g6h.j");
+    let found = find_step_process("my-job", &step("a-b", &[]), &processes);
+    assert_eq!(found.unwrap().name, "my-job-a-b-x1y2z3.j");
+    assert!(find_step_process("my-job", &step("c", &[]), &processes).is_none());
+  }
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  process::ProcessKind,
+  system::NativeEventAction,
+};
+
+use crate::{
+  utils,
+  tasks::generic::ObjTaskFuture,
+  repositories::generic::*,
+  models::{LeaseDb, SystemState},
+};
+
+/// Key of the lease held by the leader of the cluster
+pub const LEADER_KEY: &str = "nanocl.io/leader";
+/// Seconds a lease stay valid without being renewed
+pub const LEASE_TTL: u64 = 15;
+/// Seconds between two renewal of the leases of the node
+pub const LEASE_RENEW_INTERVAL: u64 = 5;
+/// Seconds a client lease stay valid, the client must acquire it again
+/// before to keep it
+pub const CLIENT_LEASE_TTL: u64 = 60;
+
+/// Split a task key (`Kind@key`) into the process kind and the key
+/// of the object, only cargoes, vms and jobs have tasks to take over.
+pub fn parse_task_key(task_key: &str) -> Option<(ProcessKind, String)> {
+  let (kind, key) = task_key.split_once('@')?;
+  let kind = ProcessKind::from_str(&kind.to_lowercase()).ok()?;
+  if key.is_empty() {
+    return None;
+  }
+  Some((kind, key.to_owned()))
+}
+
+/// Wrap the task of an object so it only run if this node own its lease.
+/// When another node hold the lease the task is skipped, the node
+/// holding it act on the object and it's taken over only once expired.
+/// The lease is renewed in background while the task is in the task manager
+/// and released when it's done.
+pub fn own_task(
+  task_key: &str,
+  action: &NativeEventAction,
+  task: ObjTaskFuture,
+  state: &SystemState,
+) -> ObjTaskFuture {
+  let task_key = task_key.to_owned();
+  let action = action.clone();
+  let state = state.clone();
+  Box::pin(async move {
+    let node = &state.inner.config.hostname;
+    if !LeaseDb::acquire(
+      &task_key,
+      node,
+      &action.to_string(),
+      LEASE_TTL,
+      &state.inner.pool,
+    )
+    .await?
+    {
+      log::debug!("lease::own_task: {task_key} is owned by another node");
+      return Ok(());
+    }
+    let res = task.await;
+    if let Err(err) = LeaseDb::release(
+      &task_key,
+      &state.inner.config.hostname,
+      &state.inner.pool,
+    )
+    .await
+    {
+      log::warn!("lease::own_task: {task_key} {err}");
+    }
+    res
+  })
+}
+
+/// Acquire or renew the leader lease and return whether this node is the leader
+pub async fn elect(state: &SystemState) -> IoResult<bool> {
+  let is_leader = LeaseDb::acquire(
+    LEADER_KEY,
+    &state.inner.config.hostname,
+    "leader",
+    LEASE_TTL,
+    &state.inner.pool,
+  )
+  .await?;
+  let was_leader = state
+    .inner
+    .is_leader
+    .swap(is_leader, std::sync::atomic::Ordering::SeqCst);
+  if is_leader != was_leader {
+    log::info!("lease::elect: leader {is_leader}");
+  }
+  Ok(is_leader)
+}
+
+/// Release the leases of the tasks that aren't running anymore
+/// and extend the others.
+/// The other leases, like the leader one, are released by their holder
+/// and the client leases are only extended by their client.
+pub async fn renew(state: &SystemState) -> IoResult<()> {
+  let node = &state.inner.config.hostname;
+  let filter = GenericFilter::new()
+    .r#where("node_name", GenericClause::Eq(node.to_owned()));
+  let leases = LeaseDb::read_by(&filter, &state.inner.pool).await?;
+  for lease in leases {
+    if parse_task_key(&lease.key).is_none() {
+      continue;
+    }
+    if state
+      .inner
+      .task_manager
+      .get_task(&lease.key)
+      .await
+      .is_none()
+    {
+      LeaseDb::release(&lease.key, node, &state.inner.pool).await?;
+    }
+  }
+  LeaseDb::renew_by_node(node, LEASE_TTL, &state.inner.pool).await?;
+  Ok(())
+}
+
+/// Take over the tasks of the nodes that didn't renew their leases in time.
+/// The expired lease is dropped and its action emitted again on this node.
+pub async fn take_over(state: &SystemState) -> IoResult<()> {
+  let leases = LeaseDb::read_expired(&state.inner.pool).await?;
+  for lease in leases {
+    if lease.key == LEADER_KEY || lease.node_name == state.inner.config.hostname
+    {
+      continue;
+    }
+    LeaseDb::release(&lease.key, &lease.node_name, &state.inner.pool).await?;
+    let Some((kind, key)) = parse_task_key(&lease.key) else {
+      continue;
+    };
+    let Ok(action) = NativeEventAction::from_str(&lease.action) else {
+      continue;
+    };
+    log::info!(
+      "lease::take_over: {} {} from {}",
+      lease.action,
+      lease.key,
+      lease.node_name
+    );
+    let res = match action {
+      NativeEventAction::Starting => {
+        utils::container::emit_starting(&key, &kind, state).await
+      }
+      NativeEventAction::Stopping => {
+        utils::container::emit_stopping(&key, &kind, state).await
+      }
+      NativeEventAction::Updating | NativeEventAction::Destroying => {
+        utils::container::emit_action(&key, &kind, action, state).await
+      }
+      _ => Ok(()),
+    };
+    if let Err(err) = res {
+      log::warn!("lease::take_over: {} {err}", lease.key);
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn task_key() {
+    let (kind, key) = parse_task_key("Cargo@my-cargo.global").unwrap();
+    assert_eq!(kind, ProcessKind::Cargo);
+    assert_eq!(key, "my-cargo.global");
+    let (kind, key) = parse_task_key("Job@my-job").unwrap();
+    assert_eq!(kind, ProcessKind::Job);
+    assert_eq!(key, "my-job");
+    assert!(parse_task_key("Vm@").is_none());
+    assert!(parse_task_key("Resource@my-resource").is_none());
+    assert!(parse_task_key(LEADER_KEY).is_none());
+  }
+}
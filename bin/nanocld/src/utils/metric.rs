@@ -0,0 +1,465 @@
+use std::collections::{BTreeMap, HashMap};
+
+use nanocl_error::{
+  io::IoResult,
+  http::{HttpError, HttpResult},
+};
+use nanocl_stubs::metric::{MetricAggregateQuery, MetricPoint, MetricSeries};
+
+use crate::{
+  utils,
+  models::{MetricDb, MetricRollupDb, SystemState},
+};
+
+/// Seconds covered by a rollup
+pub const ROLLUP_INTERVAL: i64 = 60;
+/// Seconds to wait after the end of a bucket before rolling it up
+/// to let the last metrics be saved
+const ROLLUP_DELAY: i64 = 10;
+/// Number of buckets rolled up at once
+const ROLLUP_BATCH: i64 = 10;
+/// Maximum number of points returned by an aggregation
+const MAX_POINTS: i64 = 10_000;
+/// Growth between two bins of the histograms,
+/// the percentiles are precise to about 2.5%
+const HISTOGRAM_BASE: f64 = 1.05;
+/// Bin of the values lower or equal to zero
+const HISTOGRAM_ZERO: i32 = i32::MIN;
+
+/// Values of a field aggregated over a bucket of time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rollup {
+  pub count: i64,
+  pub sum: f64,
+  pub min: f64,
+  pub max: f64,
+  /// Number of values by bin of a log scale
+  pub histogram: BTreeMap<i32, i64>,
+}
+
+impl Rollup {
+  /// Add a value
+  pub fn add(&mut self, value: f64) {
+    if self.count == 0 || value < self.min {
+      self.min = value;
+    }
+    if self.count == 0 || value > self.max {
+      self.max = value;
+    }
+    self.count += 1;
+    self.sum += value;
+    *self.histogram.entry(get_bin(value)).or_default() += 1;
+  }
+
+  /// Merge the values of another rollup
+  pub fn merge(&mut self, other: &Rollup) {
+    if other.count == 0 {
+      return;
+    }
+    if self.count == 0 || other.min < self.min {
+      self.min = other.min;
+    }
+    if self.count == 0 || other.max > self.max {
+      self.max = other.max;
+    }
+    self.count += other.count;
+    self.sum += other.sum;
+    for (bin, count) in &other.histogram {
+      *self.histogram.entry(*bin).or_default() += count;
+    }
+  }
+
+  /// Average of the values
+  pub fn avg(&self) -> f64 {
+    if self.count == 0 {
+      return 0.0;
+    }
+    self.sum / self.count as f64
+  }
+
+  /// Estimate the value under which the given ratio of the values are
+  pub fn percentile(&self, ratio: f64) -> f64 {
+    if self.count == 0 {
+      return 0.0;
+    }
+    let rank = ((ratio * self.count as f64).ceil() as i64).max(1);
+    let mut seen = 0;
+    for (bin, count) in &self.histogram {
+      seen += count;
+      if seen >= rank {
+        return get_bin_value(*bin).clamp(self.min, self.max);
+      }
+    }
+    self.max
+  }
+
+  /// Convert into a point of a series starting at the given time
+  pub fn to_point(&self, time: chrono::NaiveDateTime) -> MetricPoint {
+    MetricPoint {
+      time,
+      count: self.count,
+      min: self.min,
+      avg: self.avg(),
+      max: self.max,
+      p50: self.percentile(0.5),
+      p90: self.percentile(0.9),
+      p95: self.percentile(0.95),
+      p99: self.percentile(0.99),
+    }
+  }
+}
+
+impl TryFrom<&MetricRollupDb> for Rollup {
+  type Error = serde_json::Error;
+
+  fn try_from(item: &MetricRollupDb) -> Result<Self, Self::Error> {
+    Ok(Rollup {
+      count: item.count,
+      sum: item.sum,
+      min: item.min,
+      max: item.max,
+      histogram: serde_json::from_value(item.histogram.clone())?,
+    })
+  }
+}
+
+/// Bin of the histogram of a value
+fn get_bin(value: f64) -> i32 {
+  if value <= 0.0 || !value.is_finite() {
+    return HISTOGRAM_ZERO;
+  }
+  value.log(HISTOGRAM_BASE).floor() as i32
+}
+
+/// Value representing a bin of the histogram
+fn get_bin_value(bin: i32) -> f64 {
+  if bin == HISTOGRAM_ZERO {
+    return 0.0;
+  }
+  HISTOGRAM_BASE.powf(bin as f64 + 0.5)
+}
+
+/// Duration of the given seconds
+fn seconds(seconds: i64) -> chrono::Duration {
+  chrono::Duration::try_seconds(seconds).unwrap_or_default()
+}
+
+/// Start of the bucket of `interval` seconds containing the date
+pub fn get_bucket(
+  date: chrono::NaiveDateTime,
+  interval: i64,
+) -> chrono::NaiveDateTime {
+  let timestamp = date.and_utc().timestamp();
+  let start = timestamp - timestamp.rem_euclid(interval);
+  chrono::DateTime::from_timestamp(start, 0)
+    .map(|date| date.naive_utc())
+    .unwrap_or(date)
+}
+
+/// Numeric fields of the data of a metric that can be aggregated.
+/// The `nanocl.io/metrs` metrics have a `Cpu` and a `Memory` field in percent,
+/// for the other kinds all the numeric fields at the top of the data are used.
+pub fn get_fields(kind: &str, data: &serde_json::Value) -> Vec<(String, f64)> {
+  if kind == "nanocl.io/metrs" {
+    let mut fields = Vec::new();
+    if let Some(cpus) = data["Cpus"].as_array() {
+      let usages = cpus
+        .iter()
+        .filter_map(|cpu| cpu["Usage"].as_f64())
+        .collect::<Vec<_>>();
+      if !usages.is_empty() {
+        let cpu = usages.iter().sum::<f64>() / usages.len() as f64;
+        fields.push(("Cpu".to_owned(), cpu));
+      }
+    }
+    let used = data["Memory"]["Used"].as_f64();
+    let total = data["Memory"]["Total"]
+      .as_f64()
+      .filter(|total| *total > 0.0);
+    if let (Some(used), Some(total)) = (used, total) {
+      fields.push(("Memory".to_owned(), used / total * 100.0));
+    }
+    return fields;
+  }
+  let Some(data) = data.as_object() else {
+    return Vec::new();
+  };
+  data
+    .iter()
+    .filter_map(|(name, value)| value.as_f64().map(|v| (name.clone(), v)))
+    .collect()
+}
+
+/// Rollup the fields of the metrics by kind and bucket
+pub fn rollup_metrics(
+  metrics: &[MetricDb],
+) -> HashMap<(String, String, chrono::NaiveDateTime), Rollup> {
+  let mut rollups: HashMap<_, Rollup> = HashMap::new();
+  for metric in metrics {
+    let bucket = get_bucket(metric.created_at, ROLLUP_INTERVAL);
+    for (field, value) in get_fields(&metric.kind, &metric.data) {
+      rollups
+        .entry((metric.kind.clone(), field, bucket))
+        .or_default()
+        .add(value);
+    }
+  }
+  rollups
+}
+
+/// Merge the rollups into buckets of `interval` seconds ordered by time
+pub fn merge_rollups(
+  rollups: &[MetricRollupDb],
+  interval: i64,
+) -> IoResult<Vec<MetricPoint>> {
+  let mut buckets: BTreeMap<chrono::NaiveDateTime, Rollup> = BTreeMap::new();
+  for item in rollups {
+    let rollup = Rollup::try_from(item)?;
+    buckets
+      .entry(get_bucket(item.bucket_at, interval))
+      .or_default()
+      .merge(&rollup);
+  }
+  Ok(
+    buckets
+      .iter()
+      .map(|(time, rollup)| rollup.to_point(*time))
+      .collect(),
+  )
+}
+
+/// Rollup the metrics saved by the current node from the bucket starting
+/// at `since` or from the last saved rollup when not given.
+/// Only the complete buckets are rolled up so a rollup is never updated,
+/// return the start of the next bucket to rollup.
+pub async fn rollup(
+  since: Option<chrono::NaiveDateTime>,
+  state: &SystemState,
+) -> IoResult<Option<chrono::NaiveDateTime>> {
+  let pool = &state.inner.pool;
+  let node_name = &state.inner.config.hostname;
+  let now = chrono::Utc::now().naive_utc();
+  let end = get_bucket(now - seconds(ROLLUP_DELAY), ROLLUP_INTERVAL);
+  let since = match since {
+    Some(since) => Some(since),
+    None => MetricRollupDb::last_bucket(node_name, pool)
+      .await?
+      .map(|bucket| bucket + seconds(ROLLUP_INTERVAL)),
+  };
+  let mut since = match since {
+    Some(since) => since,
+    None => match MetricDb::first_after(node_name, None, pool).await? {
+      Some(date) => get_bucket(date, ROLLUP_INTERVAL),
+      None => return Ok(None),
+    },
+  };
+  let expires_at =
+    utils::retention::rollup_expires_at(&state.inner.config.retention);
+  while since < end {
+    let until = (since + seconds(ROLLUP_INTERVAL * ROLLUP_BATCH)).min(end);
+    let metrics = MetricDb::read_range(node_name, since, until, pool).await?;
+    if metrics.is_empty() {
+      // Skip directly to the next saved metric
+      since = match MetricDb::first_after(node_name, Some(until), pool).await? {
+        Some(date) => get_bucket(date, ROLLUP_INTERVAL).min(end),
+        None => end,
+      };
+      continue;
+    }
+    let items = rollup_metrics(&metrics)
+      .into_iter()
+      .map(|((kind, field, bucket_at), rollup)| {
+        Ok::<_, serde_json::Error>(MetricRollupDb {
+          key: uuid::Uuid::new_v4(),
+          created_at: now,
+          expires_at,
+          bucket_at,
+          node_name: node_name.clone(),
+          kind,
+          field,
+          count: rollup.count,
+          sum: rollup.sum,
+          min: rollup.min,
+          max: rollup.max,
+          histogram: serde_json::to_value(&rollup.histogram)?,
+        })
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    MetricRollupDb::create_many(items, pool).await?;
+    since = until;
+  }
+  Ok(Some(since))
+}
+
+/// Aggregate a field of the metrics of a kind over a time range
+/// from their rollups, the last minute isn't rolled up yet.
+pub async fn aggregate(
+  query: &MetricAggregateQuery,
+  state: &SystemState,
+) -> HttpResult<MetricSeries> {
+  let interval = i64::try_from(query.interval.unwrap_or(60))
+    .map_err(|_| HttpError::bad_request("interval is too large"))?;
+  if interval == 0 {
+    return Err(HttpError::bad_request("interval must be positive"));
+  }
+  // Rollups can't be split so the interval is rounded to the minute
+  let interval = ((interval + ROLLUP_INTERVAL - 1) / ROLLUP_INTERVAL)
+    .saturating_mul(ROLLUP_INTERVAL);
+  let now = chrono::Utc::now().timestamp();
+  let until = query.until.unwrap_or(now);
+  let since = query.since.unwrap_or(until.saturating_sub(3600));
+  if since >= until {
+    return Err(HttpError::bad_request("since must be before until"));
+  }
+  if (until - since) / interval > MAX_POINTS {
+    return Err(HttpError::bad_request(format!(
+      "too many points, the interval must be at least {} seconds",
+      (until - since) / MAX_POINTS
+    )));
+  }
+  let to_date = |timestamp: i64| {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+      .map(|date| date.naive_utc())
+      .ok_or_else(|| {
+        HttpError::bad_request(format!("invalid date {timestamp}"))
+      })
+  };
+  let since = get_bucket(to_date(since)?, interval);
+  let until = to_date(until)?;
+  let rollups = MetricRollupDb::read_range(
+    &query.kind,
+    &query.field,
+    query.node.as_deref(),
+    since,
+    until,
+    &state.inner.pool,
+  )
+  .await?;
+  Ok(MetricSeries {
+    kind: query.kind.clone(),
+    field: query.field.clone(),
+    interval: interval as u64,
+    points: merge_rollups(&rollups, interval)?,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(timestamp: i64) -> chrono::NaiveDateTime {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+      .unwrap()
+      .naive_utc()
+  }
+
+  #[test]
+  fn fields() {
+    let data = serde_json::json!({
+      "Cpus": [{ "Usage": 10.0 }, { "Usage": 30.0 }],
+      "Memory": { "Used": 25, "Total": 100 },
+    });
+    assert_eq!(
+      get_fields("nanocl.io/metrs", &data),
+      vec![("Cpu".to_owned(), 20.0), ("Memory".to_owned(), 25.0)]
+    );
+    let data = serde_json::json!({
+      "Uri": "/",
+      "Status": 200,
+      "RequestTime": 0.5,
+    });
+    let mut fields = get_fields("ncproxy.io/http", &data);
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+      fields,
+      vec![
+        ("RequestTime".to_owned(), 0.5),
+        ("Status".to_owned(), 200.0)
+      ]
+    );
+    assert!(get_fields("test.io/test", &serde_json::json!([1])).is_empty());
+  }
+
+  #[test]
+  fn percentiles() {
+    let mut rollup = Rollup::default();
+    for value in 1..=100 {
+      rollup.add(value as f64);
+    }
+    assert_eq!(rollup.count, 100);
+    assert_eq!(rollup.min, 1.0);
+    assert_eq!(rollup.max, 100.0);
+    assert_eq!(rollup.avg(), 50.5);
+    for (ratio, expected) in [(0.5, 50.0), (0.9, 90.0), (0.99, 99.0)] {
+      let value = rollup.percentile(ratio);
+      assert!((value - expected).abs() / expected < 0.05, "{value}");
+    }
+    assert_eq!(rollup.percentile(1.0), 100.0);
+    // Merging rollups gives the same result as adding the values at once
+    let (mut low, mut high) = (Rollup::default(), Rollup::default());
+    for value in 1..=100 {
+      if value <= 30 {
+        low.add(value as f64);
+      } else {
+        high.add(value as f64);
+      }
+    }
+    low.merge(&high);
+    assert_eq!(low, rollup);
+    let mut zero = Rollup::default();
+    zero.add(0.0);
+    zero.add(0.0);
+    zero.add(4.0);
+    assert_eq!(zero.percentile(0.5), 0.0);
+    assert_eq!(Rollup::default().percentile(0.5), 0.0);
+  }
+
+  #[test]
+  fn buckets() {
+    assert_eq!(get_bucket(date(125), 60), date(120));
+    assert_eq!(get_bucket(date(120), 60), date(120));
+    assert_eq!(get_bucket(date(125), 300), date(0));
+    let metric = |timestamp: i64, value: f64| MetricDb {
+      key: uuid::Uuid::new_v4(),
+      created_at: date(timestamp),
+      expires_at: date(timestamp),
+      node_name: "node1".to_owned(),
+      kind: "test.io/test".to_owned(),
+      data: serde_json::json!({ "Value": value }),
+      note: None,
+    };
+    let rollups =
+      rollup_metrics(&[metric(60, 1.0), metric(90, 3.0), metric(130, 5.0)]);
+    assert_eq!(rollups.len(), 2);
+    let key = ("test.io/test".to_owned(), "Value".to_owned(), date(60));
+    assert_eq!(rollups[&key].count, 2);
+    assert_eq!(rollups[&key].avg(), 2.0);
+    let items = rollups
+      .into_iter()
+      .map(|((kind, field, bucket_at), rollup)| MetricRollupDb {
+        key: uuid::Uuid::new_v4(),
+        created_at: bucket_at,
+        expires_at: bucket_at,
+        bucket_at,
+        node_name: "node1".to_owned(),
+        kind,
+        field,
+        count: rollup.count,
+        sum: rollup.sum,
+        min: rollup.min,
+        max: rollup.max,
+        histogram: serde_json::to_value(&rollup.histogram).unwrap(),
+      })
+      .collect::<Vec<_>>();
+    let points = merge_rollups(&items, 60).unwrap();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].time, date(60));
+    assert_eq!(points[1].max, 5.0);
+    let points = merge_rollups(&items, 300).unwrap();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].time, date(0));
+    assert_eq!(points[0].count, 3);
+    assert_eq!(points[0].min, 1.0);
+    assert_eq!(points[0].avg, 3.0);
+  }
+}
@@ -10,6 +10,22 @@ pub mod exec;
 pub mod ctrl_client;
 pub mod server;
 pub mod container;
+pub mod placement;
+pub mod rollout;
+pub mod autoscale;
+pub mod crypto;
+pub mod token;
+pub mod audit;
+pub mod rbac;
+pub mod quota;
+pub mod network_policy;
+pub mod job;
+pub mod lease;
+pub mod node;
+pub mod reconcile;
+pub mod retention;
+pub mod metric;
+pub mod prometheus;
 pub mod query_string;
 pub mod network;
 
@@ -42,13 +58,12 @@ pub mod tests {
       .try_init();
   }
 
-  pub async fn gen_test_system(routes: Config, version: &str) -> TestSystem {
-    before();
-    // Build a test daemon config
+  /// Daemon config of the tests
+  pub fn gen_test_config() -> DaemonConfig {
     let home = env::var("HOME").expect("Failed to get home dir");
     let docker_host = env::var("DOCKER_SOCKET_PATH")
       .unwrap_or_else(|_| String::from("/var/run/docker.sock"));
-    let config = DaemonConfig {
+    DaemonConfig {
       state_dir: format!("{home}/.nanocl_dev/state"),
       docker_host,
       hostname: "nanocl.internal".to_owned(),
@@ -57,7 +72,15 @@ pub mod tests {
           .to_owned(),
       ),
       ..Default::default()
-    };
+    }
+  }
+
+  pub async fn gen_test_system_from(
+    config: DaemonConfig,
+    routes: Config,
+    version: &str,
+  ) -> TestSystem {
+    before();
     let state = SystemState::new(&config).await.unwrap();
     let state_ptr = state.clone();
     // Create test server
@@ -71,6 +94,10 @@ pub mod tests {
     TestSystem { state, client }
   }
 
+  pub async fn gen_test_system(routes: Config, version: &str) -> TestSystem {
+    gen_test_system_from(gen_test_config(), routes, version).await
+  }
+
   pub async fn gen_default_test_system() -> TestSystem {
     gen_test_system(services::ntex_config, VERSION).await
   }
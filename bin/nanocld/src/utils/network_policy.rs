@@ -0,0 +1,356 @@
+/// Enforcement of the network policies
+/// The policies are translated to iptables rules in the `NANOCL-NETPOL` chain
+/// jumped from `DOCKER-USER`, they filter the traffic between the cargoes
+/// of a namespace on his bridge. The rules are loaded with `iptables-restore`
+/// by executing it in the `nnetpol.system` cargo running in the host network.
+use std::collections::HashMap;
+
+use futures_util::StreamExt;
+use bollard_next::{
+  exec::{CreateExecOptions, StartExecResults},
+  network::InspectNetworkOptions,
+};
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  network_policy::NetworkPolicy,
+  process::{Process, ProcessKind},
+};
+
+use crate::{
+  repositories::generic::*,
+  models::{NetworkPolicyDb, ProcessDb, SystemState},
+};
+
+/// Key of the cargo with the capabilities to update the firewall of the node
+pub const FIREWALL_CARGO: &str = "nnetpol.system";
+/// Chain filtering the traffic between the cargoes
+const CHAIN: &str = "NANOCL-NETPOL";
+/// Maximum number of objects read to compute the rules
+const MAX_OBJECTS: usize = 10_000;
+/// Load the rules given as first argument and jump to the chain from `DOCKER-USER`
+const APPLY_SCRIPT: &str = "printf '%s' \"$1\" | iptables-restore --noflush \
+  && { iptables -C DOCKER-USER -j NANOCL-NETPOL 2>/dev/null \
+  || iptables -I DOCKER-USER -j NANOCL-NETPOL; }";
+
+/// A cargo instance running on the node with his address on the namespace bridge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+  /// Namespace of the cargo
+  pub namespace: String,
+  /// Name of the cargo
+  pub cargo: String,
+  /// Labels of the container
+  pub labels: HashMap<String, String>,
+  /// Ip address of the container on the namespace bridge
+  pub ip: String,
+}
+
+impl Endpoint {
+  /// Endpoint of a running cargo process attached to his namespace network
+  pub fn from_process(process: &Process) -> Option<Self> {
+    if process.kind != ProcessKind::Cargo {
+      return None;
+    }
+    let data = &process.data;
+    if !data.state.as_ref()?.running.unwrap_or_default() {
+      return None;
+    }
+    let labels = data.config.as_ref()?.labels.clone().unwrap_or_default();
+    let namespace = labels.get("io.nanocl.n")?.clone();
+    let cargo = process
+      .kind_key
+      .strip_suffix(&format!(".{namespace}"))?
+      .to_owned();
+    let ip = data
+      .network_settings
+      .as_ref()?
+      .networks
+      .as_ref()?
+      .get(&namespace)?
+      .ip_address
+      .clone()
+      .filter(|ip| !ip.is_empty())?;
+    Some(Self {
+      namespace,
+      cargo,
+      labels,
+      ip,
+    })
+  }
+}
+
+/// Generate the `iptables-restore` input for the policies,
+/// `bridges` give the bridge interface of each namespace.
+/// A cargo targeted by a policy only accept the traffic allowed by one
+/// of the policies targeting it, the other cargoes aren't filtered.
+pub fn gen_rules(
+  policies: &[NetworkPolicy],
+  endpoints: &[Endpoint],
+  bridges: &HashMap<String, String>,
+) -> String {
+  let mut rules = vec![
+    "*filter".to_owned(),
+    format!(":{CHAIN} - [0:0]"),
+    format!("-A {CHAIN} -m conntrack --ctstate RELATED,ESTABLISHED -j RETURN"),
+  ];
+  for endpoint in endpoints {
+    let Some(bridge) = bridges.get(&endpoint.namespace) else {
+      continue;
+    };
+    let targeting = policies
+      .iter()
+      .filter(|policy| policy.namespace_name == endpoint.namespace)
+      .filter(|policy| policy.target.matches(&endpoint.cargo, &endpoint.labels))
+      .collect::<Vec<_>>();
+    if targeting.is_empty() {
+      continue;
+    }
+    let dest =
+      format!("-A {CHAIN} -i {bridge} -o {bridge} -d {}/32", endpoint.ip);
+    for ingress in targeting.iter().flat_map(|policy| &policy.ingress) {
+      let sources = endpoints.iter().filter(|source| {
+        source.namespace == endpoint.namespace
+          && (ingress.from.is_empty()
+            || ingress
+              .from
+              .iter()
+              .any(|from| from.matches(&source.cargo, &source.labels)))
+      });
+      for source in sources {
+        match &ingress.ports {
+          None => {
+            rules.push(format!("{dest} -s {}/32 -j RETURN", source.ip));
+          }
+          Some(ports) => {
+            for port in ports {
+              rules.push(format!(
+                "{dest} -s {}/32 -p {} --dport {} -j RETURN",
+                source.ip, port.protocol, port.port
+              ));
+            }
+          }
+        }
+      }
+    }
+    rules.push(format!("{dest} -j DROP"));
+  }
+  rules.push("COMMIT".to_owned());
+  rules.join("\n") + "\n"
+}
+
+/// Name of the bridge interface of a namespace network
+async fn get_bridge(
+  namespace: &str,
+  state: &SystemState,
+) -> IoResult<Option<String>> {
+  let network = state
+    .inner
+    .docker_api
+    .inspect_network(namespace, None::<InspectNetworkOptions<String>>)
+    .await
+    .map_err(|err| {
+      IoError::interrupted("Network policy", err.to_string().as_str())
+    })?;
+  let name = network
+    .options
+    .as_ref()
+    .and_then(|options| options.get("com.docker.network.bridge.name"))
+    .cloned();
+  if name.is_some() {
+    return Ok(name);
+  }
+  Ok(
+    network
+      .id
+      .map(|id| format!("br-{}", &id[..id.len().min(12)])),
+  )
+}
+
+/// Execute the firewall script in the firewall cargo of the node
+async fn apply_rules(
+  container: &str,
+  rules: &str,
+  state: &SystemState,
+) -> IoResult<()> {
+  let docker_api = &state.inner.docker_api;
+  let map_err = |err: bollard_next::errors::Error| {
+    IoError::interrupted("Network policy", err.to_string().as_str())
+  };
+  let exec = docker_api
+    .create_exec(
+      container,
+      CreateExecOptions {
+        cmd: Some(
+          ["sh", "-c", APPLY_SCRIPT, "sh", rules]
+            .map(ToOwned::to_owned)
+            .to_vec(),
+        ),
+        attach_stdout: Some(true),
+        attach_stderr: Some(true),
+        ..Default::default()
+      },
+    )
+    .await
+    .map_err(map_err)?;
+  let mut logs = String::new();
+  if let StartExecResults::Attached { mut output, .. } = docker_api
+    .start_exec(&exec.id, None)
+    .await
+    .map_err(map_err)?
+  {
+    while let Some(Ok(log)) = output.next().await {
+      logs.push_str(&log.to_string());
+    }
+  }
+  let res = docker_api.inspect_exec(&exec.id).await.map_err(map_err)?;
+  if res.exit_code.unwrap_or_default() != 0 {
+    return Err(IoError::interrupted("Network policy", logs.trim()));
+  }
+  Ok(())
+}
+
+/// Update the firewall of the node with the policies
+/// and the cargo instances currently running on it.
+/// The rules are only loaded when they changed since the last update.
+pub async fn sync(state: &SystemState) -> IoResult<()> {
+  let mut last_rules = state.inner.network_rules.lock().await;
+  let node = &state.inner.config.hostname;
+  let filter = GenericFilter::new()
+    .r#where("node_name", GenericClause::Eq(node.clone()))
+    .r#where("kind", GenericClause::Eq(ProcessKind::Cargo.to_string()))
+    .limit(MAX_OBJECTS);
+  let processes =
+    ProcessDb::transform_read_by(&filter, &state.inner.pool).await?;
+  let Some(firewall) = processes.iter().find(|process| {
+    process.kind_key == FIREWALL_CARGO
+      && process
+        .data
+        .state
+        .as_ref()
+        .and_then(|state| state.running)
+        .unwrap_or_default()
+  }) else {
+    log::debug!("network_policy::sync: {FIREWALL_CARGO} isn't running");
+    *last_rules = None;
+    return Ok(());
+  };
+  let mut endpoints = processes
+    .iter()
+    .filter_map(Endpoint::from_process)
+    .collect::<Vec<_>>();
+  endpoints.sort_by(|a, b| (&a.namespace, &a.ip).cmp(&(&b.namespace, &b.ip)));
+  let mut policies = NetworkPolicyDb::transform_read_by(
+    &GenericFilter::new().limit(MAX_OBJECTS),
+    &state.inner.pool,
+  )
+  .await?;
+  policies.sort_by(|a, b| a.key.cmp(&b.key));
+  let mut bridges = HashMap::new();
+  for policy in &policies {
+    if bridges.contains_key(&policy.namespace_name) {
+      continue;
+    }
+    if let Some(bridge) = get_bridge(&policy.namespace_name, state).await? {
+      bridges.insert(policy.namespace_name.clone(), bridge);
+    }
+  }
+  let rules = gen_rules(&policies, &endpoints, &bridges);
+  // The firewall cargo is part of the cache key so the rules are loaded again
+  // when it's restarted, eg: after a restart of docker that reset the chains
+  let started_at = firewall
+    .data
+    .state
+    .as_ref()
+    .and_then(|state| state.started_at.clone())
+    .unwrap_or_default();
+  let cache = format!("{}@{started_at}\n{rules}", firewall.key);
+  if last_rules.as_deref() == Some(cache.as_str()) {
+    return Ok(());
+  }
+  apply_rules(&firewall.key, &rules, state).await?;
+  log::info!(
+    "network_policy::sync: {} policies applied to {} endpoints",
+    policies.len(),
+    endpoints.len()
+  );
+  *last_rules = Some(cache);
+  Ok(())
+}
+
+/// Update the firewall of the node in background
+pub fn spawn_sync(state: &SystemState) {
+  let state = state.clone();
+  ntex::rt::spawn(async move {
+    if let Err(err) = sync(&state).await {
+      log::warn!("network_policy::sync: {err}");
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::network_policy::{
+    NetworkPolicyIngress, NetworkPolicyPort, NetworkPolicyProtocol,
+    NetworkPolicySelector,
+  };
+
+  use super::*;
+
+  fn endpoint(cargo: &str, ip: &str, role: &str) -> Endpoint {
+    Endpoint {
+      namespace: "global".to_owned(),
+      cargo: cargo.to_owned(),
+      labels: HashMap::from([("role".to_owned(), role.to_owned())]),
+      ip: ip.to_owned(),
+    }
+  }
+
+  #[test]
+  fn rules() {
+    let endpoints = vec![
+      endpoint("db", "10.0.0.2", "db"),
+      endpoint("api", "10.0.0.3", "backend"),
+      endpoint("web", "10.0.0.4", "frontend"),
+    ];
+    let policy = NetworkPolicy {
+      key: "db.global".to_owned(),
+      name: "db".to_owned(),
+      namespace_name: "global".to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+      updated_at: chrono::Utc::now().naive_utc(),
+      target: NetworkPolicySelector {
+        cargoes: Some(vec!["db".to_owned()]),
+        labels: None,
+      },
+      ingress: vec![NetworkPolicyIngress {
+        from: vec![NetworkPolicySelector {
+          cargoes: None,
+          labels: Some(HashMap::from([(
+            "role".to_owned(),
+            "backend".to_owned(),
+          )])),
+        }],
+        ports: Some(vec![NetworkPolicyPort {
+          port: 5432,
+          protocol: NetworkPolicyProtocol::Tcp,
+        }]),
+      }],
+      metadata: None,
+    };
+    let bridges = HashMap::from([("global".to_owned(), "br-0".to_owned())]);
+    let rules = gen_rules(&[policy], &endpoints, &bridges);
+    assert_eq!(
+      rules,
+      "*filter\n\
+      :NANOCL-NETPOL - [0:0]\n\
+      -A NANOCL-NETPOL -m conntrack --ctstate RELATED,ESTABLISHED -j RETURN\n\
+      -A NANOCL-NETPOL -i br-0 -o br-0 -d 10.0.0.2/32 -s 10.0.0.3/32 -p tcp --dport 5432 -j RETURN\n\
+      -A NANOCL-NETPOL -i br-0 -o br-0 -d 10.0.0.2/32 -j DROP\n\
+      COMMIT\n"
+    );
+    let rules = gen_rules(&[], &endpoints, &bridges);
+    assert!(!rules.contains("DROP"));
+  }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeSet;
+
+use nanocl_error::{http::HttpResult, io::IoResult};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  node::{NodeCapacity, NodeState},
+  process::ProcessKind,
+  system::ObjPsStatusKind,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{CargoDb, NodeDb, ProcessDb, SystemState},
+};
+
+/// Seconds between two heartbeats of a node
+pub const HEARTBEAT_INTERVAL: u64 = 10;
+/// Seconds without heartbeat after which a node is marked as `NotReady`
+pub const NODE_TIMEOUT: u64 = 30;
+
+/// Publish the heartbeat of the current node,
+/// it's ready as long as the docker daemon answer.
+/// When it's ready again the replicas moved to other nodes are removed.
+pub async fn heartbeat(state: &SystemState) -> IoResult<()> {
+  let (node_state, capacity) = match state.inner.docker_api.info().await {
+    Ok(info) => (
+      NodeState::Ready,
+      Some(NodeCapacity {
+        cpus: info.ncpu.unwrap_or_default(),
+        memory: info.mem_total.unwrap_or_default(),
+        containers_running: info.containers_running.unwrap_or_default(),
+      }),
+    ),
+    Err(err) => {
+      log::warn!("node::heartbeat: docker {err}");
+      (NodeState::NotReady, None)
+    }
+  };
+  let previous =
+    NodeDb::read_by_pk(&state.inner.config.hostname, &state.inner.pool).await?;
+  NodeDb::heartbeat(
+    &state.inner.config.hostname,
+    &node_state,
+    capacity.as_ref(),
+    &state.inner.pool,
+  )
+  .await?;
+  if previous.state == NodeState::NotReady && node_state == NodeState::Ready {
+    log::info!("node::heartbeat: ready again, removing the orphan replicas");
+    remove_orphans(state).await?;
+  }
+  Ok(())
+}
+
+/// List the name of the nodes marked as `NotReady`
+pub async fn list_not_ready(state: &SystemState) -> IoResult<BTreeSet<String>> {
+  let filter = GenericFilter::new()
+    .r#where("state", GenericClause::Eq(NodeState::NotReady.to_string()))
+    .limit(10_000);
+  let nodes = NodeDb::read_by(&filter, &state.inner.pool).await?;
+  Ok(nodes.into_iter().map(|node| node.name).collect())
+}
+
+/// Start the missing replicas of a cargo on the current node when the
+/// placement assign to it replicas that were running on a lost node.
+/// Replicas placed relatively to the node handling the cargo are only
+/// rescheduled by the leader.
+async fn reschedule_cargo(key: &str, state: &SystemState) -> HttpResult<()> {
+  let cargo = CargoDb::transform_read_by_pk(key, &state.inner.pool).await?;
+  if cargo.status.wanted != ObjPsStatusKind::Start {
+    return Ok(());
+  }
+  let mode = cargo.spec.replication.as_ref();
+  if utils::placement::is_local_mode(mode) && !state.is_leader() {
+    return Ok(());
+  }
+  let number = utils::placement::get_local_number(&cargo, state).await?;
+  let local = utils::container::list_replicas(key, state)
+    .await?
+    .into_iter()
+    .filter(|process| process.node_name == state.inner.config.hostname)
+    .count();
+  if local >= number {
+    return Ok(());
+  }
+  log::info!("node::reschedule: cargo {key} {local}/{number} replicas");
+  utils::container::emit_starting(key, &ProcessKind::Cargo, state).await
+}
+
+/// Delete the replicas of a cargo on the current node that aren't part of
+/// its placement anymore, the newest ones are removed first.
+/// Replicas placed on the node handling the cargo are orphans when another
+/// node run them.
+async fn remove_cargo_orphans(
+  key: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let cargo = CargoDb::transform_read_by_pk(key, &state.inner.pool).await?;
+  let (local, others): (Vec<_>, Vec<_>) =
+    utils::container::list_replicas(key, state)
+      .await?
+      .into_iter()
+      .partition(|process| process.node_name == state.inner.config.hostname);
+  let number =
+    if utils::placement::is_local_mode(cargo.spec.replication.as_ref()) {
+      if others.is_empty() {
+        local.len()
+      } else {
+        0
+      }
+    } else {
+      utils::placement::get_local_number(&cargo, state).await?
+    };
+  if local.len() <= number {
+    return Ok(());
+  }
+  log::info!(
+    "node::remove_orphans: cargo {key} {} replicas",
+    local.len() - number
+  );
+  utils::container::delete_extra_replicas(local, number, state).await
+}
+
+/// Delete the cargo replicas of the current node rescheduled on other nodes
+/// while it was `NotReady`
+pub async fn remove_orphans(state: &SystemState) -> IoResult<()> {
+  let filter = GenericFilter::new()
+    .r#where(
+      "node_name",
+      GenericClause::Eq(state.inner.config.hostname.clone()),
+    )
+    .r#where("kind", GenericClause::Eq("cargo".to_owned()));
+  let keys = ProcessDb::read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .map(|process| process.kind_key)
+    .collect::<BTreeSet<_>>();
+  for key in keys {
+    if let Err(err) = remove_cargo_orphans(&key, state).await {
+      log::warn!("node::remove_orphans: cargo {key} {err}");
+    }
+  }
+  Ok(())
+}
+
+/// Reschedule the cargo replicas of the given nodes onto the ready ones
+pub async fn reschedule(nodes: &[String], state: &SystemState) -> IoResult<()> {
+  let filter = GenericFilter::new()
+    .r#where("node_name", GenericClause::In(nodes.to_vec()))
+    .r#where("kind", GenericClause::Eq("cargo".to_owned()));
+  let keys = ProcessDb::read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .map(|process| process.kind_key)
+    .collect::<BTreeSet<_>>();
+  for key in keys {
+    if let Err(err) = reschedule_cargo(&key, state).await {
+      log::warn!("node::reschedule: cargo {key} {err}");
+    }
+  }
+  Ok(())
+}
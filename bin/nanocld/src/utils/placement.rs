@@ -0,0 +1,496 @@
+/// Placement engine for cargo replicas
+/// It turns a [ReplicationMode](ReplicationMode) into a list of nodes
+/// with the number of replicas each of them must run.
+/// The plan is deterministic so every node sharing the same store agree on it.
+use std::collections::{BTreeMap, HashMap};
+
+use openssl::sha::sha256;
+
+use nanocl_error::http::{HttpError, HttpResult};
+
+use nanocl_stubs::{
+  cargo::Cargo,
+  cargo_spec::ReplicationMode,
+  generic::{GenericClause, GenericFilter},
+  node::NodeState,
+};
+
+use crate::{
+  repositories::generic::*,
+  models::{NodeDb, SystemState},
+};
+
+/// Number of replicas a node must run for a cargo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeAssignment {
+  /// Name of the node
+  pub node: String,
+  /// Number of replicas to run on this node
+  pub number: usize,
+}
+
+/// State of the cluster used to compute a placement
+#[derive(Debug, Clone, Default)]
+pub struct PlacementCtx {
+  /// Key of the cargo to place
+  pub key: String,
+  /// Name of the node computing the placement
+  pub local_node: String,
+  /// Name of every node registered in the store
+  pub nodes: Vec<String>,
+  /// Node names indexed by node group
+  pub groups: HashMap<String, Vec<String>>,
+  /// Number of existing replicas indexed by node name
+  pub current: HashMap<String, usize>,
+  /// Name of the nodes that missed their heartbeats
+  pub not_ready: Vec<String>,
+}
+
+impl PlacementCtx {
+  /// Pick the node that should run a single replica among the ready candidates.
+  /// The candidates are sorted and the node is chosen with a hash of the
+  /// replica key so every node pick the same one whatever it's running,
+  /// and the replicas of different cargoes are spread on the nodes.
+  fn pick_one(&self, candidates: &[String], replica: &str) -> Option<String> {
+    let mut candidates = self.ready(candidates.to_owned());
+    candidates.sort();
+    candidates.dedup();
+    if candidates.is_empty() {
+      return None;
+    }
+    let replica_key = format!("{}/{replica}", self.key);
+    let hash = sha256(replica_key.as_bytes());
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    let index = u64::from_be_bytes(bytes) % candidates.len() as u64;
+    candidates.get(index as usize).cloned()
+  }
+
+  /// Remove the nodes that aren't ready from the candidates so their replicas
+  /// are rescheduled, when none of them is ready they are all kept.
+  fn ready(&self, candidates: Vec<String>) -> Vec<String> {
+    let ready = candidates
+      .iter()
+      .filter(|node| !self.not_ready.contains(node))
+      .cloned()
+      .collect::<Vec<_>>();
+    if ready.is_empty() {
+      return candidates;
+    }
+    ready
+  }
+
+  /// Return the nodes of the given groups or an error if a group doesn't exist
+  fn group_nodes(&self, group: &str) -> HttpResult<Vec<String>> {
+    match self.groups.get(group) {
+      Some(nodes) if !nodes.is_empty() => Ok(nodes.clone()),
+      _ => Err(HttpError::bad_request(format!(
+        "Node group {group} doesn't exist or is empty"
+      ))),
+    }
+  }
+
+  /// Ensure the given node names are registered
+  fn ensure_nodes(&self, names: &[String]) -> HttpResult<()> {
+    for name in names {
+      if !self.nodes.contains(name) {
+        return Err(HttpError::bad_request(format!(
+          "Node {name} doesn't exist"
+        )));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Convert the replica number of a replication mode
+fn parse_number(number: i64) -> HttpResult<usize> {
+  usize::try_from(number).map_err(|_| {
+    HttpError::bad_request(format!(
+      "Replication number must be positive got {number}"
+    ))
+  })
+}
+
+/// Compute the replicas each node must run for the given replication mode.
+/// When no mode is given a single replica runs on the local node.
+/// Nodes that aren't ready are skipped unless the mode target the nodes by name.
+pub fn compute(
+  mode: Option<&ReplicationMode>,
+  ctx: &PlacementCtx,
+) -> HttpResult<Vec<NodeAssignment>> {
+  let mut plan: BTreeMap<String, usize> = BTreeMap::new();
+  let mut nodes = ctx.nodes.clone();
+  if nodes.is_empty() {
+    nodes.push(ctx.local_node.clone());
+  }
+  match mode {
+    None => {
+      plan.insert(ctx.local_node.clone(), 1);
+    }
+    Some(ReplicationMode::Unique) | Some(ReplicationMode::Auto) => {
+      if let Some(node) = ctx.pick_one(&nodes, "0") {
+        plan.insert(node, 1);
+      }
+    }
+    Some(ReplicationMode::UniqueByNode) => {
+      for node in ctx.ready(nodes) {
+        plan.insert(node, 1);
+      }
+    }
+    Some(ReplicationMode::UniqueByNodeGroups { groups }) => {
+      for group in groups {
+        let group_nodes = ctx.group_nodes(group)?;
+        if let Some(node) = ctx.pick_one(&group_nodes, group) {
+          *plan.entry(node).or_default() += 1;
+        }
+      }
+    }
+    Some(ReplicationMode::UniqueByNodeNames { names }) => {
+      ctx.ensure_nodes(names)?;
+      for name in names {
+        plan.insert(name.clone(), 1);
+      }
+    }
+    Some(ReplicationMode::Static(replication)) => {
+      plan.insert(ctx.local_node.clone(), replication.number);
+    }
+    Some(ReplicationMode::StaticByNodes(replication)) => {
+      for node in ctx.ready(nodes) {
+        plan.insert(node, replication.number);
+      }
+    }
+    Some(ReplicationMode::StaticByNodeGroups { groups, number }) => {
+      let number = parse_number(*number)?;
+      for group in groups {
+        for node in ctx.ready(ctx.group_nodes(group)?) {
+          *plan.entry(node).or_default() += number;
+        }
+      }
+    }
+    Some(ReplicationMode::StaticByNodeNames { names, number }) => {
+      let number = parse_number(*number)?;
+      ctx.ensure_nodes(names)?;
+      for name in names {
+        plan.insert(name.clone(), number);
+      }
+    }
+    Some(ReplicationMode::Autoscale(autoscale)) => {
+      // The autoscaler decide the number of replicas within the bounds
+      let current = ctx.current.get(&ctx.local_node).copied().unwrap_or(0);
+      let number =
+        current.clamp(autoscale.min, autoscale.max.max(autoscale.min));
+      plan.insert(ctx.local_node.clone(), number);
+    }
+  }
+  Ok(
+    plan
+      .into_iter()
+      .map(|(node, number)| NodeAssignment { node, number })
+      .collect(),
+  )
+}
+
+/// Whether the replicas of the mode are placed on the node computing the
+/// placement instead of being spread on the nodes of the cluster
+pub fn is_local_mode(mode: Option<&ReplicationMode>) -> bool {
+  matches!(
+    mode,
+    None
+      | Some(ReplicationMode::Static(_))
+      | Some(ReplicationMode::Autoscale(_))
+  )
+}
+
+/// Number of replicas assigned to the given node
+pub fn get_node_number(assignments: &[NodeAssignment], node: &str) -> usize {
+  assignments
+    .iter()
+    .filter(|assignment| assignment.node == node)
+    .map(|assignment| assignment.number)
+    .sum()
+}
+
+/// Build the placement context of a cargo by reading the `nodes` table,
+/// the node groups referenced by the replication mode and the existing processes.
+pub async fn gen_ctx(
+  cargo: &Cargo,
+  state: &SystemState,
+) -> HttpResult<PlacementCtx> {
+  let nodes =
+    NodeDb::read_by(&GenericFilter::new().limit(10_000), &state.inner.pool)
+      .await?
+      .into_iter()
+      .map(|node| node.name)
+      .collect::<Vec<_>>();
+  let groups = match &cargo.spec.replication {
+    Some(ReplicationMode::UniqueByNodeGroups { groups })
+    | Some(ReplicationMode::StaticByNodeGroups { groups, .. }) => {
+      NodeDb::read_by_groups(groups, &state.inner.pool).await?
+    }
+    _ => HashMap::new(),
+  };
+  let not_ready = NodeDb::read_by(
+    &GenericFilter::new()
+      .r#where("state", GenericClause::Eq(NodeState::NotReady.to_string()))
+      .limit(10_000),
+    &state.inner.pool,
+  )
+  .await?
+  .into_iter()
+  .map(|node| node.name)
+  .collect::<Vec<_>>();
+  let mut current: HashMap<String, usize> = HashMap::new();
+  let processes =
+    super::container::list_replicas(&cargo.spec.cargo_key, state).await?;
+  for process in processes {
+    *current.entry(process.node_name).or_default() += 1;
+  }
+  Ok(PlacementCtx {
+    key: cargo.spec.cargo_key.clone(),
+    local_node: state.inner.config.hostname.clone(),
+    nodes,
+    groups,
+    current,
+    not_ready,
+  })
+}
+
+/// Compute the placement of a cargo with the current state of the cluster
+pub async fn get_cargo_assignments(
+  cargo: &Cargo,
+  state: &SystemState,
+) -> HttpResult<Vec<NodeAssignment>> {
+  let ctx = gen_ctx(cargo, state).await?;
+  compute(cargo.spec.replication.as_ref(), &ctx)
+}
+
+/// Number of replicas of a cargo the current node must run
+pub async fn get_local_number(
+  cargo: &Cargo,
+  state: &SystemState,
+) -> HttpResult<usize> {
+  let assignments = get_cargo_assignments(cargo, state).await?;
+  let number = get_node_number(&assignments, &state.inner.config.hostname);
+  log::debug!(
+    "placement: cargo {} assignments {assignments:?} local {number}",
+    cargo.spec.cargo_key
+  );
+  Ok(number)
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::cargo_spec::{ReplicationAutoscale, ReplicationStatic};
+
+  use super::*;
+
+  fn test_ctx() -> PlacementCtx {
+    PlacementCtx {
+      key: "test.global".to_owned(),
+      local_node: "node2".to_owned(),
+      nodes: vec!["node3".to_owned(), "node1".to_owned(), "node2".to_owned()],
+      groups: HashMap::from([
+        (
+          "eu".to_owned(),
+          vec!["node1".to_owned(), "node2".to_owned()],
+        ),
+        ("us".to_owned(), vec!["node3".to_owned()]),
+      ]),
+      current: HashMap::new(),
+      not_ready: Vec::new(),
+    }
+  }
+
+  fn assignment(node: &str, number: usize) -> NodeAssignment {
+    NodeAssignment {
+      node: node.to_owned(),
+      number,
+    }
+  }
+
+  #[test]
+  fn default_and_static() {
+    let ctx = test_ctx();
+    let plan = compute(None, &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 1)]);
+    let mode = ReplicationMode::Static(ReplicationStatic { number: 3 });
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 3)]);
+    let mode = ReplicationMode::StaticByNodes(ReplicationStatic { number: 2 });
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(
+      plan,
+      vec![
+        assignment("node1", 2),
+        assignment("node2", 2),
+        assignment("node3", 2)
+      ]
+    );
+  }
+
+  #[test]
+  fn unique() {
+    let ctx = test_ctx();
+    let plan = compute(Some(&ReplicationMode::Unique), &ctx).unwrap();
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].number, 1);
+    // Every node agree on the placement whatever it's running
+    for local_node in ["node1", "node3"] {
+      let mut other = test_ctx();
+      local_node.clone_into(&mut other.local_node);
+      other.nodes.reverse();
+      other.current.insert(local_node.to_owned(), 1);
+      assert_eq!(
+        compute(Some(&ReplicationMode::Unique), &other).unwrap(),
+        plan
+      );
+    }
+    // The replicas of the cargoes are spread on the nodes
+    let nodes = (0..20)
+      .filter_map(|index| {
+        let mut other = test_ctx();
+        other.key = format!("test-{index}.global");
+        compute(Some(&ReplicationMode::Auto), &other)
+          .unwrap()
+          .pop()
+          .map(|assignment| assignment.node)
+      })
+      .collect::<std::collections::BTreeSet<_>>();
+    assert!(nodes.len() > 1);
+    let plan = compute(Some(&ReplicationMode::UniqueByNode), &ctx).unwrap();
+    assert_eq!(plan.len(), 3);
+    assert_eq!(get_node_number(&plan, "node1"), 1);
+  }
+
+  #[test]
+  fn by_groups_and_names() {
+    let ctx = test_ctx();
+    let mode = ReplicationMode::UniqueByNodeGroups {
+      groups: vec!["eu".to_owned(), "us".to_owned()],
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan.len(), 2);
+    assert_eq!(get_node_number(&plan, "node3"), 1);
+    let mode = ReplicationMode::StaticByNodeGroups {
+      groups: vec!["eu".to_owned()],
+      number: 2,
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node1", 2), assignment("node2", 2)]);
+    assert_eq!(get_node_number(&plan, "node3"), 0);
+    // The replicas of overlapping groups add up on their common nodes
+    let mut overlapping = test_ctx();
+    overlapping.groups.insert(
+      "edge".to_owned(),
+      vec!["node2".to_owned(), "node3".to_owned()],
+    );
+    let mode = ReplicationMode::StaticByNodeGroups {
+      groups: vec!["eu".to_owned(), "edge".to_owned()],
+      number: 2,
+    };
+    let plan = compute(Some(&mode), &overlapping).unwrap();
+    assert_eq!(
+      plan,
+      vec![
+        assignment("node1", 2),
+        assignment("node2", 4),
+        assignment("node3", 2)
+      ]
+    );
+    let mode = ReplicationMode::UniqueByNodeGroups {
+      groups: vec!["edge".to_owned(), "us".to_owned()],
+    };
+    let plan = compute(Some(&mode), &overlapping).unwrap();
+    assert_eq!(
+      plan
+        .iter()
+        .map(|assignment| assignment.number)
+        .sum::<usize>(),
+      2
+    );
+    let mode = ReplicationMode::StaticByNodeNames {
+      names: vec!["node3".to_owned()],
+      number: 4,
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node3", 4)]);
+    let mode = ReplicationMode::UniqueByNodeNames {
+      names: vec!["node1".to_owned(), "node3".to_owned()],
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node1", 1), assignment("node3", 1)]);
+  }
+
+  #[test]
+  fn not_ready() {
+    let mut ctx = test_ctx();
+    ctx.not_ready = vec!["node1".to_owned(), "node3".to_owned()];
+    // The replica of a not ready node is moved
+    let plan = compute(Some(&ReplicationMode::Auto), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 1)]);
+    let plan = compute(Some(&ReplicationMode::Unique), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 1)]);
+    ctx.not_ready = vec!["node2".to_owned()];
+    let plan = compute(Some(&ReplicationMode::UniqueByNode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node1", 1), assignment("node3", 1)]);
+    let mode = ReplicationMode::UniqueByNodeGroups {
+      groups: vec!["eu".to_owned()],
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node1", 1)]);
+    // Nodes targeted by name are kept
+    let mode = ReplicationMode::UniqueByNodeNames {
+      names: vec!["node2".to_owned()],
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 1)]);
+    // When no node is ready they are all kept
+    ctx.not_ready = vec!["node1".to_owned(), "node2".to_owned()];
+    let mode = ReplicationMode::StaticByNodeGroups {
+      groups: vec!["eu".to_owned()],
+      number: 1,
+    };
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node1", 1), assignment("node2", 1)]);
+  }
+
+  #[test]
+  fn autoscale() {
+    let mut ctx = test_ctx();
+    let mode = ReplicationMode::Autoscale(ReplicationAutoscale {
+      min: 2,
+      max: 5,
+      cpu_target: Some(80),
+      memory_target: None,
+    });
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 2)]);
+    // The current number of replicas is kept within the bounds
+    ctx.current.insert("node2".to_owned(), 4);
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 4)]);
+    ctx.current.insert("node2".to_owned(), 8);
+    let plan = compute(Some(&mode), &ctx).unwrap();
+    assert_eq!(plan, vec![assignment("node2", 5)]);
+  }
+
+  #[test]
+  fn invalid() {
+    let ctx = test_ctx();
+    let mode = ReplicationMode::UniqueByNodeGroups {
+      groups: vec!["asia".to_owned()],
+    };
+    assert!(compute(Some(&mode), &ctx).is_err());
+    let mode = ReplicationMode::StaticByNodeNames {
+      names: vec!["node4".to_owned()],
+      number: 1,
+    };
+    assert!(compute(Some(&mode), &ctx).is_err());
+    let mode = ReplicationMode::StaticByNodeNames {
+      names: vec!["node1".to_owned()],
+      number: -1,
+    };
+    assert!(compute(Some(&mode), &ctx).is_err());
+  }
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  node::{NodeCapacity, NodeState},
+  process::ProcessKind,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{CargoDb, JobDb, MetricDb, NodeDb, ProcessDb, SystemState, VmDb},
+};
+
+/// Content type of the OpenMetrics text format
+pub const CONTENT_TYPE: &str =
+  "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Type of a metric family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+  Gauge,
+  Counter,
+}
+
+impl std::fmt::Display for MetricType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Gauge => write!(f, "gauge"),
+      Self::Counter => write!(f, "counter"),
+    }
+  }
+}
+
+/// A metric family with his samples
+#[derive(Debug, Clone)]
+pub struct MetricFamily {
+  pub name: &'static str,
+  pub kind: MetricType,
+  pub help: &'static str,
+  pub samples: Vec<(Vec<(&'static str, String)>, f64)>,
+}
+
+impl MetricFamily {
+  pub fn gauge(name: &'static str, help: &'static str) -> Self {
+    Self {
+      name,
+      kind: MetricType::Gauge,
+      help,
+      samples: Vec::new(),
+    }
+  }
+
+  pub fn counter(name: &'static str, help: &'static str) -> Self {
+    Self {
+      name,
+      kind: MetricType::Counter,
+      help,
+      samples: Vec::new(),
+    }
+  }
+
+  /// Add a sample with the given labels
+  pub fn add(&mut self, labels: &[(&'static str, &str)], value: f64) {
+    let labels = labels
+      .iter()
+      .map(|(name, value)| (*name, (*value).to_owned()))
+      .collect();
+    self.samples.push((labels, value));
+  }
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+/// Format a sample value
+fn format_value(value: f64) -> String {
+  if value.is_nan() {
+    "NaN".to_owned()
+  } else if value == f64::INFINITY {
+    "+Inf".to_owned()
+  } else if value == f64::NEG_INFINITY {
+    "-Inf".to_owned()
+  } else {
+    value.to_string()
+  }
+}
+
+/// Render the families in the OpenMetrics text format
+pub fn render(families: &[MetricFamily]) -> String {
+  let mut lines = Vec::new();
+  for family in families {
+    lines.push(format!("# TYPE {} {}", family.name, family.kind));
+    lines.push(format!("# HELP {} {}", family.name, family.help));
+    let suffix = match family.kind {
+      MetricType::Gauge => "",
+      MetricType::Counter => "_total",
+    };
+    for (labels, value) in &family.samples {
+      let labels = labels
+        .iter()
+        .map(|(name, value)| format!("{name}=\"{}\"", escape(value)))
+        .collect::<Vec<_>>();
+      let labels = if labels.is_empty() {
+        String::new()
+      } else {
+        format!("{{{}}}", labels.join(","))
+      };
+      lines.push(format!(
+        "{}{suffix}{labels} {}",
+        family.name,
+        format_value(*value)
+      ));
+    }
+  }
+  lines.push("# EOF".to_owned());
+  lines.push(String::new());
+  lines.join("\n")
+}
+
+/// Metrics of the nodes from their heartbeat and their last metrs metric
+async fn collect_nodes(
+  state: &SystemState,
+  families: &mut Vec<MetricFamily>,
+) -> IoResult<()> {
+  let mut ready =
+    MetricFamily::gauge("nanocl_node_ready", "Whether the node is ready");
+  let mut heartbeat = MetricFamily::gauge(
+    "nanocl_node_heartbeat_timestamp_seconds",
+    "Last heartbeat of the node",
+  );
+  let mut cpus =
+    MetricFamily::gauge("nanocl_node_cpus", "Number of cpus of the node");
+  let mut memory =
+    MetricFamily::gauge("nanocl_node_memory_bytes", "Total memory of the node");
+  let mut containers = MetricFamily::gauge(
+    "nanocl_node_containers_running",
+    "Number of containers running on the node",
+  );
+  let mut cpu_usage = MetricFamily::gauge(
+    "nanocl_node_cpu_usage_percent",
+    "Average usage of the cpus of the node",
+  );
+  let mut memory_usage = MetricFamily::gauge(
+    "nanocl_node_memory_usage_percent",
+    "Memory used on the node",
+  );
+  let nodes = NodeDb::read_by(&GenericFilter::new(), &state.inner.pool).await?;
+  for node in &nodes {
+    let labels = [("node", node.name.as_str())];
+    ready.add(
+      &labels,
+      if node.state == NodeState::Ready {
+        1.0
+      } else {
+        0.0
+      },
+    );
+    heartbeat.add(&labels, node.heartbeat_at.and_utc().timestamp() as f64);
+    if let Some(capacity) = node.capacity.clone().and_then(|capacity| {
+      serde_json::from_value::<NodeCapacity>(capacity).ok()
+    }) {
+      cpus.add(&labels, capacity.cpus as f64);
+      memory.add(&labels, capacity.memory as f64);
+      containers.add(&labels, capacity.containers_running as f64);
+    }
+    let filter = GenericFilter::new()
+      .r#where("kind", GenericClause::Eq("nanocl.io/metrs".to_owned()))
+      .r#where("node_name", GenericClause::Eq(node.name.clone()))
+      .limit(1);
+    let Some(metric) = MetricDb::read_by(&filter, &state.inner.pool)
+      .await?
+      .into_iter()
+      .next()
+    else {
+      continue;
+    };
+    for (field, value) in utils::metric::get_fields(&metric.kind, &metric.data)
+    {
+      match field.as_str() {
+        "Cpu" => cpu_usage.add(&labels, value),
+        "Memory" => memory_usage.add(&labels, value),
+        _ => {}
+      }
+    }
+  }
+  families.extend([
+    ready,
+    heartbeat,
+    cpus,
+    memory,
+    containers,
+    cpu_usage,
+    memory_usage,
+  ]);
+  Ok(())
+}
+
+/// Metrics of the cargoes, vms, jobs and their processes
+async fn collect_objects(
+  state: &SystemState,
+  families: &mut Vec<MetricFamily>,
+) -> IoResult<()> {
+  let mut objects = MetricFamily::gauge(
+    "nanocl_objects",
+    "Number of objects by kind, namespace and actual status",
+  );
+  let mut instances = MetricFamily::gauge(
+    "nanocl_cargo_instances",
+    "Number of instances of the cargo",
+  );
+  let mut instances_running = MetricFamily::gauge(
+    "nanocl_cargo_instances_running",
+    "Number of running instances of the cargo",
+  );
+  let mut running = MetricFamily::gauge(
+    "nanocl_process_running",
+    "Whether the container of the process is running",
+  );
+  let mut restarts = MetricFamily::counter(
+    "nanocl_process_restarts",
+    "Number of times the container of the process was restarted",
+  );
+  let pool = &state.inner.pool;
+  let filter = GenericFilter::new();
+  let processes = ProcessDb::transform_read_by(&filter, pool).await?;
+  // Number of instances and running instances by kind key
+  let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+  for process in &processes {
+    let is_running = process
+      .data
+      .state
+      .as_ref()
+      .and_then(|state| state.running)
+      .unwrap_or_default();
+    let kind = process.kind.to_string();
+    let labels = [
+      ("node", process.node_name.as_str()),
+      ("kind", kind.as_str()),
+      ("kind_key", process.kind_key.as_str()),
+      ("name", process.name.as_str()),
+    ];
+    running.add(&labels, if is_running { 1.0 } else { 0.0 });
+    restarts.add(
+      &labels,
+      process.data.restart_count.unwrap_or_default() as f64,
+    );
+    if process.kind == ProcessKind::Cargo {
+      let count = counts.entry(&process.kind_key).or_default();
+      count.0 += 1;
+      if is_running {
+        count.1 += 1;
+      }
+    }
+  }
+  // Number of objects by kind, namespace and status
+  let mut statuses: BTreeMap<(&str, String, String), usize> = BTreeMap::new();
+  let cargoes = CargoDb::transform_read_by(&filter, pool).await?;
+  for cargo in &cargoes {
+    let (total, running) = counts
+      .get(cargo.spec.cargo_key.as_str())
+      .copied()
+      .unwrap_or_default();
+    let labels = [
+      ("namespace", cargo.namespace_name.as_str()),
+      ("cargo", cargo.spec.name.as_str()),
+    ];
+    instances.add(&labels, total as f64);
+    instances_running.add(&labels, running as f64);
+    *statuses
+      .entry((
+        "cargo",
+        cargo.namespace_name.clone(),
+        cargo.status.actual.to_string(),
+      ))
+      .or_default() += 1;
+  }
+  let vms = VmDb::transform_read_by(&filter, pool).await?;
+  for vm in &vms {
+    *statuses
+      .entry((
+        "vm",
+        vm.namespace_name.clone(),
+        vm.status.actual.to_string(),
+      ))
+      .or_default() += 1;
+  }
+  let jobs = JobDb::transform_read_by(&filter, pool).await?;
+  for job in &jobs {
+    *statuses
+      .entry(("job", String::new(), job.status.actual.to_string()))
+      .or_default() += 1;
+  }
+  for ((kind, namespace, status), count) in &statuses {
+    if namespace.is_empty() {
+      objects.add(&[("kind", kind), ("status", status)], *count as f64);
+    } else {
+      objects.add(
+        &[("kind", kind), ("namespace", namespace), ("status", status)],
+        *count as f64,
+      );
+    }
+  }
+  families.extend([objects, instances, instances_running, running, restarts]);
+  Ok(())
+}
+
+/// Counters of the http requests proxied by the current node
+fn collect_http(state: &SystemState, families: &mut Vec<MetricFamily>) {
+  let mut requests = MetricFamily::counter(
+    "nanocl_http_requests",
+    "Number of http requests proxied by the node",
+  );
+  let mut request_time = MetricFamily::counter(
+    "nanocl_http_request_time_seconds",
+    "Time spent processing the http requests proxied by the node",
+  );
+  let mut bytes_sent = MetricFamily::counter(
+    "nanocl_http_sent_bytes",
+    "Bytes sent to the clients of the http requests proxied by the node",
+  );
+  let node = state.inner.config.hostname.as_str();
+  for ((host, status), counter) in state.inner.http_counters.snapshot() {
+    let status = status.to_string();
+    let labels = [
+      ("node", node),
+      ("host", host.as_str()),
+      ("status", status.as_str()),
+    ];
+    requests.add(&labels, counter.requests as f64);
+    request_time.add(&labels, counter.request_time);
+    bytes_sent.add(&labels, counter.bytes_sent as f64);
+  }
+  families.extend([requests, request_time, bytes_sent]);
+}
+
+/// Collect the metrics of the cluster and the http counters of the node
+pub async fn collect(state: &SystemState) -> IoResult<Vec<MetricFamily>> {
+  let mut families = Vec::new();
+  collect_nodes(state, &mut families).await?;
+  collect_objects(state, &mut families).await?;
+  collect_http(state, &mut families);
+  Ok(families)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_families() {
+    let mut gauge = MetricFamily::gauge("nanocl_test", "Test gauge");
+    gauge.add(&[("node", "node1"), ("name", "a \"b\"\n")], 1.5);
+    gauge.add(&[], f64::INFINITY);
+    let mut counter = MetricFamily::counter("nanocl_test_count", "Test count");
+    counter.add(&[("node", "node1")], 3.0);
+    assert_eq!(
+      render(&[gauge, counter]),
+      [
+        "# TYPE nanocl_test gauge",
+        "# HELP nanocl_test Test gauge",
+        "nanocl_test{node=\"node1\",name=\"a \\\"b\\\"\\n\"} 1.5",
+        "nanocl_test +Inf",
+        "# TYPE nanocl_test_count counter",
+        "# HELP nanocl_test_count Test count",
+        "nanocl_test_count_total{node=\"node1\"} 3",
+        "# EOF",
+        "",
+      ]
+      .join("\n")
+    );
+  }
+}
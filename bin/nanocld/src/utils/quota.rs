@@ -0,0 +1,501 @@
+/// Quotas of the namespaces
+/// The usage of a namespace is computed from the specs of his cargoes,
+/// virtual machines and for the `global` namespace of the jobs.
+/// A creation or an update is refused when it grows a usage above his limit.
+use bollard_next::container::Config;
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  cargo_spec::{CargoSpecPartial, ReplicationMode},
+  generic::{GenericClause, GenericFilter},
+  job::{Job, JobPartial},
+  namespace::{NamespaceQuota, NamespaceQuotaInspect, NamespaceQuotaUsage},
+  vm_spec::VmSpecPartial,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{CargoDb, JobDb, NamespaceQuotaDb, NodeDb, SystemState, VmDb},
+};
+
+/// Maximum number of objects read to compute a usage
+const MAX_OBJECTS: usize = 10_000;
+/// Disk size in GB of a virtual machine when not set
+const DEFAULT_VM_DISK: u64 = 20;
+
+/// Memory in bytes and cpu in units of 10<sup>-9</sup> CPUs
+/// reserved by the `HostConfig` of a container
+fn get_container_limits(config: &Config) -> (Option<i64>, Option<i64>) {
+  let Some(host_config) = &config.host_config else {
+    return (None, None);
+  };
+  let memory = host_config.memory.filter(|memory| *memory > 0);
+  let nano_cpus =
+    host_config.nano_cpus.filter(|cpus| *cpus > 0).or_else(|| {
+      match (host_config.cpu_quota, host_config.cpu_period) {
+        (Some(quota), Some(period)) if quota > 0 && period > 0 => {
+          Some(quota.saturating_mul(1_000_000_000) / period)
+        }
+        _ => None,
+      }
+    });
+  (memory, nano_cpus)
+}
+
+/// Ensure a container define the limits constrained by the quota
+fn ensure_container_limits(
+  namespace: &str,
+  config: &Config,
+  quota: &NamespaceQuota,
+) -> HttpResult<()> {
+  let (memory, nano_cpus) = get_container_limits(config);
+  if quota.max_memory.is_some() && memory.is_none() {
+    return Err(HttpError::forbidden(format!(
+      "Namespace {namespace} has a memory quota, HostConfig.Memory must be set"
+    )));
+  }
+  if quota.max_nano_cpus.is_some() && nano_cpus.is_none() {
+    return Err(HttpError::forbidden(format!(
+      "Namespace {namespace} has a cpu quota, HostConfig.NanoCpus must be set"
+    )));
+  }
+  Ok(())
+}
+
+/// Number of replicas a replication mode can run at most,
+/// the per node modes are multiplied by the number of targeted nodes.
+async fn count_replicas(
+  mode: Option<&ReplicationMode>,
+  nodes: usize,
+  state: &SystemState,
+) -> HttpResult<usize> {
+  let count = match mode {
+    None | Some(ReplicationMode::Auto) | Some(ReplicationMode::Unique) => 1,
+    Some(ReplicationMode::UniqueByNode) => nodes.max(1),
+    Some(ReplicationMode::UniqueByNodeGroups { groups }) => groups.len(),
+    Some(ReplicationMode::UniqueByNodeNames { names }) => names.len(),
+    Some(ReplicationMode::Static(replication)) => replication.number,
+    Some(ReplicationMode::StaticByNodes(replication)) => {
+      replication.number.saturating_mul(nodes.max(1))
+    }
+    Some(ReplicationMode::StaticByNodeGroups { groups, number }) => {
+      let groups = NodeDb::read_by_groups(groups, &state.inner.pool).await?;
+      let nodes = groups.values().map(|nodes| nodes.len()).sum::<usize>();
+      usize::try_from(*number)
+        .unwrap_or_default()
+        .saturating_mul(nodes)
+    }
+    Some(ReplicationMode::StaticByNodeNames { names, number }) => {
+      usize::try_from(*number)
+        .unwrap_or_default()
+        .saturating_mul(names.len())
+    }
+    Some(ReplicationMode::Autoscale(autoscale)) => {
+      autoscale.max.max(autoscale.min)
+    }
+  };
+  Ok(count)
+}
+
+/// Usage of a cargo with the given replication and container
+async fn get_cargo_usage(
+  mode: Option<&ReplicationMode>,
+  container: &Config,
+  nodes: usize,
+  state: &SystemState,
+) -> HttpResult<NamespaceQuotaUsage> {
+  let replicas = count_replicas(mode, nodes, state).await?;
+  let (memory, nano_cpus) = get_container_limits(container);
+  let count = i64::try_from(replicas).unwrap_or(i64::MAX);
+  Ok(NamespaceQuotaUsage {
+    cargoes: 1,
+    replicas,
+    memory: memory.unwrap_or_default().saturating_mul(count),
+    nano_cpus: nano_cpus.unwrap_or_default().saturating_mul(count),
+    ..Default::default()
+  })
+}
+
+/// Containers of a job, the steps included
+fn get_job_containers<'a>(
+  containers: &'a [Config],
+  steps: Option<&'a Vec<nanocl_stubs::job::JobStep>>,
+) -> Vec<&'a Config> {
+  containers
+    .iter()
+    .chain(steps.into_iter().flatten().map(|step| &step.container))
+    .collect()
+}
+
+/// Usage of a job, every container is counted as it can run at once
+fn get_job_usage(containers: &[&Config]) -> NamespaceQuotaUsage {
+  containers
+    .iter()
+    .fold(NamespaceQuotaUsage::default(), |usage, container| {
+      let (memory, nano_cpus) = get_container_limits(container);
+      NamespaceQuotaUsage {
+        memory: usage.memory.saturating_add(memory.unwrap_or_default()),
+        nano_cpus: usage
+          .nano_cpus
+          .saturating_add(nano_cpus.unwrap_or_default()),
+        ..usage
+      }
+    })
+}
+
+/// Sum two usages
+fn add_usage(
+  usage: NamespaceQuotaUsage,
+  other: &NamespaceQuotaUsage,
+) -> NamespaceQuotaUsage {
+  NamespaceQuotaUsage {
+    cargoes: usage.cargoes.saturating_add(other.cargoes),
+    replicas: usage.replicas.saturating_add(other.replicas),
+    memory: usage.memory.saturating_add(other.memory),
+    nano_cpus: usage.nano_cpus.saturating_add(other.nano_cpus),
+    vms: usage.vms.saturating_add(other.vms),
+    vm_disk: usage.vm_disk.saturating_add(other.vm_disk),
+  }
+}
+
+/// Number of nodes of the cluster used to count the per node replicas
+async fn count_nodes(state: &SystemState) -> HttpResult<usize> {
+  let count =
+    NodeDb::count_by(&GenericFilter::new(), &state.inner.pool).await?;
+  Ok(usize::try_from(count).unwrap_or_default())
+}
+
+/// Compute the usage of a namespace, the object with the key `exclude`
+/// isn't counted so it can be replaced by his new spec
+pub async fn get_usage(
+  namespace: &str,
+  exclude: Option<&str>,
+  state: &SystemState,
+) -> HttpResult<NamespaceQuotaUsage> {
+  let nodes = count_nodes(state).await?;
+  let filter = GenericFilter::new()
+    .r#where("namespace_name", GenericClause::Eq(namespace.to_owned()))
+    .limit(MAX_OBJECTS);
+  let mut usage = NamespaceQuotaUsage::default();
+  let cargoes = CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for cargo in cargoes {
+    if Some(cargo.spec.cargo_key.as_str()) == exclude {
+      continue;
+    }
+    let cargo_usage = get_cargo_usage(
+      cargo.spec.replication.as_ref(),
+      &cargo.spec.container,
+      nodes,
+      state,
+    )
+    .await?;
+    usage = add_usage(usage, &cargo_usage);
+  }
+  let vms = VmDb::transform_read_by(&filter, &state.inner.pool).await?;
+  for vm in vms {
+    if Some(vm.spec.vm_key.as_str()) == exclude {
+      continue;
+    }
+    usage.vms += 1;
+    usage.vm_disk = usage
+      .vm_disk
+      .saturating_add(vm.spec.disk.size.unwrap_or(DEFAULT_VM_DISK));
+  }
+  // Jobs aren't namespaced, they run in the default namespace
+  if namespace == utils::key::resolve_nsp(&None) {
+    let jobs = JobDb::transform_read_by(
+      &GenericFilter::new().limit(MAX_OBJECTS),
+      &state.inner.pool,
+    )
+    .await?;
+    for job in jobs {
+      let Job {
+        name,
+        containers,
+        steps,
+        ..
+      } = job;
+      if Some(name.as_str()) == exclude {
+        continue;
+      }
+      let containers = get_job_containers(&containers, steps.as_ref());
+      usage = add_usage(usage, &get_job_usage(&containers));
+    }
+  }
+  Ok(usage)
+}
+
+/// Read the quota of a namespace if it has one
+pub async fn read_quota(
+  namespace: &str,
+  state: &SystemState,
+) -> HttpResult<Option<NamespaceQuotaDb>> {
+  let filter = GenericFilter::new()
+    .r#where("namespace_name", GenericClause::Eq(namespace.to_owned()));
+  let quota = NamespaceQuotaDb::read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .next();
+  Ok(quota)
+}
+
+/// Inspect the quota of a namespace with his current usage
+pub async fn inspect(
+  namespace: &str,
+  state: &SystemState,
+) -> HttpResult<NamespaceQuotaInspect> {
+  let Some(item) = read_quota(namespace, state).await? else {
+    return Err(HttpError::not_found(format!(
+      "Namespace {namespace} has no quota"
+    )));
+  };
+  let usage = get_usage(namespace, None, state).await?;
+  Ok(NamespaceQuotaInspect {
+    quota: item.try_to_quota()?,
+    namespace_name: item.namespace_name,
+    created_at: item.created_at,
+    updated_at: item.updated_at,
+    usage,
+  })
+}
+
+/// Create or replace the quota of a namespace
+pub async fn put(
+  namespace: &str,
+  quota: &NamespaceQuota,
+  state: &SystemState,
+) -> HttpResult<NamespaceQuotaInspect> {
+  let item = NamespaceQuotaDb::try_new(namespace, quota)?;
+  match read_quota(namespace, state).await? {
+    Some(_) => {
+      NamespaceQuotaDb::update_pk(namespace, item, &state.inner.pool).await?;
+    }
+    None => {
+      NamespaceQuotaDb::create_from(item, &state.inner.pool).await?;
+    }
+  }
+  inspect(namespace, state).await
+}
+
+/// Return the limits that the usage would exceed if it add the requested one.
+/// A limit is only enforced when the request grows the usage of the object
+/// it replaces, so an object can still shrink in a namespace above his quota.
+fn get_violations(
+  quota: &NamespaceQuota,
+  current: &NamespaceQuotaUsage,
+  previous: &NamespaceQuotaUsage,
+  requested: &NamespaceQuotaUsage,
+) -> Vec<String> {
+  let mut violations = Vec::new();
+  let mut check = |name: &str, used: i128, prev: i128, req: i128, max| {
+    if let Some(max) = max {
+      if req > prev && used + req > max {
+        violations
+          .push(format!("{name} {used} used + {req} requested > {max}"));
+      }
+    }
+  };
+  check(
+    "cargoes",
+    current.cargoes as i128,
+    previous.cargoes as i128,
+    requested.cargoes as i128,
+    quota.max_cargoes.map(|max| max as i128),
+  );
+  check(
+    "replicas",
+    current.replicas as i128,
+    previous.replicas as i128,
+    requested.replicas as i128,
+    quota.max_replicas.map(|max| max as i128),
+  );
+  check(
+    "memory",
+    current.memory.into(),
+    previous.memory.into(),
+    requested.memory.into(),
+    quota.max_memory.map(i128::from),
+  );
+  check(
+    "nano_cpus",
+    current.nano_cpus.into(),
+    previous.nano_cpus.into(),
+    requested.nano_cpus.into(),
+    quota.max_nano_cpus.map(i128::from),
+  );
+  check(
+    "vms",
+    current.vms as i128,
+    previous.vms as i128,
+    requested.vms as i128,
+    quota.max_vms.map(|max| max as i128),
+  );
+  check(
+    "vm_disk",
+    current.vm_disk.into(),
+    previous.vm_disk.into(),
+    requested.vm_disk.into(),
+    quota.max_vm_disk.map(i128::from),
+  );
+  violations
+}
+
+/// Refuse with a forbidden error the requested usage exceeding the quota
+async fn check(
+  namespace: &str,
+  key: &str,
+  quota: &NamespaceQuota,
+  requested: &NamespaceQuotaUsage,
+  previous: &NamespaceQuotaUsage,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let current = get_usage(namespace, Some(key), state).await?;
+  let violations = get_violations(quota, &current, previous, requested);
+  if violations.is_empty() {
+    return Ok(());
+  }
+  Err(HttpError::forbidden(format!(
+    "Quota of namespace {namespace} exceeded: {}",
+    violations.join(", ")
+  )))
+}
+
+/// Ensure the cargo spec fit in the quota of his namespace,
+/// `previous` is the cargo replaced by an update
+pub async fn check_cargo(
+  namespace: &str,
+  key: &str,
+  spec: &CargoSpecPartial,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let Some(quota) = read_quota(namespace, state).await? else {
+    return Ok(());
+  };
+  let quota = quota.try_to_quota()?;
+  ensure_container_limits(namespace, &spec.container, &quota)?;
+  let nodes = count_nodes(state).await?;
+  let requested =
+    get_cargo_usage(spec.replication.as_ref(), &spec.container, nodes, state)
+      .await?;
+  let previous =
+    match CargoDb::transform_read_by_pk(key, &state.inner.pool).await {
+      Ok(cargo) => {
+        get_cargo_usage(
+          cargo.spec.replication.as_ref(),
+          &cargo.spec.container,
+          nodes,
+          state,
+        )
+        .await?
+      }
+      Err(_) => NamespaceQuotaUsage::default(),
+    };
+  check(namespace, key, &quota, &requested, &previous, state).await
+}
+
+/// Ensure the virtual machine spec fit in the quota of his namespace
+pub async fn check_vm(
+  namespace: &str,
+  key: &str,
+  spec: &VmSpecPartial,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let Some(quota) = read_quota(namespace, state).await? else {
+    return Ok(());
+  };
+  let quota = quota.try_to_quota()?;
+  let requested = NamespaceQuotaUsage {
+    vms: 1,
+    vm_disk: spec.disk.size.unwrap_or(DEFAULT_VM_DISK),
+    ..Default::default()
+  };
+  let previous = match VmDb::transform_read_by_pk(key, &state.inner.pool).await
+  {
+    Ok(vm) => NamespaceQuotaUsage {
+      vms: 1,
+      vm_disk: vm.spec.disk.size.unwrap_or(DEFAULT_VM_DISK),
+      ..Default::default()
+    },
+    Err(_) => NamespaceQuotaUsage::default(),
+  };
+  check(namespace, key, &quota, &requested, &previous, state).await
+}
+
+/// Ensure the job fit in the quota of the default namespace
+pub async fn check_job(
+  job: &JobPartial,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let namespace = utils::key::resolve_nsp(&None);
+  let Some(quota) = read_quota(&namespace, state).await? else {
+    return Ok(());
+  };
+  let quota = quota.try_to_quota()?;
+  let containers = get_job_containers(&job.containers, job.steps.as_ref());
+  for container in &containers {
+    ensure_container_limits(&namespace, container, &quota)?;
+  }
+  let requested = get_job_usage(&containers);
+  let previous = NamespaceQuotaUsage::default();
+  check(&namespace, &job.name, &quota, &requested, &previous, state).await
+}
+
+#[cfg(test)]
+mod tests {
+  use bollard_next::service::HostConfig;
+
+  use super::*;
+
+  #[test]
+  fn limits() {
+    let config = Config {
+      host_config: Some(HostConfig {
+        memory: Some(64),
+        cpu_quota: Some(50_000),
+        cpu_period: Some(100_000),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    assert_eq!(get_container_limits(&config), (Some(64), Some(500_000_000)));
+    assert_eq!(get_container_limits(&Config::default()), (None, None));
+    let quota = NamespaceQuota {
+      max_memory: Some(128),
+      ..Default::default()
+    };
+    assert!(ensure_container_limits("global", &config, &quota).is_ok());
+    assert!(
+      ensure_container_limits("global", &Config::default(), &quota).is_err()
+    );
+  }
+
+  #[test]
+  fn violations() {
+    let quota = NamespaceQuota {
+      max_cargoes: Some(2),
+      max_replicas: Some(4),
+      ..Default::default()
+    };
+    let current = NamespaceQuotaUsage {
+      cargoes: 1,
+      replicas: 3,
+      ..Default::default()
+    };
+    let requested = NamespaceQuotaUsage {
+      cargoes: 1,
+      replicas: 2,
+      ..Default::default()
+    };
+    let none = NamespaceQuotaUsage::default();
+    let violations = get_violations(&quota, &current, &none, &requested);
+    assert_eq!(violations, vec!["replicas 3 used + 2 requested > 4"]);
+    // Shrinking an object is allowed even above the quota
+    let previous = NamespaceQuotaUsage {
+      cargoes: 1,
+      replicas: 3,
+      ..Default::default()
+    };
+    assert!(get_violations(&quota, &current, &previous, &requested).is_empty());
+  }
+}
@@ -0,0 +1,131 @@
+/// Role based access control of the http api
+/// The clients authenticated with a token are restricted to the rules
+/// of the roles bound to it, the other clients are trusted
+/// (unix socket or authentication disabled).
+use ntex::web::HttpRequest;
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  process::ProcessKind,
+  role::{RoleVerb, ROLE_KINDS},
+  system::{EventActor, EventActorKind, EventKind, NativeEventAction},
+};
+
+use crate::{
+  utils::{self, token::TokenIdentity},
+  models::{RoleDb, SystemState},
+};
+
+/// Ensure the client of the request is allowed to `verb` the `kind` objects
+/// in the `namespace`, a denial is emitted as a warning event
+pub async fn authorize(
+  req: &HttpRequest,
+  state: &SystemState,
+  kind: &str,
+  verb: RoleVerb,
+  namespace: Option<&str>,
+) -> HttpResult<()> {
+  let Some(identity) = req.extensions().get::<TokenIdentity>().cloned() else {
+    return Ok(());
+  };
+  let subject = identity.subject();
+  let roles = RoleDb::read_by_subject(&subject, &state.inner.pool).await?;
+  if roles.iter().any(|role| role.allows(kind, verb, namespace)) {
+    return Ok(());
+  }
+  let msg = match namespace {
+    Some(namespace) => {
+      format!("{subject} cannot {verb} {kind} in namespace {namespace}")
+    }
+    None => format!("{subject} cannot {verb} {kind}"),
+  };
+  let actor = EventActor {
+    key: Some(identity.name),
+    kind: EventActorKind::Token,
+    attributes: None,
+  };
+  state.emit_action(
+    &actor,
+    NativeEventAction::Deny,
+    EventKind::Warning,
+    "rbac",
+    Some(msg.clone()),
+    Some(serde_json::json!({
+      "Kind": kind,
+      "Verb": verb,
+      "Namespace": namespace,
+      "Method": req.method().as_str(),
+      "Path": req.path(),
+    })),
+  );
+  Err(HttpError::forbidden(msg))
+}
+
+/// Ensure the client of the request is allowed to `verb` the processes
+/// of the given kind, the jobs don't belong to a namespace
+/// so they need a rule without namespaces like the `job` endpoints
+pub async fn authorize_process(
+  req: &HttpRequest,
+  state: &SystemState,
+  kind: &ProcessKind,
+  verb: RoleVerb,
+  namespace: &Option<String>,
+) -> HttpResult<()> {
+  let namespace = match kind {
+    ProcessKind::Job => None,
+    ProcessKind::Cargo | ProcessKind::Vm => {
+      Some(utils::key::resolve_nsp(namespace))
+    }
+  };
+  authorize(req, state, "process", verb, namespace.as_deref()).await
+}
+
+/// Ensure the kinds of the rules of a role exists
+pub fn validate_kinds(kinds: &[String]) -> HttpResult<()> {
+  for kind in kinds {
+    if kind != "*" && !ROLE_KINDS.contains(&kind.as_str()) {
+      return Err(HttpError::bad_request(format!(
+        "Unknown kind {kind} expected * or one of {}",
+        ROLE_KINDS.join(", ")
+      )));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::role::{Role, RoleRule};
+
+  use super::*;
+
+  #[test]
+  fn rules() {
+    let role = Role {
+      rules: vec![
+        RoleRule {
+          kinds: vec!["cargo".to_owned(), "vm".to_owned()],
+          verbs: vec![RoleVerb::List, RoleVerb::Logs],
+          namespaces: Some(vec!["dev".to_owned()]),
+        },
+        RoleRule {
+          kinds: vec!["*".to_owned()],
+          verbs: vec![RoleVerb::Inspect],
+          namespaces: None,
+        },
+      ],
+      ..Default::default()
+    };
+    assert!(role.allows("cargo", RoleVerb::Logs, Some("dev")));
+    assert!(role.allows("vm", RoleVerb::List, Some("dev")));
+    assert!(!role.allows("cargo", RoleVerb::Logs, Some("prod")));
+    assert!(!role.allows("cargo", RoleVerb::Delete, Some("dev")));
+    assert!(!role.allows("job", RoleVerb::List, Some("dev")));
+    // Objects outside of a namespace need an unrestricted rule
+    assert!(!role.allows("cargo", RoleVerb::List, None));
+    assert!(role.allows("secret", RoleVerb::Inspect, None));
+    assert!(role.allows("cargo", RoleVerb::Inspect, Some("prod")));
+    assert!(validate_kinds(&["*".to_owned(), "cargo".to_owned()]).is_ok());
+    assert!(validate_kinds(&["cargoes".to_owned()]).is_err());
+  }
+}
@@ -0,0 +1,371 @@
+use std::collections::HashMap;
+
+use bollard_next::container::ListContainersOptions;
+
+use nanocl_error::{
+  http::HttpResult,
+  io::{FromIo, IoResult},
+};
+use nanocl_stubs::{
+  cargo::Cargo,
+  generic::GenericFilter,
+  process::{Process, ProcessKind},
+  system::{
+    EventActor, EventKind, NativeEventAction, ObjPsStatus, ObjPsStatusKind,
+  },
+  vm::Vm,
+};
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{CargoDb, LeaseDb, ProcessDb, SystemState, VmDb},
+};
+
+/// Seconds between two reconciliation of the objects of the node
+pub const RECONCILE_INTERVAL: u64 = 30;
+
+/// Difference between the wanted instances of an object on the node
+/// and the instances actually running in docker
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Drift {
+  /// Number of instances to create
+  pub missing: usize,
+  /// Instances to remove because there is more than wanted
+  pub extra: Vec<String>,
+  /// Instances that exist but aren't running
+  pub stopped: Vec<String>,
+  /// Processes in the store without container
+  pub stale: Vec<String>,
+}
+
+impl Drift {
+  /// Whether the instances match the wanted state
+  pub fn is_empty(&self) -> bool {
+    self.missing == 0
+      && self.extra.is_empty()
+      && self.stopped.is_empty()
+      && self.stale.is_empty()
+  }
+
+  /// Describe the drift for the note of the warning event
+  pub fn to_note(&self) -> String {
+    let mut parts = Vec::new();
+    if self.missing > 0 {
+      parts.push(format!("{} missing", self.missing));
+    }
+    if !self.extra.is_empty() {
+      parts.push(format!("{} extra", self.extra.len()));
+    }
+    if !self.stopped.is_empty() {
+      parts.push(format!("{} stopped", self.stopped.len()));
+    }
+    if !self.stale.is_empty() {
+      parts.push(format!("{} stale", self.stale.len()));
+    }
+    format!("Instances drift: {}", parts.join(", "))
+  }
+}
+
+/// Compare the wanted `number` of instances with the processes of the node
+/// and the containers found in docker indexed by id with their running state.
+/// The newest instances are the extra ones.
+pub fn get_drift(
+  number: usize,
+  processes: &[Process],
+  containers: &HashMap<String, bool>,
+) -> Drift {
+  let mut drift = Drift::default();
+  let mut alive = Vec::new();
+  for process in processes {
+    if containers.contains_key(&process.key) {
+      alive.push(process);
+    } else {
+      drift.stale.push(process.key.clone());
+    }
+  }
+  alive.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+  if alive.len() > number {
+    drift.extra = alive
+      .split_off(number)
+      .into_iter()
+      .map(|process| process.key.clone())
+      .collect();
+  }
+  drift.missing = number - alive.len();
+  drift.stopped = alive
+    .into_iter()
+    .filter(|process| !containers.get(&process.key).copied().unwrap_or(false))
+    .map(|process| process.key.clone())
+    .collect();
+  drift
+}
+
+/// List the containers managed by nanocl on this node with their running state
+pub async fn list_containers(
+  state: &SystemState,
+) -> IoResult<HashMap<String, bool>> {
+  let options = Some(ListContainersOptions::<&str> {
+    all: true,
+    filters: HashMap::from([("label", vec!["io.nanocl"])]),
+    ..Default::default()
+  });
+  let containers = state
+    .inner
+    .docker_api
+    .list_containers(options)
+    .await
+    .map_err(|err| err.map_err_context(|| "Reconcile"))?;
+  Ok(
+    containers
+      .into_iter()
+      .filter_map(|container| {
+        let running = container.state.as_deref() == Some("running");
+        container.id.map(|id| (id, running))
+      })
+      .collect(),
+  )
+}
+
+/// Whether the object is wanted running and isn't handled by a task
+async fn is_stable(
+  task_key: &str,
+  status: &ObjPsStatus,
+  state: &SystemState,
+) -> IoResult<bool> {
+  if status.wanted != ObjPsStatusKind::Start {
+    return Ok(false);
+  }
+  if matches!(
+    status.actual,
+    ObjPsStatusKind::Starting
+      | ObjPsStatusKind::Updating
+      | ObjPsStatusKind::Stopping
+      | ObjPsStatusKind::Destroying
+  ) {
+    return Ok(false);
+  }
+  if state.inner.task_manager.get_task(task_key).await.is_some() {
+    return Ok(false);
+  }
+  // Another node is acting on the object
+  if LeaseDb::read_by_pk(task_key, &state.inner.pool)
+    .await
+    .is_ok()
+  {
+    return Ok(false);
+  }
+  Ok(true)
+}
+
+/// Fix the drift of an object by removing the stale processes and the extra
+/// instances then starting it again when instances are missing or stopped.
+/// The drift is reported as a warning event.
+async fn apply_drift(
+  key: &str,
+  kind: &ProcessKind,
+  actor: &EventActor,
+  drift: &Drift,
+  state: &SystemState,
+) -> HttpResult<()> {
+  if drift.is_empty() {
+    return Ok(());
+  }
+  log::warn!("reconcile: {kind:?} {key} {drift:?}");
+  state.emit_action(
+    actor,
+    NativeEventAction::Reconcile,
+    EventKind::Warning,
+    "drift",
+    Some(drift.to_note()),
+    Some(serde_json::json!({
+      "Missing": drift.missing,
+      "Extra": drift.extra,
+      "Stopped": drift.stopped,
+      "Stale": drift.stale,
+    })),
+  );
+  for process_key in &drift.stale {
+    ProcessDb::del_by_pk(process_key, &state.inner.pool).await?;
+  }
+  utils::container::delete_instances(&drift.extra, state).await?;
+  if drift.missing > 0 || !drift.stopped.is_empty() {
+    utils::container::emit_starting(key, kind, state).await?;
+  }
+  Ok(())
+}
+
+/// Restore the wanted replicas of a cargo on the current node
+async fn reconcile_cargo(
+  cargo: &Cargo,
+  containers: &HashMap<String, bool>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let key = &cargo.spec.cargo_key;
+  if !is_stable(&format!("Cargo@{key}"), &cargo.status, state).await? {
+    return Ok(());
+  }
+  let (processes, others): (Vec<_>, Vec<_>) =
+    utils::container::list_replicas(key, state)
+      .await?
+      .into_iter()
+      .partition(|process| process.node_name == state.inner.config.hostname);
+  // Replicas placed on the node handling the cargo are only restored
+  // by the nodes running them or by the leader when none run anywhere
+  let number =
+    if utils::placement::is_local_mode(cargo.spec.replication.as_ref())
+      && processes.is_empty()
+      && (!others.is_empty() || !state.is_leader())
+    {
+      0
+    } else {
+      utils::placement::get_local_number(cargo, state).await?
+    };
+  let drift = get_drift(number, &processes, containers);
+  apply_drift(
+    key,
+    &ProcessKind::Cargo,
+    &cargo.clone().into(),
+    &drift,
+    state,
+  )
+  .await
+}
+
+/// Restore the instance of a vm, it stay on the node running it
+/// and the leader recreate it when it doesn't run anywhere.
+async fn reconcile_vm(
+  vm: &Vm,
+  containers: &HashMap<String, bool>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let key = &vm.spec.vm_key;
+  if !is_stable(&format!("Vm@{key}"), &vm.status, state).await? {
+    return Ok(());
+  }
+  let (processes, others): (Vec<_>, Vec<_>) =
+    ProcessDb::read_by_kind_key(key, &state.inner.pool)
+      .await?
+      .into_iter()
+      .partition(|process| process.node_name == state.inner.config.hostname);
+  let number = if !others.is_empty() {
+    0
+  } else if !processes.is_empty() || state.is_leader() {
+    1
+  } else {
+    0
+  };
+  let drift = get_drift(number, &processes, containers);
+  apply_drift(key, &ProcessKind::Vm, &vm.clone().into(), &drift, state).await
+}
+
+/// Compare every cargo and vm with their instances and fix the drift
+pub async fn reconcile(state: &SystemState) -> IoResult<()> {
+  let containers = list_containers(state).await?;
+  let mut offset = 0;
+  loop {
+    let filter = GenericFilter::new().limit(100).offset(offset);
+    let cargoes =
+      CargoDb::transform_read_by(&filter, &state.inner.pool).await?;
+    for cargo in &cargoes {
+      if let Err(err) = reconcile_cargo(cargo, &containers, state).await {
+        log::warn!("reconcile: cargo {} {err}", cargo.spec.cargo_key);
+      }
+    }
+    if cargoes.len() < 100 {
+      break;
+    }
+    offset += 100;
+  }
+  let mut offset = 0;
+  loop {
+    let filter = GenericFilter::new().limit(100).offset(offset);
+    let vms = VmDb::transform_read_by(&filter, &state.inner.pool).await?;
+    for vm in &vms {
+      if let Err(err) = reconcile_vm(vm, &containers, state).await {
+        log::warn!("reconcile: vm {} {err}", vm.spec.vm_key);
+      }
+    }
+    if vms.len() < 100 {
+      break;
+    }
+    offset += 100;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn process(key: &str, created_at: i64) -> Process {
+    let date = chrono::DateTime::from_timestamp(created_at, 0)
+      .unwrap()
+      .naive_utc();
+    Process {
+      key: key.to_owned(),
+      created_at: date,
+      updated_at: date,
+      name: key.to_owned(),
+      kind: ProcessKind::Cargo,
+      node_name: "node1".to_owned(),
+      kind_key: "test.global".to_owned(),
+      data: Default::default(),
+    }
+  }
+
+  #[test]
+  fn replicas() {
+    let mut init = process("init", 1);
+    init.data.config = Some(bollard_next::service::ContainerConfig {
+      labels: Some(HashMap::from([(
+        "io.nanocl.init-c".to_owned(),
+        "true".to_owned(),
+      )])),
+      ..Default::default()
+    });
+    let processes = vec![
+      process("a", 1),
+      init,
+      process("init-test-abcdef.global.c", 2),
+      process("tmp-test.global.c", 3),
+    ];
+    let replicas = processes
+      .into_iter()
+      .filter(utils::container::is_replica)
+      .collect::<Vec<_>>();
+    let drift =
+      get_drift(2, &replicas, &HashMap::from([("a".to_owned(), true)]));
+    assert_eq!(drift.missing, 1);
+    assert!(drift.extra.is_empty());
+  }
+
+  #[test]
+  fn drift() {
+    let processes = vec![process("a", 1), process("c", 3), process("b", 2)];
+    let containers = HashMap::from([
+      ("a".to_owned(), true),
+      ("b".to_owned(), false),
+      ("c".to_owned(), true),
+    ]);
+    let drift = get_drift(3, &processes, &containers);
+    assert_eq!(
+      drift,
+      Drift {
+        stopped: vec!["b".to_owned()],
+        ..Default::default()
+      }
+    );
+    // The newest instances are removed first
+    let drift = get_drift(1, &processes, &containers);
+    assert_eq!(drift.extra, vec!["b".to_owned(), "c".to_owned()]);
+    assert!(drift.stopped.is_empty());
+    // A removed container is stale and must be created again
+    let containers = HashMap::from([("a".to_owned(), true)]);
+    let drift = get_drift(2, &processes, &containers);
+    assert_eq!(drift.missing, 1);
+    assert_eq!(drift.stale, vec!["c".to_owned(), "b".to_owned()]);
+    assert_eq!(drift.to_note(), "Instances drift: 1 missing, 2 stale");
+    assert!(get_drift(1, &processes[..1], &containers).is_empty());
+  }
+}
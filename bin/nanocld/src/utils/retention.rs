@@ -0,0 +1,169 @@
+use nanocl_error::io::IoResult;
+use nanocl_stubs::config::RetentionConfig;
+
+use crate::{
+  repositories::generic::*,
+  models::{
+    AuditDb, EventDb, MetricDb, MetricNodePartial, MetricRollupDb, SystemState,
+  },
+};
+
+/// Days to keep the events and metrics when not configured
+pub const DEFAULT_RETENTION_DAYS: u64 = 30;
+/// Days to keep the metric rollups when not configured
+pub const DEFAULT_ROLLUP_RETENTION_DAYS: u64 = 90;
+/// Days to keep the audit entries when not configured
+pub const DEFAULT_AUDIT_RETENTION_DAYS: u64 = 90;
+/// Number of rows deleted at once when not configured
+pub const DEFAULT_BATCH_SIZE: u64 = 1000;
+/// Seconds between two purges when not configured
+pub const DEFAULT_PRUNE_INTERVAL: u64 = 3600;
+
+/// Compute the expiration date of a row kept for the given days
+fn expires_in(days: u64) -> chrono::NaiveDateTime {
+  let days = i64::try_from(days).unwrap_or(i64::MAX);
+  let now = chrono::Utc::now().naive_utc();
+  chrono::Duration::try_days(days)
+    .and_then(|duration| now.checked_add_signed(duration))
+    .unwrap_or(chrono::NaiveDateTime::MAX)
+}
+
+/// Expiration date of a new event
+pub fn event_expires_at(conf: &RetentionConfig) -> chrono::NaiveDateTime {
+  expires_in(conf.events.unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+/// Expiration date of a new metric of the given kind
+pub fn metric_expires_at(
+  kind: &str,
+  conf: &RetentionConfig,
+) -> chrono::NaiveDateTime {
+  let days = conf
+    .metric_kinds
+    .as_ref()
+    .and_then(|kinds| kinds.get(kind).copied())
+    .or(conf.metrics)
+    .unwrap_or(DEFAULT_RETENTION_DAYS);
+  expires_in(days)
+}
+
+/// Expiration date of a new metric rollup
+pub fn rollup_expires_at(conf: &RetentionConfig) -> chrono::NaiveDateTime {
+  expires_in(conf.rollups.unwrap_or(DEFAULT_ROLLUP_RETENTION_DAYS))
+}
+
+/// Expiration date of a new audit entry
+pub fn audit_expires_at(conf: &RetentionConfig) -> chrono::NaiveDateTime {
+  expires_in(conf.audits.unwrap_or(DEFAULT_AUDIT_RETENTION_DAYS))
+}
+
+/// Number of rows deleted at once
+fn get_batch_size(conf: &RetentionConfig) -> i64 {
+  let size = conf.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+  i64::try_from(size).unwrap_or(i64::MAX)
+}
+
+/// Save a metric with the expiration of his kind
+pub async fn create_metric(
+  metric: &MetricNodePartial,
+  state: &SystemState,
+) -> IoResult<MetricDb> {
+  let mut item = MetricDb::from(metric);
+  item.expires_at =
+    metric_expires_at(&item.kind, &state.inner.config.retention);
+  MetricDb::create_from(item, &state.inner.pool).await
+}
+
+/// Save the metrics at once with the expiration of their kind
+/// and return the number of created metrics
+pub async fn create_metrics(
+  metrics: &[MetricNodePartial],
+  state: &SystemState,
+) -> IoResult<usize> {
+  let items = metrics
+    .iter()
+    .map(|metric| {
+      let mut item = MetricDb::from(metric);
+      item.expires_at =
+        metric_expires_at(&item.kind, &state.inner.config.retention);
+      item
+    })
+    .collect::<Vec<_>>();
+  MetricDb::create_many(items, &state.inner.pool).await
+}
+
+/// Delete the expired events batch by batch and return how many were deleted
+pub async fn prune_events(state: &SystemState) -> IoResult<usize> {
+  let limit = get_batch_size(&state.inner.config.retention);
+  let mut total = 0;
+  loop {
+    let count = EventDb::del_expired(limit, &state.inner.pool).await?;
+    total += count;
+    if (count as i64) < limit {
+      break;
+    }
+  }
+  Ok(total)
+}
+
+/// Delete the expired audit entries batch by batch
+/// and return how many were deleted
+pub async fn prune_audits(state: &SystemState) -> IoResult<usize> {
+  let limit = get_batch_size(&state.inner.config.retention);
+  let mut total = 0;
+  loop {
+    let count = AuditDb::del_expired(limit, &state.inner.pool).await?;
+    total += count;
+    if (count as i64) < limit {
+      break;
+    }
+  }
+  Ok(total)
+}
+
+/// Delete the expired metrics and their expired rollups batch by batch
+/// and return how many were deleted
+pub async fn prune_metrics(state: &SystemState) -> IoResult<usize> {
+  let limit = get_batch_size(&state.inner.config.retention);
+  let mut total = 0;
+  loop {
+    let count = MetricDb::del_expired(limit, &state.inner.pool).await?;
+    total += count;
+    if (count as i64) < limit {
+      break;
+    }
+  }
+  loop {
+    let count = MetricRollupDb::del_expired(limit, &state.inner.pool).await?;
+    total += count;
+    if (count as i64) < limit {
+      break;
+    }
+  }
+  Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+
+  #[test]
+  fn expiration() {
+    let conf = RetentionConfig {
+      metrics: Some(7),
+      metric_kinds: Some(HashMap::from([("ncproxy.io/http".to_owned(), 1)])),
+      ..Default::default()
+    };
+    let now = chrono::Utc::now().naive_utc();
+    let days = |date: chrono::NaiveDateTime| (date - now).num_days();
+    assert_eq!(days(event_expires_at(&conf)), 30);
+    assert_eq!(days(metric_expires_at("nanocl.io/metrs", &conf)), 7);
+    assert_eq!(days(metric_expires_at("ncproxy.io/http", &conf)), 1);
+    assert_eq!(days(rollup_expires_at(&conf)), 90);
+    assert_eq!(days(audit_expires_at(&conf)), 90);
+    assert_eq!(expires_in(u64::MAX), chrono::NaiveDateTime::MAX);
+    assert_eq!(get_batch_size(&conf), 1000);
+  }
+}
@@ -0,0 +1,461 @@
+/// Rolling update of the replicas of a cargo
+/// Old replicas are replaced by batches bounded by the [UpdateStrategy](UpdateStrategy),
+/// every batch must be healthy before the next one start.
+/// When a batch fail the new replicas are removed, the old ones are restored
+/// and the cargo is reverted to his previous spec.
+use std::time::Duration;
+
+use futures::{StreamExt, future::join_all};
+use futures_util::stream::FuturesUnordered;
+use bollard_next::{
+  container::{
+    InspectContainerOptions, RenameContainerOptions, StartContainerOptions,
+    StopContainerOptions,
+  },
+  service::HealthStatusEnum,
+};
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  cargo::Cargo, cargo_spec::UpdateStrategy, process::Process,
+  system::NativeEventAction,
+};
+
+use crate::{
+  repositories::generic::*,
+  models::{CargoDb, CargoUpdateDb, SpecDb, SystemState},
+};
+
+/// Default number of seconds to wait for a batch to be healthy
+const DEFAULT_HEALTH_TIMEOUT: u64 = 60;
+
+/// A step of a rolling update
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RolloutBatch {
+  /// Old replicas to stop before creating the new ones
+  pub stop: usize,
+  /// New replicas to create and wait for
+  pub create: usize,
+  /// Old replicas to stop once the new ones are healthy
+  pub retire: usize,
+}
+
+/// Return the max surge and max unavailable of a strategy
+/// When both are 0 we fallback to a surge of 1 to be able to progress
+fn get_limits(strategy: Option<&UpdateStrategy>) -> (usize, usize) {
+  let strategy = strategy.cloned().unwrap_or_default();
+  let surge = strategy.max_surge.unwrap_or(1);
+  let unavailable = strategy.max_unavailable.unwrap_or(0);
+  if surge + unavailable == 0 {
+    return (1, 0);
+  }
+  (surge, unavailable)
+}
+
+/// Ensure an update strategy can make progress
+pub fn validate_strategy(strategy: &UpdateStrategy) -> HttpResult<()> {
+  if strategy.max_surge == Some(0) && strategy.max_unavailable == Some(0) {
+    return Err(HttpError::bad_request(
+      "MaxSurge and MaxUnavailable cannot be both 0",
+    ));
+  }
+  if strategy.health_timeout == Some(0) {
+    return Err(HttpError::bad_request(
+      "HealthTimeout must be greater than 0",
+    ));
+  }
+  Ok(())
+}
+
+/// Split the replacement of `old` replicas by `wanted` new replicas into batches
+/// At any time there is at most `wanted + max_surge` replicas running
+/// and at least `wanted - max_unavailable` available.
+pub fn plan_batches(
+  old: usize,
+  wanted: usize,
+  strategy: Option<&UpdateStrategy>,
+) -> Vec<RolloutBatch> {
+  let (surge, unavailable) = get_limits(strategy);
+  let mut batches = Vec::new();
+  let mut remaining = old;
+  let mut created = 0;
+  while created < wanted || remaining > 0 {
+    let stop = unavailable.min(remaining);
+    remaining -= stop;
+    let create = (surge + unavailable).min(wanted - created);
+    created += create;
+    let retire = if created == wanted {
+      remaining
+    } else {
+      (remaining + created).saturating_sub(wanted).min(remaining)
+    };
+    remaining -= retire;
+    batches.push(RolloutBatch {
+      stop,
+      create,
+      retire,
+    });
+  }
+  batches
+}
+
+/// Wait for the given containers to be healthy
+/// Containers without health check are considered healthy once running
+async fn wait_healthy(
+  processes: &[Process],
+  timeout: Duration,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let deadline = std::time::Instant::now() + timeout;
+  let mut pending = processes.iter().map(|p| p.key.clone()).collect::<Vec<_>>();
+  while !pending.is_empty() {
+    let mut still_pending = Vec::new();
+    for key in pending {
+      let inspect = state
+        .inner
+        .docker_api
+        .inspect_container(&key, None::<InspectContainerOptions>)
+        .await?;
+      let container_state = inspect.state.unwrap_or_default();
+      let health = container_state
+        .health
+        .and_then(|health| health.status)
+        .unwrap_or(HealthStatusEnum::NONE);
+      match health {
+        HealthStatusEnum::HEALTHY => {}
+        HealthStatusEnum::UNHEALTHY => {
+          return Err(HttpError::internal_server_error(format!(
+            "Container {key} is unhealthy"
+          )));
+        }
+        HealthStatusEnum::STARTING => still_pending.push(key),
+        HealthStatusEnum::NONE | HealthStatusEnum::EMPTY => {
+          if container_state.restarting.unwrap_or_default()
+            || container_state.dead.unwrap_or_default()
+          {
+            return Err(HttpError::internal_server_error(format!(
+              "Container {key} is not running"
+            )));
+          }
+          if !container_state.running.unwrap_or_default() {
+            still_pending.push(key);
+          }
+        }
+      }
+    }
+    pending = still_pending;
+    if pending.is_empty() {
+      break;
+    }
+    if std::time::Instant::now() >= deadline {
+      return Err(HttpError::internal_server_error(format!(
+        "Containers {} are not healthy after {}s",
+        pending.join(", "),
+        timeout.as_secs()
+      )));
+    }
+    ntex::time::sleep(Duration::from_secs(1)).await;
+  }
+  Ok(())
+}
+
+/// Stop the given containers
+/// Containers already stopped are ignored
+async fn stop_processes(processes: &[Process], state: &SystemState) {
+  processes
+    .iter()
+    .map(|process| async {
+      if let Err(err) = state
+        .inner
+        .docker_api
+        .stop_container(&process.key, None::<StopContainerOptions>)
+        .await
+      {
+        log::warn!("rollout: unable to stop {}: {err}", process.name);
+      }
+    })
+    .collect::<FuturesUnordered<_>>()
+    .collect::<Vec<_>>()
+    .await;
+}
+
+/// Rename a container
+async fn rename_process(
+  process: &Process,
+  name: String,
+  state: &SystemState,
+) -> HttpResult<()> {
+  state
+    .inner
+    .docker_api
+    .rename_container(&process.key, RenameContainerOptions { name })
+    .await?;
+  Ok(())
+}
+
+/// Rename the given containers, the names are computed from the original one.
+/// When a rename fail the containers already renamed get their `old_name` back.
+async fn rename_processes<F, G>(
+  processes: &[Process],
+  new_name: F,
+  old_name: G,
+  state: &SystemState,
+) -> HttpResult<()>
+where
+  F: Fn(&Process) -> String,
+  G: Fn(&Process) -> String,
+{
+  let results = join_all(
+    processes
+      .iter()
+      .map(|process| rename_process(process, new_name(process), state)),
+  )
+  .await;
+  let mut error = None;
+  let mut renamed = Vec::new();
+  for (process, res) in processes.iter().zip(results) {
+    match res {
+      Ok(_) => renamed.push(process),
+      Err(err) => {
+        error.get_or_insert(err);
+      }
+    }
+  }
+  let Some(err) = error else {
+    return Ok(());
+  };
+  for process in renamed {
+    if let Err(err) = rename_process(process, old_name(process), state).await {
+      log::warn!("rollout: undo rename of {}: {err}", process.key);
+    }
+  }
+  Err(err)
+}
+
+/// Put back the previous spec of the cargo without creating a new history
+async fn revert_spec(cargo: &Cargo, state: &SystemState) -> HttpResult<()> {
+  let histories =
+    SpecDb::read_by_kind_key(&cargo.spec.cargo_key, &state.inner.pool).await?;
+  let Some(previous) = histories
+    .iter()
+    .skip_while(|spec| spec.key != cargo.spec.key)
+    .nth(1)
+  else {
+    return Ok(());
+  };
+  CargoDb::update_pk(
+    &cargo.spec.cargo_key,
+    CargoUpdateDb {
+      spec_key: Some(previous.key),
+      ..Default::default()
+    },
+    &state.inner.pool,
+  )
+  .await?;
+  Ok(())
+}
+
+/// Remove the new replicas, restore the old ones and revert the spec
+async fn rollback(
+  cargo: &Cargo,
+  old: &[Process],
+  new: &[Process],
+  state: &SystemState,
+) -> HttpResult<()> {
+  super::container::delete_instances(
+    &new.iter().map(|p| p.key.clone()).collect::<Vec<_>>(),
+    state,
+  )
+  .await?;
+  rename_processes(
+    old,
+    |process| process.name.clone(),
+    |process| format!("tmp-{}", process.name),
+    state,
+  )
+  .await?;
+  for process in old {
+    // Old replicas still running are already started
+    let _ = state
+      .inner
+      .docker_api
+      .start_container(&process.key, None::<StartContainerOptions<String>>)
+      .await;
+  }
+  revert_spec(cargo, state).await?;
+  Ok(())
+}
+
+/// Execute the batches of a rolling update
+async fn run_batches(
+  cargo: &Cargo,
+  old: &[Process],
+  wanted: usize,
+  new: &mut Vec<Process>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let timeout = Duration::from_secs(
+    cargo
+      .spec
+      .update_strategy
+      .as_ref()
+      .and_then(|strategy| strategy.health_timeout)
+      .unwrap_or(DEFAULT_HEALTH_TIMEOUT),
+  );
+  let batches =
+    plan_batches(old.len(), wanted, cargo.spec.update_strategy.as_ref());
+  let mut remaining = old.iter();
+  for batch in batches {
+    log::debug!("rollout: cargo {} batch {batch:?}", cargo.spec.cargo_key);
+    let to_stop = remaining
+      .by_ref()
+      .take(batch.stop)
+      .cloned()
+      .collect::<Vec<_>>();
+    stop_processes(&to_stop, state).await;
+    let start = new.len();
+    let created = super::container::create_cargo_replicas(
+      cargo,
+      start..start + batch.create,
+      state,
+    )
+    .await?;
+    new.extend(created.clone());
+    for process in &created {
+      state
+        .inner
+        .docker_api
+        .start_container(&process.key, None::<StartContainerOptions<String>>)
+        .await?;
+    }
+    wait_healthy(&created, timeout, state).await?;
+    let to_retire = remaining
+      .by_ref()
+      .take(batch.retire)
+      .cloned()
+      .collect::<Vec<_>>();
+    stop_processes(&to_retire, state).await;
+  }
+  Ok(())
+}
+
+/// Replace the `old` replicas of a cargo running on the current node
+/// by `wanted` replicas with the current spec.
+/// Return an error if the update failed and was rolled back.
+pub async fn rolling_update(
+  cargo: &Cargo,
+  old: &[Process],
+  wanted: usize,
+  state: &SystemState,
+) -> HttpResult<()> {
+  // Restarting instances cannot be renamed so we stop them first
+  let restarting = old
+    .iter()
+    .filter(|process| {
+      process
+        .data
+        .state
+        .clone()
+        .unwrap_or_default()
+        .restarting
+        .unwrap_or_default()
+    })
+    .cloned()
+    .collect::<Vec<_>>();
+  stop_processes(&restarting, state).await;
+  // Rename old instances to flag them for deletion
+  rename_processes(
+    old,
+    |process| format!("tmp-{}", process.name),
+    |process| process.name.clone(),
+    state,
+  )
+  .await?;
+  let mut new = Vec::new();
+  if let Err(err) = run_batches(cargo, old, wanted, &mut new, state).await {
+    log::error!(
+      "rollout: cargo {} update failed rolling back: {err}",
+      cargo.spec.cargo_key
+    );
+    if let Err(err) = rollback(cargo, old, &new, state).await {
+      log::error!("rollout: cargo {} rollback: {err}", cargo.spec.cargo_key);
+    }
+    state.emit_warning_native_action(
+      cargo,
+      NativeEventAction::Rollback,
+      Some(err.to_string()),
+    );
+    return Err(err);
+  }
+  super::container::delete_instances(
+    &old.iter().map(|p| p.key.clone()).collect::<Vec<_>>(),
+    state,
+  )
+  .await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn batch(stop: usize, create: usize, retire: usize) -> RolloutBatch {
+    RolloutBatch {
+      stop,
+      create,
+      retire,
+    }
+  }
+
+  fn strategy(surge: usize, unavailable: usize) -> UpdateStrategy {
+    UpdateStrategy {
+      max_surge: Some(surge),
+      max_unavailable: Some(unavailable),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn default_strategy() {
+    let batches = plan_batches(3, 3, None);
+    assert_eq!(batches, vec![batch(0, 1, 1); 3]);
+    let batches = plan_batches(0, 2, None);
+    assert_eq!(batches, vec![batch(0, 1, 0); 2]);
+    // An invalid strategy fallback to the default surge
+    let batches = plan_batches(1, 1, Some(&strategy(0, 0)));
+    assert_eq!(batches, vec![batch(0, 1, 1)]);
+  }
+
+  #[test]
+  fn surge_and_unavailable() {
+    let batches = plan_batches(3, 3, Some(&strategy(0, 1)));
+    assert_eq!(batches, vec![batch(1, 1, 0); 3]);
+    let batches = plan_batches(4, 4, Some(&strategy(2, 1)));
+    assert_eq!(batches, vec![batch(1, 3, 2), batch(1, 1, 0)]);
+  }
+
+  #[test]
+  fn scale() {
+    let batches = plan_batches(4, 2, None);
+    assert_eq!(batches, vec![batch(0, 1, 3), batch(0, 1, 1)]);
+    let batches = plan_batches(1, 3, None);
+    assert_eq!(
+      batches,
+      vec![batch(0, 1, 0), batch(0, 1, 0), batch(0, 1, 1)]
+    );
+    let batches = plan_batches(2, 0, None);
+    assert_eq!(batches, vec![batch(0, 0, 2)]);
+  }
+
+  #[test]
+  fn validate() {
+    assert!(validate_strategy(&UpdateStrategy::default()).is_ok());
+    assert!(validate_strategy(&strategy(0, 1)).is_ok());
+    assert!(validate_strategy(&strategy(0, 0)).is_err());
+    let strategy = UpdateStrategy {
+      health_timeout: Some(0),
+      ..Default::default()
+    };
+    assert!(validate_strategy(&strategy).is_err());
+  }
+}
@@ -0,0 +1,145 @@
+/// Bearer tokens of the http api
+/// A token secret is `ncl_` followed by 32 random bytes in hexadecimal,
+/// only his sha256 hash is stored so a leak of the store doesn't leak them.
+use ntex::web::WebRequest;
+use openssl::{rand::rand_bytes, sha::sha256};
+
+use nanocl_error::{
+  io::{IoError, IoResult},
+  http::{HttpError, HttpResult},
+};
+use nanocl_stubs::token::{TokenCreated, TokenPartial};
+use nanocl_utils::ntex::middlewares::BearerValidator;
+
+use crate::{
+  utils,
+  repositories::generic::*,
+  models::{SystemState, TokenDb},
+};
+
+/// Prefix of the token secrets
+pub const TOKEN_PREFIX: &str = "ncl_";
+/// Seconds between two updates of the last usage of a token
+const TOUCH_INTERVAL: i64 = 60;
+
+/// Encode bytes in lowercase hexadecimal
+pub fn to_hex(bytes: &[u8]) -> String {
+  bytes
+    .iter()
+    .map(|byte| format!("{byte:02x}"))
+    .collect::<Vec<_>>()
+    .join("")
+}
+
+/// Generate a new token secret
+pub fn generate_secret() -> IoResult<String> {
+  let mut bytes = [0; 32];
+  rand_bytes(&mut bytes)
+    .map_err(|err| IoError::other("Token", &err.to_string()))?;
+  Ok(format!("{TOKEN_PREFIX}{}", to_hex(&bytes)))
+}
+
+/// Hash of a token secret as stored in the database
+pub fn hash_secret(secret: &str) -> String {
+  to_hex(&sha256(secret.as_bytes()))
+}
+
+/// Create a token and return it with his secret
+pub async fn create(
+  partial: &TokenPartial,
+  state: &SystemState,
+) -> HttpResult<TokenCreated> {
+  utils::key::validate_name(&partial.name)?;
+  let now = chrono::Utc::now().naive_utc();
+  if partial.expires_at.map(|date| date <= now).unwrap_or(false) {
+    return Err(HttpError::bad_request("Token expiration is in the past"));
+  }
+  let secret = generate_secret()?;
+  let item = TokenDb {
+    key: partial.name.clone(),
+    created_at: now,
+    expires_at: partial.expires_at,
+    last_used_at: None,
+    hash: hash_secret(&secret),
+    metadata: partial.metadata.clone(),
+  };
+  let token = TokenDb::create_from(item, &state.inner.pool).await?;
+  Ok(TokenCreated {
+    token: token.into(),
+    secret,
+  })
+}
+
+/// Identity of the client authenticated with a token
+#[derive(Debug, Clone)]
+pub struct TokenIdentity {
+  /// The name of the token
+  pub name: String,
+}
+
+impl TokenIdentity {
+  /// Subject of the identity used to bind it to roles
+  pub fn subject(&self) -> String {
+    format!("token:{}", self.name)
+  }
+}
+
+/// Validate the bearer tokens with the tokens of the store
+/// when the authentication is enabled in the daemon config
+pub struct TokenAuth;
+
+impl BearerValidator for TokenAuth {
+  type Identity = TokenIdentity;
+
+  fn is_required<Err>(&self, req: &WebRequest<Err>) -> bool {
+    let Some(state) = req.app_state::<SystemState>() else {
+      return false;
+    };
+    state.inner.config.auth && req.peer_addr().is_some()
+  }
+
+  async fn validate<Err>(
+    &self,
+    req: &WebRequest<Err>,
+    token: &str,
+  ) -> Result<Self::Identity, String> {
+    let Some(state) = req.app_state::<SystemState>() else {
+      return Err("system state unavailable".to_owned());
+    };
+    let pool = &state.inner.pool;
+    let token = TokenDb::read_by_hash(&hash_secret(token), pool)
+      .await
+      .map_err(|_| "invalid token".to_owned())?;
+    let now = chrono::Utc::now().naive_utc();
+    if token.is_expired(now) {
+      return Err("token expired".to_owned());
+    }
+    let is_stale = token
+      .last_used_at
+      .map(|date| (now - date).num_seconds() >= TOUCH_INTERVAL)
+      .unwrap_or(true);
+    if is_stale {
+      if let Err(err) = TokenDb::touch(&token.key, now, pool).await {
+        log::warn!("token: {} {err}", token.key);
+      }
+    }
+    Ok(TokenIdentity { name: token.key })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn secret() {
+    let secret = generate_secret().unwrap();
+    assert!(secret.starts_with(TOKEN_PREFIX));
+    assert_eq!(secret.len(), TOKEN_PREFIX.len() + 64);
+    assert_ne!(secret, generate_secret().unwrap());
+    assert_eq!(
+      hash_secret("test"),
+      "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+    );
+  }
+}
@@ -90,6 +90,15 @@ impl Store {
     Ok(())
   }
 
+  pub async fn read_conf_file(
+    &self,
+    name: &str,
+    kind: &NginxRuleKind,
+  ) -> Option<String> {
+    let path = self.gen_path(name, kind);
+    tokio::fs::read_to_string(&path.0).await.ok()
+  }
+
   pub async fn delete_conf_file(&self, name: &str, kind: &NginxRuleKind) {
     let path = self.gen_path(name, kind);
     let _ = tokio::fs::remove_file(&path.0).await;
@@ -1,7 +1,11 @@
-use std::sync::Arc;
+use std::{rc::Rc, cell::RefCell, sync::Arc};
 
 use ntex::rt;
-use futures::{SinkExt, StreamExt, channel::mpsc};
+use futures::{
+  SinkExt, StreamExt,
+  channel::{mpsc, oneshot},
+  lock::Mutex,
+};
 
 use nanocl_error::io::{IoResult, IoError};
 
@@ -18,6 +22,8 @@ pub struct SystemState {
   pub client: NanocldClient,
   pub event_emitter: EventEmitter,
   pub nginx_dir: String,
+  /// Held while certificates are ordered with ACME
+  pub acme_lock: Arc<Mutex<()>>,
 }
 
 pub type SystemStateRef = Arc<SystemState>;
@@ -25,11 +31,15 @@ pub type SystemStateRef = Arc<SystemState>;
 /// Type of event that can be emitted
 pub enum SystemEventKind {
   Reload,
+  /// Reload and send the result once nginx is reloaded
+  ReloadWait(oneshot::Sender<IoResult<()>>),
 }
 
 struct SystemEventInner {
   client: NanocldClient,
   task: ntex::rt::JoinHandle<IoResult<()>>,
+  /// Waiting for the next reload, they stay when a reload task is aborted
+  waiters: Rc<RefCell<Vec<oneshot::Sender<IoResult<()>>>>>,
 }
 
 pub struct SystemEvent(SystemEventInner);
@@ -39,21 +49,30 @@ impl SystemEvent {
     Self(SystemEventInner {
       client: client.clone(),
       task: rt::spawn(async move { Ok::<_, IoError>(()) }),
+      waiters: Default::default(),
     })
   }
 
-  pub fn handle(&mut self, _e: SystemEventKind) {
+  pub fn handle(&mut self, e: SystemEventKind) {
+    if let SystemEventKind::ReloadWait(waiter) = e {
+      self.0.waiters.borrow_mut().push(waiter);
+    }
     let abort_handle = self.0.task.abort_handle();
     if !abort_handle.is_finished() {
       log::info!("system: aborting reload task");
       abort_handle.abort();
     }
     let client = self.0.client.clone();
+    let waiters = self.0.waiters.clone();
     self.0.task = rt::spawn(async move {
       ntex::time::sleep(std::time::Duration::from_millis(750)).await;
-      if let Err(err) = utils::nginx::reload(&client).await {
+      let res = utils::nginx::reload(&client).await;
+      if let Err(err) = &res {
         log::warn!("system: {err}");
       }
+      for waiter in waiters.borrow_mut().drain(..) {
+        let _ = waiter.send(res.clone());
+      }
       Ok::<_, IoError>(())
     });
   }
@@ -88,4 +107,12 @@ impl EventEmitter {
   pub async fn emit_reload(&self) {
     self.emit(SystemEventKind::Reload).await;
   }
+
+  /// Reload nginx and wait for it to be reloaded
+  pub async fn reload(&self) -> IoResult<()> {
+    let (tx, rx) = oneshot::channel();
+    self.emit(SystemEventKind::ReloadWait(tx)).await;
+    rx.await
+      .map_err(|_| IoError::interrupted("Reload", "event loop stopped"))?
+  }
 }
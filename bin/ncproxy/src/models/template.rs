@@ -3,17 +3,69 @@ use serde::{Serialize, Deserialize};
 
 use nanocl_error::io::{IoResult, IoError};
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BasicAuthTemplate {
+  pub realm: String,
+  pub user_file: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthRequestHeaderTemplate {
+  pub name: String,
+  pub variable: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthRequestTemplate {
+  pub path: String,
+  pub upstream: String,
+  pub headers: Vec<AuthRequestHeaderTemplate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheTemplate {
+  pub zone: String,
+  pub path: String,
+  pub zone_size: usize,
+  pub max_size: Option<usize>,
+  pub valid: Vec<String>,
+  pub bypass: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompressionTemplate {
+  pub gzip: Option<String>,
+  pub brotli: Option<String>,
+  pub types: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorsTemplate {
+  pub variable: String,
+  pub any_origin: bool,
+  pub origins: Vec<String>,
+  pub methods: String,
+  pub headers: Option<String>,
+  pub credentials: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LocationTemplate {
   pub path: String,
   pub upstream_key: String,
   pub upstream_path: String,
+  pub rewrite: Option<String>,
   pub redirect: Option<String>,
   pub limit_req: Option<LimitReq>,
   pub allowed_ips: Option<Vec<String>>,
   pub version: Option<f64>,
   pub headers: Option<Vec<String>>,
   pub ssl: Option<ProxySslConfig>,
+  pub basic_auth: Option<BasicAuthTemplate>,
+  pub auth_request: Option<AuthRequestTemplate>,
+  pub cache: Option<CacheTemplate>,
+  pub compression: Option<CompressionTemplate>,
+  pub cors: Option<CorsTemplate>,
 }
 
 pub struct Template<'a> {
@@ -58,3 +110,7 @@ pub const UPSTREAM_TEMPLATE: &Template = &Template {
 pub const UNIX_UPSTREAM_TEMPLATE: &Template = &Template {
   data: include_str!("templates/unix_upstream.conf"),
 };
+
+pub const SPLIT_TEMPLATE: &Template = &Template {
+  data: include_str!("templates/split.conf"),
+};
@@ -4,7 +4,10 @@ use nanocld_client::stubs::proxy::{
   ProxyRule, ProxyRuleHttp, ProxyRuleStream, ResourceProxyRule,
   ProxyHttpLocation, ProxySsl, ProxyStreamProtocol, StreamTarget,
   LocationTarget, UpstreamTarget, HttpTarget, UriTarget, UrlRedirect,
-  UnixTarget, ProxySslConfig,
+  UnixTarget, ProxySslConfig, UpstreamBalancing, UpstreamHashKey, SplitTarget,
+  SplitWeight, SplitPin, ProxySslAcme, ProxyBasicAuth, ProxyAuthRequest,
+  ProxyAuthRequestTarget, ProxyCache, ProxyCacheValid, ProxyCacheBypass,
+  ProxyCompression, ProxyCors,
 };
 
 use super::rule;
@@ -46,10 +49,24 @@ impl utoipa::Modify for VersionModifier {
     ProxyHttpLocation,
     ProxySsl,
     ProxySslConfig,
+    ProxySslAcme,
+    ProxyBasicAuth,
+    ProxyAuthRequest,
+    ProxyAuthRequestTarget,
+    ProxyCache,
+    ProxyCacheValid,
+    ProxyCacheBypass,
+    ProxyCompression,
+    ProxyCors,
     ProxyStreamProtocol,
     StreamTarget,
     LocationTarget,
     UpstreamTarget,
+    UpstreamBalancing,
+    UpstreamHashKey,
+    SplitTarget,
+    SplitWeight,
+    SplitPin,
     HttpTarget,
     UriTarget,
     UrlRedirect,
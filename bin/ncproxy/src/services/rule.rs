@@ -17,6 +17,7 @@ use crate::{utils, models::SystemStateRef};
   ),
   responses(
     (status = 200, description = "The created rule", body = ResourceProxyRule),
+    (status = 400, description = "The rule is refused by nginx, the previous one is kept"),
   ),
 ))]
 #[web::put("/rules/{name}")]
@@ -26,8 +27,10 @@ pub async fn apply_rule(
   payload: web::types::Json<ResourceProxyRule>,
 ) -> Result<web::HttpResponse, HttpError> {
   log::info!("apply_rule: {}", path.1);
-  utils::nginx::add_rule(&path.1, &payload, &state).await?;
-  state.event_emitter.emit_reload().await;
+  utils::nginx::apply_rule(&path.1, &payload, &state)
+    .await
+    .map_err(|err| HttpError::bad_request(err.to_string()))?;
+  utils::acme::spawn_renew(&path.1, &payload, &state);
   Ok(web::HttpResponse::Ok().json(&payload.into_inner()))
 }
 
@@ -83,4 +86,35 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "delete a rule");
     clean_test_cargo().await.unwrap();
   }
+
+  #[ntex::test]
+  async fn rejected() {
+    let name = "ncproxy-io-test-rejected";
+    let client = gen_default_test_client().await;
+    ensure_test_cargo().await.unwrap();
+    let payload = read_rule("tests/basic.yml").unwrap();
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put a rule");
+    let invalid = read_rule("tests/invalid.yml").unwrap();
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&invalid), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "put a rule refused by nginx"
+    );
+    // The previous rule is kept and the other rules can still be applied
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put a rule again");
+    let res = client
+      .send_delete(&format!("/rules/{name}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "delete a rule");
+    clean_test_cargo().await.unwrap();
+  }
 }
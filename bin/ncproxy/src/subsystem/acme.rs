@@ -0,0 +1,59 @@
+/// Renew the certificates issued with ACME before their expiry
+use std::{sync::Arc, time::Duration};
+
+use ntex::rt;
+
+use nanocl_error::io::{IoResult, FromIo};
+
+use nanocld_client::stubs::generic::{GenericClause, GenericFilter};
+
+use crate::{vars, utils, models::SystemStateRef};
+
+/// Interval between two checks of the expiry of the certificates
+const RENEW_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+/// Delay before the first check for nginx to be configured
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+/// Renew the certificates of every rule using ACME
+async fn renew(state: &SystemStateRef) -> IoResult<()> {
+  let filter = GenericFilter::new()
+    .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()));
+  let resources =
+    state
+      .client
+      .list_resource(Some(&filter))
+      .await
+      .map_err(|err| {
+        err.map_err_context(|| "Unable to list resources from nanocl daemon")
+      })?;
+  for resource in resources {
+    let name = &resource.spec.resource_key;
+    let rule = match utils::resource::serialize(&resource.spec.data) {
+      Err(err) => {
+        log::warn!("acme::renew: {name} {err}");
+        continue;
+      }
+      Ok(rule) => rule,
+    };
+    if let Err(err) = utils::acme::renew_and_apply(name, &rule, state).await {
+      log::warn!("acme::renew: {name} {err}");
+    }
+  }
+  Ok(())
+}
+
+/// Spawn a thread checking periodically the expiry of the certificates
+pub(crate) fn spawn(state: &SystemStateRef) {
+  let state = Arc::clone(state);
+  rt::Arbiter::new().exec_fn(move || {
+    rt::spawn(async move {
+      ntex::time::sleep(STARTUP_DELAY).await;
+      loop {
+        if let Err(err) = renew(&state).await {
+          log::warn!("acme::spawn: {err}");
+        }
+        ntex::time::sleep(RENEW_INTERVAL).await;
+      }
+    });
+  });
+}
@@ -110,15 +110,21 @@ async fn on_event(event: &Event, state: &SystemStateRef) -> IoResult<()> {
     }
     (EventActorKind::Secret, NativeEventAction::Create)
     | (EventActorKind::Secret, NativeEventAction::Update) => {
-      let resources = utils::resource::list_by_secret(
-        &actor.key.unwrap_or_default(),
-        &state.client,
-      )
-      .await?;
+      let key = actor.key.unwrap_or_default();
+      if key.starts_with("acme-challenge-") {
+        return utils::acme::sync_challenge(&key, state).await;
+      }
+      let resources =
+        utils::resource::list_by_secret(&key, &state.client).await?;
       utils::resource::update_rules(&resources, state).await?;
       let _ = state.event_emitter.emit_reload().await;
       Ok(())
     }
+    (EventActorKind::Secret, NativeEventAction::Destroy) => {
+      utils::acme::remove_challenge(&actor.key.unwrap_or_default(), state)
+        .await;
+      Ok(())
+    }
     _ => Ok(()),
   }
 }
@@ -158,6 +164,9 @@ async fn r#loop(state: &SystemStateRef) {
           continue;
         }
         let _ = utils::nginx::ensure_conf(state).await;
+        if let Err(err) = utils::acme::sync_challenges(state).await {
+          log::warn!("event::loop: {err}");
+        }
         log::info!("event::loop: subscribed to nanocld events");
         while let Some(event) = stream.next().await {
           let event = match event {
@@ -9,7 +9,7 @@ use crate::{
   models::{Store, SystemState, SystemStateRef, EventEmitter},
 };
 
-use super::{event, metric};
+use super::{event, metric, acme};
 
 pub async fn init(cli: &Cli) -> IoResult<SystemStateRef> {
   #[allow(unused)]
@@ -28,8 +28,10 @@ pub async fn init(cli: &Cli) -> IoResult<SystemStateRef> {
     event_emitter,
     store: Store::new(&cli.state_dir),
     nginx_dir: cli.nginx_dir.clone(),
+    acme_lock: Default::default(),
   });
   event::spawn(&state);
   metric::spawn(&state);
+  acme::spawn(&state);
   Ok(state)
 }
@@ -1,14 +1,15 @@
-/// Watch for change inside the access log directory
-/// and print them to the console
-/// that way we can see what is happening in real time with docker logs
+/// Follow the access logs of nginx and submit every line as a metric
 /// so the daemon will be able to save them to the database
-/// and broadcast them in real time
+/// and broadcast them in real time.
+/// The position read in each log is saved so no line is lost
+/// when ncproxy restart or when the logs are rotated or truncated.
 use std::{
-  sync::Arc,
+  sync::{Arc, mpsc},
   fs::File,
-  path::Path,
+  path::{Path, PathBuf},
   time::Duration,
-  io::{Read, Seek, BufReader, SeekFrom},
+  os::unix::fs::MetadataExt,
+  io::{Read, Seek, SeekFrom},
 };
 
 use ntex::{rt, http};
@@ -18,100 +19,242 @@ use nanocl_error::io::{IoResult, IoError, FromIo};
 
 use nanocld_client::{
   NanocldClient,
-  stubs::metric::{MetricPartial, HttpMetric},
+  stubs::metric::{MetricPartial, HttpMetric, StreamMetric},
 };
 
 use crate::models::SystemStateRef;
 
-fn read(path: &Path) -> IoResult<serde_json::Value> {
-  if !path.exists() {
-    return Err(IoError::not_found("Metric", &format!("{}", path.display())));
+/// Maximum number of lines submitted at once
+const BATCH_SIZE: usize = 500;
+/// Maximum number of bytes read at once
+const MAX_READ: u64 = 4 * 1024 * 1024;
+/// Interval to check the logs when nothing is notified
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Follow a log file like `tail -F` from the first line not submitted yet
+struct LogTailer {
+  /// Kind of the metrics
+  kind: &'static str,
+  /// Path of the log
+  path: PathBuf,
+  /// Path where the position in the log is saved
+  position_path: PathBuf,
+  /// The opened log, it's kept open to read the end of a rotated log
+  file: Option<File>,
+  /// Inode of the opened log
+  ino: u64,
+  /// Offset of the first line not submitted yet
+  offset: u64,
+  /// Position saved by a previous run as inode and offset
+  saved: Option<(u64, u64)>,
+  /// Whether the log was opened once, when no position was saved
+  /// the first log is read from his end to not submit his history
+  opened: bool,
+}
+
+impl LogTailer {
+  fn new(kind: &'static str, path: &Path, position_path: &Path) -> Self {
+    let saved =
+      std::fs::read_to_string(position_path)
+        .ok()
+        .and_then(|content| {
+          let (ino, offset) = content.trim().split_once(' ')?;
+          Some((ino.parse().ok()?, offset.parse().ok()?))
+        });
+    Self {
+      kind,
+      path: path.to_owned(),
+      position_path: position_path.to_owned(),
+      file: None,
+      ino: 0,
+      offset: 0,
+      saved,
+      opened: false,
+    }
   }
-  let file = match File::open(path) {
-    Ok(file) => file,
-    Err(e) => {
-      return Err(e.map_err_context(|| "metric").into());
+
+  /// Open the log when it exists and restore the saved position
+  fn open(&mut self) -> IoResult<bool> {
+    if !self.path.exists() {
+      return Ok(false);
     }
-  };
-  let mut buf_reader = BufReader::new(file);
-  let mut pos = match buf_reader.seek(SeekFrom::End(-2)) {
-    Ok(pos) => pos,
-    Err(e) => {
-      return Err(e.map_err_context(|| "metric").into());
+    let file = File::open(&self.path)
+      .map_err(|err| err.map_err_context(|| self.path.display()))?;
+    let metadata = file
+      .metadata()
+      .map_err(|err| err.map_err_context(|| self.path.display()))?;
+    self.ino = metadata.ino();
+    self.offset = match self.saved.take() {
+      Some((ino, offset)) if ino == self.ino => offset,
+      Some(_) => 0,
+      None if !self.opened => metadata.len(),
+      None => 0,
+    };
+    self.opened = true;
+    self.file = Some(file);
+    Ok(true)
+  }
+
+  /// Whether the log was replaced by a new file
+  fn is_rotated(&self) -> bool {
+    match std::fs::metadata(&self.path) {
+      Ok(metadata) => metadata.ino() != self.ino,
+      Err(_) => false,
     }
-  };
-  let mut last_line = String::new();
-  while pos > 0 {
-    match buf_reader.seek(SeekFrom::Start(pos)) {
-      Ok(_) => {}
-      Err(e) => {
-        log::warn!("metric::read: {e}");
-        return Err(e.map_err_context(|| "metric").into());
+  }
+
+  /// Read at most `BATCH_SIZE` complete lines from the offset
+  /// and return them with the offset following them
+  fn read(&mut self) -> IoResult<(Vec<String>, u64)> {
+    if self.file.is_none() && !self.open()? {
+      return Ok((Vec::new(), self.offset));
+    }
+    let Some(file) = self.file.as_mut() else {
+      return Ok((Vec::new(), self.offset));
+    };
+    let len = file
+      .metadata()
+      .map_err(|err| err.map_err_context(|| self.path.display()))?
+      .len();
+    if len < self.offset {
+      log::warn!("metric::read: {} truncated", self.path.display());
+      self.offset = 0;
+    }
+    file
+      .seek(SeekFrom::Start(self.offset))
+      .map_err(|err| err.map_err_context(|| self.path.display()))?;
+    let mut buf = Vec::new();
+    file
+      .take(MAX_READ)
+      .read_to_end(&mut buf)
+      .map_err(|err| err.map_err_context(|| self.path.display()))?;
+    let mut lines = Vec::new();
+    let mut consumed = 0;
+    while lines.len() < BATCH_SIZE {
+      let Some(end) = buf[consumed..].iter().position(|b| *b == b'\n') else {
+        break;
+      };
+      let line = String::from_utf8_lossy(&buf[consumed..consumed + end]);
+      if !line.trim().is_empty() {
+        lines.push(line.into_owned());
       }
+      consumed += end + 1;
     }
-    let mut buffer = [0; 1];
-    let res = buf_reader.read_exact(&mut buffer);
-    if buffer[0] == b'\n' || res.is_err() {
-      break;
+    if consumed == 0 && buf.len() as u64 == MAX_READ {
+      log::warn!("metric::read: skipping a too long line");
+      consumed = buf.len();
+    }
+    if consumed == 0 && self.is_rotated() {
+      // The end of the rotated log is read, follow the new one
+      log::debug!("metric::read: {} rotated", self.path.display());
+      self.file = None;
+      self.offset = 0;
+      return self.read();
+    }
+    Ok((lines, self.offset + consumed as u64))
+  }
+
+  /// Move the offset after the submitted lines and save it
+  fn commit(&mut self, offset: u64) {
+    self.offset = offset;
+    let content = format!("{} {}", self.ino, self.offset);
+    if let Err(err) = std::fs::write(&self.position_path, content) {
+      log::warn!("metric::commit: {err}");
     }
-    last_line.insert(0, buffer[0] as char);
-    pos -= 1;
   }
-  log::trace!("metric::read: {last_line}");
-  let data = serde_json::from_str(&last_line).map_err(|e| {
-    e.map_err_context(|| format!("metric failed to parse {last_line}"))
-  })?;
-  Ok(data)
 }
 
-async fn create(
-  kind: &str,
-  path: &Path,
-  client: &NanocldClient,
-) -> IoResult<()> {
-  let data = read(path)?;
-  log::trace!("metric::create: {kind} {data}");
-  let display = match kind {
+/// Parse a line of a log into a metric
+fn parse(kind: &str, line: &str) -> IoResult<MetricPartial> {
+  match kind {
     "ncproxy.io/http" => {
-      let data = serde_json::from_value::<HttpMetric>(data.clone())?;
-      let upstream_addr = data.upstream_addr.unwrap_or("<none>".to_owned());
-      let status = match http::StatusCode::from_u16(data.status as u16) {
-        Err(_) => data.status.to_string(),
+      let metric = serde_json::from_str::<HttpMetric>(line)
+        .map_err(|err| err.map_err_context(|| "HttpMetric"))?;
+      let upstream_addr =
+        metric.upstream_addr.clone().unwrap_or("<none>".to_owned());
+      let status = match http::StatusCode::from_u16(metric.status as u16) {
+        Err(_) => metric.status.to_string(),
         Ok(status) => format!("{status}"),
       };
       let display = format!(
         "[{status}] {} {} {}{} -> {upstream_addr}",
-        data.server_protocol, data.request_method, data.host, data.uri,
+        metric.server_protocol, metric.request_method, metric.host, metric.uri,
       );
-      Some(display)
+      Ok(MetricPartial {
+        kind: kind.to_owned(),
+        data: serde_json::to_value(&metric)?,
+        note: Some(display),
+      })
     }
-    "ncproxy.io/stream" => None,
-    _ => None,
-  };
-  let metric = MetricPartial {
-    kind: kind.to_owned(),
-    data,
-    note: display,
-  };
-  client.create_metric(&metric).await?;
-  Ok(())
+    "ncproxy.io/stream" => {
+      let metric = serde_json::from_str::<StreamMetric>(line)
+        .map_err(|err| err.map_err_context(|| "StreamMetric"))?;
+      Ok(MetricPartial {
+        kind: kind.to_owned(),
+        data: serde_json::to_value(metric)?,
+        note: None,
+      })
+    }
+    _ => Err(IoError::invalid_input(
+      "Metric",
+      &format!("unknown kind {kind}"),
+    )),
+  }
+}
+
+/// Submit the new lines of a log until the end is reached.
+/// Malformed lines are skipped and the offset only move
+/// once the lines are submitted.
+async fn tail(tailer: &mut LogTailer, client: &NanocldClient) -> IoResult<()> {
+  loop {
+    let (lines, offset) = tailer.read()?;
+    if offset == tailer.offset {
+      return Ok(());
+    }
+    let metrics = lines
+      .iter()
+      .filter_map(|line| match parse(tailer.kind, line) {
+        Ok(metric) => Some(metric),
+        Err(err) => {
+          log::warn!("metric::tail: {err}");
+          None
+        }
+      })
+      .collect::<Vec<_>>();
+    if !metrics.is_empty() {
+      log::trace!("metric::tail: {} {}", tailer.kind, metrics.len());
+      client.create_metrics(&metrics).await?;
+    }
+    tailer.commit(offset);
+  }
 }
 
 async fn watch(state: &SystemStateRef) -> IoResult<()> {
   let path = format!("{}/log", state.store.dir);
   let path = Path::new(&path);
-  if !path.exists() {
-    std::fs::create_dir_all(path)
-      .map_err(|e| e.map_err_context(|| "metric"))?;
+  let position_dir = format!("{}/log_positions", state.store.dir);
+  let position_dir = Path::new(&position_dir);
+  for dir in [path, position_dir] {
+    if !dir.exists() {
+      std::fs::create_dir_all(dir)
+        .map_err(|e| e.map_err_context(|| "metric"))?;
+    }
   }
-  let (tx, rx) = std::sync::mpsc::channel();
+  let mut tailers = [
+    ("http.log", "ncproxy.io/http"),
+    ("stream.log", "ncproxy.io/stream"),
+  ]
+  .map(|(file_name, kind)| {
+    LogTailer::new(kind, &path.join(file_name), &position_dir.join(file_name))
+  });
+  let (tx, rx) = mpsc::channel();
   // Automatically select the best implementation for your platform.
   // You can also access each implementation directly e.g. INotifyWatcher.
   let mut watcher = match RecommendedWatcher::new(
     tx,
     Config::default()
       .with_compare_contents(true)
-      .with_poll_interval(Duration::from_secs(2)),
+      .with_poll_interval(POLL_INTERVAL),
   ) {
     Ok(watcher) => watcher,
     Err(e) => {
@@ -120,37 +263,23 @@ async fn watch(state: &SystemStateRef) -> IoResult<()> {
   };
   // Add a path to be watched. All files and directories at that path and
   // below will be monitored for changes.
-  watcher.watch(path, RecursiveMode::Recursive).unwrap();
+  watcher
+    .watch(path, RecursiveMode::Recursive)
+    .map_err(|e| IoError::interrupted("metric", &e.to_string()))?;
   log::debug!("metric::watch: {}", path.display());
-  for res in rx {
-    let event = match res {
-      Ok(event) => event,
-      Err(e) => {
-        log::warn!("metric::watch: {e}");
-        continue;
-      }
-    };
-    log::trace!("metric::watch: {event:?}");
-    if let notify::EventKind::Modify(notify::event::ModifyKind::Data(_)) =
-      event.kind
-    {
-      let path = event.paths.first();
-      if let Some(path) = path {
-        let file_name = path
-          .file_name()
-          .unwrap_or_default()
-          .to_str()
-          .unwrap_or_default();
-        let kind = match file_name {
-          "http.log" => "ncproxy.io/http",
-          "stream.log" => "ncproxy.io/stream",
-          _ => {
-            continue;
-          }
-        };
-        if let Err(err) = create(kind, path, &state.client).await {
-          log::warn!("metric::watch: {err}");
-        }
+  loop {
+    // The logs are also checked on a schedule to retry the failed submissions
+    match rx.recv_timeout(POLL_INTERVAL) {
+      Ok(Ok(event)) => log::trace!("metric::watch: {event:?}"),
+      Ok(Err(e)) => log::warn!("metric::watch: {e}"),
+      Err(mpsc::RecvTimeoutError::Timeout) => {}
+      Err(mpsc::RecvTimeoutError::Disconnected) => break,
+    }
+    // Every pending event is handled by the next read
+    while rx.try_recv().is_ok() {}
+    for tailer in tailers.iter_mut() {
+      if let Err(err) = tail(tailer, &state.client).await {
+        log::warn!("metric::watch: {} {err}", tailer.kind);
       }
     }
   }
@@ -158,8 +287,8 @@ async fn watch(state: &SystemStateRef) -> IoResult<()> {
 }
 
 /// Spawn new thread and watch for change inside the access log directory
-/// to print them to the console that way we can see what is happening in real time with docker logs
-/// and the nanocl daemon will be able to save them to the database
+/// to submit every new line to the nanocl daemon
+/// that will save them to the database
 pub(crate) fn spawn(state: &SystemStateRef) {
   let state = Arc::clone(state);
   rt::Arbiter::new().exec_fn(move || {
@@ -171,3 +300,89 @@ pub(crate) fn spawn(state: &SystemStateRef) {
     });
   });
 }
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use super::*;
+
+  fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ncproxy-tailer-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn append(path: &Path, content: &str) {
+    let mut file = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+  }
+
+  fn read_all(tailer: &mut LogTailer) -> Vec<String> {
+    let mut all = Vec::new();
+    loop {
+      let (lines, offset) = tailer.read().unwrap();
+      if offset == tailer.offset {
+        return all;
+      }
+      all.extend(lines);
+      tailer.commit(offset);
+    }
+  }
+
+  #[test]
+  fn tailer() {
+    let dir = test_dir("basic");
+    let log = dir.join("http.log");
+    let position = dir.join("http.pos");
+    append(&log, "old\n");
+    let mut tailer = LogTailer::new("ncproxy.io/http", &log, &position);
+    // The history isn't read without a saved position
+    assert!(read_all(&mut tailer).is_empty());
+    append(&log, "a\nb\nc");
+    assert_eq!(read_all(&mut tailer), vec!["a", "b"]);
+    // Lines are read once complete
+    append(&log, "\n");
+    assert_eq!(read_all(&mut tailer), vec!["c"]);
+    // Lines that aren't committed are read again
+    append(&log, "d\n");
+    assert_eq!(tailer.read().unwrap().0, vec!["d"]);
+    assert_eq!(tailer.read().unwrap().0, vec!["d"]);
+    // A restart continue from the saved position
+    let mut tailer = LogTailer::new("ncproxy.io/http", &log, &position);
+    assert_eq!(read_all(&mut tailer), vec!["d"]);
+    // Truncated log is read from the start
+    std::fs::write(&log, "e\n").unwrap();
+    assert_eq!(read_all(&mut tailer), vec!["e"]);
+    // The end of a rotated log is read before the new one
+    append(&log, "f\n");
+    std::fs::rename(&log, dir.join("http.log.1")).unwrap();
+    append(&log, "g\n");
+    assert_eq!(read_all(&mut tailer), vec!["f", "g"]);
+    // Batches are limited
+    append(&log, &"h\n".repeat(BATCH_SIZE + 1));
+    assert_eq!(tailer.read().unwrap().0.len(), BATCH_SIZE);
+    assert_eq!(read_all(&mut tailer).len(), BATCH_SIZE + 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn parse_line() {
+    let line = r#"{"date_gmt": "2024-06-01T10:00:00+00:00", "remote_addr": "127.0.0.1", "realip_remote_addr": "127.0.0.1", "proxy_host": "", "upstream_addr": "10.0.0.2:80", "server_protocol": "HTTP/1.1", "request_method": "GET", "host": "example.com", "uri": "/", "query_string": "", "request_body": "", "content_type": "", "content_length": "", "status": "200", "bytes_sent": "512", "request_time": "0.010", "upstream_bytes_sent": "", "upstream_bytes_received": "", "upstream_response_time": "", "upstream_connect_time": "", "body_bytes_sent": "256", "http_referrer": "", "http_accept_language": "", "http_user_agent": "curl"}"#;
+    let metric = parse("ncproxy.io/http", line).unwrap();
+    assert_eq!(metric.data["Status"], 200);
+    assert_eq!(metric.data["RequestTime"], 0.01);
+    assert_eq!(metric.data["Host"], "example.com");
+    assert_eq!(
+      metric.note.as_deref(),
+      Some("[200 OK] HTTP/1.1 GET example.com/ -> 10.0.0.2:80")
+    );
+    assert!(parse("ncproxy.io/http", "{\"status\": ").is_err());
+    assert!(parse("ncproxy.io/unknown", "{}").is_err());
+  }
+}
@@ -0,0 +1,926 @@
+/// Certificates issued with the ACME protocol (RFC 8555)
+/// A certificate is ordered by the instance holding the lease of its domain.
+/// The account key and the key authorizations of the http-01 challenges
+/// are stored as secrets, every instance write the challenges in the `acme`
+/// directory of the state dir for nginx to answer them.
+/// The issued certificates are stored as `nanocl.io/tls` secrets
+/// and renewed before their expiry.
+use std::{
+  pin::pin,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use ntex::{
+  rt,
+  time::Millis,
+  util::Bytes,
+  http::client::{Client, ClientResponse, Connector},
+};
+use futures::future::{Either, select};
+use openssl::{
+  asn1::Asn1Time,
+  bn::{BigNum, BigNumContext},
+  ec::{EcGroup, EcKey},
+  ecdsa::EcdsaSig,
+  error::ErrorStack,
+  hash::MessageDigest,
+  nid::Nid,
+  pkey::{PKey, Private},
+  sha::sha256,
+  ssl::{SslConnector, SslMethod, SslVerifyMode},
+  stack::Stack,
+  x509::{
+    X509, X509NameBuilder, X509ReqBuilder, extension::SubjectAlternativeName,
+  },
+};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use nanocl_error::{
+  io::{IoError, IoResult, FromIo},
+  http_client::HttpClientError,
+};
+
+use nanocld_client::stubs::{
+  node::CLIENT_LEASE_PREFIX,
+  proxy::{
+    ProxyRule, ProxySsl, ProxySslAcme, ProxySslConfig, ResourceProxyRule,
+  },
+  generic::{GenericClause, GenericFilter},
+  secret::{SecretInspectQuery, SecretPartial, SecretUpdate},
+};
+
+use crate::models::SystemStateRef;
+
+/// Certificates are renewed when they expire in less days
+const RENEW_BEFORE_DAYS: u32 = 30;
+/// Number of times the status of an authorization or an order is checked
+const POLL_ATTEMPTS: usize = 30;
+/// Delay between two checks of the status of an authorization or an order
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Maximum size of a response of the ACME server
+const BODY_LIMIT: usize = 1024 * 1024;
+/// Delay between two renewal of the lease of a domain during its order,
+/// the lease expires if not renewed for 60 seconds
+const LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(20);
+/// Number of times a request rejected for his nonce is sent again
+const NONCE_RETRIES: usize = 3;
+
+/// Name of the secret storing the certificate of a domain
+pub fn secret_name(domain: &str) -> String {
+  format!("acme-{domain}")
+}
+
+/// Name of the secret storing the key of the ACME account
+const ACCOUNT_SECRET: &str = "acme-account";
+/// Kind of the secrets storing the key authorization of a challenge
+pub const CHALLENGE_KIND: &str = "nanocl.io/acme-challenge";
+
+/// Key of the lease held by the instance ordering the certificate of a domain
+fn lease_key(domain: &str) -> String {
+  format!("{CLIENT_LEASE_PREFIX}{domain}")
+}
+
+/// Name of the secret storing the key authorization of a challenge
+fn challenge_secret_name(token: &str) -> String {
+  format!("acme-challenge-{token}")
+}
+
+/// Root of the files served by nginx for the http-01 challenges
+pub fn challenge_root(state: &SystemStateRef) -> String {
+  format!("{}/acme", state.store.dir)
+}
+
+/// Directory of the files answering the http-01 challenges
+fn challenge_dir(state: &SystemStateRef) -> PathBuf {
+  Path::new(&challenge_root(state)).join(".well-known/acme-challenge")
+}
+
+/// The token is used as file name it must be base64 url encoded
+fn is_valid_token(token: &str) -> bool {
+  !token.is_empty()
+    && token
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Whether the status of an error returned by the daemon is `status`
+fn has_status(err: &HttpClientError, status: ntex::http::StatusCode) -> bool {
+  matches!(err, HttpClientError::HttpError(err) if err.status == status)
+}
+
+fn ssl_err(err: ErrorStack) -> IoError {
+  IoError::new("Acme", err.into())
+}
+
+/// Base64 url encoding without padding used by the JWS
+fn b64url(data: &[u8]) -> String {
+  openssl::base64::encode_block(data)
+    .replace('+', "-")
+    .replace('/', "_")
+    .trim_end_matches('=')
+    .to_owned()
+}
+
+/// Public key of the account as JWK with his members in lexicographic order
+fn gen_jwk(key: &EcKey<Private>) -> Result<String, ErrorStack> {
+  let mut ctx = BigNumContext::new()?;
+  let mut x = BigNum::new()?;
+  let mut y = BigNum::new()?;
+  key
+    .public_key()
+    .affine_coordinates(key.group(), &mut x, &mut y, &mut ctx)?;
+  Ok(format!(
+    r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+    b64url(&x.to_vec_padded(32)?),
+    b64url(&y.to_vec_padded(32)?),
+  ))
+}
+
+/// Thumbprint of the account key used in the key authorizations
+fn gen_thumbprint(key: &EcKey<Private>) -> Result<String, ErrorStack> {
+  Ok(b64url(&sha256(gen_jwk(key)?.as_bytes())))
+}
+
+/// Sign a request as a flattened JWS with the account key,
+/// the key is identified by the account url once registered
+/// and an empty payload is used for the POST-as-GET requests.
+fn sign(
+  key: &EcKey<Private>,
+  kid: Option<&str>,
+  nonce: &str,
+  url: &str,
+  payload: Option<&serde_json::Value>,
+) -> IoResult<String> {
+  let mut protected = serde_json::json!({
+    "alg": "ES256",
+    "nonce": nonce,
+    "url": url,
+  });
+  match kid {
+    Some(kid) => protected["kid"] = kid.into(),
+    None => {
+      let jwk = gen_jwk(key).map_err(ssl_err)?;
+      protected["jwk"] = serde_json::from_str(&jwk)?;
+    }
+  }
+  let protected = b64url(protected.to_string().as_bytes());
+  let payload = payload
+    .map(|payload| b64url(payload.to_string().as_bytes()))
+    .unwrap_or_default();
+  let digest = sha256(format!("{protected}.{payload}").as_bytes());
+  let signature = EcdsaSig::sign(&digest, key).map_err(ssl_err)?;
+  let mut raw = signature.r().to_vec_padded(32).map_err(ssl_err)?;
+  raw.extend(signature.s().to_vec_padded(32).map_err(ssl_err)?);
+  let jws = serde_json::json!({
+    "protected": protected,
+    "payload": payload,
+    "signature": b64url(&raw),
+  });
+  Ok(jws.to_string())
+}
+
+/// Generate the key of a certificate and his signing request in DER
+fn gen_csr(domain: &str) -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
+  let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+  let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+  let mut name = X509NameBuilder::new()?;
+  name.append_entry_by_nid(Nid::COMMONNAME, domain)?;
+  let mut req = X509ReqBuilder::new()?;
+  req.set_subject_name(&name.build())?;
+  req.set_pubkey(&key)?;
+  let mut extensions = Stack::new()?;
+  extensions.push(
+    SubjectAlternativeName::new()
+      .dns(domain)
+      .build(&req.x509v3_context(None))?,
+  )?;
+  req.add_extensions(&extensions)?;
+  req.sign(&key, MessageDigest::sha256())?;
+  Ok((req.build().to_der()?, key.private_key_to_pem_pkcs8()?))
+}
+
+/// Whether a certificate in PEM must be renewed
+fn expires_soon(certificate: &str) -> bool {
+  let Ok(certificate) = X509::from_pem(certificate.as_bytes()) else {
+    return true;
+  };
+  let Ok(threshold) = Asn1Time::days_from_now(RENEW_BEFORE_DAYS) else {
+    return true;
+  };
+  certificate.not_after() < threshold
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AcmeDirectory {
+  new_nonce: String,
+  new_account: String,
+  new_order: String,
+}
+
+#[derive(Default, Deserialize)]
+struct AcmeProblem {
+  #[serde(default)]
+  r#type: String,
+  #[serde(default)]
+  detail: String,
+}
+
+#[derive(Deserialize)]
+struct AcmeStatus {
+  status: String,
+}
+
+#[derive(Deserialize)]
+struct AcmeOrder {
+  authorizations: Vec<String>,
+  finalize: String,
+  certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AcmeChallenge {
+  r#type: String,
+  url: String,
+  token: String,
+}
+
+#[derive(Deserialize)]
+struct AcmeAuthorization {
+  status: String,
+  challenges: Vec<AcmeChallenge>,
+}
+
+/// Response of the ACME server to a signed request
+struct AcmeResponse {
+  location: Option<String>,
+  body: Bytes,
+}
+
+impl AcmeResponse {
+  fn json<T: DeserializeOwned>(&self) -> IoResult<T> {
+    Ok(serde_json::from_slice(&self.body)?)
+  }
+}
+
+fn get_header(res: &ClientResponse, name: &str) -> Option<String> {
+  res
+    .header(name)
+    .and_then(|value| value.to_str().ok())
+    .map(ToOwned::to_owned)
+}
+
+/// Client of an ACME server for an account key
+struct AcmeClient {
+  client: Client,
+  key: EcKey<Private>,
+  directory: AcmeDirectory,
+  nonce: Option<String>,
+  kid: Option<String>,
+}
+
+impl AcmeClient {
+  async fn new(config: &ProxySslAcme, key: EcKey<Private>) -> IoResult<Self> {
+    let mut ssl = SslConnector::builder(SslMethod::tls()).map_err(ssl_err)?;
+    ssl.set_alpn_protos(b"\x08http/1.1").map_err(ssl_err)?;
+    if config.skip_tls_verify.unwrap_or_default() {
+      ssl.set_verify(SslVerifyMode::NONE);
+    }
+    let connector = Connector::default()
+      .openssl(ssl.build())
+      .timeout(Millis(10_000))
+      .finish();
+    let client = Client::build()
+      .connector(connector)
+      .timeout(Millis(30_000))
+      .finish();
+    let mut res = client
+      .get(&config.directory_url)
+      .send()
+      .await
+      .map_err(|err| err.map_err_context(|| &config.directory_url))?;
+    let body = res
+      .body()
+      .limit(BODY_LIMIT)
+      .await
+      .map_err(|err| err.map_err_context(|| &config.directory_url))?;
+    if !res.status().is_success() {
+      return Err(IoError::interrupted(
+        "Acme",
+        format!("{}: {}", config.directory_url, res.status()).as_str(),
+      ));
+    }
+    Ok(Self {
+      client,
+      key,
+      directory: serde_json::from_slice(&body)?,
+      nonce: None,
+      kid: None,
+    })
+  }
+
+  async fn get_nonce(&mut self) -> IoResult<String> {
+    if let Some(nonce) = self.nonce.take() {
+      return Ok(nonce);
+    }
+    let url = &self.directory.new_nonce;
+    let res = self
+      .client
+      .head(url)
+      .send()
+      .await
+      .map_err(|err| err.map_err_context(|| url))?;
+    get_header(&res, "replay-nonce").ok_or_else(|| {
+      IoError::invalid_data("Acme", "no nonce returned by the server")
+    })
+  }
+
+  /// Send a signed request, without payload for a POST-as-GET
+  async fn post(
+    &mut self,
+    url: &str,
+    payload: Option<&serde_json::Value>,
+  ) -> IoResult<AcmeResponse> {
+    let mut retries = 0;
+    loop {
+      let nonce = self.get_nonce().await?;
+      let body = sign(&self.key, self.kid.as_deref(), &nonce, url, payload)?;
+      let mut res = self
+        .client
+        .post(url)
+        .content_type("application/jose+json")
+        .send_body(body)
+        .await
+        .map_err(|err| err.map_err_context(|| url))?;
+      self.nonce = get_header(&res, "replay-nonce");
+      let location = get_header(&res, "location");
+      let body = res
+        .body()
+        .limit(BODY_LIMIT)
+        .await
+        .map_err(|err| err.map_err_context(|| url))?;
+      if res.status().is_success() {
+        return Ok(AcmeResponse { location, body });
+      }
+      let problem =
+        serde_json::from_slice::<AcmeProblem>(&body).unwrap_or_default();
+      // The nonce can expire, the request is sent again with a new one
+      if problem.r#type == "urn:ietf:params:acme:error:badNonce"
+        && retries < NONCE_RETRIES
+      {
+        retries += 1;
+        continue;
+      }
+      return Err(IoError::interrupted(
+        "Acme",
+        format!("{url}: {} {}", res.status(), problem.detail).as_str(),
+      ));
+    }
+  }
+
+  /// Wait for an authorization or an order to be valid
+  async fn poll(&mut self, url: &str) -> IoResult<AcmeResponse> {
+    for _ in 0..POLL_ATTEMPTS {
+      let res = self.post(url, None).await?;
+      match res.json::<AcmeStatus>()?.status.as_str() {
+        "valid" => return Ok(res),
+        "invalid" => {
+          return Err(IoError::interrupted(
+            "Acme",
+            format!("{url} is invalid: {}", String::from_utf8_lossy(&res.body))
+              .as_str(),
+          ))
+        }
+        _ => ntex::time::sleep(POLL_INTERVAL).await,
+      }
+    }
+    Err(IoError::interrupted(
+      "Acme",
+      format!("{url} isn't valid after {POLL_ATTEMPTS} attempts").as_str(),
+    ))
+  }
+
+  /// Create the account of the key or retrieve it if it already exists
+  async fn register(&mut self, email: &str) -> IoResult<()> {
+    let url = self.directory.new_account.clone();
+    let payload = serde_json::json!({
+      "termsOfServiceAgreed": true,
+      "contact": [format!("mailto:{email}")],
+    });
+    let res = self.post(&url, Some(&payload)).await?;
+    self.kid = Some(res.location.ok_or_else(|| {
+      IoError::invalid_data("Acme", "no account url returned by the server")
+    })?);
+    Ok(())
+  }
+
+  /// Answer a http-01 challenge by storing his key authorization
+  /// for every instance to serve it until his authorization is valid
+  async fn validate(
+    &mut self,
+    authorization_url: &str,
+    state: &SystemStateRef,
+  ) -> IoResult<()> {
+    let authorization = self
+      .post(authorization_url, None)
+      .await?
+      .json::<AcmeAuthorization>()?;
+    if authorization.status == "valid" {
+      return Ok(());
+    }
+    let challenge = authorization
+      .challenges
+      .iter()
+      .find(|challenge| challenge.r#type == "http-01")
+      .ok_or_else(|| {
+        IoError::invalid_data("Acme", "no http-01 challenge proposed")
+      })?;
+    if !is_valid_token(&challenge.token) {
+      return Err(IoError::invalid_data("Acme", "invalid challenge token"));
+    }
+    let thumbprint = gen_thumbprint(&self.key).map_err(ssl_err)?;
+    let token = challenge.token.clone();
+    let secret = SecretPartial {
+      name: challenge_secret_name(&token),
+      kind: CHALLENGE_KIND.to_owned(),
+      immutable: false,
+      metadata: None,
+      data: serde_json::json!({
+        "Token": token,
+        "KeyAuthorization": format!("{token}.{thumbprint}"),
+      }),
+    };
+    state.client.create_secret(&secret).await?;
+    // The file is written by the event of the secret on the other instances
+    let res = match write_challenge(&secret.data, state).await {
+      Err(err) => Err(err),
+      Ok(_) => {
+        let challenge_url = challenge.url.clone();
+        match self
+          .post(&challenge_url, Some(&serde_json::json!({})))
+          .await
+        {
+          Err(err) => Err(err),
+          Ok(_) => self.poll(authorization_url).await.map(|_| ()),
+        }
+      }
+    };
+    if let Err(err) = state.client.delete_secret(&secret.name).await {
+      log::warn!("acme::validate: {} {err}", secret.name);
+    }
+    let _ = tokio::fs::remove_file(challenge_dir(state).join(&token)).await;
+    res
+  }
+
+  /// Order a certificate for a domain
+  async fn order(
+    &mut self,
+    domain: &str,
+    state: &SystemStateRef,
+  ) -> IoResult<ProxySslConfig> {
+    let url = self.directory.new_order.clone();
+    let payload = serde_json::json!({
+      "identifiers": [{ "type": "dns", "value": domain }],
+    });
+    let res = self.post(&url, Some(&payload)).await?;
+    let order_url = res.location.clone().ok_or_else(|| {
+      IoError::invalid_data("Acme", "no order url returned by the server")
+    })?;
+    let order = res.json::<AcmeOrder>()?;
+    for authorization_url in &order.authorizations {
+      self.validate(authorization_url, state).await?;
+    }
+    let (csr, key) = gen_csr(domain).map_err(ssl_err)?;
+    let payload = serde_json::json!({ "csr": b64url(&csr) });
+    self.post(&order.finalize, Some(&payload)).await?;
+    let order = self.poll(&order_url).await?.json::<AcmeOrder>()?;
+    let certificate_url = order.certificate.ok_or_else(|| {
+      IoError::invalid_data("Acme", "no certificate url returned by the server")
+    })?;
+    let certificate = self.post(&certificate_url, None).await?.body;
+    Ok(ProxySslConfig {
+      certificate: String::from_utf8_lossy(&certificate).into_owned(),
+      certificate_key: String::from_utf8_lossy(&key).into_owned(),
+      certificate_client: None,
+      verify_client: None,
+      dhparam: None,
+    })
+  }
+}
+
+/// Write the key authorization of a challenge stored in a secret
+/// for nginx to answer it
+pub async fn write_challenge(
+  data: &serde_json::Value,
+  state: &SystemStateRef,
+) -> IoResult<()> {
+  let (Some(token), Some(key_authorization)) = (
+    data.get("Token").and_then(|token| token.as_str()),
+    data.get("KeyAuthorization").and_then(|ka| ka.as_str()),
+  ) else {
+    return Err(IoError::invalid_data("Acme", "invalid challenge secret"));
+  };
+  if !is_valid_token(token) {
+    return Err(IoError::invalid_data("Acme", "invalid challenge token"));
+  }
+  let dir = challenge_dir(state);
+  tokio::fs::create_dir_all(&dir).await?;
+  tokio::fs::write(dir.join(token), key_authorization).await?;
+  Ok(())
+}
+
+/// Write the challenge of a secret created by the instance ordering it
+pub async fn sync_challenge(
+  name: &str,
+  state: &SystemStateRef,
+) -> IoResult<()> {
+  let query = SecretInspectQuery { reveal: Some(true) };
+  let secret = state.client.inspect_secret(name, Some(&query)).await?;
+  if secret.kind != CHALLENGE_KIND {
+    return Ok(());
+  }
+  write_challenge(&secret.data, state).await
+}
+
+/// Remove the challenge of a deleted secret
+pub async fn remove_challenge(name: &str, state: &SystemStateRef) {
+  let Some(token) = name.strip_prefix("acme-challenge-") else {
+    return;
+  };
+  if !is_valid_token(token) {
+    return;
+  }
+  let _ = tokio::fs::remove_file(challenge_dir(state).join(token)).await;
+}
+
+/// Write the challenges pending when the instance subscribe to the events
+pub async fn sync_challenges(state: &SystemStateRef) -> IoResult<()> {
+  let filter = GenericFilter::new()
+    .r#where("kind", GenericClause::Eq(CHALLENGE_KIND.to_owned()));
+  let secrets = state.client.list_secret(Some(&filter)).await?;
+  for secret in secrets {
+    if let Err(err) = sync_challenge(&secret.name, state).await {
+      log::warn!("acme::sync_challenges: {} {err}", secret.name);
+    }
+  }
+  Ok(())
+}
+
+/// Make the key of the ACME account written by a previous version
+/// readable only by his owner
+async fn restrict_account_file(path: &str) -> IoResult<()> {
+  use std::os::unix::fs::PermissionsExt;
+  let permissions = std::fs::Permissions::from_mode(0o600);
+  tokio::fs::set_permissions(path, permissions).await?;
+  Ok(())
+}
+
+/// Read the key of the ACME account shared by the instances or generate it.
+/// A key from a previous version stored in `account.pem` is kept.
+async fn read_account_key(state: &SystemStateRef) -> IoResult<EcKey<Private>> {
+  let query = SecretInspectQuery { reveal: Some(true) };
+  let read_secret = || async {
+    let secret = state
+      .client
+      .inspect_secret(ACCOUNT_SECRET, Some(&query))
+      .await?;
+    let pem = secret
+      .data
+      .get("Key")
+      .and_then(|key| key.as_str())
+      .ok_or_else(|| {
+        IoError::invalid_data("Acme", "invalid account key secret")
+      })?;
+    EcKey::private_key_from_pem(pem.as_bytes()).map_err(ssl_err)
+  };
+  match state.client.inspect_secret(ACCOUNT_SECRET, None).await {
+    Ok(_) => return read_secret().await,
+    Err(err) if has_status(&err, ntex::http::StatusCode::NOT_FOUND) => {}
+    Err(err) => return Err(err.into()),
+  }
+  let path = format!("{}/account.pem", challenge_root(state));
+  let key = match tokio::fs::read(&path).await {
+    Ok(pem) => {
+      restrict_account_file(&path).await?;
+      EcKey::private_key_from_pem(&pem).map_err(ssl_err)?
+    }
+    Err(_) => {
+      let group =
+        EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(ssl_err)?;
+      EcKey::generate(&group).map_err(ssl_err)?
+    }
+  };
+  let pem = key.private_key_to_pem().map_err(ssl_err)?;
+  let secret = SecretPartial {
+    name: ACCOUNT_SECRET.to_owned(),
+    kind: "nanocl.io/acme-account".to_owned(),
+    immutable: true,
+    metadata: None,
+    data: serde_json::json!({ "Key": String::from_utf8_lossy(&pem) }),
+  };
+  match state.client.create_secret(&secret).await {
+    Ok(_) => Ok(key),
+    // Created by another instance in the meantime
+    Err(err) if has_status(&err, ntex::http::StatusCode::CONFLICT) => {
+      read_secret().await
+    }
+    Err(err) => Err(err.into()),
+  }
+}
+
+/// Whether the certificate stored in a secret must be issued or renewed
+async fn needs_renewal(name: &str, state: &SystemStateRef) -> IoResult<bool> {
+  let query = SecretInspectQuery { reveal: Some(true) };
+  let secret = match state.client.inspect_secret(name, Some(&query)).await {
+    Ok(secret) => secret,
+    Err(err) if has_status(&err, ntex::http::StatusCode::NOT_FOUND) => {
+      return Ok(true);
+    }
+    Err(err) => return Err(err.into()),
+  };
+  let Ok(ssl) = serde_json::from_value::<ProxySslConfig>(secret.data) else {
+    return Ok(true);
+  };
+  Ok(expires_soon(&ssl.certificate))
+}
+
+/// Save a certificate in his secret
+async fn store_certificate(
+  name: &str,
+  ssl: &ProxySslConfig,
+  state: &SystemStateRef,
+) -> IoResult<()> {
+  let data = serde_json::to_value(ssl)?;
+  if state.client.inspect_secret(name, None).await.is_ok() {
+    let update = SecretUpdate {
+      metadata: None,
+      data,
+    };
+    state.client.patch_secret(name, &update).await?;
+    return Ok(());
+  }
+  let secret = SecretPartial {
+    name: name.to_owned(),
+    kind: "nanocl.io/tls".to_owned(),
+    immutable: false,
+    metadata: None,
+    data,
+  };
+  state.client.create_secret(&secret).await?;
+  Ok(())
+}
+
+/// Order the certificate of a domain if it's missing or expires soon
+/// and this instance hold the lease of the domain.
+/// Return true when a certificate has been stored.
+async fn renew_domain(
+  domain: &str,
+  acme: &ProxySslAcme,
+  state: &SystemStateRef,
+) -> IoResult<bool> {
+  let lease = lease_key(domain);
+  match state.client.acquire_node_lease(&lease, "acme-order").await {
+    Ok(_) => {}
+    Err(err) if has_status(&err, ntex::http::StatusCode::CONFLICT) => {
+      log::debug!("acme::renew_domain: {domain} is ordered by another node");
+      return Ok(false);
+    }
+    Err(err) => return Err(err.into()),
+  }
+  // The order is abandoned if the lease can't be kept
+  let keep_lease = async {
+    loop {
+      ntex::time::sleep(LEASE_RENEW_INTERVAL).await;
+      if let Err(err) =
+        state.client.acquire_node_lease(&lease, "acme-order").await
+      {
+        return err;
+      }
+    }
+  };
+  let res =
+    match select(pin!(order_domain(domain, acme, state)), pin!(keep_lease))
+      .await
+    {
+      Either::Left((res, _)) => res,
+      Either::Right((err, _)) => Err(err.into()),
+    };
+  if let Err(err) = state.client.release_node_lease(&lease).await {
+    log::warn!("acme::renew_domain: {lease} {err}");
+  }
+  res
+}
+
+async fn order_domain(
+  domain: &str,
+  acme: &ProxySslAcme,
+  state: &SystemStateRef,
+) -> IoResult<bool> {
+  let name = secret_name(domain);
+  if !needs_renewal(&name, state).await? {
+    return Ok(false);
+  }
+  log::info!("acme::order_domain: ordering a certificate for {domain}");
+  let key = read_account_key(state).await?;
+  let mut client = AcmeClient::new(acme, key).await?;
+  client.register(&acme.email).await?;
+  let ssl = client.order(domain, state).await?;
+  store_certificate(&name, &ssl, state).await?;
+  log::info!("acme::order_domain: certificate stored in secret {name}");
+  Ok(true)
+}
+
+/// Issue or renew the certificates of the http rules using ACME
+/// Return true when a certificate has been stored.
+pub async fn renew_rule(
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
+) -> IoResult<bool> {
+  // Orders of the instance are done one at a time, the lease of the domain
+  // ensure a single instance of the cluster order it
+  let _lock = state.acme_lock.lock().await;
+  let mut renewed = false;
+  for rule in &rule.rules {
+    let ProxyRule::Http(http_rule) = rule else {
+      continue;
+    };
+    let Some(ProxySsl::Acme(acme)) = &http_rule.ssl else {
+      continue;
+    };
+    let Some(domain) = &http_rule.domain else {
+      return Err(IoError::invalid_data(
+        "Acme",
+        "a domain is required to issue a certificate",
+      ));
+    };
+    if !needs_renewal(&secret_name(domain), state).await? {
+      continue;
+    }
+    renewed |= renew_domain(domain, acme, state).await?;
+  }
+  Ok(renewed)
+}
+
+/// Issue or renew the certificates of a rule and apply it again
+/// to use them when they have been updated
+pub async fn renew_and_apply(
+  name: &str,
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
+) -> IoResult<()> {
+  if renew_rule(rule, state).await? {
+    super::nginx::add_rule(name, rule, state).await?;
+    state.event_emitter.emit_reload().await;
+  }
+  Ok(())
+}
+
+/// Issue the missing certificates of a rule in background
+/// once nginx is reloaded to answer the challenges
+pub fn spawn_renew(
+  name: &str,
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
+) {
+  let uses_acme = rule.rules.iter().any(|rule| {
+    matches!(rule, ProxyRule::Http(http_rule)
+      if matches!(http_rule.ssl, Some(ProxySsl::Acme(_))))
+  });
+  if !uses_acme {
+    return;
+  }
+  let name = name.to_owned();
+  let rule = rule.clone();
+  let state = state.clone();
+  rt::spawn(async move {
+    if let Err(err) = state.event_emitter.reload().await {
+      log::warn!("acme::spawn_renew: {name} {err}");
+      return;
+    }
+    if let Err(err) = renew_and_apply(&name, &rule, &state).await {
+      log::warn!("acme::spawn_renew: {name} {err}");
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use openssl::{bn::BigNum, ecdsa::EcdsaSig};
+
+  use super::*;
+
+  fn account_key() -> EcKey<Private> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    EcKey::generate(&group).unwrap()
+  }
+
+  fn b64url_decode(data: &str) -> Vec<u8> {
+    let mut data = data.replace('-', "+").replace('_', "/");
+    while data.len() % 4 != 0 {
+      data.push('=');
+    }
+    openssl::base64::decode_block(&data).unwrap()
+  }
+
+  #[test]
+  fn jws() {
+    let key = account_key();
+    let payload = serde_json::json!({ "termsOfServiceAgreed": true });
+    let jws =
+      sign(&key, None, "nonce", "https://acme/new-acct", Some(&payload))
+        .unwrap();
+    let jws = serde_json::from_str::<serde_json::Value>(&jws).unwrap();
+    let protected = jws["protected"].as_str().unwrap();
+    let header =
+      serde_json::from_slice::<serde_json::Value>(&b64url_decode(protected))
+        .unwrap();
+    assert_eq!(header["alg"], "ES256");
+    assert_eq!(header["nonce"], "nonce");
+    assert_eq!(header["url"], "https://acme/new-acct");
+    assert_eq!(header["jwk"]["kty"], "EC");
+    assert!(header.get("kid").is_none());
+    let payload = jws["payload"].as_str().unwrap();
+    assert_eq!(
+      serde_json::from_slice::<serde_json::Value>(&b64url_decode(payload))
+        .unwrap()["termsOfServiceAgreed"],
+      true
+    );
+    // The signature is the raw r and s of an ECDSA P-256 signature
+    let signature = b64url_decode(jws["signature"].as_str().unwrap());
+    assert_eq!(signature.len(), 64);
+    let signature = EcdsaSig::from_private_components(
+      BigNum::from_slice(&signature[..32]).unwrap(),
+      BigNum::from_slice(&signature[32..]).unwrap(),
+    )
+    .unwrap();
+    let digest = sha256(format!("{protected}.{payload}").as_bytes());
+    assert!(signature.verify(&digest, &key).unwrap());
+    // POST-as-GET are signed with the account url and an empty payload
+    let jws = sign(
+      &key,
+      Some("https://acme/acct/1"),
+      "nonce",
+      "https://acme/x",
+      None,
+    )
+    .unwrap();
+    let jws = serde_json::from_str::<serde_json::Value>(&jws).unwrap();
+    assert_eq!(jws["payload"], "");
+    let header = serde_json::from_slice::<serde_json::Value>(&b64url_decode(
+      jws["protected"].as_str().unwrap(),
+    ))
+    .unwrap();
+    assert_eq!(header["kid"], "https://acme/acct/1");
+    assert!(header.get("jwk").is_none());
+  }
+
+  #[test]
+  fn thumbprint() {
+    let key = account_key();
+    let jwk = gen_jwk(&key).unwrap();
+    assert!(jwk.starts_with(r#"{"crv":"P-256","kty":"EC","x":""#));
+    let thumbprint = gen_thumbprint(&key).unwrap();
+    assert_eq!(thumbprint.len(), 43);
+    assert!(!thumbprint.contains(['+', '/', '=']));
+  }
+
+  #[test]
+  fn csr() {
+    let (csr, key) = gen_csr("example.com").unwrap();
+    let req = openssl::x509::X509Req::from_der(&csr).unwrap();
+    let key = PKey::private_key_from_pem(&key).unwrap();
+    assert!(req.verify(&key).unwrap());
+    let cn = req
+      .subject_name()
+      .entries_by_nid(Nid::COMMONNAME)
+      .next()
+      .unwrap()
+      .data()
+      .as_utf8()
+      .unwrap()
+      .to_string();
+    assert_eq!(cn, "example.com");
+  }
+
+  #[test]
+  fn expiry() {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    let gen_certificate = |days| {
+      let mut builder = X509::builder().unwrap();
+      builder.set_pubkey(&key).unwrap();
+      builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+      builder
+        .set_not_after(&Asn1Time::days_from_now(days).unwrap())
+        .unwrap();
+      builder.sign(&key, MessageDigest::sha256()).unwrap();
+      String::from_utf8(builder.build().to_pem().unwrap()).unwrap()
+    };
+    assert!(expires_soon(&gen_certificate(10)));
+    assert!(!expires_soon(&gen_certificate(90)));
+    assert!(expires_soon("not a certificate"));
+  }
+}
@@ -2,6 +2,7 @@ pub mod server;
 pub mod rule;
 pub mod nginx;
 pub mod resource;
+pub mod acme;
 
 #[cfg(test)]
 pub(crate) mod tests {
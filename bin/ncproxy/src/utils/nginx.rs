@@ -6,7 +6,7 @@ use futures::StreamExt;
 use nanocl_error::io::{IoError, IoResult};
 
 use nanocld_client::{
-  stubs::proxy::{ResourceProxyRule, ProxyRule, LocationTarget},
+  stubs::proxy::{ResourceProxyRule, ProxyRule, LocationTarget, ProxySsl},
   bollard_next::exec::{CreateExecOptions, StartExecOptions},
   NanocldClient,
 };
@@ -31,6 +31,8 @@ pub async fn ensure_conf(state: &SystemStateRef) -> IoResult<()> {
       "streams-enabled",
       "log",
       "secrets",
+      "acme",
+      "cache",
     ]
     .into_iter()
     .map(|name| {
@@ -90,19 +92,91 @@ async fn exec_nginx_cmd(cmd: &str, client: &NanocldClient) -> IoResult<()> {
 
 pub async fn reload(client: &NanocldClient) -> IoResult<()> {
   log::info!("nginx::reload: starting");
+  // The reload signal succeeds even when the configuration is refused
+  self::test(client).await?;
   exec_nginx_cmd("nginx -s reload", client).await?;
   log::info!("nginx::reload: done");
   Ok(())
 }
 
+/// Configuration files of a rule before it's written,
+/// they are restored when nginx refuse the new ones
+struct RuleBackup {
+  site: Option<String>,
+  stream: Option<String>,
+}
+
+impl RuleBackup {
+  async fn read(name: &str, state: &SystemStateRef) -> Self {
+    Self {
+      site: state.store.read_conf_file(name, &NginxRuleKind::Site).await,
+      stream: state
+        .store
+        .read_conf_file(name, &NginxRuleKind::Stream)
+        .await,
+    }
+  }
+
+  async fn restore(self, name: &str, state: &SystemStateRef) {
+    for (data, kind) in [
+      (self.site, NginxRuleKind::Site),
+      (self.stream, NginxRuleKind::Stream),
+    ] {
+      state.store.delete_conf_file(name, &kind).await;
+      let Some(data) = data else {
+        continue;
+      };
+      if let Err(err) = state.store.write_conf_file(name, &data, &kind).await {
+        log::warn!("nginx::restore: {name} {err}");
+      }
+    }
+  }
+}
+
+/// Write the configuration of a rule and test it,
+/// the previous configuration is restored if nginx refuse it
 pub async fn add_rule(
   name: &str,
   rule: &ResourceProxyRule,
   state: &SystemStateRef,
+) -> IoResult<()> {
+  let backup = RuleBackup::read(name, state).await;
+  write_rule(name, rule, state).await?;
+  if let Err(err) = self::test(&state.client).await {
+    backup.restore(name, state).await;
+    return Err(err);
+  }
+  Ok(())
+}
+
+/// Write the configuration of a rule and wait for nginx to be reloaded with it,
+/// the previous configuration is restored if nginx refuse it
+pub async fn apply_rule(
+  name: &str,
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
+) -> IoResult<()> {
+  let backup = RuleBackup::read(name, state).await;
+  if let Err(err) = write_rule(name, rule, state).await {
+    backup.restore(name, state).await;
+    return Err(err);
+  }
+  if let Err(err) = state.event_emitter.reload().await {
+    backup.restore(name, state).await;
+    return Err(err);
+  }
+  Ok(())
+}
+
+/// Write the configuration files of a rule
+async fn write_rule(
+  name: &str,
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
 ) -> IoResult<()> {
   let mut stream_conf = String::new();
   let mut http_conf = String::new();
-  for rule in &rule.rules {
+  for (rule_index, rule) in rule.rules.iter().enumerate() {
     match rule {
       ProxyRule::Stream(stream_rule) => {
         let listen = super::rule::get_network_addr(
@@ -124,13 +198,15 @@ pub async fn add_rule(
           Ok(upstream_key) => upstream_key,
         };
         let ssl = match &stream_rule.ssl {
-          Some(ssl) => match super::rule::gen_ssl_config(ssl, state).await {
-            Err(err) => {
-              log::warn!("Not ssl found for {name} {ssl:#?} {err}");
-              None
+          Some(ssl) => {
+            match super::rule::gen_ssl_config(ssl, None, state).await {
+              Err(err) => {
+                log::warn!("Not ssl found for {name} {ssl:#?} {err}");
+                None
+              }
+              Ok(ssl) => Some(ssl),
             }
-            Ok(ssl) => Some(ssl),
-          },
+          }
           None => None,
         };
         if stream_rule.ssl.is_some() && ssl.is_none() {
@@ -153,7 +229,13 @@ pub async fn add_rule(
           super::rule::get_network_addr(&http_rule.network, 443, &state.client)
             .await?;
         let ssl = match &http_rule.ssl {
-          Some(ssl) => match super::rule::gen_ssl_config(ssl, state).await {
+          Some(ssl) => match super::rule::gen_ssl_config(
+            ssl,
+            http_rule.domain.as_deref(),
+            state,
+          )
+          .await
+          {
             Err(err) => {
               log::warn!("Not ssl found for {name} {ssl:#?} {err}");
               None
@@ -162,7 +244,29 @@ pub async fn add_rule(
           },
           None => None,
         };
-        for location in &http_rule.locations {
+        for (location_index, location) in http_rule.locations.iter().enumerate()
+        {
+          let (basic_auth, auth_request) = match super::rule::gen_location_auth(
+            location,
+            location_index,
+            state,
+          )
+          .await
+          {
+            Err(err) => {
+              log::warn!("Not auth found for {name} {} {err}", location.path);
+              continue;
+            }
+            Ok(auth) => auth,
+          };
+          // Invalid policies are refused instead of ignored
+          let (cache, compression, cors) = super::rule::gen_location_policies(
+            location,
+            name,
+            rule_index,
+            location_index,
+            state,
+          )?;
           match &location.target {
             LocationTarget::Upstream(upstream) => {
               let upstream_key = match super::rule::gen_upstream(
@@ -180,7 +284,7 @@ pub async fn add_rule(
               };
               let ssl = match &upstream.ssl {
                 Some(ssl) => {
-                  match super::rule::gen_ssl_config(ssl, state).await {
+                  match super::rule::gen_ssl_config(ssl, None, state).await {
                     Err(err) => {
                       log::warn!("Not ssl found for {name} {ssl:#?} {err}");
                       None
@@ -196,10 +300,16 @@ pub async fn add_rule(
                 upstream_key: format!("http://{upstream_key}"),
                 redirect: None,
                 upstream_path: upstream.path.clone().unwrap_or("/".to_owned()),
+                rewrite: None,
                 version: location.version,
                 allowed_ips: location.allowed_ips.clone(),
                 headers: location.headers.clone(),
                 ssl,
+                basic_auth,
+                auth_request,
+                cache,
+                compression,
+                cors,
               };
               locations.push(location);
             }
@@ -216,10 +326,16 @@ pub async fn add_rule(
                 redirect: None,
                 limit_req: location.limit_req.clone(),
                 upstream_path: "/".to_owned(),
+                rewrite: None,
                 version: location.version,
                 allowed_ips: location.allowed_ips.clone(),
                 headers: location.headers.clone(),
                 ssl: None,
+                basic_auth,
+                auth_request,
+                cache,
+                compression,
+                cors,
               };
               locations.push(location);
             }
@@ -230,10 +346,53 @@ pub async fn add_rule(
                 limit_req: location.limit_req.clone(),
                 version: location.version,
                 upstream_path: "/".to_owned(),
+                rewrite: None,
                 allowed_ips: location.allowed_ips.clone(),
                 headers: location.headers.clone(),
                 redirect: http.redirect.clone().map(|r| format!("{r}")),
                 ssl: None,
+                basic_auth,
+                auth_request,
+                cache,
+                compression,
+                cors,
+              };
+              locations.push(location);
+            }
+            LocationTarget::Split(split) => {
+              let variable = super::rule::gen_split_variable(
+                name,
+                rule_index,
+                location_index,
+              );
+              let (split_conf, variable, upstream_path) =
+                match super::rule::gen_split(split, &variable, state).await {
+                  Err(err) => {
+                    log::warn!("{err} {:#?}", split);
+                    continue;
+                  }
+                  Ok(split) => split,
+                };
+              http_conf += &split_conf;
+              let location = LocationTemplate {
+                path: location.path.clone(),
+                upstream_key: format!("http://${variable}"),
+                redirect: None,
+                limit_req: location.limit_req.clone(),
+                upstream_path: String::new(),
+                rewrite: super::rule::gen_split_rewrite(
+                  &location.path,
+                  &upstream_path,
+                ),
+                version: location.version,
+                allowed_ips: location.allowed_ips.clone(),
+                headers: location.headers.clone(),
+                ssl: None,
+                basic_auth,
+                auth_request,
+                cache,
+                compression,
+                cors,
               };
               locations.push(location);
             }
@@ -248,6 +407,8 @@ pub async fn add_rule(
           "locations": locations,
           "ssl": ssl,
           "hide_upstream": http_rule.ssl.is_some() && ssl.is_none(),
+          "acme_root": matches!(http_rule.ssl, Some(ProxySsl::Acme(_)))
+            .then(|| super::acme::challenge_root(state)),
         }))?;
         http_conf += &data;
       }
@@ -265,10 +426,6 @@ pub async fn add_rule(
       .write_conf_file(name, &http_conf, &NginxRuleKind::Site)
       .await?;
   }
-  if let Err(err) = self::test(&state.client).await {
-    let _ = del_rule(name, state).await;
-    return Err(err);
-  }
   Ok(())
 }
 
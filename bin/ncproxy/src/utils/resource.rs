@@ -24,9 +24,26 @@ pub async fn list_by_secret(
         serde_json::json!({ "Rules": [ { "Ssl": name }  ] }),
       ),
     );
-  let resources = client.list_resource(Some(&filter)).await.map_err(|err| {
-    err.map_err_context(|| "Unable to list resources from nanocl daemon")
-  })?;
+  let ssl_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
+  let filter = GenericFilter::new()
+  .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()))
+  .r#where(
+    "data",
+    GenericClause::Contains(
+      serde_json::json!({ "Rules": [ { "Locations": [ { "BasicAuth": { "Secret": name } } ] }  ] }),
+    ),
+  );
+  let basic_auth_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
+  let resources = ssl_resources
+    .into_iter()
+    .chain(basic_auth_resources.into_iter())
+    .collect::<Vec<Resource>>();
   if resources.is_empty() {
     return Err(IoError::not_found(
       "Resource",
@@ -67,9 +84,35 @@ pub(crate) async fn list_by_cargo(
     client.list_resource(Some(&filter)).await.map_err(|err| {
       err.map_err_context(|| "Unable to list resources from nanocl daemon")
     })?;
+  let filter = GenericFilter::new()
+  .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()))
+  .r#where(
+    "data",
+    GenericClause::Contains(
+      serde_json::json!({ "Rules": [ { "Locations": [ { "Target": { "Split": [ { "Target": { "Key": target_key } } ] } } ] }  ] }),
+    ),
+  );
+  let split_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
+  let filter = GenericFilter::new()
+  .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()))
+  .r#where(
+    "data",
+    GenericClause::Contains(
+      serde_json::json!({ "Rules": [ { "Locations": [ { "AuthRequest": { "Target": { "Key": target_key } } } ] }  ] }),
+    ),
+  );
+  let auth_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
   let resources = http_resources
     .into_iter()
     .chain(stream_resources.into_iter())
+    .chain(split_resources.into_iter())
+    .chain(auth_resources.into_iter())
     .collect::<Vec<nanocld_client::stubs::resource::Resource>>();
   if resources.is_empty() {
     return Err(IoError::not_found(
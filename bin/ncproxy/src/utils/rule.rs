@@ -1,17 +1,25 @@
+use openssl::sha::sha256;
+
 use nanocl_error::io::{IoResult, IoError, FromIo};
 
 use nanocld_client::{
   NanocldClient,
   stubs::{
     process::Process,
+    secret::SecretInspectQuery,
     proxy::{
       UpstreamTarget, StreamTarget, UnixTarget, ProxySsl, ProxySslConfig,
+      UpstreamBalancing, UpstreamHashKey, SplitTarget, ProxyHttpLocation,
+      ProxyBasicAuth, ProxyAuthRequest, ProxyAuthRequestTarget, ProxyCache,
+      ProxyCacheValid, ProxyCacheBypass, ProxyCompression, ProxyCors,
     },
   },
 };
 
 use crate::models::{
   SystemStateRef, NginxRuleKind, UPSTREAM_TEMPLATE, UNIX_UPSTREAM_TEMPLATE,
+  SPLIT_TEMPLATE, BasicAuthTemplate, AuthRequestTemplate,
+  AuthRequestHeaderTemplate, CacheTemplate, CompressionTemplate, CorsTemplate,
 };
 
 /// Get public address of host
@@ -116,36 +124,174 @@ pub async fn get_network_addr(
 
 pub async fn gen_ssl_config(
   ssl: &ProxySsl,
+  domain: Option<&str>,
   state: &SystemStateRef,
 ) -> IoResult<ProxySslConfig> {
-  match ssl {
-    ProxySsl::Config(ssl_config) => Ok(ssl_config.clone()),
-    ProxySsl::Secret(secret) => {
-      let secret = state.client.inspect_secret(secret).await?;
-      let mut ssl_config =
-        serde_json::from_value::<ProxySslConfig>(secret.data).map_err(
-          |err| err.map_err_context(|| "Unable to deserialize ProxySslConfig"),
-        )?;
-      let secret_path = format!("{}/secrets/{}", state.store.dir, secret.name);
-      let cert_path = format!("{secret_path}.cert");
-      tokio::fs::write(&cert_path, ssl_config.certificate.clone()).await?;
-      let key_path = format!("{secret_path}.key");
-      tokio::fs::write(&key_path, ssl_config.certificate_key.clone()).await?;
-      if let Some(certificate_client) = ssl_config.certificate_client {
-        let certificate_client_path = format!("{secret_path}.ca");
-        tokio::fs::write(&certificate_client_path, certificate_client).await?;
-        ssl_config.certificate_client = Some(certificate_client_path);
-      }
-      if let Some(dh_param) = ssl_config.dhparam {
-        let dh_param_path = format!("{secret_path}.pem");
-        tokio::fs::write(&dh_param_path, dh_param).await?;
-        ssl_config.dhparam = Some(dh_param_path);
-      }
-      ssl_config.certificate = cert_path;
-      ssl_config.certificate_key = key_path;
-      Ok(ssl_config)
+  let secret = match ssl {
+    ProxySsl::Config(ssl_config) => return Ok(ssl_config.clone()),
+    ProxySsl::Secret(secret) => secret.clone(),
+    // Certificates issued with ACME are stored in a secret named by domain
+    ProxySsl::Acme(_) => {
+      let domain = domain.ok_or(IoError::invalid_data(
+        "ProxySsl",
+        "acme is only available for http rules with a domain",
+      ))?;
+      super::acme::secret_name(domain)
+    }
+  };
+  let query = SecretInspectQuery { reveal: Some(true) };
+  let secret = state.client.inspect_secret(&secret, Some(&query)).await?;
+  let mut ssl_config = serde_json::from_value::<ProxySslConfig>(secret.data)
+    .map_err(|err| {
+      err.map_err_context(|| "Unable to deserialize ProxySslConfig")
+    })?;
+  let secret_path = format!("{}/secrets/{}", state.store.dir, secret.name);
+  let cert_path = format!("{secret_path}.cert");
+  tokio::fs::write(&cert_path, ssl_config.certificate.clone()).await?;
+  let key_path = format!("{secret_path}.key");
+  tokio::fs::write(&key_path, ssl_config.certificate_key.clone()).await?;
+  if let Some(certificate_client) = ssl_config.certificate_client {
+    let certificate_client_path = format!("{secret_path}.ca");
+    tokio::fs::write(&certificate_client_path, certificate_client).await?;
+    ssl_config.certificate_client = Some(certificate_client_path);
+  }
+  if let Some(dh_param) = ssl_config.dhparam {
+    let dh_param_path = format!("{secret_path}.pem");
+    tokio::fs::write(&dh_param_path, dh_param).await?;
+    ssl_config.dhparam = Some(dh_param_path);
+  }
+  ssl_config.certificate = cert_path;
+  ssl_config.certificate_key = key_path;
+  Ok(ssl_config)
+}
+
+/// Whether a header or a cookie name can be read from a nginx variable
+fn is_valid_name(name: &str, allow_dash: bool) -> bool {
+  !name.is_empty()
+    && name.chars().all(|c| {
+      c.is_ascii_alphanumeric() || c == '_' || (allow_dash && c == '-')
+    })
+}
+
+/// Nginx variable holding the value of a header, a cookie
+/// or a query argument of a request
+fn gen_request_variable(
+  prefix: &str,
+  name: &str,
+  context: &str,
+) -> IoResult<String> {
+  // Cookies and arguments with a dash can't be read from a nginx variable
+  if !is_valid_name(name, prefix == "http") {
+    return Err(IoError::invalid_data(
+      context,
+      &format!("invalid name {name}"),
+    ));
+  }
+  let name = name.to_lowercase().replace('-', "_");
+  Ok(format!("${prefix}_{name}"))
+}
+
+/// Nginx variable holding the value of a header or a cookie of a request
+fn gen_hash_variable(key: &UpstreamHashKey) -> IoResult<String> {
+  match key {
+    UpstreamHashKey::Header(name) => {
+      gen_request_variable("http", name, "UpstreamHashKey")
+    }
+    UpstreamHashKey::Cookie(name) => {
+      gen_request_variable("cookie", name, "UpstreamHashKey")
+    }
+  }
+}
+
+/// Directive of the load balancing strategy of an upstream,
+/// none for the default round robin
+fn gen_balancing(
+  balancing: &UpstreamBalancing,
+  kind: &NginxRuleKind,
+) -> IoResult<Option<String>> {
+  let is_stream = matches!(kind, NginxRuleKind::Stream);
+  let directive = match balancing {
+    UpstreamBalancing::RoundRobin => return Ok(None),
+    UpstreamBalancing::LeastConn => "least_conn".to_owned(),
+    // ip_hash isn't available in the stream module
+    UpstreamBalancing::IpHash if is_stream => {
+      "hash $remote_addr consistent".to_owned()
+    }
+    UpstreamBalancing::IpHash => "ip_hash".to_owned(),
+    UpstreamBalancing::Hash(_) if is_stream => {
+      return Err(IoError::invalid_data(
+        "UpstreamBalancing",
+        "hash on a header or a cookie is only available for http",
+      ))
     }
+    UpstreamBalancing::Hash(key) => {
+      format!("hash {} consistent", gen_hash_variable(key)?)
+    }
+  };
+  Ok(Some(directive))
+}
+
+/// Parameters of the server directives of an upstream
+fn gen_server_params(target: &UpstreamTarget) -> IoResult<String> {
+  let mut params = String::new();
+  if let Some(weight) = target.weight {
+    if weight == 0 {
+      return Err(IoError::invalid_data(
+        "UpstreamTarget",
+        "weight must be greater than 0",
+      ));
+    }
+    params += &format!(" weight={weight}");
+  }
+  if let Some(max_fails) = target.max_fails {
+    params += &format!(" max_fails={max_fails}");
   }
+  if let Some(fail_timeout) = target.fail_timeout {
+    params += &format!(" fail_timeout={fail_timeout}s");
+  }
+  Ok(params)
+}
+
+/// Key of the upstream of a target, suffixed by a hash of the balancing
+/// options so targets of the same instances with different options
+/// don't share the same upstream
+fn gen_upstream_key(base: &str, target: &UpstreamTarget) -> String {
+  if target.balancing.is_none()
+    && target.weight.is_none()
+    && target.max_fails.is_none()
+    && target.fail_timeout.is_none()
+  {
+    return base.to_owned();
+  }
+  let options = serde_json::json!([
+    target.balancing,
+    target.weight,
+    target.max_fails,
+    target.fail_timeout,
+  ]);
+  let hash = sha256(options.to_string().as_bytes());
+  let hash = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]);
+  format!("{base}-{hash:08x}")
+}
+
+/// Generate the upstream block of a target for the given instance addresses
+pub fn gen_upstream_conf(
+  key: &str,
+  addresses: &[String],
+  target: &UpstreamTarget,
+  kind: &NginxRuleKind,
+) -> IoResult<String> {
+  let balancing = match &target.balancing {
+    Some(balancing) => gen_balancing(balancing, kind)?,
+    None => None,
+  };
+  UPSTREAM_TEMPLATE.compile(&liquid::object!({
+    "key": key,
+    "port": target.port,
+    "addresses": addresses,
+    "balancing": balancing,
+    "params": gen_server_params(target)?,
+  }))
 }
 
 pub async fn gen_upstream(
@@ -169,12 +315,11 @@ pub async fn gen_upstream(
         })?;
       let addresses =
         get_addresses(&cargo.instances, &target_namespace).await?;
-      let key = format!("{}-{}-cargo", cargo.spec.cargo_key, port);
-      let data = UPSTREAM_TEMPLATE.compile(&liquid::object!({
-        "key": key,
-        "port": port,
-        "addresses": addresses,
-      }))?;
+      let key = gen_upstream_key(
+        &format!("{}-{}-cargo", cargo.spec.cargo_key, port),
+        target,
+      );
+      let data = gen_upstream_conf(&key, &addresses, target, kind)?;
       (key, data)
     }
     "v" => {
@@ -186,12 +331,9 @@ pub async fn gen_upstream(
           err.map_err_context(|| format!("Unable to inspect vm {target_name}"))
         })?;
       let addresses = get_addresses(&vm.instances, &target_namespace).await?;
-      let key = format!("{}-{}-vm", vm.spec.vm_key, port);
-      let data = UPSTREAM_TEMPLATE.compile(&liquid::object!({
-        "key": key,
-        "port": port,
-        "addresses": addresses,
-      }))?;
+      let key =
+        gen_upstream_key(&format!("{}-{}-vm", vm.spec.vm_key, port), target);
+      let data = gen_upstream_conf(&key, &addresses, target, kind)?;
       (key, data)
     }
     _ => {
@@ -205,6 +347,497 @@ pub async fn gen_upstream(
   Ok(key)
 }
 
+/// Name of a nginx variable or zone unique for each location of a rule
+fn gen_location_name(
+  prefix: &str,
+  name: &str,
+  rule: usize,
+  location: usize,
+) -> String {
+  let name = name
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+    .collect::<String>();
+  format!("{prefix}_{name}_{rule}_{location}")
+}
+
+/// Name of the nginx variable choosing the upstream of a split location
+pub fn gen_split_variable(name: &str, rule: usize, location: usize) -> String {
+  gen_location_name("split", name, rule, location)
+}
+
+/// Rewrite of the request uri done by `proxy_pass` with an uri,
+/// `proxy_pass` with a variable forward the request uri unchanged
+pub fn gen_split_rewrite(path: &str, upstream_path: &str) -> Option<String> {
+  // Only prefix locations have their path replaced
+  if !path.starts_with('/') || path == upstream_path {
+    return None;
+  }
+  let path = path
+    .chars()
+    .map(|c| match c {
+      '.' | '+' | '*' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|' | '^'
+      | '$' | '\\' => format!("\\{c}"),
+      c => c.to_string(),
+    })
+    .collect::<String>();
+  Some(format!("^{path}(.*)$ {upstream_path}$1"))
+}
+
+/// Generate the `split_clients` and `map` blocks choosing the upstream
+/// of a split, `upstreams` are the upstream keys of the targets of the split.
+/// Return the blocks with the variable holding the upstream to use.
+pub fn gen_split_conf(
+  variable: &str,
+  split: &SplitTarget,
+  upstreams: &[String],
+) -> IoResult<(String, String)> {
+  let total = split
+    .split
+    .iter()
+    .map(|target| u64::from(target.weight))
+    .sum::<u64>();
+  if split.split.is_empty() || split.split.len() != upstreams.len() {
+    return Err(IoError::invalid_data("SplitTarget", "no target to split"));
+  }
+  let last = split.split.len() - 1;
+  let mut targets = vec![];
+  for (index, (target, upstream)) in
+    split.split.iter().zip(upstreams).enumerate()
+  {
+    // Percentages are rounded down so their sum is never above 100
+    let hundredths = u64::from(target.weight) * 10_000 / total;
+    if hundredths == 0 {
+      return Err(IoError::invalid_data(
+        "SplitWeight",
+        &format!("weight of {} is too small", target.target.key),
+      ));
+    }
+    let percent = if index == last {
+      "*".to_owned()
+    } else {
+      format!("{}.{:02}%", hundredths / 100, hundredths % 100)
+    };
+    targets.push(liquid::object!({
+      "percent": percent,
+      "upstream": upstream,
+    }));
+  }
+  let mut current = format!("{variable}_split");
+  let split_variable = current.clone();
+  let mut pins = vec![];
+  let split_pins = split.pins.as_deref().unwrap_or_default();
+  for (index, pin) in split_pins.iter().enumerate().rev() {
+    let is_valid = !pin.value.is_empty()
+      && pin
+        .value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if !is_valid {
+      return Err(IoError::invalid_data(
+        "SplitPin",
+        &format!("invalid value {}", pin.value),
+      ));
+    }
+    let upstream = split
+      .split
+      .iter()
+      .position(|target| target.target.key == pin.target)
+      .map(|index| &upstreams[index])
+      .ok_or_else(|| {
+        IoError::invalid_data(
+          "SplitPin",
+          &format!("{} isn't a target of the split", pin.target),
+        )
+      })?;
+    let pin_variable = format!("{variable}_pin{index}");
+    pins.push(liquid::object!({
+      "source": gen_hash_variable(&pin.on)?,
+      "variable": pin_variable,
+      "value": pin.value,
+      "upstream": upstream,
+      "default": current,
+    }));
+    current = pin_variable;
+  }
+  let data = SPLIT_TEMPLATE.compile(&liquid::object!({
+    "variable": split_variable,
+    "targets": targets,
+    "pins": pins,
+  }))?;
+  Ok((data, current))
+}
+
+/// Generate the upstreams of the targets of a split with the blocks choosing
+/// between them. Return the blocks, the variable holding the upstream to use
+/// and the path of the targets.
+pub async fn gen_split(
+  split: &SplitTarget,
+  variable: &str,
+  state: &SystemStateRef,
+) -> IoResult<(String, String, String)> {
+  let path = split
+    .split
+    .first()
+    .and_then(|target| target.target.path.clone())
+    .unwrap_or("/".to_owned());
+  let mut upstreams = vec![];
+  for target in &split.split {
+    if target.target.ssl.is_some() {
+      return Err(IoError::invalid_data(
+        "SplitTarget",
+        "ssl isn't supported on the targets of a split",
+      ));
+    }
+    if target.target.path.as_deref().unwrap_or("/") != path {
+      return Err(IoError::invalid_data(
+        "SplitTarget",
+        "the targets of a split must have the same path",
+      ));
+    }
+    upstreams
+      .push(gen_upstream(&target.target, &NginxRuleKind::Site, state).await?);
+  }
+  let (data, variable) = gen_split_conf(variable, split, &upstreams)?;
+  Ok((data, variable, path))
+}
+
+/// Content of the file read by `auth_basic`, one `user:hash` entry by line
+fn gen_htpasswd(entries: &[String]) -> IoResult<String> {
+  let mut content = String::new();
+  for entry in entries {
+    let is_valid = !entry.contains(['\n', '\r'])
+      && entry
+        .split_once(':')
+        .is_some_and(|(user, hash)| !user.is_empty() && !hash.is_empty());
+    if !is_valid {
+      // The entry isn't logged to not leak the hash
+      return Err(IoError::invalid_data(
+        "ProxyBasicAuth",
+        "htpasswd entries must be in the user:hash format",
+      ));
+    }
+    content += entry;
+    content.push('\n');
+  }
+  Ok(content)
+}
+
+/// Write the htpasswd entries of the secret of a `basic_auth`
+pub async fn gen_basic_auth(
+  basic_auth: &ProxyBasicAuth,
+  state: &SystemStateRef,
+) -> IoResult<BasicAuthTemplate> {
+  let realm = basic_auth.realm.clone().unwrap_or("Restricted".to_owned());
+  // The realm is rendered in a quoted string where $ starts a variable
+  if realm.contains(['"', '\\', '$']) || realm.chars().any(char::is_control) {
+    return Err(IoError::invalid_data(
+      "ProxyBasicAuth",
+      &format!("invalid realm {realm}"),
+    ));
+  }
+  let query = SecretInspectQuery { reveal: Some(true) };
+  let secret = state
+    .client
+    .inspect_secret(&basic_auth.secret, Some(&query))
+    .await?;
+  if secret.kind != "nanocl.io/htpasswd" {
+    return Err(IoError::invalid_data(
+      "ProxyBasicAuth",
+      &format!("secret {} isn't a nanocl.io/htpasswd secret", secret.name),
+    ));
+  }
+  let entries =
+    serde_json::from_value::<Vec<String>>(secret.data).map_err(|err| {
+      err.map_err_context(|| "Unable to deserialize htpasswd entries")
+    })?;
+  let user_file =
+    format!("{}/secrets/{}.htpasswd", state.store.dir, secret.name);
+  tokio::fs::write(&user_file, gen_htpasswd(&entries)?).await?;
+  Ok(BasicAuthTemplate { realm, user_file })
+}
+
+/// Header of the response of an `auth_request` forwarded to the upstream
+fn gen_auth_request_header(name: &str) -> IoResult<AuthRequestHeaderTemplate> {
+  if !is_valid_name(name, true) {
+    return Err(IoError::invalid_data(
+      "ProxyAuthRequest",
+      &format!("invalid header {name}"),
+    ));
+  }
+  Ok(AuthRequestHeaderTemplate {
+    name: name.to_owned(),
+    variable: name.to_lowercase().replace('-', "_"),
+  })
+}
+
+/// Internal location receiving the subrequests of an `auth_request`,
+/// the path is unique for each location of a server
+pub async fn gen_auth_request(
+  auth_request: &ProxyAuthRequest,
+  location: usize,
+  state: &SystemStateRef,
+) -> IoResult<AuthRequestTemplate> {
+  let upstream = match &auth_request.target {
+    ProxyAuthRequestTarget::Upstream(target) => {
+      let key = gen_upstream(target, &NginxRuleKind::Site, state).await?;
+      format!("http://{key}{}", target.path.as_deref().unwrap_or("/"))
+    }
+    ProxyAuthRequestTarget::Url(url) => {
+      let is_valid = (url.starts_with("http://")
+        || url.starts_with("https://"))
+        && !url.contains(|c: char| c.is_whitespace() || c == ';');
+      if !is_valid {
+        return Err(IoError::invalid_data(
+          "ProxyAuthRequest",
+          &format!("invalid url {url}"),
+        ));
+      }
+      url.clone()
+    }
+  };
+  let headers = auth_request
+    .headers
+    .as_deref()
+    .unwrap_or_default()
+    .iter()
+    .map(|name| gen_auth_request_header(name))
+    .collect::<IoResult<Vec<_>>>()?;
+  Ok(AuthRequestTemplate {
+    path: format!("/.nanocl/auth/{location}"),
+    upstream,
+    headers,
+  })
+}
+
+/// Authentication of a location, the location must not be rendered
+/// when it fails to not expose it without authentication
+pub async fn gen_location_auth(
+  location: &ProxyHttpLocation,
+  index: usize,
+  state: &SystemStateRef,
+) -> IoResult<(Option<BasicAuthTemplate>, Option<AuthRequestTemplate>)> {
+  let basic_auth = match &location.basic_auth {
+    Some(basic_auth) => Some(gen_basic_auth(basic_auth, state).await?),
+    None => None,
+  };
+  let auth_request = match &location.auth_request {
+    Some(auth_request) => {
+      Some(gen_auth_request(auth_request, index, state).await?)
+    }
+    None => None,
+  };
+  Ok((basic_auth, auth_request))
+}
+
+/// Arguments of a `proxy_cache_valid` directive
+fn gen_cache_valid(valid: &ProxyCacheValid) -> IoResult<String> {
+  let statuses = valid.status.as_deref().unwrap_or_default();
+  if let Some(status) = statuses.iter().find(|s| !(100..600).contains(*s)) {
+    return Err(IoError::invalid_data(
+      "ProxyCacheValid",
+      &format!("invalid status {status}"),
+    ));
+  }
+  let statuses = match statuses {
+    [] => "any".to_owned(),
+    _ => statuses
+      .iter()
+      .map(ToString::to_string)
+      .collect::<Vec<_>>()
+      .join(" "),
+  };
+  Ok(format!("{statuses} {}s", valid.ttl))
+}
+
+/// Cache of a location stored in a directory of the given root
+pub fn gen_cache(
+  cache: &ProxyCache,
+  zone: &str,
+  root: &str,
+) -> IoResult<CacheTemplate> {
+  if cache.zone_size == 0 {
+    return Err(IoError::invalid_data(
+      "ProxyCache",
+      "zone size must be greater than 0",
+    ));
+  }
+  let valid = cache
+    .valid
+    .iter()
+    .map(gen_cache_valid)
+    .collect::<IoResult<Vec<_>>>()?;
+  let bypass = cache
+    .bypass
+    .as_deref()
+    .unwrap_or_default()
+    .iter()
+    .map(|bypass| match bypass {
+      ProxyCacheBypass::Header(name) => {
+        gen_request_variable("http", name, "ProxyCacheBypass")
+      }
+      ProxyCacheBypass::Cookie(name) => {
+        gen_request_variable("cookie", name, "ProxyCacheBypass")
+      }
+      ProxyCacheBypass::Arg(name) => {
+        gen_request_variable("arg", name, "ProxyCacheBypass")
+      }
+    })
+    .collect::<IoResult<Vec<_>>>()?;
+  Ok(CacheTemplate {
+    zone: zone.to_owned(),
+    path: format!("{root}/{zone}"),
+    zone_size: cache.zone_size,
+    max_size: cache.max_size,
+    valid,
+    bypass: (!bypass.is_empty()).then(|| bypass.join(" ")),
+  })
+}
+
+/// Whether a mime type can be rendered in a `gzip_types` directive
+fn is_valid_mime(mime: &str) -> bool {
+  let is_valid_part = |part: &str| {
+    !part.is_empty()
+      && part
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-+._*".contains(c))
+  };
+  mime == "*"
+    || mime
+      .split_once('/')
+      .is_some_and(|(kind, sub)| is_valid_part(kind) && is_valid_part(sub))
+}
+
+/// Compression of the responses of a location,
+/// the settings not given are inherited from the main configuration
+pub fn gen_compression(
+  compression: &ProxyCompression,
+) -> IoResult<CompressionTemplate> {
+  let types = compression.types.as_deref().unwrap_or_default();
+  if let Some(mime) = types.iter().find(|mime| !is_valid_mime(mime)) {
+    return Err(IoError::invalid_data(
+      "ProxyCompression",
+      &format!("invalid mime type {mime}"),
+    ));
+  }
+  let switch = |enabled: bool| if enabled { "on" } else { "off" }.to_owned();
+  Ok(CompressionTemplate {
+    gzip: compression.gzip.map(switch),
+    brotli: compression.brotli.map(switch),
+    types: (!types.is_empty()).then(|| types.join(" ")),
+  })
+}
+
+/// Whether an origin can be rendered in the map of the allowed origins
+fn is_valid_origin(origin: &str) -> bool {
+  let host = origin
+    .strip_prefix("https://")
+    .or_else(|| origin.strip_prefix("http://"))
+    .unwrap_or_default();
+  !host.is_empty()
+    && host
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "-.:[]".contains(c))
+}
+
+/// Cross-origin policy of a location, the allowed origins are matched
+/// in a map setting the given variable
+pub fn gen_cors(cors: &ProxyCors, variable: &str) -> IoResult<CorsTemplate> {
+  let any_origin = cors.origins.iter().any(|origin| origin == "*");
+  let credentials = cors.credentials.unwrap_or_default();
+  if cors.origins.is_empty() {
+    return Err(IoError::invalid_data(
+      "ProxyCors",
+      "at least one origin must be allowed",
+    ));
+  }
+  if any_origin && credentials {
+    return Err(IoError::invalid_data(
+      "ProxyCors",
+      "credentials can't be allowed for any origin",
+    ));
+  }
+  if let Some(origin) = cors
+    .origins
+    .iter()
+    .find(|origin| *origin != "*" && !is_valid_origin(origin))
+  {
+    return Err(IoError::invalid_data(
+      "ProxyCors",
+      &format!("invalid origin {origin}"),
+    ));
+  }
+  let methods = match &cors.methods {
+    Some(methods) => methods.clone(),
+    None => vec!["GET".to_owned(), "HEAD".to_owned(), "POST".to_owned()],
+  };
+  let is_valid_method = |method: &String| {
+    !method.is_empty() && method.chars().all(|c| c.is_ascii_uppercase())
+  };
+  if let Some(method) = methods.iter().find(|method| !is_valid_method(method)) {
+    return Err(IoError::invalid_data(
+      "ProxyCors",
+      &format!("invalid method {method}"),
+    ));
+  }
+  let headers = cors.headers.as_deref().unwrap_or_default();
+  if let Some(header) =
+    headers.iter().find(|header| !is_valid_name(header, true))
+  {
+    return Err(IoError::invalid_data(
+      "ProxyCors",
+      &format!("invalid header {header}"),
+    ));
+  }
+  Ok(CorsTemplate {
+    variable: variable.to_owned(),
+    any_origin,
+    // Every origin is allowed by the default value of the map
+    origins: if any_origin {
+      vec![]
+    } else {
+      cors.origins.clone()
+    },
+    methods: methods.join(", "),
+    headers: (!headers.is_empty()).then(|| headers.join(", ")),
+    credentials,
+  })
+}
+
+/// Cache, compression and cross-origin policy of a location
+pub fn gen_location_policies(
+  location: &ProxyHttpLocation,
+  name: &str,
+  rule: usize,
+  index: usize,
+  state: &SystemStateRef,
+) -> IoResult<(
+  Option<CacheTemplate>,
+  Option<CompressionTemplate>,
+  Option<CorsTemplate>,
+)> {
+  let cache = match &location.cache {
+    Some(cache) => {
+      let zone = gen_location_name("cache", name, rule, index);
+      let root = format!("{}/cache", state.store.dir);
+      Some(gen_cache(cache, &zone, &root)?)
+    }
+    None => None,
+  };
+  let compression = match &location.compression {
+    Some(compression) => Some(gen_compression(compression)?),
+    None => None,
+  };
+  let cors = match &location.cors {
+    Some(cors) => {
+      let variable = gen_location_name("cors", name, rule, index);
+      Some(gen_cors(cors, &variable)?)
+    }
+    None => None,
+  };
+  Ok((cache, compression, cors))
+}
+
 pub async fn gen_unix_target_key(
   unix: &UnixTarget,
   kind: &NginxRuleKind,
@@ -238,3 +871,362 @@ pub async fn gen_stream_upstream_key(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use nanocld_client::stubs::proxy::{SplitPin, SplitWeight};
+
+  use super::*;
+
+  fn target() -> UpstreamTarget {
+    UpstreamTarget {
+      key: "app.global.c".to_owned(),
+      port: 80,
+      path: None,
+      disable_logging: None,
+      ssl: None,
+      balancing: None,
+      weight: None,
+      max_fails: None,
+      fail_timeout: None,
+    }
+  }
+
+  /// Non empty lines of a generated configuration
+  fn lines(conf: &str) -> Vec<&str> {
+    conf
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .collect()
+  }
+
+  #[test]
+  fn upstream_conf() {
+    let addresses = vec!["10.0.0.2".to_owned(), "10.0.0.3".to_owned()];
+    let conf =
+      gen_upstream_conf("app", &addresses, &target(), &NginxRuleKind::Site)
+        .unwrap();
+    assert_eq!(
+      lines(&conf),
+      vec![
+        "upstream app {",
+        "server 10.0.0.2:80;",
+        "server 10.0.0.3:80;",
+        "}"
+      ]
+    );
+    let target = UpstreamTarget {
+      balancing: Some(UpstreamBalancing::Hash(UpstreamHashKey::Header(
+        "X-User-Id".to_owned(),
+      ))),
+      weight: Some(3),
+      max_fails: Some(2),
+      fail_timeout: Some(10),
+      ..target()
+    };
+    let conf =
+      gen_upstream_conf("app", &addresses, &target, &NginxRuleKind::Site)
+        .unwrap();
+    assert_eq!(
+      lines(&conf),
+      vec![
+        "upstream app {",
+        "hash $http_x_user_id consistent;",
+        "server 10.0.0.2:80 weight=3 max_fails=2 fail_timeout=10s;",
+        "server 10.0.0.3:80 weight=3 max_fails=2 fail_timeout=10s;",
+        "}"
+      ]
+    );
+    assert!(gen_upstream_conf(
+      "app",
+      &addresses,
+      &target,
+      &NginxRuleKind::Stream
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn balancing() {
+    let site = NginxRuleKind::Site;
+    let stream = NginxRuleKind::Stream;
+    let cookie =
+      UpstreamBalancing::Hash(UpstreamHashKey::Cookie("session".to_owned()));
+    assert_eq!(
+      gen_balancing(&UpstreamBalancing::RoundRobin, &site).unwrap(),
+      None
+    );
+    assert_eq!(
+      gen_balancing(&UpstreamBalancing::LeastConn, &stream).unwrap(),
+      Some("least_conn".to_owned())
+    );
+    assert_eq!(
+      gen_balancing(&UpstreamBalancing::IpHash, &site).unwrap(),
+      Some("ip_hash".to_owned())
+    );
+    assert_eq!(
+      gen_balancing(&UpstreamBalancing::IpHash, &stream).unwrap(),
+      Some("hash $remote_addr consistent".to_owned())
+    );
+    assert_eq!(
+      gen_balancing(&cookie, &site).unwrap(),
+      Some("hash $cookie_session consistent".to_owned())
+    );
+    let invalid = UpstreamBalancing::Hash(UpstreamHashKey::Cookie(
+      "session; deny all".to_owned(),
+    ));
+    assert!(gen_balancing(&invalid, &site).is_err());
+    assert!(gen_server_params(&UpstreamTarget {
+      weight: Some(0),
+      ..target()
+    })
+    .is_err());
+  }
+
+  #[test]
+  fn weights() {
+    let addresses = vec!["10.0.0.2".to_owned()];
+    let heavy = UpstreamTarget {
+      weight: Some(3),
+      ..target()
+    };
+    let light = UpstreamTarget {
+      key: "app-v2.global.c".to_owned(),
+      weight: Some(1),
+      ..target()
+    };
+    let heavy_key = gen_upstream_key("app-80-cargo", &heavy);
+    let light_key = gen_upstream_key("app-v2-80-cargo", &light);
+    let heavy_conf =
+      gen_upstream_conf(&heavy_key, &addresses, &heavy, &NginxRuleKind::Site)
+        .unwrap();
+    let light_conf =
+      gen_upstream_conf(&light_key, &addresses, &light, &NginxRuleKind::Site)
+        .unwrap();
+    assert_eq!(
+      lines(&heavy_conf),
+      vec![
+        format!("upstream {heavy_key} {{").as_str(),
+        "server 10.0.0.2:80 weight=3;",
+        "}"
+      ]
+    );
+    assert_eq!(
+      lines(&light_conf),
+      vec![
+        format!("upstream {light_key} {{").as_str(),
+        "server 10.0.0.2:80 weight=1;",
+        "}"
+      ]
+    );
+  }
+
+  #[test]
+  fn upstream_key() {
+    assert_eq!(gen_upstream_key("app-80-cargo", &target()), "app-80-cargo");
+    let least_conn = UpstreamTarget {
+      balancing: Some(UpstreamBalancing::LeastConn),
+      ..target()
+    };
+    let key = gen_upstream_key("app-80-cargo", &least_conn);
+    assert!(key.starts_with("app-80-cargo-"));
+    assert_eq!(key, gen_upstream_key("app-80-cargo", &least_conn));
+    assert_ne!(
+      key,
+      gen_upstream_key(
+        "app-80-cargo",
+        &UpstreamTarget {
+          weight: Some(2),
+          ..target()
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn split_conf() {
+    let weighted = |key: &str, weight: u16| SplitWeight {
+      weight,
+      target: UpstreamTarget {
+        key: key.to_owned(),
+        ..target()
+      },
+    };
+    let mut split = SplitTarget {
+      split: vec![
+        weighted("app-v1.global.c", 9),
+        weighted("app-v2.global.c", 1),
+      ],
+      pins: Some(vec![
+        SplitPin {
+          on: UpstreamHashKey::Header("X-Canary".to_owned()),
+          value: "1".to_owned(),
+          target: "app-v2.global.c".to_owned(),
+        },
+        SplitPin {
+          on: UpstreamHashKey::Cookie("variant".to_owned()),
+          value: "v1".to_owned(),
+          target: "app-v1.global.c".to_owned(),
+        },
+      ]),
+    };
+    let upstreams =
+      vec!["app-v1-80-cargo".to_owned(), "app-v2-80-cargo".to_owned()];
+    let variable = gen_split_variable("my-app.com", 0, 1);
+    assert_eq!(variable, "split_my_app_com_0_1");
+    let (conf, variable) =
+      gen_split_conf(&variable, &split, &upstreams).unwrap();
+    assert_eq!(variable, "split_my_app_com_0_1_pin0");
+    assert_eq!(
+      lines(&conf),
+      vec![
+        "split_clients \"$remote_addr\" $split_my_app_com_0_1_split {",
+        "90.00% app-v1-80-cargo;",
+        "* app-v2-80-cargo;",
+        "}",
+        "map $cookie_variant $split_my_app_com_0_1_pin1 {",
+        "\"v1\" app-v1-80-cargo;",
+        "default $split_my_app_com_0_1_split;",
+        "}",
+        "map $http_x_canary $split_my_app_com_0_1_pin0 {",
+        "\"1\" app-v2-80-cargo;",
+        "default $split_my_app_com_0_1_pin1;",
+        "}",
+      ]
+    );
+    let pin = &mut split.pins.as_mut().unwrap()[0];
+    "1\"; deny all".clone_into(&mut pin.value);
+    assert!(gen_split_conf("split", &split, &upstreams).is_err());
+    let pin = &mut split.pins.as_mut().unwrap()[0];
+    "1".clone_into(&mut pin.value);
+    "unknown.global.c".clone_into(&mut pin.target);
+    assert!(gen_split_conf("split", &split, &upstreams).is_err());
+    split.pins = None;
+    split.split[1].weight = 0;
+    assert!(gen_split_conf("split", &split, &upstreams).is_err());
+  }
+
+  #[test]
+  fn split_rewrite() {
+    assert_eq!(gen_split_rewrite("/", "/"), None);
+    assert_eq!(gen_split_rewrite("~ ^/api", "/"), None);
+    assert_eq!(
+      gen_split_rewrite("/api.v1", "/"),
+      Some("^/api\\.v1(.*)$ /$1".to_owned())
+    );
+  }
+
+  #[test]
+  fn htpasswd() {
+    let entries = vec![
+      "admin:$apr1$lZL6V/ci$eIMz/iKDkbtys/uU7LEK00".to_owned(),
+      "ops:{PLAIN}secret".to_owned(),
+    ];
+    assert_eq!(
+      gen_htpasswd(&entries).unwrap(),
+      "admin:$apr1$lZL6V/ci$eIMz/iKDkbtys/uU7LEK00\nops:{PLAIN}secret\n"
+    );
+    assert!(gen_htpasswd(&["admin".to_owned()]).is_err());
+    assert!(gen_htpasswd(&[":hash".to_owned()]).is_err());
+    assert!(gen_htpasswd(&["admin:hash\nops:hash".to_owned()]).is_err());
+  }
+
+  #[test]
+  fn auth_request_header() {
+    let header = gen_auth_request_header("X-Auth-User").unwrap();
+    assert_eq!(header.name, "X-Auth-User");
+    assert_eq!(header.variable, "x_auth_user");
+    assert!(gen_auth_request_header("X-User;").is_err());
+    assert!(gen_auth_request_header("").is_err());
+  }
+
+  #[test]
+  fn cache() {
+    let mut cache = ProxyCache {
+      zone_size: 10,
+      max_size: Some(100),
+      valid: vec![
+        ProxyCacheValid {
+          status: Some(vec![200, 302]),
+          ttl: 600,
+        },
+        ProxyCacheValid {
+          status: None,
+          ttl: 60,
+        },
+      ],
+      bypass: Some(vec![
+        ProxyCacheBypass::Header("Authorization".to_owned()),
+        ProxyCacheBypass::Cookie("session".to_owned()),
+        ProxyCacheBypass::Arg("nocache".to_owned()),
+      ]),
+    };
+    let template = gen_cache(&cache, "cache_app_0_0", "/state/cache").unwrap();
+    assert_eq!(template.path, "/state/cache/cache_app_0_0");
+    assert_eq!(template.valid, vec!["200 302 600s", "any 60s"]);
+    assert_eq!(
+      template.bypass.as_deref(),
+      Some("$http_authorization $cookie_session $arg_nocache")
+    );
+    cache.bypass = Some(vec![ProxyCacheBypass::Arg("no-cache".to_owned())]);
+    assert!(gen_cache(&cache, "cache_app_0_0", "/state/cache").is_err());
+    cache.bypass = None;
+    cache.valid[0].status = Some(vec![42]);
+    assert!(gen_cache(&cache, "cache_app_0_0", "/state/cache").is_err());
+  }
+
+  #[test]
+  fn compression() {
+    let compression = ProxyCompression {
+      gzip: Some(true),
+      brotli: Some(false),
+      types: Some(vec![
+        "application/json".to_owned(),
+        "image/svg+xml".to_owned(),
+      ]),
+    };
+    let template = gen_compression(&compression).unwrap();
+    assert_eq!(template.gzip.as_deref(), Some("on"));
+    assert_eq!(template.brotli.as_deref(), Some("off"));
+    assert_eq!(
+      template.types.as_deref(),
+      Some("application/json image/svg+xml")
+    );
+    let compression = ProxyCompression {
+      gzip: None,
+      brotli: None,
+      types: Some(vec!["text/html;".to_owned()]),
+    };
+    assert!(gen_compression(&compression).is_err());
+  }
+
+  #[test]
+  fn cors() {
+    let mut cors = ProxyCors {
+      origins: vec![
+        "https://app.example.com".to_owned(),
+        "http://localhost:3000".to_owned(),
+      ],
+      methods: None,
+      headers: Some(vec!["Content-Type".to_owned()]),
+      credentials: Some(true),
+    };
+    let template = gen_cors(&cors, "cors_app_0_0").unwrap();
+    assert!(!template.any_origin);
+    assert_eq!(template.origins.len(), 2);
+    assert_eq!(template.methods, "GET, HEAD, POST");
+    assert_eq!(template.headers.as_deref(), Some("Content-Type"));
+    cors.origins.push("*".to_owned());
+    assert!(gen_cors(&cors, "cors_app_0_0").is_err());
+    cors.credentials = None;
+    let template = gen_cors(&cors, "cors_app_0_0").unwrap();
+    assert!(template.any_origin);
+    assert!(template.origins.is_empty());
+    cors.origins = vec!["https://app.example.com\"".to_owned()];
+    assert!(gen_cors(&cors, "cors_app_0_0").is_err());
+    cors.origins = vec!["*".to_owned()];
+    cors.methods = Some(vec!["get".to_owned()]);
+    assert!(gen_cors(&cors, "cors_app_0_0").is_err());
+  }
+}
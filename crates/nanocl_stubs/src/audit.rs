@@ -0,0 +1,48 @@
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// An audit entry, recorded for every mutating call of the http api.
+/// It tells who asked for what and how the daemon answered.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test", derive(Default))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct Audit {
+  /// Unique identifier of the entry
+  pub key: uuid::Uuid,
+  /// When the request have been received
+  pub created_at: chrono::NaiveDateTime,
+  /// When the entry expires
+  pub expires_at: chrono::NaiveDateTime,
+  /// Name of the node that served the request
+  pub node: String,
+  /// Http method of the request
+  pub method: String,
+  /// Path of the request with his query string
+  pub path: String,
+  /// Identity of the client, `token:{name}` or `cert:{common name}`,
+  /// none for the trusted clients (unix socket)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub identity: Option<String>,
+  /// Namespace targeted by the request if any
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub namespace: Option<String>,
+  /// Sha256 of the body of the request, none if empty
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub body_hash: Option<String>,
+  /// Http status code of the response
+  pub status_code: u16,
+  /// Milliseconds spent to answer the request
+  pub latency_ms: u64,
+}
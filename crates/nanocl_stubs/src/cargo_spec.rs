@@ -39,6 +39,9 @@ pub enum ReplicationMode {
   StaticByNodeGroups { groups: Vec<String>, number: i64 },
   /// NumberByNodeNames is used to manually set the number of replicas in each node name
   StaticByNodeNames { names: Vec<String>, number: i64 },
+  /// Autoscale is used to scale the number of replicas in one node
+  /// between a minimum and a maximum based on their CPU and memory usage
+  Autoscale(ReplicationAutoscale),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -53,6 +56,68 @@ pub struct ReplicationStatic {
   pub number: usize,
 }
 
+/// Autoscaling configuration of a cargo
+/// Targets are percentages, the CPU usage is relative to one core
+/// and the memory usage relative to the memory limit of the container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ReplicationAutoscale {
+  /// Minimum number of replicas, at least 1
+  pub min: usize,
+  /// Maximum number of replicas
+  pub max: usize,
+  /// Average CPU usage to maintain across the replicas
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cpu_target: Option<u32>,
+  /// Average memory usage to maintain across the replicas
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub memory_target: Option<u32>,
+}
+
+/// Strategy used to replace the replicas of a cargo when his spec is updated
+/// Replicas are replaced in batches and each batch must be healthy
+/// before the next one start, otherwise the update is rolled back.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct UpdateStrategy {
+  /// Number of replicas that can be created above the wanted number (default 1)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_surge: Option<usize>,
+  /// Number of replicas that can be unavailable during the update (default 0)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_unavailable: Option<usize>,
+  /// Seconds to wait for a batch to be healthy before rolling back (default 60)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub health_timeout: Option<u64>,
+}
+
 /// A cargo spec partial is used to create a Cargo
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -104,6 +169,12 @@ pub struct CargoSpecPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub replication: Option<ReplicationMode>,
+  /// Strategy used to replace the replicas when the cargo is updated
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub update_strategy: Option<UpdateStrategy>,
 }
 
 /// Payload used to patch a cargo
@@ -167,6 +238,12 @@ pub struct CargoSpecUpdate {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub replication: Option<ReplicationMode>,
+  /// Strategy used to replace the replicas when the cargo is updated
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub update_strategy: Option<UpdateStrategy>,
 }
 
 impl From<CargoSpecPartial> for CargoSpecUpdate {
@@ -180,6 +257,7 @@ impl From<CargoSpecPartial> for CargoSpecUpdate {
       secrets: spec.secrets,
       image_pull_secret: spec.image_pull_secret,
       image_pull_policy: spec.image_pull_policy,
+      update_strategy: spec.update_strategy,
     }
   }
 }
@@ -242,6 +320,12 @@ pub struct CargoSpec {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub replication: Option<ReplicationMode>,
+  /// Strategy used to replace the replicas when the cargo is updated
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub update_strategy: Option<UpdateStrategy>,
 }
 
 impl From<CargoSpec> for CargoSpecPartial {
@@ -255,6 +339,7 @@ impl From<CargoSpec> for CargoSpecPartial {
       secrets: spec.secrets,
       image_pull_secret: spec.image_pull_secret,
       image_pull_policy: spec.image_pull_policy,
+      update_strategy: spec.update_strategy,
     }
   }
 }
@@ -1,8 +1,34 @@
+use std::collections::HashMap;
+
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
 use super::system::SslConfig;
 
+/// Retention of the events, metrics and audits saved in the store
+/// Expired rows are deleted in batches on a schedule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RetentionConfig {
+  /// Days to keep the events (default to 30)
+  pub events: Option<u64>,
+  /// Days to keep the metrics (default to 30)
+  pub metrics: Option<u64>,
+  /// Days to keep the metrics of a given kind overriding `metrics`
+  /// Eg: `ncproxy.io/http: 7`
+  pub metric_kinds: Option<HashMap<String, u64>>,
+  /// Days to keep the metric rollups used for aggregations (default to 90)
+  pub rollups: Option<u64>,
+  /// Days to keep the audit entries (default to 90)
+  pub audits: Option<u64>,
+  /// Number of rows deleted at once (default to 1000)
+  pub batch_size: Option<u64>,
+  /// Seconds between two purges (default to 3600)
+  pub interval: Option<u64>,
+}
+
 /// Configuration of the daemon
 /// It is used to configure the daemon
 #[derive(Debug, Clone)]
@@ -33,6 +59,16 @@ pub struct DaemonConfig {
   pub gid: u32,
   /// Optional ssl configuration
   pub ssl: Option<SslConfig>,
+  /// Path to the key wrapping the master keys of the secrets
+  /// It must be the same on every node of the cluster
+  /// Default to `{state_dir}/master.key`
+  pub master_key_path: Option<String>,
+  /// Retention of the events and metrics
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub retention: RetentionConfig,
+  /// Require a bearer token for the requests received over tcp
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub auth: bool,
 }
 
 /// Configuration File of the daemon
@@ -52,6 +88,13 @@ pub struct DaemonConfigFile {
   pub gateway: Option<String>,
   /// Hostname to use for the node automatically detected if not set
   pub hostname: Option<String>,
+  /// Path to the key wrapping the master keys of the secrets
+  /// It must be the same on every node of the cluster
+  pub master_key_path: Option<String>,
+  /// Retention of the events and metrics
+  pub retention: Option<RetentionConfig>,
+  /// Require a bearer token for the requests received over tcp
+  pub auth: Option<bool>,
 }
 
 impl Default for DaemonConfig {
@@ -68,6 +111,9 @@ impl Default for DaemonConfig {
       nodes: Vec::default(),
       advertise_addr: String::default(),
       ssl: None,
+      master_key_path: None,
+      retention: RetentionConfig::default(),
+      auth: false,
     }
   }
 }
@@ -6,6 +6,42 @@ use crate::generic::ImagePullPolicy;
 use crate::process::Process;
 use crate::system::{EventActor, EventActorKind, ObjPsStatus};
 
+/// A step of a job running a container once his dependencies are done.
+/// Steps that doesn't depend on each other run in parallel.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct JobStep {
+  /// Name of the step, unique in the job
+  pub name: String,
+  /// Name of the steps that must be done before this one start
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub depends_on: Option<Vec<String>>,
+  /// Number of times the step is restarted when it fail
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub retry: Option<usize>,
+  /// When the step fail the steps depending on it still run
+  /// and the job doesn't fail
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub continue_on_failure: Option<bool>,
+  /// Container to run
+  pub container: Config,
+}
+
 /// Job partial is used to create a new job
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -54,8 +90,34 @@ pub struct JobPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
-  /// List of container to run
+  /// List of container to run in sequence
+  #[cfg_attr(feature = "serde", serde(default))]
   pub containers: Vec<Config>,
+  /// Steps to run according to their dependencies instead of `containers`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub steps: Option<Vec<JobStep>>,
+  /// Number of times a failed instance is re-created before the job fail
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backoff_limit: Option<usize>,
+  /// Seconds to wait before re-creating a failed instance,
+  /// doubled at each retry (default to 10)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backoff_delay: Option<u64>,
+  /// Seconds after which the running instances are killed and the job fail
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub active_deadline_seconds: Option<u64>,
 }
 
 /// Convert a job into a job partial
@@ -68,13 +130,18 @@ impl From<Job> for JobPartial {
       schedule: job.schedule,
       ttl: job.ttl,
       containers: job.containers,
+      steps: job.steps,
+      backoff_limit: job.backoff_limit,
+      backoff_delay: job.backoff_delay,
+      active_deadline_seconds: job.active_deadline_seconds,
       image_pull_secret: job.image_pull_secret,
       image_pull_policy: job.image_pull_policy,
     }
   }
 }
 
-/// A job is a collection of containers to run in sequence as a single unit to act like a command
+/// A job is a collection of containers to run in sequence or as a graph of steps
+/// as a single unit to act like a command
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -126,8 +193,46 @@ pub struct Job {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
-  /// Containers to run
+  /// Containers to run in sequence
+  #[cfg_attr(feature = "serde", serde(default))]
   pub containers: Vec<Config>,
+  /// Steps to run according to their dependencies instead of `containers`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub steps: Option<Vec<JobStep>>,
+  /// Number of times a failed instance is re-created before the job fail
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backoff_limit: Option<usize>,
+  /// Seconds to wait before re-creating a failed instance,
+  /// doubled at each retry (default to 10)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backoff_delay: Option<u64>,
+  /// Seconds after which the running instances are killed and the job fail
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub active_deadline_seconds: Option<u64>,
+  /// Last time the job was started by its schedule
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub last_run_at: Option<chrono::NaiveDateTime>,
+  /// Next time the job will be started by its schedule
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub next_run_at: Option<chrono::NaiveDateTime>,
 }
 
 /// Convert a Job into an EventActor
@@ -14,6 +14,10 @@ pub mod vm_spec;
 pub mod vm_image;
 pub mod metric;
 pub mod secret;
+pub mod token;
+pub mod role;
+pub mod network_policy;
+pub mod audit;
 pub mod job;
 pub mod process;
 pub mod resource;
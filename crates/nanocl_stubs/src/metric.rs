@@ -40,6 +40,68 @@ pub struct MetricPartial {
   pub note: Option<String>,
 }
 
+/// Query to aggregate a field of the metrics of a kind over a time range
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MetricAggregateQuery {
+  /// Kind of the metrics
+  pub kind: String,
+  /// Field of the data to aggregate, eg: `RequestTime` or `Cpu`
+  pub field: String,
+  /// Only include the metrics of this node
+  pub node: Option<String>,
+  /// Start of the range as unix timestamp (default to one hour ago)
+  pub since: Option<i64>,
+  /// End of the range as unix timestamp (default to now)
+  pub until: Option<i64>,
+  /// Size of a bucket in seconds rounded to the minute (default to 60)
+  pub interval: Option<u64>,
+}
+
+/// Aggregated values of a field over a bucket of time
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct MetricPoint {
+  /// Start of the bucket
+  pub time: chrono::NaiveDateTime,
+  /// Number of values
+  pub count: i64,
+  /// Minimum value
+  pub min: f64,
+  /// Average value
+  pub avg: f64,
+  /// Maximum value
+  pub max: f64,
+  /// Median value
+  pub p50: f64,
+  /// 90th percentile
+  pub p90: f64,
+  /// 95th percentile
+  pub p95: f64,
+  /// 99th percentile
+  pub p99: f64,
+}
+
+/// Series of aggregated values of a field of the metrics of a kind
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct MetricSeries {
+  /// Kind of the metrics
+  pub kind: String,
+  /// Aggregated field
+  pub field: String,
+  /// Size of a bucket in seconds
+  pub interval: u64,
+  /// Buckets containing at least one value ordered by time
+  pub points: Vec<MetricPoint>,
+}
+
 /// ## deserialize empty string
 ///
 /// Serde helper to deserialize string that can be empty to `Option<String>`.
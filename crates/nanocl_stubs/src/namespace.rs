@@ -86,6 +86,96 @@ pub struct NamespaceInspect {
   pub network: Network,
 }
 
+/// Limits of the objects of a namespace, a limit not set is unlimited
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NamespaceQuota {
+  /// Maximum number of cargoes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_cargoes: Option<usize>,
+  /// Maximum number of replicas summed over the cargoes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_replicas: Option<usize>,
+  /// Maximum memory in bytes summed over the `HostConfig` of the replicas
+  /// when set every container must define his memory limit
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_memory: Option<i64>,
+  /// Maximum cpu in units of 10<sup>-9</sup> CPUs summed over the `HostConfig`
+  /// of the replicas, when set every container must define his cpu limit
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_nano_cpus: Option<i64>,
+  /// Maximum number of virtual machines
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_vms: Option<usize>,
+  /// Maximum disk size in GB summed over the virtual machines
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_vm_disk: Option<u64>,
+}
+
+/// Resources used by the objects of a namespace counted against his quota
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NamespaceQuotaUsage {
+  /// Number of cargoes
+  pub cargoes: usize,
+  /// Number of replicas summed over the cargoes
+  pub replicas: usize,
+  /// Memory in bytes summed over the replicas
+  pub memory: i64,
+  /// Cpu in units of 10<sup>-9</sup> CPUs summed over the replicas
+  pub nano_cpus: i64,
+  /// Number of virtual machines
+  pub vms: usize,
+  /// Disk size in GB summed over the virtual machines
+  pub vm_disk: u64,
+}
+
+/// The quota of a namespace with his current usage
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NamespaceQuotaInspect {
+  /// Name of the namespace
+  pub namespace_name: String,
+  /// When the quota was created
+  pub created_at: chrono::NaiveDateTime,
+  /// When the quota was updated
+  pub updated_at: chrono::NaiveDateTime,
+  /// Limits of the namespace
+  pub quota: NamespaceQuota,
+  /// Current usage of the namespace
+  pub usage: NamespaceQuotaUsage,
+}
+
 /// Convert a Namespace into an EventActor
 impl From<Namespace> for EventActor {
   fn from(namespace: Namespace) -> Self {
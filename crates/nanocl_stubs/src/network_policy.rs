@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Select cargoes of a namespace by their name and the labels of their container.
+/// An empty selector match every cargo of the namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicySelector {
+  /// Names of the cargoes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cargoes: Option<Vec<String>>,
+  /// Labels the container of the cargoes must have
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub labels: Option<HashMap<String, String>>,
+}
+
+impl NetworkPolicySelector {
+  /// Whether the cargo with the given name and container labels is selected
+  pub fn matches(
+    &self,
+    cargo_name: &str,
+    labels: &HashMap<String, String>,
+  ) -> bool {
+    if let Some(cargoes) = &self.cargoes {
+      if !cargoes.iter().any(|name| name == cargo_name) {
+        return false;
+      }
+    }
+    match &self.labels {
+      None => true,
+      Some(wanted) => wanted
+        .iter()
+        .all(|(key, value)| labels.get(key) == Some(value)),
+    }
+  }
+}
+
+/// Transport protocol of a port
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum NetworkPolicyProtocol {
+  #[default]
+  Tcp,
+  Udp,
+}
+
+impl std::fmt::Display for NetworkPolicyProtocol {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Tcp => write!(f, "tcp"),
+      Self::Udp => write!(f, "udp"),
+    }
+  }
+}
+
+/// A port allowed by an ingress rule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicyPort {
+  /// Port of the target cargoes
+  pub port: u16,
+  /// Protocol of the port, tcp by default
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub protocol: NetworkPolicyProtocol,
+}
+
+/// Ingress allowed to the target cargoes of a policy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicyIngress {
+  /// Cargoes of the namespace allowed to connect
+  pub from: Vec<NetworkPolicySelector>,
+  /// Ports allowed, every port if none
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ports: Option<Vec<NetworkPolicyPort>>,
+}
+
+/// A partial network policy object. This is used to create or replace a policy.
+/// Once a cargo is targeted by a policy only the ingress allowed
+/// by the policies targeting it are accepted from the other cargoes
+/// of his namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicyPartial {
+  /// The name of the policy
+  pub name: String,
+  /// Cargoes isolated by the policy
+  pub target: NetworkPolicySelector,
+  /// Ingress allowed to the target cargoes, none if empty
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub ingress: Vec<NetworkPolicyIngress>,
+  /// The metadata of the policy (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+/// The rules of a network policy stored in the database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicySpec {
+  /// Cargoes isolated by the policy
+  pub target: NetworkPolicySelector,
+  /// Ingress allowed to the target cargoes
+  pub ingress: Vec<NetworkPolicyIngress>,
+}
+
+/// This structure represent a network policy in the database
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test", derive(Default))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NetworkPolicy {
+  /// The key of the policy generated with `name` and `namespace_name`
+  pub key: String,
+  /// The name of the policy
+  pub name: String,
+  /// The namespace of the policy
+  pub namespace_name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// Cargoes isolated by the policy
+  pub target: NetworkPolicySelector,
+  /// Ingress allowed to the target cargoes
+  pub ingress: Vec<NetworkPolicyIngress>,
+  /// The metadata of the policy (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
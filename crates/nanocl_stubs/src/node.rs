@@ -1,6 +1,74 @@
+use std::str::FromStr;
+
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
+/// Liveness state of a node
+/// A node that miss his heartbeats is marked as `NotReady`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NodeState {
+  #[default]
+  Ready,
+  NotReady,
+}
+
+impl FromStr for NodeState {
+  type Err = std::io::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "Ready" => Ok(Self::Ready),
+      "NotReady" => Ok(Self::NotReady),
+      _ => Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid node state {s}"),
+      )),
+    }
+  }
+}
+
+impl TryFrom<String> for NodeState {
+  type Error = std::io::Error;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    Self::from_str(&value)
+  }
+}
+
+impl From<NodeState> for String {
+  fn from(state: NodeState) -> Self {
+    state.to_string()
+  }
+}
+
+impl std::fmt::Display for NodeState {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      Self::Ready => "Ready",
+      Self::NotReady => "NotReady",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// Resources of a node published with his heartbeats
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NodeCapacity {
+  /// Number of cpus
+  pub cpus: i64,
+  /// Total memory in bytes
+  pub memory: i64,
+  /// Number of running containers
+  pub containers_running: i64,
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -20,4 +88,47 @@ pub struct Node {
   /// User defined metadata
   #[serde(skip_serializing_if = "Option::is_none")]
   pub metadata: Option<serde_json::Value>,
+  /// Liveness state of the node
+  pub state: NodeState,
+  /// Last time the node sent a heartbeat
+  pub heartbeat_at: chrono::NaiveDateTime,
+  /// Resources of the node sent with his last heartbeat
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub capacity: Option<NodeCapacity>,
+}
+
+/// Prefix of the keys of the leases a client can acquire for the node
+/// of the daemon, the other keys are reserved to the daemon
+pub const CLIENT_LEASE_PREFIX: &str = "ncproxy.io/acme/";
+
+/// Payload to acquire a lease for the node of the daemon
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NodeLeasePartial {
+  /// The action done while holding the lease
+  pub action: String,
+}
+
+/// A lease giving to a node the ownership of a key until it expires.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct NodeLease {
+  /// The key of the lease
+  pub key: String,
+  /// The created at date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last time the lease was acquired or renewed
+  pub updated_at: chrono::NaiveDateTime,
+  /// The node holding the lease
+  pub node_name: String,
+  /// The action the node is doing while holding the lease
+  pub action: String,
+  /// When the lease expires if not renewed
+  pub expires_at: chrono::NaiveDateTime,
 }
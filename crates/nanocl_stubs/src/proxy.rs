@@ -47,6 +47,30 @@ pub struct ProxySslConfig {
   pub dhparam: Option<String>,
 }
 
+/// Certificate issued and renewed with the ACME protocol,
+/// the domain of the rule is validated with the http-01 challenge
+/// and the terms of service of the ACME server are agreed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxySslAcme {
+  /// Url of the directory of the ACME server
+  pub directory_url: String,
+  /// Contact e-mail of the ACME account
+  pub email: String,
+  /// Don't verify the certificate of the ACME server, for test servers only
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub skip_tls_verify: Option<bool>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -55,6 +79,44 @@ pub struct ProxySslConfig {
 pub enum ProxySsl {
   Config(ProxySslConfig),
   Secret(String),
+  Acme(ProxySslAcme),
+}
+
+/// Request value used as key of a consistent hash balancing
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub enum UpstreamHashKey {
+  /// Name of a request header
+  Header(String),
+  /// Name of a request cookie
+  Cookie(String),
+}
+
+/// Load balancing strategy between the instances of a target
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub enum UpstreamBalancing {
+  /// Requests are distributed in turn
+  #[default]
+  RoundRobin,
+  /// Requests are sent to the instance with the least active connections
+  LeastConn,
+  /// Requests of a client address are sent to the same instance
+  IpHash,
+  /// Requests with the same header or cookie are sent to the same instance
+  Hash(UpstreamHashKey),
 }
 
 /// Config for targeting a cargo or a vm
@@ -89,6 +151,32 @@ pub struct UpstreamTarget {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub ssl: Option<ProxySsl>,
+  /// Load balancing strategy between the instances, round robin by default
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub balancing: Option<UpstreamBalancing>,
+  /// Weight of the instances of the target relative to the other servers
+  /// of its upstream, at least 1
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub weight: Option<u16>,
+  /// Number of failed attempts before an instance is considered unavailable
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_fails: Option<u16>,
+  /// Seconds during which the failed attempts are counted
+  /// and an unavailable instance isn't used
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub fail_timeout: Option<u32>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -137,6 +225,61 @@ pub struct HttpTarget {
   pub redirect: Option<UrlRedirect>,
 }
 
+/// A target receiving a share of the traffic of a split
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct SplitWeight {
+  /// Weight of the target relative to the other targets of the split
+  pub weight: u16,
+  /// The cargo or the vm to target
+  pub target: UpstreamTarget,
+}
+
+/// Pin the requests with a given header or cookie value to a target of a split
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct SplitPin {
+  /// The header or the cookie to match
+  pub on: UpstreamHashKey,
+  /// The value of the header or the cookie
+  pub value: String,
+  /// The key of the target of the split receiving the requests
+  pub target: String,
+}
+
+/// Split the traffic of a location between multiple cargoes or vms,
+/// a client is sent to the same target as long as his address doesn't change
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct SplitTarget {
+  /// The targets sharing the traffic, they must have the same path
+  pub split: Vec<SplitWeight>,
+  /// Requests pinned to a target, the first matching pin is used
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub pins: Option<Vec<SplitPin>>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -149,6 +292,8 @@ pub enum LocationTarget {
   Http(HttpTarget),
   /// Target a specific unix socket
   Unix(UnixTarget),
+  /// Split the traffic between existing cargoes
+  Split(SplitTarget),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -273,6 +418,186 @@ pub struct LimitReq {
   pub delay: Option<usize>,
 }
 
+/// Time to live of the cached responses with the given statuses
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyCacheValid {
+  /// Statuses of the responses, every status when empty
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub status: Option<Vec<u16>>,
+  /// Time to live in seconds
+  pub ttl: u32,
+}
+
+/// Requests fetched from the target and never cached
+/// when they have a non empty value other than `0` for the given key
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub enum ProxyCacheBypass {
+  Header(String),
+  Cookie(String),
+  Arg(String),
+}
+
+/// Cache the responses of the target of a location
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyCache {
+  /// Size of the shared memory zone of the cache keys in megabytes
+  pub zone_size: usize,
+  /// Max size of the cached responses on disk in megabytes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_size: Option<usize>,
+  /// Time to live of the responses by status
+  pub valid: Vec<ProxyCacheValid>,
+  /// Rules of the requests bypassing the cache
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub bypass: Option<Vec<ProxyCacheBypass>>,
+}
+
+/// Compress the responses of a location
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyCompression {
+  /// Enable or disable gzip
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub gzip: Option<bool>,
+  /// Enable or disable brotli
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub brotli: Option<bool>,
+  /// Mime types compressed in addition to `text/html`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub types: Option<Vec<String>>,
+}
+
+/// Cross-origin resource sharing policy of a location
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyCors {
+  /// Allowed origins, `*` for any origin
+  pub origins: Vec<String>,
+  /// Allowed methods, `GET`, `HEAD` and `POST` by default
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub methods: Option<Vec<String>>,
+  /// Allowed request headers
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub headers: Option<Vec<String>>,
+  /// Allow the requests with credentials, not allowed with any origin
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub credentials: Option<bool>,
+}
+
+/// Require the credentials of a user listed in a `nanocl.io/htpasswd` secret
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyBasicAuth {
+  /// Name of the secret holding the htpasswd entries
+  pub secret: String,
+  /// Realm displayed when the credentials are asked, `Restricted` by default
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub realm: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged, rename_all = "PascalCase"))]
+pub enum ProxyAuthRequestTarget {
+  /// Forward to an existing cargo
+  Upstream(UpstreamTarget),
+  /// Forward to a specific http url
+  Url(String),
+}
+
+/// Authorize every request of a location with a subrequest,
+/// the request is allowed when the target answers with a 2xx status
+/// and denied with the 401 or 403 status it answers otherwise.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyAuthRequest {
+  /// The target receiving the subrequest
+  pub target: ProxyAuthRequestTarget,
+  /// Headers of the response of the target forwarded to the location target
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub headers: Option<Vec<String>>,
+}
+
 /// Defines a proxy rule location
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -311,6 +636,36 @@ pub struct ProxyHttpLocation {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub version: Option<f64>,
+  /// Require the credentials of a user
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub basic_auth: Option<ProxyBasicAuth>,
+  /// Authorize the requests with a cargo or an url
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub auth_request: Option<ProxyAuthRequest>,
+  /// Cache the responses of the target
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cache: Option<ProxyCache>,
+  /// Compress the responses
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub compression: Option<ProxyCompression>,
+  /// Cross-origin resource sharing policy
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cors: Option<ProxyCors>,
 }
 
 /// Defines a proxy rule http config
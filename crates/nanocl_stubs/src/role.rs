@@ -0,0 +1,193 @@
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// Kinds of objects a role can grant access to, `*` match every kind
+pub const ROLE_KINDS: &[&str] = &[
+  "cargo",
+  "vm",
+  "vm_image",
+  "job",
+  "process",
+  "resource",
+  "resource_kind",
+  "secret",
+  "namespace",
+  "node",
+  "metric",
+  "event",
+  "system",
+  "token",
+  "role",
+  "audit",
+  "network_policy",
+];
+
+/// An action a role can grant on a kind of object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum RoleVerb {
+  List,
+  Inspect,
+  Create,
+  Patch,
+  Delete,
+  Exec,
+  Logs,
+}
+
+impl FromStr for RoleVerb {
+  type Err = std::io::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "list" => Ok(Self::List),
+      "inspect" => Ok(Self::Inspect),
+      "create" => Ok(Self::Create),
+      "patch" => Ok(Self::Patch),
+      "delete" => Ok(Self::Delete),
+      "exec" => Ok(Self::Exec),
+      "logs" => Ok(Self::Logs),
+      _ => Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("invalid role verb: {s}"),
+      )),
+    }
+  }
+}
+
+impl std::fmt::Display for RoleVerb {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      Self::List => "list",
+      Self::Inspect => "inspect",
+      Self::Create => "create",
+      Self::Patch => "patch",
+      Self::Delete => "delete",
+      Self::Exec => "exec",
+      Self::Logs => "logs",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// A rule granting verbs on kinds of objects
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct RoleRule {
+  /// Kinds of objects the rule apply to (eg: `cargo`) or `*` for all
+  pub kinds: Vec<String>,
+  /// Verbs granted on these kinds
+  pub verbs: Vec<RoleVerb>,
+  /// Namespaces where the verbs are granted, every namespace if none.
+  /// Objects outside of a namespace (eg: secrets) are only granted
+  /// when the rule isn't restricted to some namespaces or contains `*`.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub namespaces: Option<Vec<String>>,
+}
+
+impl RoleRule {
+  /// Whether the rule grant the verb on the kind in the namespace
+  pub fn allows(
+    &self,
+    kind: &str,
+    verb: RoleVerb,
+    namespace: Option<&str>,
+  ) -> bool {
+    if !self.kinds.iter().any(|k| k == "*" || k == kind) {
+      return false;
+    }
+    if !self.verbs.contains(&verb) {
+      return false;
+    }
+    match (&self.namespaces, namespace) {
+      (None, _) => true,
+      (Some(namespaces), None) => namespaces.iter().any(|n| n == "*"),
+      (Some(namespaces), Some(namespace)) => {
+        namespaces.iter().any(|n| n == "*" || n == namespace)
+      }
+    }
+  }
+}
+
+/// A partial role object. This is used to create or replace a role.
+/// A role grant verbs on kinds of objects to the identities of his subjects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct RolePartial {
+  /// The name of the role
+  pub name: String,
+  /// The rules of the role
+  pub rules: Vec<RoleRule>,
+  /// Identities bound to the role (eg: `token:ci`)
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub subjects: Vec<String>,
+  /// The metadata of the role (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+/// This structure represent a role in the database
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test", derive(Default))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct Role {
+  /// The name of the role
+  pub name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The rules of the role
+  pub rules: Vec<RoleRule>,
+  /// Identities bound to the role (eg: `token:ci`)
+  pub subjects: Vec<String>,
+  /// The metadata of the role (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl Role {
+  /// Whether one of the rules grant the verb on the kind in the namespace
+  pub fn allows(
+    &self,
+    kind: &str,
+    verb: RoleVerb,
+    namespace: Option<&str>,
+  ) -> bool {
+    self
+      .rules
+      .iter()
+      .any(|rule| rule.allows(kind, verb, namespace))
+  }
+}
@@ -66,6 +66,74 @@ pub struct Secret {
   pub data: serde_json::Value,
 }
 
+/// Value replacing the secret data when it isn't revealed
+pub const SECRET_MASK: &str = "********";
+
+impl Secret {
+  /// Replace every value of the secret data with a mask
+  /// keeping his structure so it can be displayed without leaking it
+  pub fn redact(mut self) -> Self {
+    fn mask(value: &mut serde_json::Value) {
+      match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Array(items) => items.iter_mut().for_each(mask),
+        serde_json::Value::Object(object) => object.values_mut().for_each(mask),
+        _ => *value = serde_json::Value::String(SECRET_MASK.to_owned()),
+      }
+    }
+    mask(&mut self.data);
+    self
+  }
+}
+
+/// A summary of a secret without his data
+/// It's the data structure returned by the list operation
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct SecretSummary {
+  /// The name of the secret
+  pub name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The kind of secret
+  pub kind: String,
+  /// The secret cannot be updated
+  pub immutable: bool,
+  // The metadata (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl From<Secret> for SecretSummary {
+  fn from(secret: Secret) -> Self {
+    SecretSummary {
+      name: secret.name,
+      created_at: secret.created_at,
+      updated_at: secret.updated_at,
+      kind: secret.kind,
+      immutable: secret.immutable,
+      metadata: secret.metadata,
+    }
+  }
+}
+
+/// Inspect secret query
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SecretInspectQuery {
+  /// Return the data of the secret instead of a mask
+  pub reveal: Option<bool>,
+}
+
 impl From<Secret> for SecretPartial {
   fn from(secret: Secret) -> Self {
     SecretPartial {
@@ -122,3 +190,16 @@ impl From<SecretPartial> for SecretUpdate {
     }
   }
 }
+
+/// Result of the rotation of the master key used to encrypt the secrets
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct SecretKeyRotation {
+  /// The id of the new master key
+  pub key_id: String,
+  /// The number of secrets encrypted with the new master key
+  pub secrets: usize,
+}
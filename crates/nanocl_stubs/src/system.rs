@@ -168,6 +168,7 @@ pub enum EventActorKind {
   Secret,
   Process,
   ContainerImage,
+  Token,
 }
 
 impl std::fmt::Display for EventActorKind {
@@ -181,6 +182,7 @@ impl std::fmt::Display for EventActorKind {
       EventActorKind::Secret => write!(f, "Secret"),
       EventActorKind::Process => write!(f, "Process"),
       EventActorKind::ContainerImage => write!(f, "ContainerImage"),
+      EventActorKind::Token => write!(f, "Token"),
     }
   }
 }
@@ -206,6 +208,11 @@ pub enum NativeEventAction {
   Die,
   Downloading,
   Download,
+  Rollback,
+  Scale,
+  Retry,
+  Reconcile,
+  Deny,
   Other(String),
 }
 
@@ -229,6 +236,11 @@ impl FromStr for NativeEventAction {
       "die" => Ok(NativeEventAction::Die),
       "downloading" => Ok(NativeEventAction::Downloading),
       "download" => Ok(NativeEventAction::Download),
+      "rollback" => Ok(NativeEventAction::Rollback),
+      "scale" => Ok(NativeEventAction::Scale),
+      "retry" => Ok(NativeEventAction::Retry),
+      "reconcile" => Ok(NativeEventAction::Reconcile),
+      "deny" => Ok(NativeEventAction::Deny),
       _ => Ok(NativeEventAction::Other(s.to_owned())),
     }
   }
@@ -252,6 +264,11 @@ impl std::fmt::Display for NativeEventAction {
       NativeEventAction::Die => write!(f, "die"),
       NativeEventAction::Downloading => write!(f, "downloading"),
       NativeEventAction::Download => write!(f, "download"),
+      NativeEventAction::Rollback => write!(f, "rollback"),
+      NativeEventAction::Scale => write!(f, "scale"),
+      NativeEventAction::Retry => write!(f, "retry"),
+      NativeEventAction::Reconcile => write!(f, "reconcile"),
+      NativeEventAction::Deny => write!(f, "deny"),
       NativeEventAction::Other(s) => write!(f, "{}", s),
     }
   }
@@ -0,0 +1,77 @@
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// A partial token object. This is used to create a token.
+/// A token authenticate the clients of the http api of the daemon,
+/// only his hash is stored and the secret is returned once at creation.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct TokenPartial {
+  /// The name of the token
+  pub name: String,
+  /// When the token expires, it never expires if none
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub expires_at: Option<chrono::NaiveDateTime>,
+  /// The metadata of the token (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+/// This structure represent a token in the database without his hash
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test", derive(Default))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct Token {
+  /// The name of the token
+  pub name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// When the token expires, it never expires if none
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub expires_at: Option<chrono::NaiveDateTime>,
+  /// The last time the token was used
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub last_used_at: Option<chrono::NaiveDateTime>,
+  /// The metadata of the token (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+/// A newly created token with his secret, it cannot be retrieved later
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct TokenCreated {
+  /// The token
+  pub token: Token,
+  /// The secret to use as bearer token
+  pub secret: String,
+}
@@ -0,0 +1,125 @@
+/// Bearer token authentication middleware
+use std::{future::Future, rc::Rc};
+
+use ntex::http::header::{self, HeaderValue};
+use ntex::{Service, ServiceCtx, Middleware};
+use ntex::web::{WebRequest, WebResponse, Error, ErrorRenderer, HttpResponse};
+
+/// Validate the bearer tokens of the requests
+pub trait BearerValidator: 'static {
+  /// Identity of the owner of a token,
+  /// inserted in the extensions of the authenticated requests
+  type Identity: 'static;
+
+  /// Whether the request must be authenticated,
+  /// by default the requests without peer address (unix socket) are trusted
+  fn is_required<Err>(&self, req: &WebRequest<Err>) -> bool {
+    req.peer_addr().is_some()
+  }
+
+  /// Validate the token and return the identity of his owner
+  /// or the reason why it's refused
+  fn validate<Err>(
+    &self,
+    req: &WebRequest<Err>,
+    token: &str,
+  ) -> impl Future<Output = Result<Self::Identity, String>>;
+}
+
+/// Bearer authentication middleware creator
+///
+/// ```no_run,ignore
+/// use ntex::web;
+/// use ntex::middleware::BearerAuth;
+///
+/// web::scope("/{version}")
+///  .wrap(BearerAuth::new(MyValidator))
+///  .route("/test", web::get().to(|| async { "test" }));
+/// ```
+pub struct BearerAuth<V> {
+  validator: Rc<V>,
+}
+
+impl<V> BearerAuth<V> {
+  pub fn new(validator: V) -> Self {
+    Self {
+      validator: Rc::new(validator),
+    }
+  }
+}
+
+impl<S, V> Middleware<S> for BearerAuth<V> {
+  type Service = BearerAuthMiddleware<S, V>;
+
+  fn create(&self, service: S) -> Self::Service {
+    BearerAuthMiddleware {
+      service,
+      validator: self.validator.clone(),
+    }
+  }
+}
+
+pub struct BearerAuthMiddleware<S, V> {
+  service: S,
+  validator: Rc<V>,
+}
+
+/// Extract the token of the authorization header
+fn get_token<Err>(req: &WebRequest<Err>) -> Option<String> {
+  let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+  let (scheme, token) = value.trim().split_once(' ')?;
+  if !scheme.eq_ignore_ascii_case("bearer") {
+    return None;
+  }
+  let token = token.trim();
+  if token.is_empty() {
+    return None;
+  }
+  Some(token.to_owned())
+}
+
+impl<S, V, Err> Service<WebRequest<Err>> for BearerAuthMiddleware<S, V>
+where
+  S: Service<WebRequest<Err>, Response = WebResponse, Error = Error>,
+  V: BearerValidator,
+  Err: ErrorRenderer,
+{
+  type Response = WebResponse;
+  type Error = Error;
+
+  ntex::forward_ready!(service);
+  ntex::forward_shutdown!(service);
+
+  async fn call(
+    &self,
+    req: WebRequest<Err>,
+    ctx: ServiceCtx<'_, Self>,
+  ) -> Result<Self::Response, Self::Error> {
+    if !self.validator.is_required(&req) {
+      return ctx.call(&self.service, req).await;
+    }
+    let res = match get_token(&req) {
+      None => Err("missing bearer token".to_owned()),
+      Some(token) => self.validator.validate(&req, &token).await,
+    };
+    match res {
+      Ok(identity) => {
+        req.extensions_mut().insert(identity);
+        ctx.call(&self.service, req).await
+      }
+      Err(msg) => {
+        let mut res = req.into_response(
+          HttpResponse::Unauthorized()
+            .json(&serde_json::json!({
+              "msg": msg,
+            }))
+            .into_body(),
+        );
+        res
+          .headers_mut()
+          .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        Ok(res)
+      }
+    }
+  }
+}
@@ -5,3 +5,6 @@ pub use serialize_error::SerializeError;
 
 mod versioning;
 pub use versioning::Versioning;
+
+mod bearer_auth;
+pub use bearer_auth::{BearerAuth, BearerValidator};
@@ -0,0 +1,93 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::audit::Audit;
+use nanocl_stubs::generic::{GenericCount, GenericFilter};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for audit entries
+  const AUDIT_PATH: &'static str = "/audit";
+
+  /// List audit entries of the mutating api calls
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_audit(None).await;
+  /// ```
+  pub async fn list_audit(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<Vec<Audit>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get(Self::AUDIT_PATH, Some(&query)).await?;
+    Self::res_json(res).await
+  }
+
+  /// Inspect an audit entry by it's key
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let audit = client.inspect_audit("b3c6f1f0-...").await?;
+  /// ```
+  pub async fn inspect_audit(&self, key: &str) -> HttpClientResult<Audit> {
+    let res = self
+      .send_get(
+        &format!("{}/{key}/inspect", Self::AUDIT_PATH),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Count audit entries
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let count = client.count_audit(None).await?;
+  /// ```
+  pub async fn count_audit(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<GenericCount> {
+    let query = Self::convert_query(query)?;
+    let res = self
+      .send_get(&format!("{}/count", Self::AUDIT_PATH), Some(&query))
+      .await?;
+    Self::res_json(res).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn basic() {
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    let items = client.list_audit(None).await.unwrap();
+    client.count_audit(None).await.unwrap();
+    if let Some(item) = items.first() {
+      let audit = client.inspect_audit(&item.key.to_string()).await.unwrap();
+      assert_eq!(audit.key, item.key);
+    }
+  }
+}
@@ -134,6 +134,10 @@ mod tests {
         ttl: None,
         image_pull_secret: None,
         image_pull_policy: None,
+        steps: None,
+        backoff_limit: None,
+        backoff_delay: None,
+        active_deadline_seconds: None,
       })
       .await
       .unwrap();
@@ -9,6 +9,10 @@ pub(crate) mod vm;
 pub(crate) mod vm_image;
 pub(crate) mod node;
 pub(crate) mod secret;
+pub(crate) mod token;
+pub(crate) mod role;
+pub(crate) mod network_policy;
+pub(crate) mod audit;
 pub(crate) mod job;
 pub(crate) mod process;
 pub(crate) mod metric;
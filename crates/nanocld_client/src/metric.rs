@@ -1,8 +1,8 @@
 use nanocl_error::http_client::HttpClientResult;
 
 use nanocl_stubs::{
-  generic::GenericFilter,
-  metric::{Metric, MetricPartial},
+  generic::{GenericCount, GenericFilter},
+  metric::{Metric, MetricAggregateQuery, MetricPartial, MetricSeries},
 };
 
 use super::http_client::NanocldClient;
@@ -57,6 +57,36 @@ impl NanocldClient {
     Self::res_json(res).await
   }
 
+  /// Create metrics at once in the system
+  /// and return the number of created metrics
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::metric::MetricPartial;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.create_metrics(&[MetricPartial {
+  ///  kind: "my-source.io/type".to_owned(),
+  ///  data: serde_json::json!({ "Value": 1 }),
+  ///  note: None,
+  /// }]).await;
+  /// ```
+  pub async fn create_metrics(
+    &self,
+    metrics: &[MetricPartial],
+  ) -> HttpClientResult<GenericCount> {
+    let res = self
+      .send_post(
+        &format!("{}/bulk", Self::METRIC_PATH),
+        Some(metrics),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
   /// Inspect a metric in the system
   ///
   /// ## Example
@@ -76,6 +106,53 @@ impl NanocldClient {
       .await?;
     Self::res_json(res).await
   }
+
+  /// Delete the expired metrics and return how many were deleted
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.prune_metric().await;
+  /// ```
+  pub async fn prune_metric(&self) -> HttpClientResult<GenericCount> {
+    let res = self
+      .send_post(
+        &format!("{}/prune", Self::METRIC_PATH),
+        None::<String>,
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Aggregate a field of the metrics of a kind over a time range
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::metric::MetricAggregateQuery;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.aggregate_metric(&MetricAggregateQuery {
+  ///   kind: "ncproxy.io/http".to_owned(),
+  ///   field: "RequestTime".to_owned(),
+  ///   interval: Some(300),
+  ///   ..Default::default()
+  /// }).await;
+  /// ```
+  pub async fn aggregate_metric(
+    &self,
+    query: &MetricAggregateQuery,
+  ) -> HttpClientResult<MetricSeries> {
+    let res = self
+      .send_get(&format!("{}/aggregate", Self::METRIC_PATH), Some(query))
+      .await?;
+    Self::res_json(res).await
+  }
 }
 
 #[cfg(test)]
@@ -109,5 +186,14 @@ mod tests {
       .inspect_metric(metrics[0].key.to_string().as_str())
       .await
       .unwrap();
+    let series = client
+      .aggregate_metric(&MetricAggregateQuery {
+        kind: "nanocl.io/metrs".to_owned(),
+        field: "Cpu".to_owned(),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    assert_eq!(series.interval, 60);
   }
 }
@@ -3,7 +3,8 @@ use nanocl_error::http_client::HttpClientResult;
 use nanocl_stubs::{
   generic::GenericFilter,
   namespace::{
-    Namespace, NamespaceInspect, NamespacePartial, NamespaceSummary,
+    Namespace, NamespaceInspect, NamespacePartial, NamespaceQuota,
+    NamespaceQuotaInspect, NamespaceSummary,
   },
 };
 
@@ -86,6 +87,82 @@ impl NanocldClient {
       .await?;
     Ok(())
   }
+
+  /// Inspect the quota of a namespace with his current usage
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.inspect_namespace_quota("my-namespace").await;
+  /// ```
+  pub async fn inspect_namespace_quota(
+    &self,
+    name: &str,
+  ) -> HttpClientResult<NamespaceQuotaInspect> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}/quota", Self::NAMESPACE_PATH),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Create or replace the quota of a namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::namespace::NamespaceQuota;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let quota = NamespaceQuota {
+  ///   max_cargoes: Some(10),
+  ///   ..Default::default()
+  /// };
+  /// let res = client.put_namespace_quota("my-namespace", &quota).await;
+  /// ```
+  pub async fn put_namespace_quota(
+    &self,
+    name: &str,
+    quota: &NamespaceQuota,
+  ) -> HttpClientResult<NamespaceQuotaInspect> {
+    let res = self
+      .send_put(
+        &format!("{}/{name}/quota", Self::NAMESPACE_PATH),
+        Some(quota),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Delete the quota of a namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.delete_namespace_quota("my-namespace").await;
+  /// ```
+  pub async fn delete_namespace_quota(
+    &self,
+    name: &str,
+  ) -> HttpClientResult<()> {
+    self
+      .send_delete(
+        &format!("{}/{name}/quota", Self::NAMESPACE_PATH),
+        None::<String>,
+      )
+      .await?;
+    Ok(())
+  }
 }
 
 #[cfg(test)]
@@ -107,6 +184,14 @@ mod tests {
     assert_eq!(namespace.name, NAMESPACE);
     let namespace = client.inspect_namespace(NAMESPACE).await.unwrap();
     assert_eq!(namespace.name, NAMESPACE);
+    let quota = NamespaceQuota {
+      max_cargoes: Some(4),
+      ..Default::default()
+    };
+    let item = client.put_namespace_quota(NAMESPACE, &quota).await.unwrap();
+    assert_eq!(item.quota, quota);
+    client.inspect_namespace_quota(NAMESPACE).await.unwrap();
+    client.delete_namespace_quota(NAMESPACE).await.unwrap();
     client.delete_namespace(NAMESPACE).await.unwrap();
   }
 }
@@ -0,0 +1,181 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::generic::{GenericFilterNsp, GenericNspQuery};
+use nanocl_stubs::network_policy::{NetworkPolicy, NetworkPolicyPartial};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for network policies
+  const NETWORK_POLICY_PATH: &'static str = "/network-policies";
+
+  /// List the network policies of a namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_network_policy(None).await;
+  /// ```
+  pub async fn list_network_policy(
+    &self,
+    query: Option<&GenericFilterNsp>,
+  ) -> HttpClientResult<Vec<NetworkPolicy>> {
+    let query = Self::convert_query(query)?;
+    let res = self
+      .send_get(Self::NETWORK_POLICY_PATH, Some(&query))
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Create a new network policy in a namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let policy = client.create_network_policy(&policy, None).await?;
+  /// ```
+  pub async fn create_network_policy(
+    &self,
+    item: &NetworkPolicyPartial,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<NetworkPolicy> {
+    let res = self
+      .send_post(
+        Self::NETWORK_POLICY_PATH,
+        Some(item),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Replace the target and the ingress of a network policy
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let policy = client.put_network_policy(&policy, None).await?;
+  /// ```
+  pub async fn put_network_policy(
+    &self,
+    item: &NetworkPolicyPartial,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<NetworkPolicy> {
+    let res = self
+      .send_put(
+        &format!("{}/{}", Self::NETWORK_POLICY_PATH, item.name),
+        Some(item),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Inspect a network policy by it's name
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let policy = client.inspect_network_policy("my-policy", None).await?;
+  /// ```
+  pub async fn inspect_network_policy(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<NetworkPolicy> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}/inspect", Self::NETWORK_POLICY_PATH),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Delete a network policy by it's name
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// client.delete_network_policy("my-policy", None).await?;
+  /// ```
+  pub async fn delete_network_policy(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_delete(
+        &format!("{}/{name}", Self::NETWORK_POLICY_PATH),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::network_policy::{
+    NetworkPolicyIngress, NetworkPolicyPort, NetworkPolicySelector,
+  };
+
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn basic() {
+    const POLICY_NAME: &str = "network-policy-test";
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    client.list_network_policy(None).await.unwrap();
+    let mut policy = NetworkPolicyPartial {
+      name: POLICY_NAME.to_owned(),
+      target: NetworkPolicySelector {
+        cargoes: Some(vec!["db".to_owned()]),
+        labels: None,
+      },
+      ingress: vec![NetworkPolicyIngress {
+        from: vec![],
+        ports: None,
+      }],
+      metadata: None,
+    };
+    let created = client.create_network_policy(&policy, None).await.unwrap();
+    assert_eq!(created.name, POLICY_NAME);
+    policy.ingress[0].ports = Some(vec![NetworkPolicyPort {
+      port: 5432,
+      ..Default::default()
+    }]);
+    let updated = client.put_network_policy(&policy, None).await.unwrap();
+    assert_eq!(updated.ingress, policy.ingress);
+    let policy = client
+      .inspect_network_policy(POLICY_NAME, None)
+      .await
+      .unwrap();
+    assert_eq!(policy.name, POLICY_NAME);
+    client
+      .delete_network_policy(POLICY_NAME, None)
+      .await
+      .unwrap();
+  }
+}
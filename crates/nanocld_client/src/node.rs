@@ -1,6 +1,6 @@
 use nanocl_error::http_client::HttpClientResult;
 
-use nanocl_stubs::node::Node;
+use nanocl_stubs::node::{Node, NodeLease, NodeLeasePartial};
 
 use super::http_client::NanocldClient;
 
@@ -23,6 +23,53 @@ impl NanocldClient {
     let res = self.send_get(Self::NODE_PATH, None::<String>).await?;
     Self::res_json(res).await
   }
+
+  /// Acquire or extend a lease under `ncproxy.io/acme/` for the node
+  /// of the daemon, it expires unless it's acquired again within 60 seconds.
+  /// It fails with a conflict when the lease is held by another node
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let lease = client.acquire_node_lease("ncproxy.io/acme/example.com", "my-action").await;
+  /// ```
+  pub async fn acquire_node_lease(
+    &self,
+    key: &str,
+    action: &str,
+  ) -> HttpClientResult<NodeLease> {
+    let payload = NodeLeasePartial {
+      action: action.to_owned(),
+    };
+    let res = self
+      .send_put(
+        &format!("{}/leases/{key}", Self::NODE_PATH),
+        Some(&payload),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Release a lease held by the node of the daemon
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// client.release_node_lease("ncproxy.io/acme/example.com").await;
+  /// ```
+  pub async fn release_node_lease(&self, key: &str) -> HttpClientResult<()> {
+    self
+      .send_delete(&format!("{}/leases/{key}", Self::NODE_PATH), None::<String>)
+      .await?;
+    Ok(())
+  }
 }
 
 #[cfg(test)]
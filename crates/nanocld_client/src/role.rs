@@ -0,0 +1,146 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::generic::GenericFilter;
+use nanocl_stubs::role::{Role, RolePartial};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for roles
+  const ROLE_PATH: &'static str = "/roles";
+
+  /// List existing roles
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_role(None).await;
+  /// ```
+  pub async fn list_role(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<Vec<Role>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get(Self::ROLE_PATH, Some(&query)).await?;
+    Self::res_json(res).await
+  }
+
+  /// Create a new role
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let role = client.create_role(&role).await?;
+  /// ```
+  pub async fn create_role(
+    &self,
+    item: &RolePartial,
+  ) -> HttpClientResult<Role> {
+    let res = self
+      .send_post(Self::ROLE_PATH, Some(item), None::<String>)
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Replace the rules and subjects of a role
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let role = client.put_role(&role).await?;
+  /// ```
+  pub async fn put_role(&self, item: &RolePartial) -> HttpClientResult<Role> {
+    let res = self
+      .send_put(
+        &format!("{}/{}", Self::ROLE_PATH, item.name),
+        Some(item),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Inspect a role by it's name
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let role = client.inspect_role("my-role").await?;
+  /// ```
+  pub async fn inspect_role(&self, name: &str) -> HttpClientResult<Role> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}/inspect", Self::ROLE_PATH),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Delete a role by it's name
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// client.delete_role("my-role").await?;
+  /// ```
+  pub async fn delete_role(&self, name: &str) -> HttpClientResult<()> {
+    self
+      .send_delete(&format!("{}/{name}", Self::ROLE_PATH), None::<String>)
+      .await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::role::{RoleRule, RoleVerb};
+
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn basic() {
+    const ROLE_NAME: &str = "role-test";
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    client.list_role(None).await.unwrap();
+    let mut role = RolePartial {
+      name: ROLE_NAME.to_owned(),
+      rules: vec![RoleRule {
+        kinds: vec!["cargo".to_owned()],
+        verbs: vec![RoleVerb::List],
+        namespaces: Some(vec!["global".to_owned()]),
+      }],
+      subjects: vec!["token:role-test".to_owned()],
+      metadata: None,
+    };
+    let created = client.create_role(&role).await.unwrap();
+    assert_eq!(created.name, ROLE_NAME);
+    role.rules[0].verbs.push(RoleVerb::Inspect);
+    let updated = client.put_role(&role).await.unwrap();
+    assert_eq!(updated.rules[0].verbs.len(), 2);
+    let role = client.inspect_role(ROLE_NAME).await.unwrap();
+    assert_eq!(role.name, ROLE_NAME);
+    client.delete_role(ROLE_NAME).await.unwrap();
+  }
+}
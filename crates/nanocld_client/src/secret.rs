@@ -1,7 +1,10 @@
 use nanocl_error::http_client::HttpClientResult;
 
 use nanocl_stubs::generic::GenericFilter;
-use nanocl_stubs::secret::{Secret, SecretPartial, SecretUpdate};
+use nanocl_stubs::secret::{
+  Secret, SecretInspectQuery, SecretKeyRotation, SecretPartial, SecretSummary,
+  SecretUpdate,
+};
 
 use super::http_client::NanocldClient;
 
@@ -9,7 +12,7 @@ impl NanocldClient {
   /// ## Default path for secrets
   const SECRET_PATH: &'static str = "/secrets";
 
-  /// List existing secrets in the system.
+  /// List existing secrets in the system without their data.
   ///
   /// ## Example
   ///
@@ -22,7 +25,7 @@ impl NanocldClient {
   pub async fn list_secret(
     &self,
     query: Option<&GenericFilter>,
-  ) -> HttpClientResult<Vec<Secret>> {
+  ) -> HttpClientResult<Vec<SecretSummary>> {
     let query = Self::convert_query(query)?;
     let res = self.send_get(Self::SECRET_PATH, Some(&query)).await?;
     Self::res_json(res).await
@@ -56,6 +59,7 @@ impl NanocldClient {
   }
 
   /// Inspect a secret by it's key to get more information about it
+  /// The data is masked unless `reveal` is set in the query
   ///
   /// ## Example
   ///
@@ -63,14 +67,15 @@ impl NanocldClient {
   /// use nanocld_client::NanocldClient;
   ///
   /// let client = NanocldClient::connect_to("http://localhost:8585", None);
-  /// let secret = client.inspect_secret("my-secret").await?;
+  /// let secret = client.inspect_secret("my-secret", None).await?;
   /// ```
-  pub async fn inspect_secret(&self, key: &str) -> HttpClientResult<Secret> {
+  pub async fn inspect_secret(
+    &self,
+    key: &str,
+    query: Option<&SecretInspectQuery>,
+  ) -> HttpClientResult<Secret> {
     let res = self
-      .send_get(
-        &format!("{}/{key}/inspect", Self::SECRET_PATH),
-        None::<String>,
-      )
+      .send_get(&format!("{}/{key}/inspect", Self::SECRET_PATH), query)
       .await?;
     Self::res_json(res).await
   }
@@ -91,6 +96,27 @@ impl NanocldClient {
       .await?;
     Ok(())
   }
+
+  /// Generate a new master key and encrypt every secret with it
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let rotation = client.rotate_secret_key().await?;
+  /// ```
+  pub async fn rotate_secret_key(&self) -> HttpClientResult<SecretKeyRotation> {
+    let res = self
+      .send_post(
+        &format!("{}/rotate-key", Self::SECRET_PATH),
+        None::<String>,
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
 }
 
 #[cfg(test)]
@@ -117,8 +143,15 @@ mod tests {
     };
     let secret = client.create_secret(&secret).await.unwrap();
     assert_eq!(secret.name, SECRET_NAME);
-    let secret = client.inspect_secret(SECRET_NAME).await.unwrap();
+    let secret = client.inspect_secret(SECRET_NAME, None).await.unwrap();
     assert_eq!(secret.name, SECRET_NAME);
+    assert_ne!(secret.data, serde_json::json!({"key": "value"}));
+    let query = SecretInspectQuery { reveal: Some(true) };
+    let secret = client
+      .inspect_secret(SECRET_NAME, Some(&query))
+      .await
+      .unwrap();
+    assert_eq!(secret.data, serde_json::json!({"key": "value"}));
     client.delete_secret(SECRET_NAME).await.unwrap();
   }
 }
@@ -3,6 +3,7 @@ use ntex::channel::mpsc::Receiver;
 use nanocl_error::http::HttpResult;
 use nanocl_error::http_client::HttpClientResult;
 
+use nanocl_stubs::generic::GenericCount;
 use nanocl_stubs::system::{BinaryInfo, Event, EventCondition, HostInfo};
 
 use super::http_client::NanocldClient;
@@ -47,6 +48,23 @@ impl NanocldClient {
     Ok(Self::res_stream(res).await)
   }
 
+  /// Delete the expired events and return how many were deleted
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.prune_events().await;
+  /// ```
+  pub async fn prune_events(&self) -> HttpClientResult<GenericCount> {
+    let res = self
+      .send_post("/events/prune", None::<String>, None::<String>)
+      .await?;
+    Self::res_json(res).await
+  }
+
   /// Check if the daemon is running
   ///
   /// ## Example
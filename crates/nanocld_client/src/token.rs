@@ -0,0 +1,122 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::generic::GenericFilter;
+use nanocl_stubs::token::{Token, TokenCreated, TokenPartial};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for tokens
+  const TOKEN_PATH: &'static str = "/tokens";
+
+  /// List existing api tokens without their secret.
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_token(None).await;
+  /// ```
+  pub async fn list_token(
+    &self,
+    query: Option<&GenericFilter>,
+  ) -> HttpClientResult<Vec<Token>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get(Self::TOKEN_PATH, Some(&query)).await?;
+    Self::res_json(res).await
+  }
+
+  /// Create a new api token, his secret is only returned here
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let created = client.create_token(&token).await?;
+  /// println!("{}", created.secret);
+  /// ```
+  pub async fn create_token(
+    &self,
+    item: &TokenPartial,
+  ) -> HttpClientResult<TokenCreated> {
+    let res = self
+      .send_post(Self::TOKEN_PATH, Some(item), None::<String>)
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Inspect an api token by it's name
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let token = client.inspect_token("my-token").await?;
+  /// ```
+  pub async fn inspect_token(&self, name: &str) -> HttpClientResult<Token> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}/inspect", Self::TOKEN_PATH),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Delete an api token by it's name
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// client.delete_token("my-token").await?;
+  /// ```
+  pub async fn delete_token(&self, name: &str) -> HttpClientResult<()> {
+    self
+      .send_delete(&format!("{}/{name}", Self::TOKEN_PATH), None::<String>)
+      .await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::ConnectOpts;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn basic() {
+    const TOKEN_NAME: &str = "token-test";
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    client.list_token(None).await.unwrap();
+    let token = TokenPartial {
+      name: TOKEN_NAME.to_owned(),
+      ..Default::default()
+    };
+    let created = client.create_token(&token).await.unwrap();
+    assert_eq!(created.token.name, TOKEN_NAME);
+    // The secret can be used as bearer token
+    let client = NanocldClient::connect_to(&ConnectOpts {
+      url: "http://nanocl.internal:8585".into(),
+      token: Some(created.secret),
+      ..Default::default()
+    })
+    .expect("Failed to create a nanocl client");
+    let token = client.inspect_token(TOKEN_NAME).await.unwrap();
+    assert_eq!(token.name, TOKEN_NAME);
+    client.delete_token(TOKEN_NAME).await.unwrap();
+  }
+}